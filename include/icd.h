@@ -0,0 +1,42 @@
+/*
+ * C interface of invisible-char-detector, built with `cargo build --release --features ffi`
+ * (target/release/libinvisible_char_detector.so, .dylib, or invisible_char_detector.dll).
+ *
+ * Detections are returned as the JSON array `invisible-char-detector --json` prints for one
+ * file. Every returned string must be released with icd_string_free().
+ */
+
+#ifndef ICD_H
+#define ICD_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * Scan `len` bytes of UTF-8 (or UTF-16 with a byte order mark) text. Returns the detections
+ * as a JSON array ("[]" when clean), or NULL if the bytes aren't valid text.
+ */
+char *icd_scan_utf8(const uint8_t *data, size_t len);
+
+/*
+ * Like icd_scan_utf8(), reporting the detections under the file name `name` (NUL-terminated
+ * UTF-8), which also enables name-dependent handling such as JSON and YAML structure paths.
+ * Returns NULL if `name` is NULL or not UTF-8.
+ */
+char *icd_scan_file(const char *name, const uint8_t *data, size_t len);
+
+/* Release a string returned by icd_scan_utf8() or icd_scan_file(). NULL is ignored. */
+void icd_string_free(char *s);
+
+/* The library version, e.g. "1.0.0". Static; don't release it. */
+const char *icd_version(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* ICD_H */
@@ -0,0 +1,111 @@
+//! `--scan-archives`: read the files inside zip and tar archives without unpacking them.
+//!
+//! VS Code extensions (.vsix), npm tarballs (.tgz), and crates (.crate) are archives, and they
+//! are what actually ships to users. Entries are read in memory and reported as
+//! `archive.zip!path/inside.js`.
+
+use std::io::{Cursor, Read};
+
+use flate2::read::GzDecoder;
+
+/// How an archive is stored.
+#[derive(Debug, Clone, Copy)]
+pub enum ArchiveKind {
+    /// .zip, .vsix, .jar, .whl, .nupkg
+    Zip,
+
+    /// .tar
+    Tar,
+
+    /// .tar.gz, .tgz, .crate
+    TarGz,
+}
+
+const ZIP_EXTENSIONS: &[&str] = &[".zip", ".vsix", ".jar", ".whl", ".nupkg"];
+const TAR_GZ_EXTENSIONS: &[&str] = &[".tar.gz", ".tgz", ".crate"];
+
+/// The kind of archive at `path`, judged by its extension.
+pub fn archive_kind(path: &str) -> Option<ArchiveKind> {
+    let path = path.to_ascii_lowercase();
+    if ZIP_EXTENSIONS.iter().any(|ext| path.ends_with(ext)) {
+        Some(ArchiveKind::Zip)
+    } else if TAR_GZ_EXTENSIONS.iter().any(|ext| path.ends_with(ext)) {
+        Some(ArchiveKind::TarGz)
+    } else if path.ends_with(".tar") {
+        Some(ArchiveKind::Tar)
+    } else {
+        None
+    }
+}
+
+/// A regular file inside an archive.
+pub struct ArchiveEntry {
+    /// Path of the entry within the archive.
+    pub path: String,
+    pub content: Vec<u8>,
+}
+
+/// Read up to `limit` bytes of an entry; `None` if it is larger (sizes in headers can lie,
+/// so this is checked on the decompressed data).
+fn read_limited(entry: impl Read, limit: Option<u64>) -> Result<Option<Vec<u8>>, String> {
+    let mut content = Vec::new();
+    let cap = limit.map_or(u64::MAX, |limit| limit.saturating_add(1));
+    entry
+        .take(cap)
+        .read_to_end(&mut content)
+        .map_err(|e| e.to_string())?;
+    Ok(limit
+        .is_none_or(|limit| content.len() as u64 <= limit)
+        .then_some(content))
+}
+
+/// All regular files in the archive `bytes`, skipping entries larger than `max_size`.
+pub fn read_entries(
+    bytes: &[u8],
+    kind: ArchiveKind,
+    max_size: Option<u64>,
+) -> Result<Vec<ArchiveEntry>, String> {
+    match kind {
+        ArchiveKind::Zip => read_zip(bytes, max_size),
+        ArchiveKind::Tar => read_tar(bytes, max_size),
+        ArchiveKind::TarGz => read_tar(GzDecoder::new(bytes), max_size),
+    }
+}
+
+fn read_zip(bytes: &[u8], max_size: Option<u64>) -> Result<Vec<ArchiveEntry>, String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| e.to_string())?;
+    let mut entries = Vec::new();
+    for i in 0..archive.len() {
+        let file = archive.by_index(i).map_err(|e| e.to_string())?;
+        if !file.is_file() {
+            continue;
+        }
+        // Most tools write UTF-8 names without setting the flag that says so
+        let path = match std::str::from_utf8(file.name_raw()) {
+            Ok(name) => name.to_string(),
+            Err(_) => file.name().to_string(),
+        };
+        if let Some(content) = read_limited(file, max_size)? {
+            entries.push(ArchiveEntry { path, content });
+        }
+    }
+    Ok(entries)
+}
+
+fn read_tar(reader: impl Read, max_size: Option<u64>) -> Result<Vec<ArchiveEntry>, String> {
+    let mut archive = tar::Archive::new(reader);
+    let mut entries = Vec::new();
+    for entry in archive.entries().map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = String::from_utf8_lossy(&entry.path_bytes())
+            .trim_start_matches("./")
+            .to_string();
+        if let Some(content) = read_limited(entry, max_size)? {
+            entries.push(ArchiveEntry { path, content });
+        }
+    }
+    Ok(entries)
+}
@@ -0,0 +1,65 @@
+//! `--format azure`: Azure Pipelines logging commands, so each detection is an issue
+//! annotated on the run's summary and the task is marked failed.
+//!
+//! Errors and warnings become `##vso[task.logissue]` commands of the same type. Azure has no
+//! info issues, so info findings are printed as plain log lines that leave the result alone.
+
+use crate::rules::Severity;
+use crate::{visualize, Detection};
+
+/// `value` escaped for a logging command property.
+fn escape_property(value: &str) -> String {
+    escape_message(value)
+        .replace(';', "%3B")
+        .replace(']', "%5D")
+}
+
+/// `value` escaped for a logging command message.
+fn escape_message(value: &str) -> String {
+    value
+        .replace('%', "%AZP25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Format detections as Azure Pipelines logging commands.
+pub fn format_azure(detections: &[Detection]) -> String {
+    let mut output = String::new();
+    for d in detections {
+        let message = format!("U+{:04X} {}: {}", d.code, d.name, d.description);
+        let kind = match d.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => {
+                output.push_str(&format!(
+                    "{}:{}:{}: info: {} [{}]\n",
+                    visualize(&d.file),
+                    d.line,
+                    d.utf16_column,
+                    escape_message(&message),
+                    d.rule
+                ));
+                continue;
+            }
+        };
+        // A character in a name has no line or column in the file
+        let position = if d.line > 0 {
+            format!(";linenumber={};columnnumber={}", d.line, d.utf16_column)
+        } else {
+            String::new()
+        };
+        output.push_str(&format!(
+            "##vso[task.logissue type={};sourcepath={}{};code={}]{}\n",
+            kind,
+            escape_property(&visualize(&d.file)),
+            position,
+            escape_property(&d.rule),
+            escape_message(&message)
+        ));
+    }
+
+    if detections.iter().any(|d| d.severity.fails()) {
+        output.push_str("##vso[task.complete result=Failed;]Suspicious characters found\n");
+    }
+    output
+}
@@ -0,0 +1,123 @@
+//! `cargo icd`: scan a Cargo workspace for suspicious characters.
+//!
+//! Cargo runs `cargo-icd icd [ARGS]` for `cargo icd [ARGS]`. The workspace is found with
+//! `cargo metadata`, and each member's directory (its `src/`, `tests/`, `benches/`,
+//! `examples/`, and docs) is scanned by `invisible-char-detector`, with `target/` skipped as it
+//! always is. Members inside the workspace root are covered by one scan of the root; a member
+//! outside it gets a scan of its own. Every other argument is passed on to the scanner, so
+//! `cargo icd --json` or `cargo icd --format short` work as they do for a single directory.
+
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+
+use serde_json::Value;
+
+const USAGE: &str = "\
+Usage: cargo icd [--manifest-path <PATH>] [SCANNER OPTIONS]
+
+Scans every member of the current Cargo workspace with invisible-char-detector.
+Scanner options are those of `invisible-char-detector --help`.";
+
+/// Name of the scanner binary.
+const SCANNER: &str = "invisible-char-detector";
+
+/// The workspace root and the directories of its members, from `cargo metadata`.
+fn workspace(manifest_path: Option<&str>) -> Result<(PathBuf, Vec<PathBuf>), String> {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let mut command = Command::new(cargo);
+    command.args(["metadata", "--format-version", "1", "--no-deps"]);
+    if let Some(path) = manifest_path {
+        command.args(["--manifest-path", path]);
+    }
+    let output = command
+        .output()
+        .map_err(|e| format!("could not run cargo metadata: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "cargo metadata failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let metadata: Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("could not parse cargo metadata: {}", e))?;
+    let root = metadata["workspace_root"]
+        .as_str()
+        .map(PathBuf::from)
+        .ok_or("cargo metadata has no workspace_root")?;
+    // With --no-deps, the packages are the workspace members
+    let members = metadata["packages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|package| package["manifest_path"].as_str())
+        .filter_map(|manifest| Path::new(manifest).parent().map(Path::to_path_buf))
+        .collect();
+    Ok((root, members))
+}
+
+/// The directories to scan: the workspace root, and members outside it.
+fn scan_roots(root: PathBuf, members: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut roots = vec![root];
+    for member in members {
+        if !roots.iter().any(|scanned| member.starts_with(scanned)) {
+            roots.push(member);
+        }
+    }
+    roots
+}
+
+/// The scanner installed next to this binary, or the one on `PATH`.
+fn scanner() -> PathBuf {
+    let name = format!("{}{}", SCANNER, std::env::consts::EXE_SUFFIX);
+    std::env::current_exe()
+        .ok()
+        .map(|exe| exe.with_file_name(&name))
+        .filter(|path| path.is_file())
+        .unwrap_or_else(|| PathBuf::from(name))
+}
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    // Cargo passes the subcommand name first
+    if args.first().is_some_and(|arg| arg == "icd") {
+        args.remove(0);
+    }
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+    let mut manifest_path = None;
+    if let Some(i) = args.iter().position(|arg| arg == "--manifest-path") {
+        if i + 1 >= args.len() {
+            eprintln!("Error: --manifest-path needs a value\n\n{}", USAGE);
+            process::exit(2);
+        }
+        manifest_path = Some(args.remove(i + 1));
+        args.remove(i);
+    }
+
+    let (root, members) = match workspace(manifest_path.as_deref()) {
+        Ok(workspace) => workspace,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(2);
+        }
+    };
+
+    // Worst exit code wins: 2 (error) over 1 (found) over 0
+    let scanner = scanner();
+    let mut code = 0;
+    for dir in scan_roots(root, members) {
+        let pattern = format!("{}/**/*", dir.display());
+        let status = Command::new(&scanner).arg(&pattern).args(&args).status();
+        match status {
+            Ok(status) => code = code.max(status.code().unwrap_or(2)),
+            Err(e) => {
+                eprintln!("Error: could not run {}: {}", scanner.display(), e);
+                process::exit(2);
+            }
+        }
+    }
+    process::exit(code);
+}
@@ -0,0 +1,141 @@
+//! `--blame`: the commit that last changed each detection's line, from `git blame`.
+//!
+//! During incident response the first question about a finding is who introduced it and when.
+//! Each file with detections gets one `git blame` for all its detected lines and one `git log`
+//! for the commits it names. Lines not committed yet, files outside a repository, and
+//! characters in file names get no blame.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Detection;
+
+/// The commit that last changed a line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Blame {
+    /// Full commit hash.
+    pub commit: String,
+
+    /// Commit author as `Name <email>`.
+    pub author: String,
+
+    /// Author date in ISO 8601 format.
+    pub date: String,
+}
+
+/// Run `git` in `dir` and return its stdout, or `None` if it fails.
+fn git_in(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The commit of each of `lines` of `path`, from `git blame --porcelain`.
+fn blame_lines(dir: &Path, name: &str, lines: &BTreeSet<usize>) -> HashMap<usize, String> {
+    let ranges: Vec<String> = lines
+        .iter()
+        .map(|line| format!("-L{},{}", line, line))
+        .collect();
+    let mut args = vec!["blame", "--porcelain"];
+    args.extend(ranges.iter().map(String::as_str));
+    args.extend(["--", name]);
+    let Some(output) = git_in(dir, &args) else {
+        return HashMap::new();
+    };
+
+    // Each line starts with "<hash> <original line> <final line>[ <group size>]"; the
+    // header's other fields and the line's content follow
+    let mut commits = HashMap::new();
+    for row in output.lines() {
+        let mut fields = row.split(' ');
+        let (Some(hash), Some(_), Some(line)) = (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        if hash.len() < 40 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+            continue;
+        }
+        // All zeros: changed in the working tree, not committed yet
+        if hash.bytes().all(|b| b == b'0') {
+            continue;
+        }
+        if let Ok(line) = line.parse() {
+            commits.insert(line, hash.to_string());
+        }
+    }
+    commits
+}
+
+/// Author and date of each of `commits`.
+fn describe_commits(dir: &Path, commits: &BTreeSet<&str>) -> HashMap<String, Blame> {
+    let mut args = vec![
+        "log",
+        "--no-walk=unsorted",
+        "--format=%H%x00%an <%ae>%x00%aI",
+    ];
+    args.extend(commits.iter().copied());
+    let Some(output) = git_in(dir, &args) else {
+        return HashMap::new();
+    };
+    output
+        .lines()
+        .filter_map(|row| {
+            let mut fields = row.split('\0');
+            let blame = Blame {
+                commit: fields.next()?.to_string(),
+                author: fields.next()?.to_string(),
+                date: fields.next()?.to_string(),
+            };
+            Some((blame.commit.clone(), blame))
+        })
+        .collect()
+}
+
+/// Set `blame` on every detection in file content that git can attribute to a commit.
+pub fn attach(detections: &mut [Detection]) {
+    let mut lines_by_file: HashMap<String, BTreeSet<usize>> = HashMap::new();
+    for d in detections.iter().filter(|d| d.line > 0) {
+        lines_by_file
+            .entry(d.file.clone())
+            .or_default()
+            .insert(d.line);
+    }
+
+    let mut blames: HashMap<(String, usize), Blame> = HashMap::new();
+    for (file, lines) in lines_by_file {
+        let path = Path::new(&file);
+        let (Some(name), Some(parent)) = (path.file_name(), path.parent()) else {
+            continue;
+        };
+        let dir = if parent.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            parent
+        };
+        let name = name.to_string_lossy();
+        let commits = blame_lines(dir, &name, &lines);
+        if commits.is_empty() {
+            continue;
+        }
+        let unique: BTreeSet<&str> = commits.values().map(String::as_str).collect();
+        let described = describe_commits(dir, &unique);
+        for (line, commit) in &commits {
+            if let Some(blame) = described.get(commit) {
+                blames.insert((file.clone(), *line), blame.clone());
+            }
+        }
+    }
+
+    for d in detections.iter_mut() {
+        d.blame = blames.get(&(d.file.clone(), d.line)).cloned();
+    }
+}
@@ -0,0 +1,130 @@
+//! Incremental scan cache (`--cache <file>`).
+//!
+//! Each scanned file is recorded with its size, modification time, a content hash, and the
+//! detections it produced. On the next run a file whose size and mtime are unchanged is not
+//! read at all; a file whose mtime changed but whose content hash still matches reuses its
+//! previous detections without rescanning.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Detection;
+
+/// Size and modification time of a file, used as the cheap "unchanged" check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    pub size: u64,
+    pub mtime_secs: u64,
+    pub mtime_nanos: u32,
+}
+
+impl FileStamp {
+    pub fn from_metadata(meta: &fs::Metadata) -> Option<Self> {
+        let mtime = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self {
+            size: meta.len(),
+            mtime_secs: mtime.as_secs(),
+            mtime_nanos: mtime.subsec_nanos(),
+        })
+    }
+}
+
+/// Cached scan result for one file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub stamp: FileStamp,
+
+    /// FNV-1a hash of the file contents, as 16 hex digits (absent for streamed files).
+    pub hash: Option<String>,
+
+    pub detections: Vec<Detection>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    /// Tool version and detection settings the entries were produced with.
+    settings: String,
+    files: BTreeMap<String, CacheEntry>,
+}
+
+/// In-memory view of a cache file.
+#[derive(Debug, Default)]
+pub struct ScanCache {
+    settings: String,
+    files: BTreeMap<String, CacheEntry>,
+}
+
+/// 64-bit FNV-1a hash of `bytes`, formatted as hex.
+///
+/// Not cryptographic: it only needs to notice that a file's content changed between runs.
+pub fn content_hash(bytes: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &b in bytes {
+        hash ^= u64::from(b);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+impl ScanCache {
+    /// Load the cache at `path`.
+    ///
+    /// A missing, unreadable, or corrupt cache, or one written with different settings, yields
+    /// an empty cache; the second value explains why an existing file was discarded.
+    pub fn load(path: &Path, settings: &str) -> (Self, Option<String>) {
+        let empty = Self {
+            settings: settings.to_string(),
+            files: BTreeMap::new(),
+        };
+
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return (empty, None),
+            Err(e) => return (empty, Some(format!("could not read cache: {}", e))),
+        };
+
+        match serde_json::from_str::<CacheFile>(&text) {
+            Ok(file) if file.settings == settings => (
+                Self {
+                    settings: file.settings,
+                    files: file.files,
+                },
+                None,
+            ),
+            Ok(_) => (empty, Some("settings changed; cache discarded".to_string())),
+            Err(e) => (empty, Some(format!("corrupt cache discarded: {}", e))),
+        }
+    }
+
+    /// Previous entry for `path`, if any.
+    pub fn get(&self, path: &str) -> Option<&CacheEntry> {
+        self.files.get(path)
+    }
+
+    pub fn insert(&mut self, path: String, entry: CacheEntry) {
+        self.files.insert(path, entry);
+    }
+
+    /// Write the cache to `path`, dropping entries for files that no longer exist.
+    ///
+    /// Written to a temporary file and renamed so an interrupted run never leaves a
+    /// truncated cache behind.
+    pub fn save(mut self, path: &Path) -> std::io::Result<()> {
+        self.files.retain(|file, _| Path::new(file).exists());
+
+        let file = CacheFile {
+            settings: self.settings,
+            files: self.files,
+        };
+        let json = serde_json::to_string(&file).map_err(std::io::Error::other)?;
+
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, path)
+    }
+}
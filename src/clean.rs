@@ -0,0 +1,304 @@
+//! `--clean-to`: sanitized copies of the files with detections, for shipping a cleaned
+//! artifact while the originals stay untouched as evidence.
+//!
+//! Copies go into a mirror of the scanned tree under the target directory. Invisible
+//! characters (zero-width, bidi controls, tags, variation selectors, ...) are removed,
+//! non-ASCII spaces become a space, and Unicode line separators a newline; names are cleaned
+//! the same way. Anything that needs a human decision (confusables, mixed scripts, encoding
+//! artifacts, info findings) is left as it is. `manifest.json` lists, per file, what was done
+//! with each detection.
+//!
+//! The same edit is given per detection as its `fix` in JSON output, for editor plugins and
+//! review bots to offer.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::rules::{self, Severity};
+use crate::Detection;
+
+/// Name of the manifest written next to the copies.
+const MANIFEST: &str = "manifest.json";
+
+/// What a clean copy does with a character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Action {
+    Removed,
+    Replaced,
+
+    /// Left in place for review.
+    Kept,
+}
+
+/// The replacement for a character of `category` at `severity`: `Some("")` to remove it,
+/// `None` to keep it.
+fn replacement(category: &str, severity: Severity) -> Option<&'static str> {
+    if severity == Severity::Info {
+        return None;
+    }
+    match category {
+        "zero-width" | "bidi" | "variation-selector" | "tag" | "deprecated-format"
+        | "prepended-mark" | "annotation" | "blank" | "filler" | "format" | "pua" | "control" => {
+            Some("")
+        }
+        "whitespace" => Some(" "),
+        "line-separator" => Some("\n"),
+        _ => None,
+    }
+}
+
+/// The edit that fixes a detection the way a clean copy does: the bytes `start..end` of the
+/// text replaced by `replacement`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fix {
+    /// 0-indexed byte offset of the first byte to replace.
+    pub start: usize,
+
+    /// 0-indexed byte offset just past the last byte to replace.
+    pub end: usize,
+
+    /// The text to put there: empty to delete the character.
+    pub replacement: String,
+}
+
+/// The fix for `d`, or `None` when it needs a human decision. Characters in names, escape
+/// sequences, and characters in files that aren't UTF-8 get none.
+pub fn fix_for(d: &Detection) -> Option<Fix> {
+    // An unterminated bidi finding is about the opener, which has a detection of its own
+    if d.line == 0
+        || d.rule == "ICD-BIDI004"
+        || d.escaped
+        || d.invalid_bytes.is_some()
+        || d.encoding.is_some()
+    {
+        return None;
+    }
+    let text = replacement(&d.category, d.severity)?;
+    let count = d.run.as_ref().map_or(1, |run| run.count);
+    let start = d.byte_offset - 1;
+    Some(Fix {
+        start,
+        end: start + d.char.len() * count,
+        replacement: text.repeat(count),
+    })
+}
+
+/// One detection in the manifest.
+#[derive(Debug, Serialize)]
+struct Change {
+    /// 0 for a character in the name.
+    line: usize,
+    char_index: usize,
+    code: u32,
+    name: String,
+    rule: String,
+    action: Action,
+
+    /// Characters in the run, when more than one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    count: Option<usize>,
+}
+
+/// A file in the manifest.
+#[derive(Debug, Serialize)]
+struct CleanedFile {
+    file: String,
+
+    /// Path of the copy, relative to the clean directory; absent if no copy was written.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    copy: Option<String>,
+
+    /// Why no copy was written.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    skipped: Option<String>,
+
+    changes: Vec<Change>,
+}
+
+/// `path` with the characters a clean copy removes or replaces in content also cleaned out
+/// of its names, made relative so it can be joined to the clean directory.
+fn clean_path(path: &str) -> PathBuf {
+    let mut clean = PathBuf::new();
+    for component in Path::new(path).components() {
+        let Component::Normal(name) = component else {
+            continue;
+        };
+        let mut out = String::new();
+        for ch in name.to_string_lossy().chars() {
+            let rule = rules::rule_for(ch as u32);
+            match rule.and_then(|rule| replacement(rule.category, rule.severity)) {
+                // A name can't hold a line break
+                Some(_) if rule.is_some_and(|rule| rule.category == "whitespace") => out.push(' '),
+                Some(_) => {}
+                None => out.push(ch),
+            }
+        }
+        if !out.is_empty() {
+            clean.push(out);
+        }
+    }
+    clean
+}
+
+/// The cleaned content of `file`, or why it can't be cleaned.
+fn clean_content(
+    file: &str,
+    detections: &[&Detection],
+    changes: &mut Vec<Change>,
+) -> Result<Vec<u8>, String> {
+    let path = Path::new(file);
+    if !path.is_file() {
+        return Err("not a file on disk (an archive or document member)".to_string());
+    }
+    if detections.iter().any(|d| d.encoding.is_some()) {
+        return Err("not UTF-8".to_string());
+    }
+    if detections.iter().any(|d| d.more_in_file.is_some()) {
+        return Err("detections were left out (--max-detections-per-file)".to_string());
+    }
+    let content = fs::read(path).map_err(|e| e.to_string())?;
+    apply(&content, detections, changes)
+}
+
+/// `content` with the characters of `detections` removed or replaced, recording each in
+/// `changes`.
+fn apply(
+    content: &[u8],
+    detections: &[&Detection],
+    changes: &mut Vec<Change>,
+) -> Result<Vec<u8>, String> {
+    // Byte range of each edit and its replacement
+    let mut edits: BTreeMap<usize, (usize, String)> = BTreeMap::new();
+    for d in detections
+        .iter()
+        .filter(|d| d.line > 0 && d.rule != "ICD-BIDI004")
+    {
+        let count = d.run.as_ref().map_or(1, |run| run.count);
+        let action = match fix_for(d) {
+            Some(fix) => {
+                let expected = d.char.repeat(count);
+                if content.get(fix.start..fix.end) != Some(expected.as_bytes()) {
+                    return Err("changed since it was scanned".to_string());
+                }
+                let action = if fix.replacement.is_empty() {
+                    Action::Removed
+                } else {
+                    Action::Replaced
+                };
+                edits.insert(fix.start, (fix.end - fix.start, fix.replacement));
+                action
+            }
+            None => Action::Kept,
+        };
+        changes.push(Change {
+            line: d.line,
+            char_index: d.char_index,
+            code: d.code,
+            name: d.name.clone(),
+            rule: d.rule.clone(),
+            action,
+            count: (count > 1).then_some(count),
+        });
+    }
+
+    let mut cleaned = Vec::with_capacity(content.len());
+    let mut at = 0;
+    for (start, (len, text)) in edits {
+        cleaned.extend_from_slice(&content[at..start]);
+        cleaned.extend_from_slice(text.as_bytes());
+        at = start + len;
+    }
+    cleaned.extend_from_slice(&content[at..]);
+    Ok(cleaned)
+}
+
+/// `text` cleaned of the characters `detections` found in it, as a clean copy would be, and
+/// how many detections were removed or replaced (`--clipboard --fix`).
+pub fn clean_text(text: &str, detections: &[Detection]) -> Result<(String, usize), String> {
+    let detections: Vec<&Detection> = detections.iter().collect();
+    let mut changes = Vec::new();
+    let cleaned = apply(text.as_bytes(), &detections, &mut changes)?;
+    let fixed = changes.iter().filter(|c| c.action != Action::Kept).count();
+    // Edits replace whole characters with ASCII, so the text stays UTF-8
+    let cleaned = String::from_utf8(cleaned).map_err(|e| e.to_string())?;
+    Ok((cleaned, fixed))
+}
+
+/// Write clean copies of the files with detections under `dir`, and the manifest. Returns
+/// the number of copies written.
+pub fn write_clean_copies(dir: &Path, detections: &[Detection]) -> Result<usize, String> {
+    let mut by_file: BTreeMap<&str, Vec<&Detection>> = BTreeMap::new();
+    for d in detections {
+        by_file.entry(&d.file).or_default().push(d);
+    }
+
+    let mut manifest = Vec::new();
+    let mut copies = HashSet::new();
+    for (file, dets) in by_file {
+        let mut changes = Vec::new();
+        // Characters in the names along the path, each reported under its own prefix
+        for d in dets.iter().filter(|d| d.line == 0) {
+            let action = match rules::rule_for(d.code) {
+                Some(rule) if rule.category == "whitespace" => Action::Replaced,
+                Some(rule) if replacement(rule.category, rule.severity).is_some() => {
+                    Action::Removed
+                }
+                _ => Action::Kept,
+            };
+            changes.push(Change {
+                line: 0,
+                char_index: d.char_index,
+                code: d.code,
+                name: d.name.clone(),
+                rule: d.rule.clone(),
+                action,
+                count: None,
+            });
+        }
+        // A directory has no content of its own; its name is cleaned in the copies' paths
+        if Path::new(file).is_dir() {
+            manifest.push(CleanedFile {
+                file: file.to_string(),
+                copy: None,
+                skipped: None,
+                changes,
+            });
+            continue;
+        }
+        let (copy, skipped) = match clean_content(file, &dets, &mut changes) {
+            Ok(cleaned) => {
+                let relative = clean_path(file);
+                let target = dir.join(&relative);
+                if !copies.insert(target.clone()) {
+                    return Err(format!(
+                        "{} and another file have the same clean name",
+                        file
+                    ));
+                }
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)
+                        .map_err(|e| format!("{}: {}", parent.display(), e))?;
+                }
+                fs::write(&target, cleaned).map_err(|e| format!("{}: {}", target.display(), e))?;
+                (Some(relative.to_string_lossy().replace('\\', "/")), None)
+            }
+            Err(reason) => (None, Some(reason)),
+        };
+        manifest.push(CleanedFile {
+            file: file.to_string(),
+            copy,
+            skipped,
+            changes,
+        });
+    }
+
+    fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let json = serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())?;
+    let path = dir.join(MANIFEST);
+    fs::write(&path, json + "\n").map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(copies.len())
+}
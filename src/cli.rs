@@ -0,0 +1,1103 @@
+//! Command-line interface definition.
+//!
+//! `scan` is the default subcommand: `invisible-char-detector "**/*.rs"` and
+//! `invisible-char-detector scan "**/*.rs"` are equivalent. The other subcommands take their own
+//! options, and unknown flags are rejected with exit code 2.
+
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+
+use clap::builder::BoolishValueParser;
+use clap::{Args, Parser, Subcommand};
+use clap_complete::Shell;
+
+use unicode_script::Script;
+
+use crate::config::ScanPreset;
+use crate::context::{ContextPolicy, SyntaxContext};
+use crate::normalize::NormalizationForm;
+use crate::redact::RedactMode;
+use crate::render::{ColorMode, EscapeStyle, GroupBy, OutputFormat};
+use crate::risk::SortBy;
+use crate::{
+    clipboard, logging, package, progress, scripts, webhook, DetectOptions, GeneratedFiles, IgnoreDirs, ScanConfig, DEFAULT_MAX_DETECTIONS_PER_FILE,
+    DEFAULT_MAX_FILE_SIZE, DEFAULT_PATTERN,
+};
+
+const AFTER_HELP: &str = "\
+EXAMPLES:
+  invisible-char-detector \"**/*.rs\"
+  invisible-char-detector \"src/**/*.ts\" --json
+  invisible-char-detector \"**/*.js\" --verbose
+  invisible-char-detector \"**/*.tsx\" --scan-bundles
+  invisible-char-detector scan-history --since v1.0.0
+
+DETECTS:
+  • Zero-width / joiners (U+200B, U+200C, U+200D, U+2060, U+FEFF; a leading byte order
+    mark is reported as info)
+  • Bidirectional controls (U+202A–U+202E, U+2066–U+2069), and lines that leave them
+    unterminated (Trojan Source, CVE-2021-42574)
+  • Directional marks (U+200E, U+200F, U+061C)
+  • Variation selectors, with hidden payloads in long runs decoded (U+FE00–U+FE0F,
+    U+E0100–U+E01EF)
+  • Tag characters, with the hidden ASCII decoded (U+E0001, U+E0020–U+E007F)
+  • Ambiguous line terminators (U+0085 NEXT LINE, U+2028, U+2029)
+  • Deprecated format characters (U+17B4, U+17B5, U+180E, U+206A–U+206F)
+  • Arabic and Syriac number signs and marks (U+0600–U+0605, U+06DD, U+070F, U+0890, U+0891,
+    U+08E2; severity warning)
+  • Interlinear annotation characters (U+FFF9–U+FFFB)
+  • Encoding artifacts (U+FFFC, U+FFFD; severity warning)
+  • Hangul fillers (U+115F, U+1160, U+3164, U+FFA0)
+  • Blank-rendering characters (U+00AD SOFT HYPHEN, U+2800 BRAILLE PATTERN BLANK)
+  • Non-ASCII whitespace (U+00A0, U+1680, U+2000–U+200A, U+202F, U+205F, U+3000)
+  • Combining mark floods (zalgo text; see --combining-threshold)
+  • Text not in NFC / NFKC (opt-in; see --check-normalization)
+  • Identifiers mixing scripts, e.g. Latin and Cyrillic (see --no-mixed-scripts)
+  • Identifiers that look the same as another once invisible characters are stripped
+    (opt-in; see --lookalike-identifiers)
+  • Homoglyphs of ASCII letters and digits (opt-in; see --confusables)
+  • Any other format (Cf) character (opt-in; see --all-format-chars)
+  • Code points in ranges given on the command line (opt-in; see --detect-range)
+  • Characters from scripts outside a policy (opt-in; see --allow-scripts)
+  • Any non-ASCII character (opt-in; see --ascii-only)
+  • Escaped invisible characters such as \"\\u200B\", &zwnj;, or %E2%80%8B (opt-in; see
+    --include-escapes)
+  • Any of these characters in file and directory names (e.g. invoice<U+202E>gpj.exe)
+  • Any of these characters in keys and variable names in .env, INI, YAML, and shell
+    files, which makes a different name than the one shown (ICD-KEY001)
+  • Private Use Area characters
+  • Suspicious control characters
+  • Overlong lines and files saturated with suspicious characters, as info (opt-in; see
+    --warn-line-length and --warn-file-detections)
+
+EXIT CODES:
+  0  No suspicious characters found
+  1  Suspicious characters detected (fail in CI)
+  2  Operational error (invalid pattern or option, read failure with --fail-on-skip)";
+
+/// Invisible Character Detector - Find suspicious Unicode in code
+#[derive(Debug, Parser)]
+#[command(
+    name = "invisible-char-detector",
+    version,
+    args_conflicts_with_subcommands = true,
+    after_help = AFTER_HELP
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Options for the default `scan` subcommand.
+    #[command(flatten)]
+    pub scan: ScanArgs,
+
+    /// Least severe message printed on stderr: debug, info, warn, or error (default info; warn
+    /// with --quiet, debug with --verbose)
+    #[arg(long, global = true, env = "ICD_LOG_LEVEL", value_name = "LEVEL")]
+    pub log_level: Option<logging::Level>,
+
+    /// Write the messages on stderr as JSON objects, one per line, for log collectors
+    #[arg(long, global = true, env = "ICD_LOG_JSON", value_parser = BoolishValueParser::new())]
+    pub log_json: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Scan files matching a glob pattern (the default subcommand)
+    Scan(ScanArgs),
+
+    /// Scan like `scan`, but print only aggregate counts: suspicious characters per code
+    /// point, rule, and file extension, with how many files each is in. Nothing is kept per
+    /// occurrence, so it stays fast and small on very large trees
+    Stats(ScanArgs),
+
+    /// Report every suspicious character ever added to the git history, with the commit,
+    /// author, and date that introduced it
+    ScanHistory(HistoryArgs),
+
+    /// Scan commit messages, tag annotations, and ref names (branches, tags) for suspicious
+    /// characters
+    ScanGitMetadata(MetadataArgs),
+
+    /// Compare two JSON reports and list new, fixed, and persisting detections (matched by
+    /// file, rule, and code point, so findings that moved lines still match). Exits 1 if
+    /// anything is new
+    DiffReports(DiffReportsArgs),
+
+    /// Add a git pre-commit hook that blocks commits adding suspicious characters (chains
+    /// onto an existing hook)
+    InstallHook {
+        /// Pattern the hook scans
+        #[arg(long, value_name = "GLOB", default_value = "**/*")]
+        pattern: String,
+    },
+
+    /// Remove the pre-commit hook added by install-hook
+    UninstallHook,
+
+    /// Run as a language server over stdio (diagnostics + quick fixes), scanning each document
+    /// with the options given here and the config file, as a scan of its path would
+    Lsp(ScanArgs),
+
+    /// Run an HTTP API: POST /scan?name=FILE with the raw text or file as the body to get
+    /// JSON detections
+    Serve(ServeArgs),
+
+    /// Keep a scanner running on a Unix socket, with the configuration and the results of
+    /// unchanged files in memory, for editor plugins to query through `client` (or the JSON
+    /// lines protocol) instead of starting a process per check. Takes the scan options
+    Daemon(DaemonArgs),
+
+    /// Send a request to a running `daemon`
+    Client(ClientArgs),
+
+    /// Scan a string given on the command line and show it with hidden characters made
+    /// visible
+    Check(CheckArgs),
+
+    /// Screen text before pasting it, such as code from an LLM or a chat: read stdin (or the
+    /// clipboard), check it with the strict profile (escapes, confusables, lookalike
+    /// identifiers, every format character), and print a short verdict. Exits 1 if anything
+    /// was found
+    CheckPaste(CheckPasteArgs),
+
+    /// Fetch a document over HTTP(S) and scan it, e.g. a raw GitHub, gist, or pastebin link
+    /// before copying code from it
+    ScanUrl(ScanUrlArgs),
+
+    /// Download a package from npm, crates.io, or PyPI and scan every file it ships, reported
+    /// as npm:left-pad@1.3.0!package/index.js
+    ScanPackage(ScanPackageArgs),
+
+    /// Write a canary file containing one labeled instance of every detectable character
+    /// category, to verify that CI, editors, and baselines catch findings
+    GenerateSample {
+        /// File to create
+        path: PathBuf,
+
+        /// Overwrite the file if it already exists
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Explain what a code point or rule means, why it's dangerous, and how to fix it
+    Explain {
+        /// A code point (U+202E), a literal character, or a rule ID (ICD-BIDI001)
+        query: String,
+    },
+
+    /// Print the JSON Schema of the `--json` output, to validate reports or generate types
+    /// from
+    Schema {
+        /// JSON layout the schema describes (as `--json-version`)
+        #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=2))]
+        json_version: u32,
+    },
+
+    /// Print a shell completion script (e.g. `completions bash > /etc/bash_completion.d/icd`)
+    Completions {
+        /// Shell to generate completions for
+        shell: Shell,
+    },
+}
+
+/// Parse a human-readable size such as `5MB`, `512KB`, `1GB`, or a plain byte count.
+fn parse_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid size: {}", value))?;
+
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1024,
+        "M" | "MB" | "MIB" => 1024 * 1024,
+        "G" | "GB" | "GIB" => 1024 * 1024 * 1024,
+        _ => return Err(format!("unknown size unit: {}", unit)),
+    };
+
+    number
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("size too large: {}", value))
+}
+
+/// Parse a line range such as `120-180`, or a single line number.
+fn parse_line_range(value: &str) -> Result<RangeInclusive<usize>, String> {
+    let (start, end) = value.split_once('-').unwrap_or((value, value));
+    let line = |text: &str| match text.trim().parse::<usize>() {
+        Ok(0) | Err(_) => Err(format!("invalid line range: {} (expected e.g. 120-180)", value)),
+        Ok(line) => Ok(line),
+    };
+    let (start, end) = (line(start)?, line(end)?);
+    if start > end {
+        return Err(format!("invalid line range: {} (start after end)", value));
+    }
+    Ok(start..=end)
+}
+
+/// Parse a code point range such as `U+2000..U+206F` (or `2000-206F`), or a single code
+/// point.
+fn parse_code_point_range(value: &str) -> Result<RangeInclusive<u32>, String> {
+    let (start, end) = value
+        .split_once("..")
+        .or_else(|| value.split_once('-'))
+        .unwrap_or((value, value));
+    let code = |text: &str| {
+        let text = text.trim();
+        let hex = ["U+", "u+", "0x", "0X"]
+            .iter()
+            .find_map(|prefix| text.strip_prefix(prefix))
+            .unwrap_or(text);
+        match u32::from_str_radix(hex, 16) {
+            Ok(code) if code <= 0x10FFFF => Ok(code),
+            _ => Err(format!(
+                "invalid code point range: {} (expected e.g. U+2000..U+206F)",
+                value
+            )),
+        }
+    };
+    let (start, end) = (code(start)?, code(end)?);
+    if start > end {
+        return Err(format!("invalid code point range: {} (start after end)", value));
+    }
+    Ok(start..=end)
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ScanArgs {
+    /// Pattern to match files (e.g., "**/*.rs"; default: every file under the current
+    /// directory that git doesn't ignore)
+    pub pattern: Option<String>,
+
+    /// More files to scan: given several paths, each one is scanned as named, as with --hook
+    #[arg(
+        value_name = "FILES",
+        conflicts_with_all = ["roots", "patch", "clipboard", "staged", "list_files", "watch", "watch_full"]
+    )]
+    pub files: Vec<String>,
+
+    /// Scan exactly the files given as arguments, as the pre-commit framework passes them: no
+    /// glob expansion, no skipped directories, no .gitignore filtering; a path that does not
+    /// exist is an error. On whenever more than one path is given
+    #[arg(
+        long,
+        env = "ICD_HOOK",
+        value_parser = BoolishValueParser::new(),
+        conflicts_with_all = ["roots", "patch", "clipboard", "staged", "list_files", "watch", "watch_full"]
+    )]
+    pub hook: bool,
+
+    /// Output results as JSON (for CI/tooling integration); same as --format json, and wins
+    /// over ICD_FORMAT
+    #[arg(long)]
+    pub json: bool,
+
+    /// Output format: text, json, short (one file:line:col line per detection, for editor
+    /// quickfix lists and problem matchers), teamcity (service messages), azure (Azure
+    /// Pipelines logging commands), or jsonl (JSON lines, each file's detections after a
+    /// "file" record)
+    #[arg(
+        long,
+        env = "ICD_FORMAT",
+        value_name = "FORMAT",
+        value_enum,
+        default_value_t = OutputFormat::Text
+    )]
+    pub format: OutputFormat,
+
+    /// JSON layout: 1 is an array of detections; 2 is an object with schema_version, tool
+    /// version, the settings used, file counts, duration, and the detections
+    #[arg(
+        long,
+        env = "ICD_JSON_VERSION",
+        value_name = "N",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u32).range(1..=2)
+    )]
+    pub json_version: u32,
+
+    /// After the detections, print counts per code point, rule, and file extension, the top
+    /// 10 files, and the scan duration (with --json, output {"detections": [...], "summary":
+    /// {...}})
+    #[arg(long, env = "ICD_SUMMARY", value_parser = BoolishValueParser::new())]
+    pub summary: bool,
+
+    /// Order files by path, or by risk score (severity, kind of character, and with --context
+    /// where it sits) so the files to review first come first
+    #[arg(long, env = "ICD_SORT_BY", value_name = "ORDER", default_value = "path")]
+    pub sort_by: SortBy,
+
+    /// Text output: list the detections by file, or by code point or category with how many
+    /// there are and in which files and lines, for seeing what to clean up first
+    #[arg(long, env = "ICD_GROUP_BY", value_name = "GROUP", default_value = "file")]
+    pub group_by: GroupBy,
+
+    /// After the detections, print the wall-clock time, bytes scanned, throughput, and the N
+    /// slowest files (default 10), to tune exclusions and --max-file-size (with --json, under
+    /// "timings")
+    #[arg(
+        long,
+        env = "ICD_TIMINGS",
+        value_name = "N",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "10"
+    )]
+    pub timings: Option<usize>,
+
+    /// After the detections, count the scanned files by encoding, byte order mark, and line
+    /// endings (CRLF, LF, mixed), listing each file with --verbose (with --json, under "files")
+    #[arg(long, env = "ICD_FILE_INFO", value_parser = BoolishValueParser::new())]
+    pub file_info: bool,
+
+    /// Print only the counts --summary prints, without the detections (same as the `stats`
+    /// subcommand); the exit code still says whether anything was found
+    #[arg(long)]
+    pub summary_only: bool,
+
+    /// Show details about ignored/unreadable files (they are logged at --log-level debug)
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    /// Print nothing but the results: no "Scanning files matching" banner, and no "No
+    /// suspicious invisible characters detected" when there are none (for clean --json pipes)
+    #[arg(short, long, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Don't show the progress line on stderr (shown only when stderr is a terminal)
+    #[arg(long, env = "ICD_NO_PROGRESS", value_parser = BoolishValueParser::new())]
+    pub no_progress: bool,
+
+    /// Include dist/, build/, out/ directories (useful for bundled extensions)
+    #[arg(long, env = "ICD_SCAN_BUNDLES", value_parser = BoolishValueParser::new())]
+    pub scan_bundles: bool,
+
+    /// Configure the scan for a common audit. vscode-extension: scan dist/ and out/ even if
+    /// git ignores them, the files inside .vsix packages, and package.json, and report
+    /// findings in generated bundles as errors (--scan-bundles --scan-archives
+    /// --no-downgrade-generated)
+    #[arg(long, env = "ICD_PRESET", value_name = "NAME", value_enum)]
+    pub preset: Option<ScanPreset>,
+
+    /// Also skip directories with this name wherever they are, like node_modules/ (repeatable
+    /// or comma-separated; adds to `ignore_dirs` in the config file)
+    #[arg(long, env = "ICD_IGNORE_DIR", value_name = "NAME", value_delimiter = ',')]
+    pub ignore_dir: Vec<String>,
+
+    /// Leave out files .gitattributes marks linguist-generated or linguist-vendored (by default
+    /// their errors are reported as warnings, so first-party findings stand out)
+    #[arg(long, env = "ICD_SKIP_GENERATED", value_parser = BoolishValueParser::new())]
+    pub skip_generated: bool,
+
+    /// Report the errors of linguist-generated and linguist-vendored files as errors
+    #[arg(
+        long,
+        env = "ICD_NO_DOWNGRADE_GENERATED",
+        value_parser = BoolishValueParser::new(),
+        conflicts_with = "skip_generated"
+    )]
+    pub no_downgrade_generated: bool,
+
+    /// Don't skip the built-in directories (node_modules, .git, .cargo, target, .vscode), so
+    /// --ignore-dir and `ignore_dirs` replace them
+    #[arg(long, env = "ICD_NO_DEFAULT_IGNORE_DIRS", value_parser = BoolishValueParser::new())]
+    pub no_default_ignore_dirs: bool,
+
+    /// Exit with code 2 if any files cannot be read (binary files don't count); --json
+    /// --json-version 2 lists them under "skipped" with the reason
+    #[arg(long, env = "ICD_FAIL_ON_SKIP", value_parser = BoolishValueParser::new())]
+    pub fail_on_skip: bool,
+
+    /// Stop at the first file with a suspicious character, report that one character, and
+    /// exit 1: the quickest yes/no answer for hooks and CI gates on large trees
+    #[arg(long, conflicts_with_all = ["watch", "summary_only"])]
+    pub fail_fast: bool,
+
+    /// Show the commit, author, and date that last changed each detection's line, from git
+    /// blame (as `blame` in JSON output)
+    #[arg(long)]
+    pub blame: bool,
+
+    /// Report paths with / as the separator on every platform, so reports from Windows and
+    /// other systems diff cleanly (on Windows they use \ by default)
+    #[arg(long, env = "ICD_FORWARD_SLASHES", value_parser = BoolishValueParser::new())]
+    pub forward_slashes: bool,
+
+    /// Make reports safe to share: paths relative to the repository root, `~` for home
+    /// directories; =hash replaces each path by a stable hash plus its extension. The same file
+    /// gets the same path on every run, so diff-reports still matches findings
+    #[arg(
+        long,
+        env = "ICD_REDACT_PATHS",
+        value_name = "MODE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "strip",
+        conflicts_with_all = ["watch", "watch_full"]
+    )]
+    pub redact_paths: Option<RedactMode>,
+
+    /// Follow symbolic links (loops are detected, each file is scanned once)
+    #[arg(long, env = "ICD_FOLLOW_SYMLINKS", value_parser = BoolishValueParser::new())]
+    pub follow_symlinks: bool,
+
+    /// Also scan the files inside .zip, .vsix, .jar, .whl, .nupkg, .tar, .tar.gz, .tgz, and .crate
+    /// archives, reported as archive.zip!path/inside.js
+    #[arg(long, env = "ICD_SCAN_ARCHIVES", value_parser = BoolishValueParser::new())]
+    pub scan_archives: bool,
+
+    /// Also scan the text of .docx, .xlsx, .pptx, .odt, .ods, .odp, and .pdf documents and
+    /// .eml emails, reported per part (report.docx!word/document.xml), page (spec.pdf!page-3),
+    /// or header and MIME part (mail.eml!headers/Subject, mail.eml!part-2.html) with the
+    /// paragraph or text object as the line number
+    #[arg(long, env = "ICD_SCAN_DOCUMENTS", value_parser = BoolishValueParser::new())]
+    pub scan_documents: bool,
+
+    /// Skip files larger than SIZE (e.g. 5MB, 512KB; default 10MB, 0 = no limit)
+    #[arg(long, env = "ICD_MAX_FILE_SIZE", value_name = "SIZE", value_parser = parse_size)]
+    pub max_file_size: Option<u64>,
+
+    /// Scan with N worker threads (default: one per CPU)
+    #[arg(short, long, env = "ICD_JOBS", value_name = "N")]
+    pub jobs: Option<usize>,
+
+    /// Reuse results for unchanged files from FILE and update it afterwards
+    #[arg(long, env = "ICD_CACHE", value_name = "FILE")]
+    pub cache: Option<PathBuf>,
+
+    /// Write copies of the files with detections under DIR, with invisible characters removed
+    /// and non-ASCII spaces replaced, plus a manifest.json of the changes; the originals are
+    /// left untouched
+    #[arg(
+        long,
+        env = "ICD_CLEAN_TO",
+        value_name = "DIR",
+        conflicts_with_all = ["patch", "watch", "watch_full", "summary_only", "fail_fast"]
+    )]
+    pub clean_to: Option<PathBuf>,
+
+    /// When the scan fails, POST a JSON summary of the findings to URL
+    #[arg(long, env = "ICD_NOTIFY_WEBHOOK", value_name = "URL")]
+    pub notify_webhook: Option<String>,
+
+    /// Send a different body to --notify-webhook: `slack` for a Slack message, or a JSON file
+    /// with {{total}}, {{files}}, {{scanned_files}}, {{pattern}}, {{top_files}}, {{summary}},
+    /// and {{detections}} placeholders
+    #[arg(
+        long,
+        env = "ICD_WEBHOOK_TEMPLATE",
+        value_name = "TEMPLATE",
+        requires = "notify_webhook",
+        value_parser = webhook::parse_template
+    )]
+    pub webhook_template: Option<webhook::Template>,
+
+    /// Include the detections in the default --notify-webhook body
+    #[arg(
+        long,
+        env = "ICD_WEBHOOK_FULL_REPORT",
+        requires = "notify_webhook",
+        value_parser = BoolishValueParser::new()
+    )]
+    pub webhook_full_report: bool,
+
+    /// Also write the findings as Markdown to the job summary of a GitHub Actions run (the
+    /// file $GITHUB_STEP_SUMMARY names)
+    #[arg(long, env = "ICD_STEP_SUMMARY", value_parser = BoolishValueParser::new())]
+    pub step_summary: bool,
+
+    /// Only report lines added or modified since git revision BASE
+    #[arg(long, value_name = "BASE")]
+    pub diff: Option<String>,
+
+    /// Read a unified diff from stdin (`git diff | invisible-char-detector --patch`) and scan
+    /// only the lines it adds, reported at their file and line in the new version. Works with
+    /// the diff of any version control system; byte_offset counts bytes of the patch
+    #[arg(
+        long,
+        conflicts_with_all = ["pattern", "diff", "staged", "lines", "watch", "watch_full"]
+    )]
+    pub patch: bool,
+
+    /// Scan the text on the system clipboard, showing each finding in its line
+    #[arg(
+        long,
+        conflicts_with_all = ["pattern", "patch", "diff", "staged", "lines", "watch", "watch_full", "clean_to"]
+    )]
+    pub clipboard: bool,
+
+    /// Scan the text on stdin as if it were the file NAME, which picks the config file's
+    /// presets and overrides and names it in the report (for editors that lint an unsaved
+    /// buffer; combine with --lines to check only what is on screen)
+    #[arg(
+        long,
+        value_name = "NAME",
+        conflicts_with_all = [
+            "pattern", "files", "hook", "roots", "patch", "clipboard", "diff", "staged",
+            "list_files", "watch", "watch_full", "clean_to"
+        ]
+    )]
+    pub stdin_filename: Option<String>,
+
+    /// With --clipboard, put the text back with invisible characters removed and non-ASCII
+    /// spaces replaced
+    #[arg(long, requires = "clipboard")]
+    pub fix: bool,
+
+    /// Scan only lines START through END of the file (e.g. 120-180), for editors that lint
+    /// the visible part of a large file and tools that check one hunk. The pattern must be a
+    /// single file (or give --stdin-filename); positions are still counted from the start of
+    /// the file
+    #[arg(long, value_name = "START-END", value_parser = parse_line_range)]
+    pub lines: Option<RangeInclusive<usize>>,
+
+    /// Scan the pattern under DIR, which may be repeated to scan several independent trees
+    /// (e.g. --root frontend --root backend) and report a result for each
+    #[arg(
+        long = "root",
+        env = "ICD_ROOT",
+        value_name = "DIR",
+        value_delimiter = ',',
+        conflicts_with_all = ["patch", "clipboard", "lines", "watch", "watch_full"]
+    )]
+    pub roots: Vec<String>,
+
+    /// Leave out files ignored by .gitignore and git's other exclude files (tracked files are
+    /// always scanned); on when no pattern is given
+    #[arg(long, env = "ICD_RESPECT_GITIGNORE", value_parser = BoolishValueParser::new())]
+    pub respect_gitignore: bool,
+
+    /// Scan the content staged in the git index (for pre-commit hooks)
+    #[arg(long)]
+    pub staged: bool,
+
+    /// Scan nothing: list the files a scan would read and those it would skip, with the
+    /// reason (glob miss, .gitignore rule, ignored directory, size cap, binary, ...)
+    #[arg(
+        long,
+        conflicts_with_all = ["patch", "clipboard", "staged", "lines", "watch", "watch_full"]
+    )]
+    pub list_files: bool,
+
+    /// Keep running and rescan matching files whenever they change
+    #[arg(long)]
+    pub watch: bool,
+
+    /// Like --watch, but also re-print the full report after each change
+    #[arg(long)]
+    pub watch_full: bool,
+
+    /// Flag N or more combining marks stacked on one character (zalgo text; default 5,
+    /// 0 = off)
+    #[arg(long, env = "ICD_COMBINING_THRESHOLD", value_name = "N")]
+    pub combining_threshold: Option<usize>,
+
+    /// Report lines longer than N bytes as info, a sign of minified or obfuscated code
+    #[arg(long, env = "ICD_WARN_LINE_LENGTH", value_name = "N")]
+    pub warn_line_length: Option<usize>,
+
+    /// Report files with N or more suspicious characters as info, a sign of an obfuscated
+    /// payload
+    #[arg(long, env = "ICD_WARN_FILE_DETECTIONS", value_name = "N")]
+    pub warn_file_detections: Option<usize>,
+
+    /// Flag text that is not in NFC; with =nfkc, also flag compatibility characters such as
+    /// ligatures and fullwidth letters
+    #[arg(
+        long,
+        env = "ICD_CHECK_NORMALIZATION",
+        value_name = "FORM",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "nfc"
+    )]
+    pub check_normalization: Option<NormalizationForm>,
+
+    /// Flag characters from other scripts that look like ASCII letters or digits (Cyrillic а,
+    /// Greek ο, ...), per the Unicode confusables data
+    #[arg(long, env = "ICD_CONFUSABLES", value_parser = BoolishValueParser::new())]
+    pub confusables: bool,
+
+    /// Flag every Unicode format (Cf) character, including ones without a specific rule
+    /// (invisible math operators, Arabic number signs, Egyptian hieroglyph controls, ...)
+    #[arg(long, env = "ICD_ALL_FORMAT_CHARS", value_parser = BoolishValueParser::new())]
+    pub all_format_chars: bool,
+
+    /// Also flag the code points in this range that no rule covers, as ICD-RANGE001 with
+    /// severity warning (e.g. U+1D173..U+1D17A, or one code point; repeatable or
+    /// comma-separated), for a one-off investigation without a config change
+    #[arg(
+        long,
+        env = "ICD_DETECT_RANGE",
+        value_name = "RANGE",
+        value_delimiter = ',',
+        value_parser = parse_code_point_range
+    )]
+    pub detect_range: Vec<RangeInclusive<u32>>,
+
+    /// Don't flag identifiers that mix scripts (e.g. Latin `p` with Cyrillic `а`)
+    #[arg(long, env = "ICD_NO_MIXED_SCRIPTS", value_parser = BoolishValueParser::new())]
+    pub no_mixed_scripts: bool,
+
+    /// Flag identifiers that look the same as another one in the same file once invisible
+    /// characters are stripped (is_admin next to is_adm<U+200B>in)
+    #[arg(long, env = "ICD_LOOKALIKE_IDENTIFIERS", value_parser = BoolishValueParser::new())]
+    pub lookalike_identifiers: bool,
+
+    /// Flag every non-ASCII character outside these scripts (e.g. Latin,Common,Greek);
+    /// overrides `allow_scripts` in the config file
+    #[arg(
+        long,
+        env = "ICD_ALLOW_SCRIPTS",
+        value_name = "SCRIPTS",
+        value_delimiter = ',',
+        value_parser = scripts::parse_script
+    )]
+    pub allow_scripts: Option<Vec<Script>>,
+
+    /// Don't flag ZWJ (U+200D) and VS16 (U+FE0F) inside well-formed emoji sequences such as
+    /// 👩 + ZWJ + 💻 or ❤ + VS16; stray joiners and selectors are still reported. On by default
+    /// in documentation (*.md, *.txt, *.rst, *.adoc) through the built-in presets
+    #[arg(long, env = "ICD_ALLOW_EMOJI", value_parser = BoolishValueParser::new())]
+    pub allow_emoji: bool,
+
+    /// List the detections that allow_emoji, prose, require_context, ignore_in_context, or
+    /// --diff kept out of the report, each with the setting that dropped it, for auditing
+    /// suppressions. They are listed apart and don't count toward the findings or exit code
+    #[arg(
+        long,
+        env = "ICD_SHOW_SUPPRESSED",
+        value_parser = BoolishValueParser::new(),
+        conflicts_with_all = ["watch", "watch_full"]
+    )]
+    pub show_suppressed: bool,
+
+    /// Flag every non-ASCII character, for repositories with an ASCII-only policy: the
+    /// characters above keep their severity, all others are reported as info
+    #[arg(long, env = "ICD_ASCII_ONLY", value_parser = BoolishValueParser::new())]
+    pub ascii_only: bool,
+
+    /// Also flag escape sequences that produce invisible characters, such as "\u200B",
+    /// \u{202E}, &#8203;, or &zwnj; in string literals and markup, and %E2%80%8B in URLs
+    #[arg(long, env = "ICD_INCLUDE_ESCAPES", value_parser = BoolishValueParser::new())]
+    pub include_escapes: bool,
+
+    /// Classify each detection in Rust, JavaScript, TypeScript, Python, and Go files as being
+    /// in a string literal, comment, identifier, or other code
+    #[arg(long, env = "ICD_CONTEXT", value_parser = BoolishValueParser::new())]
+    pub context: bool,
+
+    /// Report only detections in these contexts (e.g. identifier,string), so comments can be
+    /// tolerated; overrides `require_context` in the config file. Files in languages --context
+    /// doesn't parse are reported in full
+    #[arg(long, env = "ICD_REQUIRE_CONTEXT", value_name = "CONTEXTS", value_delimiter = ',')]
+    pub require_context: Option<Vec<SyntaxContext>>,
+
+    /// Show the line holding each character and the lines around it, with hidden characters
+    /// spelled <U+XXXX> (as `snippet` in JSON output)
+    #[arg(long)]
+    pub show_context: bool,
+
+    /// Show an xxd-style hex dump of the N bytes on each side of each character (as
+    /// `hex_context` in JSON output), to tell a real U+200B from mojibake
+    #[arg(long, value_name = "N")]
+    pub hex_context: Option<usize>,
+
+    /// Scan files that aren't valid UTF-8 instead of skipping them as unreadable, decoding
+    /// stray bytes as Windows-1252 (detections get `"encoding": "windows-1252"` in JSON output)
+    #[arg(long, env = "ICD_LOSSY", value_parser = BoolishValueParser::new())]
+    pub lossy: bool,
+
+    /// Report a byte order mark at the start of a file as an error (ICD-BOM001 is info by
+    /// default and doesn't fail the scan), for repositories with a no-BOM policy
+    #[arg(long, env = "ICD_FORBID_BOM", value_parser = BoolishValueParser::new())]
+    pub forbid_bom: bool,
+
+    /// Report each invalid UTF-8 sequence (overlong encodings, CESU-8 surrogates, truncated
+    /// sequences, stray bytes) with its offset and bytes as ICD-UTF8001, and scan the rest of
+    /// the file, instead of skipping it as unreadable; with --lossy the bad bytes are also
+    /// scanned as Windows-1252
+    #[arg(long, env = "ICD_REPORT_INVALID_UTF8", value_parser = BoolishValueParser::new())]
+    pub report_invalid_utf8: bool,
+
+    /// How text output spells hidden characters in file names and --show-context lines:
+    /// <U+200B>, ⟨ZWSP⟩, or cat -v style M-bM-^@M-^K
+    #[arg(
+        long,
+        env = "ICD_ESCAPE_STYLE",
+        value_name = "STYLE",
+        value_enum,
+        default_value_t = EscapeStyle::UnicodeNotation
+    )]
+    pub escape_style: EscapeStyle,
+
+    /// Color text output: auto colors a terminal unless NO_COLOR or ICD_NO_COLOR is set
+    #[arg(
+        long,
+        env = "ICD_COLOR",
+        value_name = "WHEN",
+        value_enum,
+        default_value_t = ColorMode::Auto
+    )]
+    pub color: ColorMode,
+
+    /// Count U+2028 LINE SEPARATOR, U+2029 PARAGRAPH SEPARATOR, and U+0085 NEXT LINE as line
+    /// breaks in reported positions, as VS Code and JetBrains editors display them (\n, \r\n,
+    /// and a lone \r always are). Not with --blame or --diff, whose lines are git's, which
+    /// counts only \n
+    #[arg(
+        long,
+        env = "ICD_TREAT_LS_AS_NEWLINE",
+        value_parser = BoolishValueParser::new(),
+        conflicts_with_all = ["blame", "diff"]
+    )]
+    pub treat_ls_as_newline: bool,
+
+    /// Report every character of a run such as 10,000 back-to-back no-break spaces separately,
+    /// instead of as one detection with a count
+    #[arg(long, env = "ICD_NO_COLLAPSE_RUNS", value_parser = BoolishValueParser::new())]
+    pub no_collapse_runs: bool,
+
+    /// Stop listing a file's detections after N and note how many more it has (default 100,
+    /// 0 = no limit)
+    #[arg(long, env = "ICD_MAX_DETECTIONS_PER_FILE", value_name = "N")]
+    pub max_detections_per_file: Option<usize>,
+
+    /// Read options from FILE (default: .icd.toml in the current directory, if present)
+    #[arg(long, env = "ICD_CONFIG", value_name = "FILE")]
+    pub config: Option<PathBuf>,
+}
+
+impl ScanArgs {
+    /// Build the scan configuration. Without a pattern, every file under the current directory
+    /// that git doesn't ignore is scanned.
+    pub fn into_config(self) -> ScanConfig {
+        let vscode = self.preset == Some(ScanPreset::VscodeExtension);
+        let mut ignore_dirs = self.ignore_dir;
+        if let Some(preset) = self.preset {
+            ignore_dirs.extend(preset.ignore_dirs().iter().map(|dir| dir.to_string()));
+        }
+        let files = (self.hook || !self.files.is_empty())
+            .then(|| self.pattern.iter().chain(&self.files).cloned().collect::<Vec<_>>());
+        ScanConfig {
+            pattern: match &self.pattern {
+                _ if files.is_some() => files.as_deref().unwrap_or_default().join(" "),
+                Some(pattern) => pattern.clone(),
+                None if self.patch => "-".to_string(),
+                None if self.clipboard => clipboard::CLIPBOARD_NAME.to_string(),
+                None => self
+                    .stdin_filename
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_PATTERN.to_string()),
+            },
+            roots: self.roots,
+            files,
+            preset: self.preset,
+            list_files: self.list_files,
+            // Extension bundles are build output, which .gitignore usually lists
+            respect_gitignore: self.respect_gitignore
+                || (self.pattern.is_none()
+                    && !self.patch
+                    && !self.clipboard
+                    && self.stdin_filename.is_none()
+                    && !vscode),
+            format: if self.json {
+                OutputFormat::Json
+            } else {
+                self.format
+            },
+            summary: self.summary,
+            sort_by: self.sort_by,
+            group_by: self.group_by,
+            timings: self.timings,
+            file_info: self.file_info,
+            json_version: self.json_version,
+            census: self.summary_only,
+            verbose: self.verbose,
+            quiet: self.quiet,
+            progress: !self.no_progress && !self.quiet && progress::available(),
+            fail_on_skip: self.fail_on_skip,
+            fail_fast: self.fail_fast,
+            blame: self.blame,
+            forward_slashes: self.forward_slashes,
+            redact_paths: self.redact_paths,
+            scan_bundles: self.scan_bundles || vscode,
+            generated: if self.skip_generated {
+                GeneratedFiles::Skip
+            } else if self.no_downgrade_generated || vscode {
+                GeneratedFiles::Scan
+            } else {
+                GeneratedFiles::Downgrade
+            },
+            ignore_dirs: IgnoreDirs {
+                defaults: !self.no_default_ignore_dirs,
+                extra: ignore_dirs,
+            },
+            follow_symlinks: self.follow_symlinks,
+            color: self.color.enabled(),
+            scan_archives: self.scan_archives || vscode,
+            scan_documents: self.scan_documents,
+            max_file_size: match self.max_file_size {
+                None => Some(DEFAULT_MAX_FILE_SIZE),
+                Some(0) => None,
+                Some(bytes) => Some(bytes),
+            },
+            jobs: self.jobs.unwrap_or(0),
+            cache: self.cache,
+            clean_to: self.clean_to,
+            notify_webhook: self.notify_webhook,
+            step_summary: self.step_summary,
+            webhook_template: self.webhook_template,
+            webhook_full_report: self.webhook_full_report,
+            patch: self.patch,
+            clipboard: self.clipboard,
+            stdin_filename: self.stdin_filename,
+            fix: self.fix,
+            diff_base: self.diff,
+            staged: self.staged,
+            watch: self.watch || self.watch_full,
+            watch_full: self.watch_full,
+            detect: DetectOptions {
+                combining_threshold: self
+                    .combining_threshold
+                    .unwrap_or(DetectOptions::default().combining_threshold),
+                normalization: self.check_normalization,
+                confusables: self.confusables,
+                all_format_chars: self.all_format_chars,
+                detect_ranges: self.detect_range,
+                mixed_scripts: !self.no_mixed_scripts,
+                lookalike_identifiers: self.lookalike_identifiers,
+                allowed_scripts: self.allow_scripts,
+                allow_emoji: self.allow_emoji,
+                ascii_only: false,
+                prose: false,
+                include_escapes: self.include_escapes,
+                syntax_context: self.context,
+                context_policy: ContextPolicy {
+                    require: self.require_context,
+                    ..ContextPolicy::default()
+                },
+                show_suppressed: self.show_suppressed,
+                // Pasted text has no file to open and look at
+                show_context: self.show_context || self.clipboard,
+                escape_style: self.escape_style,
+                unicode_line_breaks: self.treat_ls_as_newline,
+                collapse_runs: !self.no_collapse_runs,
+                max_detections: match self.max_detections_per_file {
+                    None => Some(DEFAULT_MAX_DETECTIONS_PER_FILE),
+                    Some(0) => None,
+                    Some(max) => Some(max),
+                },
+                hex_context: self.hex_context.unwrap_or(0),
+                lossy: self.lossy,
+                forbid_bom: self.forbid_bom,
+                report_invalid_utf8: self.report_invalid_utf8,
+                lines: self.lines,
+                warn_line_length: self.warn_line_length,
+                warn_file_detections: self.warn_file_detections,
+            },
+            config_file: self.config,
+            prose: None,
+            allow_emoji: self.allow_emoji.then_some(true),
+            ascii_only: self.ascii_only.then_some(true),
+            overrides: Vec::new(),
+            presets: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct HistoryArgs {
+    /// Only walk commits after this revision
+    #[arg(long, value_name = "REV")]
+    pub since: Option<String>,
+
+    /// Output results as JSON
+    #[arg(long)]
+    pub json: bool,
+
+    /// Include dist/, build/, out/ directories
+    #[arg(long)]
+    pub scan_bundles: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct MetadataArgs {
+    /// Only check the messages of commits in this revision range (e.g. v1.0.0..HEAD); tags
+    /// and ref names are always checked
+    #[arg(long, value_name = "RANGE")]
+    pub range: Option<String>,
+
+    /// Output results as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct DiffReportsArgs {
+    /// The earlier report (any --json output)
+    pub old: PathBuf,
+
+    /// The later report
+    pub new: PathBuf,
+
+    /// Output {"new": [...], "fixed": [...], "persisting": [...]} as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct CheckArgs {
+    /// Text to check (quote it so the shell keeps it intact)
+    #[arg(required_unless_present = "hex", conflicts_with = "hex")]
+    pub text: Option<String>,
+
+    /// Check the UTF-8 bytes given as hex instead (e.g. e2808b; spaces are ignored)
+    #[arg(long, value_name = "HEX")]
+    pub hex: Option<String>,
+
+    /// Output results as JSON
+    #[arg(long)]
+    pub json: bool,
+
+    /// Color text output: auto colors a terminal unless NO_COLOR is set
+    #[arg(long, value_name = "WHEN", value_enum, default_value_t = ColorMode::Auto)]
+    pub color: ColorMode,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct CheckPasteArgs {
+    /// Read the text from the clipboard instead of stdin
+    #[arg(long)]
+    pub clipboard: bool,
+
+    /// Print the text with the hidden characters removed instead of the verdict (which goes
+    /// to stderr); with --clipboard, put it back on the clipboard. Without it, a terminal is
+    /// asked whether to
+    #[arg(short, long)]
+    pub clean: bool,
+
+    /// Output the verdict and detections as JSON
+    #[arg(long, conflicts_with = "clean")]
+    pub json: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct DaemonArgs {
+    /// Socket to listen on (default: invisible-char-detector.sock in $XDG_RUNTIME_DIR, or in
+    /// the temporary directory)
+    #[arg(long, env = "ICD_SOCKET", value_name = "PATH")]
+    pub socket: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub scan: ScanArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ClientArgs {
+    /// Socket of the daemon (default: as for `daemon`)
+    #[arg(long, global = true, env = "ICD_SOCKET", value_name = "PATH")]
+    pub socket: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: ClientCommand,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum ClientCommand {
+    /// Scan files through the daemon; exits 1 if anything was found
+    Scan {
+        /// Files to scan
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+
+        /// Scan the text on stdin as if it were the (single) file, e.g. an unsaved buffer
+        #[arg(long)]
+        stdin: bool,
+
+        /// Output the daemon's responses as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Color text output: auto colors a terminal unless NO_COLOR is set
+        #[arg(long, value_name = "WHEN", value_enum, default_value_t = ColorMode::Auto)]
+        color: ColorMode,
+    },
+
+    /// Check that the daemon is running
+    Ping,
+
+    /// Make the daemon re-read its config file and forget the results it keeps
+    Reload,
+
+    /// Stop the daemon
+    Stop,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ScanUrlArgs {
+    /// http:// or https:// URL of the document
+    pub url: String,
+
+    /// Refuse documents larger than SIZE (e.g. 5MB, 512KB; default 10MB, 0 = no limit)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub max_size: Option<u64>,
+
+    /// Also accept this Content-Type (repeatable; text/* allows any text type, */* anything).
+    /// Text, JSON, JavaScript, XML, TOML, YAML, shell, and Python are accepted by default
+    #[arg(long, value_name = "TYPE")]
+    pub allow_type: Vec<String>,
+
+    /// Output results as JSON
+    #[arg(long)]
+    pub json: bool,
+
+    /// Color text output: auto colors a terminal unless NO_COLOR is set
+    #[arg(long, value_name = "WHEN", value_enum, default_value_t = ColorMode::Auto)]
+    pub color: ColorMode,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ScanPackageArgs {
+    /// REGISTRY:NAME[@VERSION] with registry npm, crates, or pypi (e.g. npm:left-pad@1.3.0,
+    /// crates:serde, pypi:requests@2.31.0); the latest release without a version
+    #[arg(value_name = "PACKAGE", value_parser = package::parse_spec)]
+    pub package: package::PackageSpec,
+
+    /// Refuse packages, and skip files in them, larger than SIZE (e.g. 50MB; default 10MB,
+    /// 0 = no limit)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub max_size: Option<u64>,
+
+    /// Output results as JSON
+    #[arg(long)]
+    pub json: bool,
+
+    /// Color text output: auto colors a terminal unless NO_COLOR is set
+    #[arg(long, value_name = "WHEN", value_enum, default_value_t = ColorMode::Auto)]
+    pub color: ColorMode,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ServeArgs {
+    /// Port to listen on
+    #[arg(long, default_value_t = 8080)]
+    pub port: u16,
+
+    /// Address to bind to
+    #[arg(long, value_name = "ADDR", default_value = "127.0.0.1")]
+    pub bind: String,
+
+    /// Reject request bodies larger than SIZE (default 10MB)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub max_body_size: Option<u64>,
+
+    /// Handle requests with N threads (default: one per CPU)
+    #[arg(short, long, value_name = "N")]
+    pub jobs: Option<usize>,
+}
@@ -0,0 +1,117 @@
+//! `--clipboard`: scan the system clipboard, for checking code copied from a web page or a
+//! chat before pasting it anywhere.
+//!
+//! The clipboard is read and written through the platform's own tools rather than a
+//! windowing library: `pbpaste`/`pbcopy` on macOS, PowerShell on Windows, and `wl-paste`/
+//! `wl-copy`, `xclip`, or `xsel` elsewhere, whichever is installed.
+
+use std::io::{ErrorKind, Write};
+use std::process::{Command, Stdio};
+
+/// Name detections in the clipboard are reported under.
+pub const CLIPBOARD_NAME: &str = "<clipboard>";
+
+/// Commands that print the clipboard, in order of preference.
+fn read_commands() -> Vec<(&'static str, Vec<&'static str>)> {
+    if cfg!(target_os = "macos") {
+        vec![("pbpaste", vec![])]
+    } else if cfg!(windows) {
+        // The console code page would mangle everything outside it
+        vec![(
+            "powershell",
+            vec![
+                "-NoProfile",
+                "-Command",
+                "[Console]::OutputEncoding = [Text.Encoding]::UTF8; Get-Clipboard -Raw",
+            ],
+        )]
+    } else {
+        let mut commands = Vec::new();
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            commands.push(("wl-paste", vec!["--no-newline"]));
+        }
+        commands.push(("xclip", vec!["-selection", "clipboard", "-out"]));
+        commands.push(("xsel", vec!["--clipboard", "--output"]));
+        commands
+    }
+}
+
+/// Commands that set the clipboard to their input, in order of preference.
+fn write_commands() -> Vec<(&'static str, Vec<&'static str>)> {
+    if cfg!(target_os = "macos") {
+        vec![("pbcopy", vec![])]
+    } else if cfg!(windows) {
+        vec![(
+            "powershell",
+            vec![
+                "-NoProfile",
+                "-Command",
+                "[Console]::InputEncoding = [Text.Encoding]::UTF8; \
+                 Set-Clipboard -Value ([Console]::In.ReadToEnd())",
+            ],
+        )]
+    } else {
+        let mut commands = Vec::new();
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            commands.push(("wl-copy", vec![]));
+        }
+        commands.push(("xclip", vec!["-selection", "clipboard", "-in"]));
+        commands.push(("xsel", vec!["--clipboard", "--input"]));
+        commands
+    }
+}
+
+/// The error when none of `commands` is installed.
+fn no_tool(commands: &[(&str, Vec<&str>)]) -> String {
+    let names: Vec<&str> = commands.iter().map(|(name, _)| *name).collect();
+    format!("no clipboard tool found (tried {})", names.join(", "))
+}
+
+/// The text on the clipboard.
+pub fn read() -> Result<String, String> {
+    let commands = read_commands();
+    for (program, args) in &commands {
+        let output = match Command::new(program).args(args).output() {
+            Ok(output) => output,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(format!("{}: {}", program, e)),
+        };
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("{}: {}", program, stderr.trim()));
+        }
+        return String::from_utf8(output.stdout)
+            .map_err(|_| "the clipboard does not hold text".to_string());
+    }
+    Err(no_tool(&commands))
+}
+
+/// Replace the text on the clipboard.
+pub fn write(text: &str) -> Result<(), String> {
+    let commands = write_commands();
+    for (program, args) in &commands {
+        // xclip and xsel stay around to serve the selection; don't let them hold our output
+        let mut child = match Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(format!("{}: {}", program, e)),
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(text.as_bytes())
+                .map_err(|e| format!("{}: {}", program, e))?;
+        }
+        let status = child.wait().map_err(|e| format!("{}: {}", program, e))?;
+        if !status.success() {
+            return Err(format!("{} exited with {}", program, status));
+        }
+        return Ok(());
+    }
+    Err(no_tool(&commands))
+}
@@ -0,0 +1,306 @@
+//! Configuration file (`.icd.toml`): project-wide defaults for detection options, with
+//! per-path overrides.
+//!
+//! ```toml
+//! allow_scripts = ["Latin", "Common", "Greek"]
+//!
+//! [[override]]
+//! paths = ["docs/ru/**"]
+//! allow_scripts = ["Latin", "Common", "Cyrillic"]
+//!
+//! [[override]]
+//! paths = ["locales/**/*.json"]
+//! prose = true
+//!
+//! # With --context classification: no-break spaces are fine in comments
+//! [ignore_in_context]
+//! ICD-WS001 = ["comment"]
+//!
+//! # Replaces the built-in preset for *.txt
+//! [preset.txt]
+//! allow_emoji = false
+//!
+//! # Also skip these directories, on top of node_modules/, .git/, target/, ...
+//! ignore_dirs = ["vendor", ".venv", "coverage", "third_party"]
+//! ```
+//!
+//! Presets are defaults by file extension: built in for documentation and translation files
+//! (see [`builtin_presets`]), replaceable per extension with `[preset.EXT]`, and turned off
+//! with `presets = false`. The top level of the file and the command line take precedence
+//! over a preset, and overrides over both. Overrides apply in file order, so a later matching
+//! override wins. Options given on the command line take precedence over the file.
+//!
+//! `ignore_dirs` adds to the directories every scan skips; `default_ignore_dirs = false`
+//! drops the built-in ones, so the list replaces them.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use clap::ValueEnum;
+use glob::Pattern;
+use serde::Deserialize;
+use unicode_script::Script;
+
+use crate::context::SyntaxContext;
+use crate::{rules, scripts, walk};
+
+/// Config file looked up in the current directory when `--config` is not given.
+pub const DEFAULT_CONFIG_FILE: &str = ".icd.toml";
+
+/// The built-in presets, by lowercase extension. Code is strict everywhere; documentation
+/// may use emoji (and Markdown is prose, where no-break spaces next to punctuation are fine);
+/// translations are prose.
+pub fn builtin_presets() -> BTreeMap<String, PathOptions> {
+    let docs = PathOptions {
+        allow_emoji: Some(true),
+        ..PathOptions::default()
+    };
+    let markdown = PathOptions {
+        prose: Some(true),
+        allow_emoji: Some(true),
+        ..PathOptions::default()
+    };
+    let translations = PathOptions {
+        prose: Some(true),
+        ..PathOptions::default()
+    };
+    let mut presets = BTreeMap::new();
+    for ext in ["md", "markdown"] {
+        presets.insert(ext.to_string(), markdown.clone());
+    }
+    for ext in ["txt", "rst", "adoc"] {
+        presets.insert(ext.to_string(), docs.clone());
+    }
+    for ext in ["po", "pot", "properties", "resx", "xlf", "xliff", "strings", "arb"] {
+        presets.insert(ext.to_string(), translations.clone());
+    }
+    presets
+}
+
+/// A named set of scan options for a common audit (`--preset`), unlike the presets above,
+/// which are detection options by file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ScanPreset {
+    /// A VS Code extension: the bundled JS in dist/ and out/ (even if git ignores it), the
+    /// contents of .vsix packages, and package.json with its contribution strings, with
+    /// findings in generated bundles kept as errors
+    VscodeExtension,
+}
+
+impl ScanPreset {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ScanPreset::VscodeExtension => "vscode-extension",
+        }
+    }
+
+    /// Directories the preset skips on top of the built-in ones.
+    pub fn ignore_dirs(self) -> &'static [&'static str] {
+        match self {
+            // The copies of VS Code that extension tests download
+            ScanPreset::VscodeExtension => &[".vscode-test"],
+        }
+    }
+}
+
+/// The lowercase extension of `path`, the key of its preset.
+pub fn preset_key(path: &str) -> Option<String> {
+    Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+}
+
+/// Options that can be set for the whole project or for some paths.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawOptions {
+    allow_scripts: Option<Vec<String>>,
+    prose: Option<bool>,
+    allow_emoji: Option<bool>,
+    ascii_only: Option<bool>,
+    require_context: Option<Vec<SyntaxContext>>,
+    ignore_in_context: Option<BTreeMap<String, Vec<SyntaxContext>>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawOverride {
+    paths: Vec<String>,
+
+    #[serde(flatten)]
+    options: RawOptions,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawConfig {
+    #[serde(flatten)]
+    options: RawOptions,
+
+    #[serde(default, rename = "override")]
+    overrides: Vec<RawOverride>,
+
+    /// `false` turns off the built-in presets.
+    presets: Option<bool>,
+
+    #[serde(default)]
+    ignore_dirs: Vec<String>,
+
+    /// `false` turns off the built-in ignored directories.
+    default_ignore_dirs: Option<bool>,
+
+    #[serde(default)]
+    preset: BTreeMap<String, RawOptions>,
+}
+
+/// Validated options from the config file; `None` leaves the built-in default in place.
+#[derive(Debug, Clone, Default)]
+pub struct PathOptions {
+    /// Scripts characters may come from (`allow_scripts`).
+    pub allow_scripts: Option<Vec<Script>>,
+
+    /// Treat files as prose, where no-break spaces next to punctuation are fine (`prose`).
+    pub prose: Option<bool>,
+
+    /// Accept the ZWJ and U+FE0F of well-formed emoji sequences (`allow_emoji`).
+    pub allow_emoji: Option<bool>,
+
+    /// Report every non-ASCII character (`ascii_only`).
+    pub ascii_only: Option<bool>,
+
+    /// Report only detections in these syntactic contexts (`require_context`).
+    pub require_context: Option<Vec<SyntaxContext>>,
+
+    /// Contexts in which to drop each rule's detections, by rule ID (`ignore_in_context`).
+    pub ignore_in_context: Option<BTreeMap<String, Vec<SyntaxContext>>>,
+}
+
+impl PathOptions {
+    fn parse(raw: RawOptions) -> Result<Self, String> {
+        let allow_scripts = match raw.allow_scripts {
+            Some(names) => Some(
+                names
+                    .iter()
+                    .map(|name| scripts::parse_script(name))
+                    .collect::<Result<_, _>>()?,
+            ),
+            None => None,
+        };
+        let ignore_in_context = match raw.ignore_in_context {
+            Some(table) => Some(
+                table
+                    .into_iter()
+                    .map(|(id, contexts)| match rules::find(&id) {
+                        Some(rule) => Ok((rule.id.to_string(), contexts)),
+                        None => Err(format!("unknown rule {} in ignore_in_context", id)),
+                    })
+                    .collect::<Result<_, String>>()?,
+            ),
+            None => None,
+        };
+        Ok(Self {
+            allow_scripts,
+            prose: raw.prose,
+            allow_emoji: raw.allow_emoji,
+            ascii_only: raw.ascii_only,
+            require_context: raw.require_context,
+            ignore_in_context,
+        })
+    }
+}
+
+/// Options that apply to the paths matching any of `patterns`.
+#[derive(Debug, Clone)]
+pub struct Override {
+    patterns: Vec<Pattern>,
+    pub options: PathOptions,
+}
+
+impl Override {
+    /// True if `path` matches one of the override's patterns.
+    pub fn matches(&self, path: &str) -> bool {
+        let path = Path::new(path.strip_prefix("./").unwrap_or(path));
+        self.patterns.iter().any(|p| walk::matches(p, path))
+    }
+}
+
+/// A parsed config file.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub options: PathOptions,
+    pub overrides: Vec<Override>,
+
+    /// Defaults by lowercase extension.
+    pub presets: BTreeMap<String, PathOptions>,
+
+    /// Directory names to skip besides the built-in ones (`ignore_dirs`).
+    pub ignore_dirs: Vec<String>,
+
+    /// `Some(false)` to stop skipping the built-in directories (`default_ignore_dirs`).
+    pub default_ignore_dirs: Option<bool>,
+}
+
+impl Config {
+    /// Parse config file text.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let raw: RawConfig = toml::from_str(text).map_err(|e| e.to_string())?;
+
+        let overrides = raw
+            .overrides
+            .into_iter()
+            .map(|o| {
+                let patterns = o
+                    .paths
+                    .iter()
+                    .map(|p| Pattern::new(p).map_err(|e| format!("invalid path pattern {}: {}", p, e)))
+                    .collect::<Result<_, _>>()?;
+                Ok(Override {
+                    patterns,
+                    options: PathOptions::parse(o.options)?,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+
+        let mut presets = if raw.presets == Some(false) {
+            BTreeMap::new()
+        } else {
+            builtin_presets()
+        };
+        for (ext, options) in raw.preset {
+            let ext = ext.trim_start_matches('.').to_ascii_lowercase();
+            presets.insert(ext, PathOptions::parse(options)?);
+        }
+
+        if let Some(dir) = raw
+            .ignore_dirs
+            .iter()
+            .find(|dir| dir.is_empty() || dir.contains(['/', '\\']))
+        {
+            return Err(format!("{:?} in ignore_dirs is not a directory name", dir));
+        }
+
+        Ok(Self {
+            options: PathOptions::parse(raw.options)?,
+            overrides,
+            presets,
+            ignore_dirs: raw.ignore_dirs,
+            default_ignore_dirs: raw.default_ignore_dirs,
+        })
+    }
+
+    /// Load `path`, or `.icd.toml` if it exists when no path is given. Without a file, only
+    /// the built-in presets are set.
+    pub fn load(path: Option<&Path>) -> Result<Self, String> {
+        let path = match path {
+            Some(path) => path,
+            None if Path::new(DEFAULT_CONFIG_FILE).is_file() => Path::new(DEFAULT_CONFIG_FILE),
+            None => return Self::parse(""),
+        };
+
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
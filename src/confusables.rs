@@ -0,0 +1,20 @@
+//! `--confusables`: homoglyphs of ASCII letters and digits, per the UTS #39 confusables data.
+//!
+//! A character is reported when its UTS #39 skeleton is made only of ASCII letters and digits,
+//! e.g. CYRILLIC SMALL LETTER A (skeleton `a`) or GREEK SMALL LETTER OMICRON (skeleton `o`).
+//! Lookalikes of punctuation (curly quotes, dashes) are common in prose and are not reported.
+
+use unicode_security::skeleton;
+
+/// The ASCII text `ch` is confusable with, or `None` if it doesn't resemble ASCII letters or digits.
+pub fn ascii_lookalike(ch: char) -> Option<String> {
+    if ch.is_ascii() {
+        return None;
+    }
+
+    let ascii: String = skeleton(ch.encode_utf8(&mut [0; 4])).collect();
+    if ascii.is_empty() || !ascii.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    Some(ascii)
+}
@@ -0,0 +1,190 @@
+//! `--context`: where in the source syntax a detection sits, using tree-sitter grammars.
+//!
+//! A bidi override inside an identifier changes what code does; the same character in a
+//! comment only changes what a reviewer reads. Files in Rust, JavaScript, TypeScript, Python,
+//! and Go are parsed, and each detection is classified as being in a string literal, a
+//! comment, an identifier, or other code.
+//!
+//! A [`ContextPolicy`] then drops detections by context: `--require-context` keeps only those
+//! in the listed contexts, and `ignore_in_context` in `.icd.toml` tolerates a rule in some
+//! contexts (no-break spaces in comments) while others stay strict everywhere.
+//!
+//! The grammars are C code and need the `syntax` feature (on by default). Without it, as in
+//! the wasm build, no file is parsed and every detection is reported.
+
+use std::collections::BTreeMap;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "syntax")]
+use tree_sitter::{Language, Node, Parser};
+
+#[cfg(feature = "syntax")]
+use crate::scripts;
+use crate::Detection;
+
+/// The syntactic context of a detection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SyntaxContext {
+    /// String, character, and template literals
+    String,
+
+    /// Line, block, and doc comments
+    Comment,
+
+    /// Names of variables, functions, types, fields, ...
+    Identifier,
+
+    /// Anything else: operators, whitespace between tokens, ...
+    Code,
+}
+
+impl SyntaxContext {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SyntaxContext::String => "string",
+            SyntaxContext::Comment => "comment",
+            SyntaxContext::Identifier => "identifier",
+            SyntaxContext::Code => "code",
+        }
+    }
+}
+
+/// The grammar for `path`, judged by its extension.
+#[cfg(feature = "syntax")]
+fn language_for(path: &str) -> Option<Language> {
+    let extension = path.rsplit_once('.')?.1.to_ascii_lowercase();
+    let language = match extension.as_str() {
+        "rs" => tree_sitter_rust::LANGUAGE,
+        "js" | "mjs" | "cjs" | "jsx" => tree_sitter_javascript::LANGUAGE,
+        "ts" | "mts" | "cts" => tree_sitter_typescript::LANGUAGE_TYPESCRIPT,
+        "tsx" => tree_sitter_typescript::LANGUAGE_TSX,
+        "py" | "pyi" => tree_sitter_python::LANGUAGE,
+        "go" => tree_sitter_go::LANGUAGE,
+        _ => return None,
+    };
+    Some(language.into())
+}
+
+/// Classify a syntax node kind, or `None` if it says nothing on its own (expressions,
+/// statements, ...).
+#[cfg(feature = "syntax")]
+fn node_context(kind: &str) -> Option<SyntaxContext> {
+    if kind.contains("comment") {
+        Some(SyntaxContext::Comment)
+    } else if kind.contains("string")
+        || matches!(kind, "char_literal" | "rune_literal" | "escape_sequence")
+    {
+        Some(SyntaxContext::String)
+    } else if kind.contains("identifier") {
+        Some(SyntaxContext::Identifier)
+    } else {
+        None
+    }
+}
+
+/// The context of the byte range `start..end` in the parsed file.
+#[cfg(feature = "syntax")]
+fn context_at(root: Node, content: &str, start: usize, end: usize) -> SyntaxContext {
+    let mut node = root.descendant_for_byte_range(start, end);
+    while let Some(current) = node {
+        if let Some(context) = node_context(current.kind()) {
+            return context;
+        }
+        node = current.parent();
+    }
+
+    // Characters that can't be part of an identifier make the parser give up on it; they
+    // still sit inside one if identifier characters surround them
+    let before = content[..start].chars().next_back();
+    let after = content.get(end..).and_then(|rest| rest.chars().next());
+    if before.is_some_and(scripts::is_identifier_char)
+        && after.is_some_and(scripts::is_identifier_char)
+    {
+        return SyntaxContext::Identifier;
+    }
+    SyntaxContext::Code
+}
+
+/// Set `context` on `detections` if `file_path` is in a supported language.
+#[cfg(feature = "syntax")]
+pub fn classify(file_path: &str, content: &str, detections: &mut [Detection]) {
+    let Some(language) = language_for(file_path) else {
+        return;
+    };
+    let mut parser = Parser::new();
+    if parser.set_language(&language).is_err() {
+        return;
+    }
+    let Some(tree) = parser.parse(content, None) else {
+        return;
+    };
+
+    for d in detections.iter_mut() {
+        // Only characters in the file content, not in its name
+        if d.line == 0 {
+            continue;
+        }
+        let start = d.byte_offset - 1;
+        let end = (start + d.char.len()).min(content.len());
+        if content.is_char_boundary(start) && content.is_char_boundary(end) {
+            d.context = Some(context_at(tree.root_node(), content, start, end));
+        }
+    }
+}
+
+/// Without the grammars, no file is in a supported language.
+#[cfg(not(feature = "syntax"))]
+pub fn classify(_file_path: &str, _content: &str, _detections: &mut [Detection]) {}
+
+/// Which detections to keep, by syntactic context. Detections without a context (files in
+/// other languages, file names) are always kept.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContextPolicy {
+    /// Keep only detections in these contexts (`--require-context`, `require_context`).
+    pub require: Option<Vec<SyntaxContext>>,
+
+    /// Drop detections of a rule (by ID) in these contexts (`ignore_in_context`).
+    pub ignore: BTreeMap<String, Vec<SyntaxContext>>,
+}
+
+impl ContextPolicy {
+    /// True if the policy can drop anything, so files need classifying.
+    pub fn is_active(&self) -> bool {
+        self.require.is_some() || !self.ignore.is_empty()
+    }
+
+    /// Why the policy drops `detection`, or `None` if it is reported.
+    fn drops(&self, detection: &Detection) -> Option<String> {
+        let context = detection.context?;
+        if self
+            .require
+            .as_ref()
+            .is_some_and(|require| !require.contains(&context))
+        {
+            return Some(format!("require_context: in a {}", context.as_str()));
+        }
+        self.ignore
+            .get(&detection.rule)
+            .is_some_and(|ignored| ignored.contains(&context))
+            .then(|| {
+                format!(
+                    "ignore_in_context: {} in a {}",
+                    detection.rule,
+                    context.as_str()
+                )
+            })
+    }
+
+    /// Remove the detections the policy drops, or with `show_suppressed` mark them with why.
+    pub fn apply(&self, detections: &mut Vec<Detection>, show_suppressed: bool) {
+        if !show_suppressed {
+            detections.retain(|d| self.drops(d).is_none());
+            return;
+        }
+        for d in detections.iter_mut().filter(|d| d.suppressed.is_none()) {
+            d.suppressed = self.drops(d);
+        }
+    }
+}
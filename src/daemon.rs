@@ -0,0 +1,327 @@
+//! `daemon` and `client`: a resident scanner for editor plugins that would otherwise start a
+//! process per keystroke.
+//!
+//! The daemon keeps the character tables, the configuration, and the results of every file
+//! it has scanned in memory, and answers on a Unix domain socket. The protocol is one JSON
+//! object per line each way: a request such as `{"command": "scan", "path": "/abs/src/a.rs"}`
+//! (with `"text"` to scan an unsaved buffer under that path instead of the file), `ping`,
+//! `reload` (re-read the config file and drop the results), or `stop`, and one [`Response`]
+//! for each. A file whose size and modification time haven't changed is answered from memory.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Detection;
+
+/// A request to the daemon.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "lowercase")]
+pub enum Request {
+    /// Scan the file at `path`, or `text` as if it were that file.
+    Scan {
+        path: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        text: Option<String>,
+    },
+    Ping,
+    Reload,
+    Stop,
+}
+
+/// The daemon's answer to one request.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Response {
+    /// `ok`, `scanned`, `cached` (unchanged since the last scan), `skipped`, or `error`.
+    pub status: String,
+
+    /// The path the detections are reported under.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+
+    /// Why a file was skipped, or what went wrong.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+
+    #[serde(default)]
+    pub detections: Vec<Detection>,
+}
+
+/// The socket used when none is given: in `$XDG_RUNTIME_DIR`, which only the user can read,
+/// or else in the temporary directory under the user's name.
+pub fn default_socket() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) {
+        Some(dir) => Path::new(&dir).join("invisible-char-detector.sock"),
+        None => {
+            let user = std::env::var("USER").unwrap_or_else(|_| "user".to_string());
+            std::env::temp_dir().join(format!("invisible-char-detector-{}.sock", user))
+        }
+    }
+}
+
+#[cfg(unix)]
+pub use unix::{request, run};
+
+#[cfg(not(unix))]
+pub fn run(_config: crate::ScanConfig, socket: &Path) -> Result<(), String> {
+    Err(unsupported(socket))
+}
+
+#[cfg(not(unix))]
+pub fn request(socket: &Path, _request: &Request) -> Result<Response, String> {
+    Err(unsupported(socket))
+}
+
+#[cfg(not(unix))]
+fn unsupported(socket: &Path) -> String {
+    format!(
+        "{}: the daemon needs Unix domain sockets; use `serve` on this platform",
+        socket.display()
+    )
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::fs;
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::{Path, PathBuf};
+    use std::process;
+    use std::sync::{Arc, RwLock};
+
+    use super::{Request, Response};
+    use crate::cache::ScanCache;
+    use crate::walk::WalkEntry;
+    use crate::{
+        config, detect_invisible_characters, detection_settings, logging, process_entry,
+        FileOutcome, ScanConfig,
+    };
+
+    /// What the daemon keeps between requests.
+    struct State {
+        /// The configuration from the command line, before the config file.
+        base: ScanConfig,
+
+        /// The configuration with the config file applied.
+        config: RwLock<ScanConfig>,
+
+        /// The results of every file scanned so far.
+        cache: RwLock<ScanCache>,
+
+        /// The daemon's working directory, which paths under it are reported relative to.
+        cwd: PathBuf,
+
+        socket: PathBuf,
+    }
+
+    impl Response {
+        fn status(status: &str) -> Self {
+            Response {
+                status: status.to_string(),
+                ..Response::default()
+            }
+        }
+
+        fn error(reason: impl Into<String>) -> Self {
+            Response {
+                reason: Some(reason.into()),
+                ..Response::status("error")
+            }
+        }
+    }
+
+    /// `base` with the config file applied.
+    fn load_config(base: &ScanConfig) -> Result<ScanConfig, String> {
+        let mut config = base.clone();
+        config.apply_config_file(config::Config::load(config.config_file.as_deref())?);
+        Ok(config)
+    }
+
+    /// The cache `config` starts with: loaded from `--cache` if given.
+    fn load_cache(config: &ScanConfig) -> ScanCache {
+        match &config.cache {
+            Some(path) => ScanCache::load(path, &detection_settings(config)).0,
+            None => ScanCache::default(),
+        }
+    }
+
+    impl State {
+        fn handle(&self, request: Request) -> Response {
+            match request {
+                Request::Scan { path, text } => self.scan(&path, text),
+                Request::Ping => Response::status("ok"),
+                Request::Reload => match load_config(&self.base) {
+                    Ok(config) => {
+                        *self.cache.write().unwrap_or_else(|e| e.into_inner()) =
+                            load_cache(&config);
+                        *self.config.write().unwrap_or_else(|e| e.into_inner()) = config;
+                        logging::info!("Reloaded the configuration");
+                        Response::status("ok")
+                    }
+                    Err(e) => Response::error(e),
+                },
+                Request::Stop => Response::status("ok"),
+            }
+        }
+
+        fn scan(&self, path: &str, text: Option<String>) -> Response {
+            // Relative to the daemon, as a scan started there reports it and overrides match
+            let path = Path::new(path)
+                .strip_prefix(&self.cwd)
+                .map_or_else(|_| path.to_string(), |p| p.to_string_lossy().into_owned());
+            let config = self.config.read().unwrap_or_else(|e| e.into_inner());
+            if let Some(text) = text {
+                let detections =
+                    detect_invisible_characters(&text, &path, &config.detect_options(&path));
+                return Response {
+                    path: Some(path),
+                    detections,
+                    ..Response::status("scanned")
+                };
+            }
+
+            let entry = WalkEntry::Matched(PathBuf::from(&path));
+            let outcome = {
+                let cache = self.cache.read().unwrap_or_else(|e| e.into_inner());
+                process_entry(entry, &config, Some(&cache)).1
+            };
+            let (status, reason, detections) = match outcome {
+                FileOutcome::Scanned {
+                    detections,
+                    from_cache,
+                    cache_update,
+                } => {
+                    if let Some(update) = cache_update {
+                        self.cache
+                            .write()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .insert(path.clone(), update);
+                    }
+                    let status = if from_cache { "cached" } else { "scanned" };
+                    (status, None, detections)
+                }
+                FileOutcome::Ignored => ("skipped", Some("in an ignored directory".into()), vec![]),
+                FileOutcome::TooLarge(size) => (
+                    "skipped",
+                    Some(format!("too large ({} bytes)", size)),
+                    vec![],
+                ),
+                FileOutcome::Binary(kind) => ("skipped", Some(kind.label.to_string()), vec![]),
+                FileOutcome::Unreadable(e) => ("error", Some(e.to_string()), vec![]),
+                FileOutcome::Symlink | FileOutcome::AlreadyVisited | FileOutcome::Duplicate(_) => {
+                    ("skipped", None, vec![])
+                }
+            };
+            Response {
+                path: Some(path),
+                reason,
+                detections,
+                ..Response::status(status)
+            }
+        }
+
+        /// Save the results to `--cache`, remove the socket, and exit.
+        fn stop(&self) -> ! {
+            let config = self.config.read().unwrap_or_else(|e| e.into_inner());
+            if let Some(path) = &config.cache {
+                let cache =
+                    std::mem::take(&mut *self.cache.write().unwrap_or_else(|e| e.into_inner()));
+                if let Err(e) = cache.save(path) {
+                    logging::warning!("could not write cache {}: {}", path.display(), e);
+                }
+            }
+            let _ = fs::remove_file(&self.socket);
+            logging::info!("Stopped");
+            process::exit(0);
+        }
+    }
+
+    /// Answer the requests on one connection until the client hangs up.
+    fn serve_connection(state: &State, stream: UnixStream) {
+        let Ok(mut writer) = stream.try_clone() else {
+            return;
+        };
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else {
+                return;
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let request = serde_json::from_str::<Request>(&line);
+            let stop = matches!(request, Ok(Request::Stop));
+            let response = match request {
+                Ok(request) => state.handle(request),
+                Err(e) => Response::error(format!("invalid request: {}", e)),
+            };
+            let json = serde_json::to_string(&response).unwrap_or_default();
+            if writeln!(writer, "{}", json).is_err() {
+                return;
+            }
+            if stop {
+                state.stop();
+            }
+        }
+    }
+
+    /// Listen on `socket` and answer requests, each connection on a thread of its own, until
+    /// a client sends `stop`.
+    pub fn run(base: ScanConfig, socket: &Path) -> Result<(), String> {
+        if socket.exists() {
+            if UnixStream::connect(socket).is_ok() {
+                return Err(format!(
+                    "a daemon is already listening on {}",
+                    socket.display()
+                ));
+            }
+            // Left behind by a daemon that didn't stop cleanly
+            fs::remove_file(socket).map_err(|e| format!("{}: {}", socket.display(), e))?;
+        }
+        let listener = UnixListener::bind(socket)
+            .map_err(|e| format!("could not listen on {}: {}", socket.display(), e))?;
+        // Whoever can connect can read any file the daemon can
+        fs::set_permissions(socket, fs::Permissions::from_mode(0o600))
+            .map_err(|e| format!("{}: {}", socket.display(), e))?;
+
+        let config = load_config(&base)?;
+        let state = Arc::new(State {
+            cache: RwLock::new(load_cache(&config)),
+            config: RwLock::new(config),
+            base,
+            cwd: std::env::current_dir().map_err(|e| e.to_string())?,
+            socket: socket.to_path_buf(),
+        });
+        logging::info!("Listening on {}", socket.display());
+
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let state = Arc::clone(&state);
+                    std::thread::spawn(move || serve_connection(&state, stream));
+                }
+                Err(e) => logging::warning!("could not accept a connection: {}", e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Send `request` to the daemon on `socket` and wait for its response.
+    pub fn request(socket: &Path, request: &Request) -> Result<Response, String> {
+        let stream = UnixStream::connect(socket).map_err(|e| {
+            format!(
+                "could not connect to the daemon on {}: {} (start one with `daemon`)",
+                socket.display(),
+                e
+            )
+        })?;
+        let mut writer = &stream;
+        let json = serde_json::to_string(request).map_err(|e| e.to_string())?;
+        writeln!(writer, "{}", json).map_err(|e| e.to_string())?;
+        let mut line = String::new();
+        BufReader::new(&stream)
+            .read_line(&mut line)
+            .map_err(|e| e.to_string())?;
+        serde_json::from_str(&line).map_err(|e| format!("invalid response from the daemon: {}", e))
+    }
+}
@@ -0,0 +1,219 @@
+//! `diff-reports`: compare two saved JSON reports and sort their detections into new, fixed,
+//! and persisting.
+//!
+//! Reports from different runs rarely agree on line numbers: an edit above a finding moves it.
+//! Detections are matched on their file, rule, and code point, and among those each detection
+//! in the new report is paired with the unmatched old one with the same `fingerprint` (see
+//! [`crate::fingerprint`]), or else the nearest one, so a finding that moved is persisting
+//! rather than one fixed and one new.
+//!
+//! Both reports may be in any `--json` layout: an array, `{"detections", "summary"}`, or the
+//! `--json-version 2` object. Only the fields matching needs are read, so reports written by
+//! older versions compare fine.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::visualize;
+
+/// The detections that may be the same finding in another run: same file, rule, and code
+/// point.
+type MatchKey = (String, String, u32);
+
+/// The fields of a reported detection that matching and printing use.
+#[derive(Debug, Deserialize)]
+struct Fields {
+    file: String,
+    line: usize,
+    char_index: usize,
+    byte_offset: usize,
+    code: u32,
+    name: String,
+    rule: String,
+
+    /// Missing from reports written before fingerprints were added.
+    #[serde(default)]
+    fingerprint: Option<String>,
+}
+
+/// A detection read from a report.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    /// The detection as the report has it, with every field.
+    value: Value,
+    file: String,
+    line: usize,
+    char_index: usize,
+    byte_offset: usize,
+    code: u32,
+    name: String,
+    rule: String,
+    fingerprint: Option<String>,
+}
+
+impl Entry {
+    fn key(&self) -> MatchKey {
+        (self.file.clone(), self.rule.clone(), self.code)
+    }
+}
+
+/// Read the detections of a JSON report.
+fn load(path: &Path) -> Result<Vec<Entry>, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    let report: Value = serde_json::from_str(&text)
+        .map_err(|e| format!("{} is not JSON: {}", path.display(), e))?;
+    let detections = match report {
+        Value::Array(detections) => detections,
+        Value::Object(mut object) => match object.remove("detections") {
+            Some(Value::Array(detections)) => detections,
+            _ => {
+                return Err(format!(
+                    "{} has no detections (a `stats` or --summary-only report?)",
+                    path.display()
+                ))
+            }
+        },
+        _ => return Err(format!("{} is not a detection report", path.display())),
+    };
+
+    detections
+        .into_iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let fields = Fields::deserialize(&value).map_err(|e| {
+                format!(
+                    "{}: detection {} is malformed: {}",
+                    path.display(),
+                    i + 1,
+                    e
+                )
+            })?;
+            Ok(Entry {
+                value,
+                file: fields.file,
+                line: fields.line,
+                char_index: fields.char_index,
+                byte_offset: fields.byte_offset,
+                code: fields.code,
+                name: fields.name,
+                rule: fields.rule,
+                fingerprint: fields.fingerprint,
+            })
+        })
+        .collect()
+}
+
+/// The difference between two reports.
+#[derive(Debug, Default)]
+pub struct ReportDiff {
+    /// Only in the new report.
+    pub new: Vec<Entry>,
+
+    /// Only in the old report.
+    pub fixed: Vec<Entry>,
+
+    /// In both, as they are in the new report.
+    pub persisting: Vec<Entry>,
+}
+
+/// Compare the reports at `old` and `new`.
+pub fn diff_reports(old: &Path, new: &Path) -> Result<ReportDiff, String> {
+    let mut old_groups: BTreeMap<MatchKey, Vec<Entry>> = BTreeMap::new();
+    for entry in load(old)? {
+        old_groups
+            .entry(entry.key())
+            .or_default()
+            .push(entry);
+    }
+    let mut new_groups: BTreeMap<MatchKey, Vec<Entry>> = BTreeMap::new();
+    for entry in load(new)? {
+        new_groups
+            .entry(entry.key())
+            .or_default()
+            .push(entry);
+    }
+
+    let mut diff = ReportDiff::default();
+    for (key, new_entries) in new_groups {
+        let mut unmatched = old_groups.remove(&key).unwrap_or_default();
+        for entry in new_entries {
+            // A matching fingerprint wins over any distance. Name detections have line 0 and
+            // compare by position in the name instead.
+            let distance = |old: &Entry| {
+                let same = old.fingerprint.is_some() && old.fingerprint == entry.fingerprint;
+                (!same, old.line.abs_diff(entry.line), old.char_index.abs_diff(entry.char_index))
+            };
+            let nearest = unmatched
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, old)| distance(old))
+                .map(|(i, _)| i);
+            match nearest {
+                Some(i) => {
+                    unmatched.swap_remove(i);
+                    diff.persisting.push(entry);
+                }
+                None => diff.new.push(entry),
+            }
+        }
+        diff.fixed.extend(unmatched);
+    }
+    diff.fixed.extend(old_groups.into_values().flatten());
+
+    for entries in [&mut diff.new, &mut diff.fixed, &mut diff.persisting] {
+        entries.sort_by(|a, b| {
+            a.file
+                .cmp(&b.file)
+                .then_with(|| (a.line != 0).cmp(&(b.line != 0)))
+                .then_with(|| a.byte_offset.cmp(&b.byte_offset))
+        });
+    }
+    Ok(diff)
+}
+
+/// The diff as JSON: `{"new": [...], "fixed": [...], "persisting": [...]}`, each detection as
+/// its report had it.
+pub fn to_json(diff: &ReportDiff) -> Value {
+    let values = |entries: &[Entry]| entries.iter().map(|e| e.value.clone()).collect::<Vec<_>>();
+    serde_json::json!({
+        "new": values(&diff.new),
+        "fixed": values(&diff.fixed),
+        "persisting": values(&diff.persisting),
+    })
+}
+
+/// Append one section of the diff to `output`.
+fn push_section(output: &mut String, title: &str, entries: &[Entry]) {
+    output.push_str(&format!("{} ({}):\n", title, entries.len()));
+    for e in entries {
+        output.push_str(&format!(
+            "  {}:{}:{}: U+{:04X} {} [{}]\n",
+            visualize(&e.file),
+            e.line,
+            e.char_index,
+            e.code,
+            e.name,
+            e.rule
+        ));
+    }
+}
+
+/// Format the diff for text output.
+pub fn format_diff_text(diff: &ReportDiff) -> String {
+    let mut output = format!(
+        "{} new, {} fixed, {} persisting\n\n",
+        diff.new.len(),
+        diff.fixed.len(),
+        diff.persisting.len()
+    );
+    push_section(&mut output, "New", &diff.new);
+    output.push('\n');
+    push_section(&mut output, "Fixed", &diff.fixed);
+    output.push('\n');
+    push_section(&mut output, "Persisting", &diff.persisting);
+    output
+}
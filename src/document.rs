@@ -0,0 +1,254 @@
+//! `--scan-documents`: extract the text of Office Open XML, OpenDocument, and PDF files,
+//! and of emails (see [`crate::email`]).
+//!
+//! .docx, .xlsx, .pptx, and .odt/.ods/.odp files are zip archives of XML parts. The text of
+//! each part is pulled out one paragraph (or spreadsheet string) per line, so a detection's
+//! line number is the paragraph number within the part and its file is
+//! `report.docx!word/document.xml`.
+//!
+//! A PDF's parts are its pages, `spec.pdf!page-3`, with the text shown by each of the page's
+//! text objects on a line of its own. Text is decoded through the fonts' encodings and
+//! ToUnicode maps; what a font gives no way to decode is skipped.
+
+use crate::archive::{self, ArchiveKind};
+use crate::email;
+
+/// Which document format a file is, which decides the parts and paragraph elements to read.
+#[derive(Debug, Clone, Copy)]
+pub enum DocumentKind {
+    Word,
+    Excel,
+    PowerPoint,
+    OpenDocument,
+    Pdf,
+    Email,
+}
+
+/// The kind of document at `path`, judged by its extension.
+pub fn document_kind(path: &str) -> Option<DocumentKind> {
+    let extension = path.rsplit_once('.')?.1.to_ascii_lowercase();
+    match extension.as_str() {
+        "docx" | "docm" | "dotx" => Some(DocumentKind::Word),
+        "xlsx" | "xlsm" => Some(DocumentKind::Excel),
+        "pptx" | "pptm" => Some(DocumentKind::PowerPoint),
+        "odt" | "ods" | "odp" => Some(DocumentKind::OpenDocument),
+        "pdf" => Some(DocumentKind::Pdf),
+        "eml" => Some(DocumentKind::Email),
+        _ => None,
+    }
+}
+
+impl DocumentKind {
+    /// True if the part `name` holds document text.
+    fn has_text(self, name: &str) -> bool {
+        let in_dir = |dir: &str, prefix: &str| {
+            name.strip_prefix(dir).is_some_and(|file| {
+                file.starts_with(prefix) && file.ends_with(".xml") && !file.contains('/')
+            })
+        };
+        match self {
+            DocumentKind::Word => [
+                "document",
+                "header",
+                "footer",
+                "footnotes",
+                "endnotes",
+                "comments",
+            ]
+            .iter()
+            .any(|prefix| in_dir("word/", prefix)),
+            DocumentKind::Excel => {
+                name == "xl/sharedStrings.xml" || in_dir("xl/worksheets/", "sheet")
+            }
+            DocumentKind::PowerPoint => {
+                in_dir("ppt/slides/", "slide") || in_dir("ppt/notesSlides/", "notesSlide")
+            }
+            DocumentKind::OpenDocument => name == "content.xml" || name == "styles.xml",
+            DocumentKind::Pdf | DocumentKind::Email => false,
+        }
+    }
+
+    /// Elements whose text is reported as one line.
+    fn paragraph_elements(self) -> &'static [&'static str] {
+        match self {
+            DocumentKind::Word => &["w:p"],
+            // Shared strings and inline strings in cells
+            DocumentKind::Excel => &["si", "is"],
+            DocumentKind::PowerPoint => &["a:p"],
+            DocumentKind::OpenDocument => &["text:p", "text:h"],
+            DocumentKind::Pdf | DocumentKind::Email => &[],
+        }
+    }
+}
+
+/// Empty elements that stand for whitespace within a paragraph.
+const SPACE_ELEMENTS: &[&str] = &[
+    "w:tab",
+    "w:br",
+    "a:br",
+    "text:tab",
+    "text:s",
+    "text:line-break",
+];
+
+/// A document part (or PDF page) and its text, one paragraph per line.
+pub struct DocumentPart {
+    pub name: String,
+    pub text: String,
+}
+
+/// Extract the text parts of a document, skipping parts larger than `max_size`.
+pub fn extract_text(
+    bytes: &[u8],
+    kind: DocumentKind,
+    max_size: Option<u64>,
+) -> Result<Vec<DocumentPart>, String> {
+    match kind {
+        DocumentKind::Pdf => return pdf_pages(bytes, max_size),
+        DocumentKind::Email => return Ok(email::extract_parts(bytes, max_size)),
+        _ => {}
+    }
+    let entries = archive::read_entries(bytes, ArchiveKind::Zip, max_size)?;
+    Ok(entries
+        .into_iter()
+        .filter(|entry| kind.has_text(&entry.path))
+        .map(|entry| DocumentPart {
+            text: paragraphs(
+                &String::from_utf8_lossy(&entry.content),
+                kind.paragraph_elements(),
+            )
+            .join("\n"),
+            name: entry.path,
+        })
+        .collect())
+}
+
+/// The text of each page of a PDF, skipping pages with more than `max_size` bytes of text.
+#[cfg(feature = "pdf")]
+fn pdf_pages(bytes: &[u8], max_size: Option<u64>) -> Result<Vec<DocumentPart>, String> {
+    let pdf = lopdf::Document::load_mem(bytes).map_err(|e| e.to_string())?;
+    let mut pages = Vec::new();
+    for number in pdf.get_pages().into_keys() {
+        // Keep what decoded even when one of the page's fonts didn't
+        let text: String = pdf
+            .extract_text_chunks(&[number])
+            .into_iter()
+            .filter_map(Result::ok)
+            .collect();
+        if max_size.is_some_and(|limit| text.len() as u64 > limit) {
+            continue;
+        }
+        pages.push(DocumentPart {
+            name: format!("page-{}", number),
+            text,
+        });
+    }
+    Ok(pages)
+}
+
+/// The text of each page of a PDF.
+#[cfg(not(feature = "pdf"))]
+fn pdf_pages(_bytes: &[u8], _max_size: Option<u64>) -> Result<Vec<DocumentPart>, String> {
+    Err("this build has no PDF support (the `pdf` feature)".to_string())
+}
+
+/// Resolve the predefined and numeric character references in XML character data.
+fn decode_references(text: &str, out: &mut String) {
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';') else {
+            break;
+        };
+        let name = &rest[1..semi];
+        let decoded = match name {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => name
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| name.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(ch) => {
+                out.push(ch);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+}
+
+/// The text of every `elements` element in `xml`, in document order. Line breaks inside a
+/// paragraph become spaces so each paragraph stays on one line.
+fn paragraphs(xml: &str, elements: &[&str]) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    let mut rest = xml;
+
+    while let Some(lt) = rest.find('<') {
+        if depth > 0 {
+            decode_references(&rest[..lt], &mut current);
+        }
+        rest = &rest[lt..];
+
+        if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+            let end = cdata.find("]]>").unwrap_or(cdata.len());
+            if depth > 0 {
+                current.push_str(&cdata[..end]);
+            }
+            rest = cdata.get(end + 3..).unwrap_or("");
+            continue;
+        }
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+
+        let Some(gt) = rest.find('>') else {
+            break;
+        };
+        let tag = &rest[1..gt];
+        rest = &rest[gt + 1..];
+
+        let closing = tag.starts_with('/');
+        let self_closing = tag.ends_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or("");
+
+        if elements.contains(&name) {
+            if closing {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    let text = current.replace(['\r', '\n'], " ");
+                    paragraphs.push(text);
+                    current.clear();
+                }
+            } else if self_closing {
+                if depth == 0 {
+                    paragraphs.push(String::new());
+                }
+            } else {
+                depth += 1;
+            }
+        } else if depth > 0 && SPACE_ELEMENTS.contains(&name) {
+            current.push(' ');
+        }
+    }
+
+    paragraphs
+}
@@ -0,0 +1,334 @@
+//! `--scan-documents` for .eml files: decode the MIME structure of an email and scan the
+//! text a mail client would show.
+//!
+//! Each header of the message is a part of its own, `mail.eml!headers/Subject`, with RFC 2047
+//! encoded words decoded and folded lines joined. Text bodies are decoded from
+//! quoted-printable or base64 and reported by their position in the MIME tree,
+//! `mail.eml!part-2.1.html`; other attachments are left alone. A forwarded message (message/rfc822) is decoded the same way
+//! under its part, `mail.eml!part-2/headers/Subject`. Text that isn't UTF-8 is decoded as
+//! Windows-1252, which also covers ASCII and Latin-1.
+
+use crate::document::DocumentPart;
+use crate::encoding::Lossy;
+
+/// How deep multiparts and forwarded messages are followed.
+const MAX_DEPTH: usize = 16;
+
+/// The headers and decoded text bodies of the email in `bytes`, skipping bodies larger than
+/// `max_size`.
+pub fn extract_parts(bytes: &[u8], max_size: Option<u64>) -> Vec<DocumentPart> {
+    let mut parts = Vec::new();
+    message(bytes, "", "", max_size, 0, &mut parts);
+    parts
+}
+
+/// A message: its headers under `prefix`, and its body as the part `section`.
+fn message(
+    raw: &[u8],
+    prefix: &str,
+    section: &str,
+    max_size: Option<u64>,
+    depth: usize,
+    parts: &mut Vec<DocumentPart>,
+) {
+    let (headers, body) = split_headers(raw);
+    for (name, value) in &headers {
+        parts.push(DocumentPart {
+            name: format!("{}headers/{}", prefix, name),
+            text: decode_words(value),
+        });
+    }
+    entity(&headers, body, section, max_size, depth, parts);
+}
+
+/// A MIME entity numbered `section` (empty for a message's only body).
+fn entity(
+    headers: &[(String, String)],
+    body: &[u8],
+    section: &str,
+    max_size: Option<u64>,
+    depth: usize,
+    parts: &mut Vec<DocumentPart>,
+) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+    let (mime_type, params) = header(headers, "Content-Type")
+        .map(content_type)
+        .unwrap_or_else(|| ("text/plain".to_string(), Vec::new()));
+    let child = |i: usize| {
+        if section.is_empty() {
+            (i + 1).to_string()
+        } else {
+            format!("{}.{}", section, i + 1)
+        }
+    };
+
+    if mime_type.starts_with("multipart/") {
+        let Some(boundary) = param(&params, "boundary") else {
+            return;
+        };
+        for (i, raw) in split_multipart(body, boundary).into_iter().enumerate() {
+            let (headers, body) = split_headers(raw);
+            entity(&headers, body, &child(i), max_size, depth + 1, parts);
+        }
+        return;
+    }
+
+    let section = if section.is_empty() { "1" } else { section };
+    let encoding = header(headers, "Content-Transfer-Encoding").unwrap_or("");
+    let decoded = decode_transfer(body, encoding);
+    let extension = match mime_type.as_str() {
+        "message/rfc822" => {
+            let prefix = format!("part-{}/", section);
+            message(&decoded, &prefix, section, max_size, depth + 1, parts);
+            return;
+        }
+        "text/plain" => "txt",
+        "text/html" => "html",
+        _ => return,
+    };
+    if max_size.is_some_and(|limit| decoded.len() as u64 > limit) {
+        return;
+    }
+    parts.push(DocumentPart {
+        name: format!("part-{}.{}", section, extension),
+        text: text(&decoded),
+    });
+}
+
+/// `bytes` as text: UTF-8 where valid, Windows-1252 elsewhere.
+fn text(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => Lossy::decode(bytes).text,
+    }
+}
+
+/// The lines of `bytes`, each with its line ending.
+fn lines(bytes: &[u8]) -> impl Iterator<Item = &[u8]> {
+    bytes.split_inclusive(|&b| b == b'\n')
+}
+
+/// `line` without its line ending.
+fn trim_line_end(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+/// The headers of an entity, folded lines joined, and its body.
+fn split_headers(raw: &[u8]) -> (Vec<(String, String)>, &[u8]) {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    let mut at = 0;
+    for line in lines(raw) {
+        at += line.len();
+        let line = trim_line_end(line);
+        if line.is_empty() {
+            return (headers, &raw[at..]);
+        }
+        let line = text(line);
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push_str(&line);
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    (headers, &[])
+}
+
+/// The value of the first header called `name`.
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(header, _)| header.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// The MIME type of a Content-Type value, lowercased, and its parameters.
+fn content_type(value: &str) -> (String, Vec<(String, String)>) {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    for ch in value.chars() {
+        match ch {
+            '"' => quoted = !quoted,
+            ';' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(ch),
+        }
+    }
+    fields.push(field);
+
+    let mime_type = fields[0].trim().to_ascii_lowercase();
+    let params = fields[1..]
+        .iter()
+        .filter_map(|field| field.split_once('='))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+    (mime_type, params)
+}
+
+/// The value of the parameter `name`.
+fn param<'a>(params: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    params
+        .iter()
+        .find(|(param, _)| param == name)
+        .map(|(_, value)| value.as_str())
+}
+
+/// The body parts of a multipart body, between its `--boundary` lines.
+fn split_multipart<'a>(body: &'a [u8], boundary: &str) -> Vec<&'a [u8]> {
+    let delimiter = format!("--{}", boundary);
+    let mut parts = Vec::new();
+    let mut start = None;
+    let mut at = 0;
+    for line in lines(body) {
+        let line_start = at;
+        at += line.len();
+        let Some(rest) = trim_line_end(line).strip_prefix(delimiter.as_bytes()) else {
+            continue;
+        };
+        let closing = rest.starts_with(b"--");
+        if !closing && !rest.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        if let Some(start) = start {
+            // The line ending before a delimiter belongs to the delimiter
+            parts.push(trim_line_end(&body[start..line_start]));
+        }
+        if closing {
+            return parts;
+        }
+        start = Some(at);
+    }
+    // A message cut off before its closing delimiter
+    if let Some(start) = start {
+        parts.push(&body[start..]);
+    }
+    parts
+}
+
+/// Decode a body in its Content-Transfer-Encoding.
+fn decode_transfer(body: &[u8], encoding: &str) -> Vec<u8> {
+    match encoding.trim().to_ascii_lowercase().as_str() {
+        "base64" => decode_base64(body),
+        "quoted-printable" => decode_quoted_printable(body, false),
+        _ => body.to_vec(),
+    }
+}
+
+/// The value of a hexadecimal digit.
+fn hex_digit(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|digit| digit as u8)
+}
+
+/// Decode quoted-printable, or with `underscores` the Q encoding of headers, where `_` is a
+/// space. Malformed escapes are kept as they are.
+fn decode_quoted_printable(input: &[u8], underscores: bool) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        match input[i] {
+            b'=' => {
+                let rest = &input[i + 1..];
+                if let [high, low, ..] = rest {
+                    if let (Some(high), Some(low)) = (hex_digit(*high), hex_digit(*low)) {
+                        out.push(high << 4 | low);
+                        i += 3;
+                        continue;
+                    }
+                }
+                // A soft line break
+                if rest.starts_with(b"\r\n") {
+                    i += 3;
+                } else if rest.starts_with(b"\n") {
+                    i += 2;
+                } else {
+                    out.push(b'=');
+                    i += 1;
+                }
+            }
+            b'_' if underscores => {
+                out.push(b' ');
+                i += 1;
+            }
+            byte => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Decode base64, skipping line breaks and anything else outside the alphabet.
+fn decode_base64(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len() / 4 * 3);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for &byte in input {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            _ => continue,
+        };
+        buffer = buffer << 6 | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    out
+}
+
+/// A header value with its RFC 2047 encoded words (`=?utf-8?B?...?=`) decoded. Whitespace
+/// between two encoded words is dropped, as mail clients do.
+fn decode_words(value: &str) -> String {
+    let mut out = String::new();
+    let mut rest = value;
+    let mut after_word = false;
+    while let Some(start) = rest.find("=?") {
+        let before = &rest[..start];
+        let Some(decoded) = encoded_word(&rest[start + 2..]) else {
+            out.push_str(&rest[..start + 2]);
+            rest = &rest[start + 2..];
+            after_word = false;
+            continue;
+        };
+        if !(after_word && before.trim().is_empty()) {
+            out.push_str(before);
+        }
+        out.push_str(&decoded.0);
+        rest = &rest[start + 2 + decoded.1..];
+        after_word = true;
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Decode the encoded word that `input` starts with (after its `=?`): its text and how many
+/// bytes of `input` it took.
+fn encoded_word(input: &str) -> Option<(String, usize)> {
+    let mut fields = input.splitn(3, '?');
+    let charset = fields.next()?;
+    let encoding = fields.next()?;
+    let rest = fields.next()?;
+    let end = rest.find("?=")?;
+    let encoded = &rest.as_bytes()[..end];
+    if encoded.contains(&b' ') {
+        return None;
+    }
+    let bytes = match encoding {
+        "B" | "b" => decode_base64(encoded),
+        "Q" | "q" => decode_quoted_printable(encoded, true),
+        _ => return None,
+    };
+    let taken = charset.len() + 1 + encoding.len() + 1 + end + 2;
+    Some((text(&bytes), taken))
+}
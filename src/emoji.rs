@@ -0,0 +1,27 @@
+//! `--allow-emoji`: recognize the joiners and presentation selectors that belong to emoji.
+//!
+//! U+200D ZERO WIDTH JOINER is legitimate between two emoji (👩 + ZWJ + 💻), and U+FE0F
+//! VARIATION SELECTOR-16 right after an emoji-capable character (❤ + VS16). The checks
+//! follow the UTS #51 emoji sequence grammar closely enough to tell those apart from stray
+//! joiners and selectors hidden in ordinary text.
+
+use unicode_properties::UnicodeEmoji;
+
+/// True if `ch` has the Emoji property (this includes `#`, `*`, and the digits, for keycaps).
+pub fn is_emoji(ch: char) -> bool {
+    ch.is_emoji_char()
+}
+
+/// True if `ch` can start an element of an emoji ZWJ sequence.
+pub fn is_emoji_base(ch: char) -> bool {
+    !ch.is_ascii() && ch.is_emoji_char()
+}
+
+/// True for characters that extend the emoji before them: VS16, skin tone modifiers, the
+/// keycap mark, and emoji tag specs (as in subdivision flags).
+pub fn is_emoji_extender(ch: char) -> bool {
+    matches!(
+        ch,
+        '\u{FE0F}' | '\u{1F3FB}'..='\u{1F3FF}' | '\u{20E3}' | '\u{E0020}'..='\u{E007F}'
+    )
+}
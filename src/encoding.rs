@@ -0,0 +1,370 @@
+//! Files that aren't UTF-8: UTF-16 with a byte order mark, and (with `--lossy`) anything else.
+//!
+//! Windows tools and PowerShell redirects write UTF-16, which fails UTF-8 decoding and used to
+//! be skipped (or taken for binary, being full of NUL bytes). A file starting with a UTF-16 BOM
+//! is transcoded and scanned, and the detections' byte offsets are mapped back to the original
+//! file. Lines, character indices, and UTF-16 columns don't depend on the encoding.
+//!
+//! Other invalid UTF-8 is skipped as unreadable, which is a blind spot an attacker can aim
+//! for. `--lossy` scans such files anyway: valid UTF-8 sequences are decoded as UTF-8 (so a
+//! real U+200B among Latin-1 text is still found) and every other byte as Windows-1252.
+//! `--report-invalid-utf8` reports the invalid sequences themselves: overlong encodings and
+//! CESU-8 surrogates are how a filter that checks decoded text gets bypassed.
+
+use serde::{Deserialize, Serialize};
+
+use crate::Detection;
+
+/// The encoding of a file that isn't UTF-8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Encoding {
+    #[serde(rename = "utf-16le")]
+    Utf16Le,
+
+    #[serde(rename = "utf-16be")]
+    Utf16Be,
+
+    /// Not valid UTF-8, scanned with `--lossy`: UTF-8 where valid, Windows-1252 elsewhere.
+    #[serde(rename = "windows-1252")]
+    Windows1252,
+}
+
+impl Encoding {
+    /// Number of bytes `text` takes in this encoding, given the file's `bytes` from where it
+    /// starts.
+    pub fn encoded_len(self, text: &str, bytes: &[u8]) -> usize {
+        match self {
+            Encoding::Utf16Le | Encoding::Utf16Be => text.encode_utf16().count() * 2,
+            // One byte, unless the character was valid UTF-8 in the file
+            Encoding::Windows1252 if bytes.starts_with(text.as_bytes()) => text.len(),
+            Encoding::Windows1252 => text.chars().count(),
+        }
+    }
+}
+
+/// The encoding announced by a UTF-16 byte order mark at the start of `bytes`. `FF FE 00 00`
+/// is the UTF-32 LE mark and not taken for UTF-16.
+pub fn utf16_bom(bytes: &[u8]) -> Option<Encoding> {
+    match bytes {
+        [0xFF, 0xFE, 0x00, 0x00, ..] => None,
+        [0xFF, 0xFE, ..] => Some(Encoding::Utf16Le),
+        [0xFE, 0xFF, ..] => Some(Encoding::Utf16Be),
+        _ => None,
+    }
+}
+
+/// Decode UTF-16 `bytes` (starting with the BOM) without the BOM. Unpaired surrogates and a
+/// dangling last byte become U+FFFD, which the scanner reports as an encoding artifact.
+pub fn decode_utf16(bytes: &[u8], encoding: Encoding) -> String {
+    let units = bytes[2..].chunks(2).map(|pair| match (encoding, pair) {
+        (Encoding::Utf16Le, [low, high]) => u16::from_le_bytes([*low, *high]),
+        (Encoding::Utf16Be, [high, low]) => u16::from_be_bytes([*high, *low]),
+        // Not a whole code unit; decodes to U+FFFD as a lone surrogate would
+        _ => 0xD800,
+    });
+    char::decode_utf16(units)
+        .map(|unit| unit.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+/// Set the byte offsets of `detections` to `original(offset)`, both 1-indexed, and mark them
+/// with the encoding. Characters in file names keep theirs.
+fn set_offsets(
+    detections: &mut [Detection],
+    encoding: Encoding,
+    original: impl Fn(usize) -> usize,
+) {
+    for d in detections.iter_mut() {
+        if d.line == 0 {
+            continue;
+        }
+        d.byte_offset = original(d.byte_offset);
+        if let Some(run) = &mut d.run {
+            run.end_byte_offset = original(run.end_byte_offset);
+        }
+        d.byte_start = d.byte_offset - 1;
+        d.byte_end = original(d.byte_end + 1) - 1;
+        d.encoding = Some(encoding);
+        // The edit is in UTF-8 and doesn't carry over
+        d.fix = None;
+    }
+}
+
+/// Point the byte offsets of `detections` in `text`, decoded from a UTF-16 file in `encoding`,
+/// at the original file's bytes (after its 2-byte BOM).
+pub fn remap_utf16(text: &str, encoding: Encoding, detections: &mut [Detection]) {
+    // 0-indexed UTF-8 offsets to map, in order
+    let mut wanted: Vec<usize> = detections
+        .iter()
+        .filter(|d| d.line > 0)
+        .flat_map(|d| {
+            let end = d.run.as_ref().map(|run| run.end_byte_offset - 1);
+            [d.byte_offset - 1, d.byte_end].into_iter().chain(end)
+        })
+        .collect();
+    wanted.sort_unstable();
+    wanted.dedup();
+
+    // One pass over the text, counting code units up to each wanted offset
+    let mut mapped = Vec::with_capacity(wanted.len());
+    let mut units = 0;
+    let mut next = wanted.iter().peekable();
+    for (offset, ch) in text.char_indices() {
+        while next.next_if(|&&w| w <= offset).is_some() {
+            mapped.push(2 + units * 2);
+        }
+        units += ch.len_utf16();
+    }
+    mapped.extend(next.map(|_| 2 + units * 2));
+
+    set_offsets(detections, encoding, |utf8_offset| {
+        match wanted.binary_search(&(utf8_offset - 1)) {
+            Ok(i) => mapped[i] + 1,
+            Err(_) => utf8_offset,
+        }
+    });
+}
+
+/// Windows-1252 characters for bytes 0x80–0x9F. The five bytes it leaves undefined map to the
+/// C1 controls of the same value, as browsers decode them.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+/// A file that isn't valid UTF-8, decoded with `--lossy`.
+pub struct Lossy {
+    pub text: String,
+
+    /// Where offsets in `text` stop matching the file: from each UTF-8 offset on, how many
+    /// bytes `text` has gained over the file (a Windows-1252 byte can become up to 3).
+    shifts: Vec<(usize, usize)>,
+}
+
+impl Lossy {
+    pub fn decode(bytes: &[u8]) -> Self {
+        let mut text = String::with_capacity(bytes.len());
+        let mut shifts = Vec::new();
+        let mut rest = bytes;
+        while !rest.is_empty() {
+            let (valid, invalid) = match std::str::from_utf8(rest) {
+                Ok(valid) => (valid, 0),
+                Err(e) => {
+                    let valid = std::str::from_utf8(&rest[..e.valid_up_to()]).unwrap_or_default();
+                    (valid, e.error_len().unwrap_or(rest.len() - e.valid_up_to()))
+                }
+            };
+            text.push_str(valid);
+            for &byte in &rest[valid.len()..valid.len() + invalid] {
+                let ch = match byte {
+                    0x80..=0x9F => WINDOWS_1252_HIGH[usize::from(byte - 0x80)],
+                    _ => char::from(byte),
+                };
+                text.push(ch);
+                let gained = shifts.last().map_or(0, |&(_, gained)| gained) + ch.len_utf8() - 1;
+                shifts.push((text.len(), gained));
+            }
+            rest = &rest[valid.len() + invalid..];
+        }
+        Lossy { text, shifts }
+    }
+
+    /// Point the byte offsets of `detections` in `text` at the original file's bytes.
+    pub fn remap(&self, detections: &mut [Detection]) {
+        set_offsets(detections, Encoding::Windows1252, |utf8_offset| {
+            let i = self.shifts.partition_point(|&(at, _)| at < utf8_offset);
+            utf8_offset - i.checked_sub(1).map_or(0, |i| self.shifts[i].1)
+        });
+    }
+}
+
+/// What is wrong with an invalid UTF-8 sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Malformation {
+    /// A code point written with more bytes than it needs (`C0 AF` for `/`), which lenient
+    /// decoders accept and filters looking for the short form miss.
+    Overlong,
+
+    /// A UTF-16 surrogate encoded as three bytes (CESU-8 or Java's modified UTF-8).
+    Surrogate,
+
+    /// A sequence that would decode beyond U+10FFFF.
+    OutOfRange,
+
+    /// A lead byte without all the continuation bytes it needs.
+    Truncated,
+
+    /// Continuation bytes without a lead byte, or a byte that never occurs in UTF-8.
+    Stray,
+}
+
+/// An invalid UTF-8 sequence in a file.
+#[derive(Debug, Clone)]
+pub struct InvalidSequence {
+    /// 0-indexed offset of the first byte.
+    pub offset: usize,
+
+    pub bytes: Vec<u8>,
+    pub malformation: Malformation,
+
+    /// What a lenient decoder makes of the bytes; a pair of encoded surrogates decodes to the
+    /// supplementary character they stand for.
+    pub decoded: Option<u32>,
+
+    /// 1-indexed line and character index, counting each byte that isn't valid UTF-8 as one
+    /// character (as `--lossy` does).
+    pub line: usize,
+    pub char_index: usize,
+    pub utf16_column: usize,
+}
+
+impl InvalidSequence {
+    /// The bytes in hex, e.g. `C0 AF`.
+    pub fn hex(&self) -> String {
+        self.bytes
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Number of bytes a UTF-8 sequence starting with `lead` has, valid or not.
+fn sequence_len(lead: u8) -> usize {
+    match lead {
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF7 => 4,
+        _ => 1,
+    }
+}
+
+/// The value the bytes of a complete sequence encode, ignoring the rules that make it invalid.
+fn sequence_value(bytes: &[u8]) -> u32 {
+    let lead_bits = match bytes.len() {
+        2 => 0x1F,
+        3 => 0x0F,
+        _ => 0x07,
+    };
+    bytes[1..]
+        .iter()
+        .fold(u32::from(bytes[0] & lead_bits), |value, b| {
+            (value << 6) | u32::from(b & 0x3F)
+        })
+}
+
+/// Classify the invalid sequence at the start of `bytes`, returning its length, what is
+/// wrong with it, and its value if it has one.
+fn classify_invalid(bytes: &[u8]) -> (usize, Malformation, Option<u32>) {
+    let is_continuation = |b: &u8| (0x80..=0xBF).contains(b);
+    let lead = bytes[0];
+    let want = sequence_len(lead);
+    if want == 1 {
+        let len = if is_continuation(&lead) {
+            1 + bytes[1..].iter().take_while(|b| is_continuation(b)).count()
+        } else {
+            1
+        };
+        return (len, Malformation::Stray, None);
+    }
+
+    let len = 1 + bytes[1..want.min(bytes.len())]
+        .iter()
+        .take_while(|b| is_continuation(b))
+        .count();
+    if len < want {
+        return (len, Malformation::Truncated, None);
+    }
+    let value = sequence_value(&bytes[..len]);
+    let shortest = match value {
+        0..=0x7F => 1,
+        0x80..=0x7FF => 2,
+        0x800..=0xFFFF => 3,
+        _ => 4,
+    };
+    if shortest < len {
+        return (len, Malformation::Overlong, Some(value));
+    }
+    if value > 0x10FFFF {
+        return (len, Malformation::OutOfRange, None);
+    }
+    // Surrogates: a high one followed by a low one is CESU-8 for a supplementary character
+    if (0xD800..=0xDBFF).contains(&value) && bytes.len() >= 6 && sequence_len(bytes[3]) == 3 {
+        let low = &bytes[3..6];
+        if low[1..].iter().all(is_continuation) {
+            let low = sequence_value(low);
+            if (0xDC00..=0xDFFF).contains(&low) {
+                let pair = 0x10000 + ((value - 0xD800) << 10) + (low - 0xDC00);
+                return (6, Malformation::Surrogate, Some(pair));
+            }
+        }
+    }
+    (len, Malformation::Surrogate, Some(value))
+}
+
+/// Line and column while walking a file, with the scanner's line model (`\n`, `\r\n`, `\r`).
+#[derive(Default)]
+struct Position {
+    line: usize,
+    char_index: usize,
+    utf16_column: usize,
+    prev: char,
+}
+
+impl Position {
+    fn advance(&mut self, ch: char) {
+        match ch {
+            '\n' if self.prev == '\r' => {}
+            '\n' | '\r' => {
+                self.line += 1;
+                self.char_index = 0;
+                self.utf16_column = 0;
+            }
+            _ => {
+                self.char_index += 1;
+                self.utf16_column += ch.len_utf16();
+            }
+        }
+        self.prev = ch;
+    }
+}
+
+/// The invalid UTF-8 sequences in `bytes`, in order.
+pub fn invalid_utf8(bytes: &[u8]) -> Vec<InvalidSequence> {
+    let mut found = Vec::new();
+    let mut position = Position {
+        line: 1,
+        ..Position::default()
+    };
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let rest = &bytes[offset..];
+        let valid_len = match std::str::from_utf8(rest) {
+            Ok(_) => rest.len(),
+            Err(e) => e.valid_up_to(),
+        };
+        let valid = std::str::from_utf8(&rest[..valid_len]).unwrap_or_default();
+        valid.chars().for_each(|ch| position.advance(ch));
+        offset += valid_len;
+        if offset == bytes.len() {
+            break;
+        }
+
+        let (len, malformation, decoded) = classify_invalid(&bytes[offset..]);
+        found.push(InvalidSequence {
+            offset,
+            bytes: bytes[offset..offset + len].to_vec(),
+            malformation,
+            decoded,
+            line: position.line,
+            char_index: position.char_index + 1,
+            utf16_column: position.utf16_column + 1,
+        });
+        // Each byte stands for one character, as --lossy decodes it
+        (0..len).for_each(|_| position.advance(char::REPLACEMENT_CHARACTER));
+        offset += len;
+    }
+    found
+}
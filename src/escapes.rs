@@ -0,0 +1,169 @@
+//! `--include-escapes`: escape sequences that produce a suspicious character at runtime.
+//!
+//! `"\u200B"` in JavaScript or `&#8203;` in HTML never puts the raw character in the file, so
+//! the character scan can't see it. This module recognizes the common escape syntaxes:
+//!
+//! - `\uXXXX`, including UTF-16 surrogate pairs (JS, JSON, Java, C#, Python)
+//! - `\u{X...}` (JS, Rust, Swift) and `\x{X...}` (Perl, PCRE)
+//! - `\UXXXXXXXX` (Python, C)
+//! - `\N{NAME}` (Python)
+//! - `&#NNNN;`, `&#xXXXX;`, and named entities for invisible characters (HTML, XML)
+//! - percent-encoded UTF-8 such as `%E2%80%8B` (URLs, query strings, config values)
+
+/// An escape sequence found in a line.
+#[derive(Debug)]
+pub struct Escape {
+    /// Byte range of the sequence within the line.
+    pub start: usize,
+    pub end: usize,
+
+    /// The code point it produces.
+    pub code: u32,
+}
+
+/// HTML named entities for invisible characters. `&nbsp;` is left out: it is the normal way
+/// to write a visible no-break space in markup.
+const NAMED_ENTITIES: &[(&str, u32)] = &[
+    ("&ZeroWidthSpace;", 0x200B),
+    ("&zwnj;", 0x200C),
+    ("&zwj;", 0x200D),
+    ("&lrm;", 0x200E),
+    ("&rlm;", 0x200F),
+    ("&NoBreak;", 0x2060),
+    ("&shy;", 0x00AD),
+];
+
+/// Parse `min` to `max` hex digits at the start of `text`, returning the value and digit count.
+fn hex_prefix(text: &str, min: usize, max: usize) -> Option<(u32, usize)> {
+    let digits = text
+        .bytes()
+        .take(max)
+        .take_while(|b| b.is_ascii_hexdigit())
+        .count();
+    if digits < min {
+        return None;
+    }
+    u32::from_str_radix(&text[..digits], 16)
+        .ok()
+        .map(|v| (v, digits))
+}
+
+/// Parse a braced hex escape body such as `{200B}`, returning the value and length.
+fn braced_hex(text: &str) -> Option<(u32, usize)> {
+    let inner = text.strip_prefix('{')?;
+    let (value, digits) = hex_prefix(inner, 1, 6)?;
+    inner[digits..]
+        .starts_with('}')
+        .then_some((value, digits + 2))
+}
+
+/// Parse the escape starting at the backslash at the start of `text`.
+fn backslash_escape(text: &str) -> Option<(u32, usize)> {
+    let rest = &text[1..];
+    match rest.as_bytes().first()? {
+        b'u' if rest[1..].starts_with('{') => braced_hex(&rest[1..]).map(|(v, n)| (v, n + 2)),
+        b'u' => {
+            let (high, _) = hex_prefix(&rest[1..], 4, 4)?;
+            // A high surrogate followed by `\u` + low surrogate encodes one astral code point
+            if (0xD800..=0xDBFF).contains(&high) && rest[5..].starts_with("\\u") {
+                if let Some((low, _)) = hex_prefix(&rest[7..], 4, 4) {
+                    if (0xDC00..=0xDFFF).contains(&low) {
+                        return Some((0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00), 12));
+                    }
+                }
+            }
+            Some((high, 6))
+        }
+        b'U' => hex_prefix(&rest[1..], 8, 8).map(|(v, _)| (v, 10)),
+        b'x' => braced_hex(&rest[1..]).map(|(v, n)| (v, n + 2)),
+        b'N' => {
+            let name = rest[1..].strip_prefix('{')?;
+            let end = name.find('}')?;
+            let ch = unicode_names2::character(&name[..end])?;
+            Some((ch as u32, end + 4))
+        }
+        _ => None,
+    }
+}
+
+/// Parse a numeric character reference such as `&#8203;` or `&#x200B;`.
+fn numeric_entity(text: &str) -> Option<(u32, usize)> {
+    let rest = text.strip_prefix("&#")?;
+    let (value, digits, prefix) = if let Some(hex) = rest.strip_prefix(['x', 'X']) {
+        let (value, digits) = hex_prefix(hex, 1, 8)?;
+        (value, digits, 3)
+    } else {
+        let digits = rest.bytes().take_while(|b| b.is_ascii_digit()).count();
+        (rest[..digits].parse().ok()?, digits, 2)
+    };
+    text[prefix + digits..]
+        .starts_with(';')
+        .then_some((value, prefix + digits + 1))
+}
+
+/// Parse the entity starting at the `&` at the start of `text`.
+fn entity(text: &str) -> Option<(u32, usize)> {
+    numeric_entity(text).or_else(|| {
+        NAMED_ENTITIES
+            .iter()
+            .find(|(name, _)| text.starts_with(name))
+            .map(|&(name, code)| (code, name.len()))
+    })
+}
+
+/// Parse a percent-encoded multi-byte UTF-8 character such as `%E2%80%8B`. Single bytes
+/// (`%20`, `%0A`) are plain ASCII and are not reported.
+fn percent_encoded(text: &str) -> Option<(u32, usize)> {
+    let byte_at = |i: usize| {
+        let triplet = text.get(i * 3..i * 3 + 3)?.strip_prefix('%')?;
+        hex_prefix(triplet, 2, 2).map(|(v, _)| v as u8)
+    };
+    let lead = byte_at(0)?;
+    let len = match lead {
+        0xC2..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF4 => 4,
+        _ => return None,
+    };
+    let bytes = (0..len).map(byte_at).collect::<Option<Vec<u8>>>()?;
+    let ch = std::str::from_utf8(&bytes).ok()?.chars().next()?;
+    Some((ch as u32, len * 3))
+}
+
+/// All escape sequences in `line`. A backslash that is itself escaped (`\\u200B`) does not
+/// start an escape.
+pub fn find_escapes(line: &str) -> Vec<Escape> {
+    let mut escapes = Vec::new();
+    let mut i = 0;
+    while let Some(offset) = line[i..].find(['\\', '&', '%']) {
+        let start = i + offset;
+        let text = &line[start..];
+
+        let parsed = if text.starts_with('\\') {
+            let run = text.bytes().take_while(|&b| b == b'\\').count();
+            if run > 1 {
+                // Pairs of backslashes are literal; only an odd one out can start an escape
+                i = start + run - run % 2;
+                continue;
+            }
+            backslash_escape(text)
+        } else if text.starts_with('%') {
+            percent_encoded(text)
+        } else {
+            entity(text)
+        };
+
+        match parsed {
+            Some((code, len)) => {
+                escapes.push(Escape {
+                    start,
+                    end: start + len,
+                    code,
+                });
+                i = start + len;
+            }
+            None => i = start + 1,
+        }
+    }
+    escapes
+}
@@ -0,0 +1,122 @@
+//! `explain`: describe a code point or rule ID for someone triaging a finding.
+
+use crate::rules::{self, Rule};
+use crate::ucd;
+use crate::classify;
+
+/// Width `explain` wraps its paragraphs to.
+const WRAP_WIDTH: usize = 78;
+
+/// What an `explain` argument refers to.
+enum Query {
+    CodePoint(u32),
+    Rule(&'static Rule),
+}
+
+/// Parse `U+202E`, `0x202E`, `202E`, a single literal character, or a rule ID.
+fn parse_query(query: &str) -> Result<Query, String> {
+    let trimmed = query.trim();
+
+    if trimmed.len() > 4 && trimmed[..4].eq_ignore_ascii_case("ICD-") {
+        return rules::find(trimmed)
+            .map(Query::Rule)
+            .ok_or_else(|| format!("unknown rule: {}", trimmed));
+    }
+
+    // Bare hex needs at least four digits, so a single `A` is the letter, not U+000A.
+    let hex = ["U+", "u+", "0x", "0X"]
+        .iter()
+        .find_map(|prefix| trimmed.strip_prefix(prefix))
+        .or_else(|| (trimmed.len() >= 4).then_some(trimmed));
+    if let Some(code) = hex.and_then(|h| u32::from_str_radix(h, 16).ok()) {
+        if char::from_u32(code).is_some() {
+            return Ok(Query::CodePoint(code));
+        }
+        return Err(format!("not a Unicode scalar value: {}", trimmed));
+    }
+
+    // A pasted character; don't trim it, it may be whitespace.
+    let mut chars = query.chars();
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) => Ok(Query::CodePoint(ch as u32)),
+        _ => Err(format!(
+            "expected a code point (U+202E), a single character, or a rule ID (ICD-BIDI001): {}",
+            query
+        )),
+    }
+}
+
+/// Append `text` to `out`, wrapped to [`WRAP_WIDTH`] with every line indented by two spaces.
+fn push_wrapped(out: &mut String, text: &str) {
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && 2 + line.len() + 1 + word.len() > WRAP_WIDTH {
+            out.push_str(&format!("  {}\n", line));
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        out.push_str(&format!("  {}\n", line));
+    }
+}
+
+fn push_rule(out: &mut String, rule: &Rule) {
+    out.push_str(&format!("Rule:     {} - {}\n", rule.id, rule.title));
+    out.push_str(&format!("Severity: {}\n", rule.severity.as_str()));
+    out.push_str(&format!("Category: {}\n", rule.category));
+    for (i, &cwe) in rule.cwe.iter().enumerate() {
+        let label = if i == 0 { "CWE:" } else { "" };
+        out.push_str(&format!("{:<9} CWE-{} {}\n", label, cwe, rules::cwe_name(cwe)));
+    }
+    if let Some(option) = rule.opt_in {
+        out.push_str(&format!("Enabled:  only with {}\n", option));
+    }
+    out.push_str(&format!("Covers:   {}\n\n", rule.covers));
+    out.push_str("Why it's dangerous:\n");
+    push_wrapped(out, rule.risk);
+    out.push_str("\nExample attack:\n");
+    push_wrapped(out, rule.example);
+    out.push_str("\nRemediation:\n");
+    push_wrapped(out, rule.remediation);
+    out.push_str("\nReferences:\n");
+    for reference in rule.references {
+        out.push_str(&format!("  {}\n", reference));
+    }
+    for cwe in rule.cwe {
+        out.push_str(&format!(
+            "  https://cwe.mitre.org/data/definitions/{}.html\n",
+            cwe
+        ));
+    }
+}
+
+/// The `explain` text for `query`.
+pub fn explain(query: &str) -> Result<String, String> {
+    let mut out = String::new();
+
+    match parse_query(query)? {
+        Query::Rule(rule) => push_rule(&mut out, rule),
+        Query::CodePoint(code) => match classify(code) {
+            Some((name, description)) => {
+                let (category, category_name) = ucd::general_category_long(code);
+                out.push_str(&format!("U+{:04X} {}\n", code, name));
+                out.push_str(&format!("Category: {} ({})\n", category, category_name));
+                out.push_str(&format!("{}\n\n", description));
+                if let Some(rule) = rules::rule_for(code) {
+                    push_rule(&mut out, rule);
+                }
+            }
+            None => out.push_str(&format!(
+                "U+{:04X} {} is not flagged by any rule.\n",
+                code,
+                ucd::char_name(code)
+            )),
+        },
+    }
+
+    Ok(out)
+}
@@ -0,0 +1,107 @@
+//! `scan-url`: fetch a document over HTTP(S) and scan it without saving it first, for checking
+//! a raw GitHub, gist, or pastebin link before copying code from it. `scan-package` downloads
+//! through here too.
+//!
+//! The body is only read when its Content-Type is on the allowlist, and reading stops at the
+//! size limit, so a link that turns out to be a binary or a huge file costs little.
+
+/// Content types read by default: text and the textual types servers give to code and data.
+pub const DEFAULT_ALLOWED_TYPES: &[&str] = &[
+    "text/*",
+    "application/json",
+    "application/javascript",
+    "application/ecmascript",
+    "application/xml",
+    "application/toml",
+    "application/yaml",
+    "application/x-yaml",
+    "application/x-sh",
+    "application/x-python",
+];
+
+/// Seconds to wait for the server before giving up.
+#[cfg(feature = "http")]
+const TIMEOUT_SECS: u64 = 30;
+
+/// True if `mime_type` is one of `allowed`, where `text/*` allows any text type and `*/*`
+/// anything.
+#[cfg(feature = "http")]
+fn type_allowed(mime_type: &str, allowed: &[String]) -> bool {
+    let mime_type = mime_type.trim().to_ascii_lowercase();
+    allowed.iter().any(|pattern| {
+        let pattern = pattern.trim().to_ascii_lowercase();
+        match pattern.strip_suffix("/*") {
+            Some("*") => true,
+            Some(kind) => mime_type
+                .split_once('/')
+                .is_some_and(|(major, _)| major == kind),
+            None => mime_type == pattern,
+        }
+    })
+}
+
+/// Check that `url` is one that can be fetched.
+fn check_url(url: &str) -> Result<(), String> {
+    let scheme = url
+        .split_once("://")
+        .map(|(scheme, _)| scheme.to_ascii_lowercase());
+    match scheme.as_deref() {
+        Some("http" | "https") => Ok(()),
+        _ => Err(format!(
+            "{}: only http:// and https:// URLs can be scanned",
+            url
+        )),
+    }
+}
+
+/// The body at `url`, if its type is `allowed` and it is no larger than `max_size` bytes
+/// (`None` for no limit).
+#[cfg(feature = "http")]
+pub fn fetch(url: &str, max_size: Option<u64>, allowed: &[String]) -> Result<Vec<u8>, String> {
+    check_url(url)?;
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(std::time::Duration::from_secs(TIMEOUT_SECS)))
+        .build()
+        .into();
+    // Registries ask clients to say who they are
+    let mut response = agent
+        .get(url)
+        .header(
+            "User-Agent",
+            concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")),
+        )
+        .call()
+        .map_err(|e| format!("{}: {}", url, e))?;
+
+    // Without a Content-Type the body is taken for a download like any other binary
+    let mime_type = response
+        .body()
+        .mime_type()
+        .unwrap_or("application/octet-stream")
+        .to_string();
+    if !type_allowed(&mime_type, allowed) {
+        return Err(format!(
+            "{}: content type {} is not allowed (see --allow-type)",
+            url, mime_type
+        ));
+    }
+
+    let body = response.body_mut().with_config();
+    let body = match max_size {
+        Some(limit) => body.limit(limit),
+        None => body.limit(u64::MAX),
+    };
+    body.read_to_vec().map_err(|e| match e {
+        ureq::Error::BodyExceedsLimit(limit) => {
+            format!("{}: larger than {} bytes (see --max-size)", url, limit)
+        }
+        e => format!("{}: {}", url, e),
+    })
+}
+
+/// The body at `url`.
+#[cfg(not(feature = "http"))]
+pub fn fetch(url: &str, _max_size: Option<u64>, _allowed: &[String]) -> Result<Vec<u8>, String> {
+    check_url(url)?;
+    Err("this build has no HTTP support (the `http` feature)".to_string())
+}
@@ -0,0 +1,93 @@
+//! C interface (`ffi` feature): scan a buffer in-process and get the detections as JSON, for
+//! embedding the detector in C, C++, or Go (cgo) without spawning a process per file.
+//!
+//! The declarations are in `include/icd.h`. Every returned string is owned by the library and
+//! must be released with `icd_string_free`. Scans use the default detection options, and the
+//! JSON is what `--json` prints for one file.
+
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+use crate::{decode_and_scan, DetectOptions};
+
+/// Scan `len` bytes at `data` as the file `name`, returning the JSON array of detections, or
+/// null if the content isn't text (see `decode_and_scan`) or the scan failed.
+fn scan_to_json(name: &str, data: &[u8]) -> *mut c_char {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let detections = decode_and_scan(data.to_vec(), name, &DetectOptions::default()).ok()?;
+        let json = serde_json::to_string(&detections).ok()?;
+        CString::new(json).ok()
+    }));
+    match result {
+        Ok(Some(json)) => json.into_raw(),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// The bytes at `data`, or an empty slice for a null pointer.
+///
+/// # Safety
+///
+/// `data` must be null or point to `len` readable bytes.
+unsafe fn bytes<'a>(data: *const u8, len: usize) -> &'a [u8] {
+    if data.is_null() {
+        &[]
+    } else {
+        slice::from_raw_parts(data, len)
+    }
+}
+
+/// Scan UTF-8 (or UTF-16 with a BOM) text and return its detections as a JSON array, or null
+/// if it isn't valid text.
+///
+/// # Safety
+///
+/// `data` must be null or point to `len` readable bytes. The result must be released with
+/// [`icd_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn icd_scan_utf8(data: *const u8, len: usize) -> *mut c_char {
+    scan_to_json("<input>", bytes(data, len))
+}
+
+/// Like [`icd_scan_utf8`], reporting the detections under the file name `name` (a
+/// NUL-terminated UTF-8 string), which also selects name-dependent handling such as JSON and
+/// YAML structure paths.
+///
+/// # Safety
+///
+/// `name` must be a valid NUL-terminated string, and `data` null or point to `len` readable
+/// bytes. The result must be released with [`icd_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn icd_scan_file(
+    name: *const c_char,
+    data: *const u8,
+    len: usize,
+) -> *mut c_char {
+    if name.is_null() {
+        return ptr::null_mut();
+    }
+    let Ok(name) = CStr::from_ptr(name).to_str() else {
+        return ptr::null_mut();
+    };
+    scan_to_json(name, bytes(data, len))
+}
+
+/// Release a string returned by the library. Null is ignored.
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by this library that hasn't been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn icd_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// The library version, e.g. `1.0.0`, as a static string that must not be released.
+#[no_mangle]
+pub extern "C" fn icd_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
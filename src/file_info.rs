@@ -0,0 +1,201 @@
+//! `--file-info`: the encoding, byte order mark, and line endings of every scanned file, for
+//! hygiene audits that would otherwise run `file` over the tree in a second pass.
+//!
+//! Each file is read once more, in chunks, after it is scanned (files whose results came from
+//! `--cache` included). The encoding is `ascii`, `utf-8`, `utf-16le`, `utf-16be` (given a
+//! byte order mark), or `other` for bytes that aren't valid UTF-8; line endings are counted
+//! in the file's own encoding.
+
+use std::collections::BTreeMap;
+use std::io::Read;
+
+use serde::Serialize;
+
+use crate::encoding::{self, Encoding};
+use crate::visualize;
+
+/// What the bytes of one file are.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileInfo {
+    pub path: String,
+
+    /// `ascii`, `utf-8`, `utf-16le`, `utf-16be`, or `other` (not valid UTF-8).
+    pub encoding: &'static str,
+
+    /// The byte order mark the file starts with: `utf-8`, `utf-16le`, `utf-16be`, or none.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bom: Option<&'static str>,
+
+    /// `lf`, `crlf`, `cr`, `mixed`, or `none` for a file without line breaks.
+    pub line_endings: &'static str,
+
+    /// Line breaks of each kind.
+    pub lf: usize,
+    pub crlf: usize,
+    pub cr: usize,
+}
+
+/// Counts line breaks across chunks, so a CR LF split between two reads is one break.
+#[derive(Default)]
+struct LineEndings {
+    lf: usize,
+    crlf: usize,
+    cr: usize,
+    after_cr: bool,
+}
+
+impl LineEndings {
+    fn add(&mut self, unit: u16) {
+        match unit {
+            0x0A if self.after_cr => {
+                self.crlf += 1;
+                self.cr -= 1;
+            }
+            0x0A => self.lf += 1,
+            0x0D => self.cr += 1,
+            _ => {}
+        }
+        self.after_cr = unit == 0x0D;
+    }
+
+    fn style(&self) -> &'static str {
+        match (self.lf > 0, self.crlf > 0, self.cr > 0) {
+            (false, false, false) => "none",
+            (true, false, false) => "lf",
+            (false, true, false) => "crlf",
+            (false, false, true) => "cr",
+            _ => "mixed",
+        }
+    }
+}
+
+/// Read `reader` to the end and say what its bytes are, reporting them under `path`.
+pub fn inspect<R: Read>(mut reader: R, path: &str) -> std::io::Result<FileInfo> {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut head = Vec::new();
+    // Read up to the BOM, however the reads split it
+    while head.len() < 4 {
+        let n = reader.read(&mut buf[..4 - head.len()])?;
+        if n == 0 {
+            break;
+        }
+        head.extend_from_slice(&buf[..n]);
+    }
+    let utf16 = encoding::utf16_bom(&head);
+    let bom = match utf16 {
+        Some(Encoding::Utf16Be) => Some("utf-16be"),
+        Some(_) => Some("utf-16le"),
+        None if head.starts_with(b"\xEF\xBB\xBF") => Some("utf-8"),
+        None => None,
+    };
+
+    let mut endings = LineEndings::default();
+    let mut ascii = true;
+    let mut valid = true;
+    // Bytes of a code point (UTF-8) or code unit (UTF-16) split between reads
+    let mut pending: Vec<u8> = match utf16 {
+        Some(_) => head[2..].to_vec(),
+        None => head,
+    };
+    loop {
+        let n = reader.read(&mut buf)?;
+        pending.extend_from_slice(&buf[..n]);
+        let done = n == 0;
+        let used = match utf16 {
+            Some(encoding) => {
+                let pairs = pending.chunks_exact(2);
+                for pair in pairs {
+                    let pair = [pair[0], pair[1]];
+                    endings.add(match encoding {
+                        Encoding::Utf16Be => u16::from_be_bytes(pair),
+                        _ => u16::from_le_bytes(pair),
+                    });
+                }
+                pending.len() / 2 * 2
+            }
+            None => {
+                let used = match std::str::from_utf8(&pending) {
+                    Ok(_) => pending.len(),
+                    Err(e) if e.error_len().is_none() && !done => e.valid_up_to(),
+                    Err(_) => {
+                        valid = false;
+                        pending.len()
+                    }
+                };
+                for &byte in &pending[..used] {
+                    ascii &= byte.is_ascii();
+                    endings.add(byte.into());
+                }
+                used
+            }
+        };
+        pending.drain(..used);
+        if done {
+            break;
+        }
+    }
+
+    let encoding = match utf16 {
+        Some(Encoding::Utf16Be) => "utf-16be",
+        Some(_) => "utf-16le",
+        None if !valid => "other",
+        None if ascii => "ascii",
+        None => "utf-8",
+    };
+    Ok(FileInfo {
+        path: path.to_string(),
+        encoding,
+        bom,
+        line_endings: endings.style(),
+        lf: endings.lf,
+        crlf: endings.crlf,
+        cr: endings.cr,
+    })
+}
+
+/// How many of `files` have each value of `key`, most common first.
+fn tally(files: &[FileInfo], key: impl Fn(&FileInfo) -> &'static str) -> String {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for file in files {
+        *counts.entry(key(file)).or_insert(0) += 1;
+    }
+    let mut counts: Vec<(&str, usize)> = counts.into_iter().collect();
+    counts.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+    counts
+        .iter()
+        .map(|(value, count)| format!("{} {}", count, value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Format the census for text output; with `verbose`, one line per file after the totals.
+pub fn format_file_info(files: &[FileInfo], verbose: bool) -> String {
+    let mut output = format!("File info: {} file(s)\n", files.len());
+    if files.is_empty() {
+        return output;
+    }
+    output.push_str(&format!("  Encodings: {}\n", tally(files, |f| f.encoding)));
+    output.push_str(&format!(
+        "  Byte order marks: {}\n",
+        tally(files, |f| f.bom.unwrap_or("none"))
+    ));
+    output.push_str(&format!(
+        "  Line endings: {}\n",
+        tally(files, |f| f.line_endings)
+    ));
+    if verbose {
+        for file in files {
+            let bom = file
+                .bom
+                .map_or(String::new(), |bom| format!(", {} BOM", bom));
+            output.push_str(&format!(
+                "    {}: {}{}, {}\n",
+                visualize(&file.path),
+                file.encoding,
+                bom,
+                file.line_endings
+            ));
+        }
+    }
+    output
+}
@@ -0,0 +1,41 @@
+//! Detection fingerprints: an identity for a finding that survives edits elsewhere in the
+//! file, so baselines, `diff-reports`, and dashboards can tell one run's findings from the
+//! next without each inventing a hash of its own.
+//!
+//! The fingerprint is the 64-bit FNV-1a hash, in hex, of the rule, the path (with forward
+//! slashes and without a leading `./`), the code point, and up to [`WINDOW`] characters on
+//! either side of the character on its line. Line and column are left out, so lines added
+//! above a finding keep its fingerprint; the same character in the same surroundings twice in
+//! one file has the same fingerprint both times.
+
+use crate::cache::content_hash;
+use crate::Detection;
+
+/// Characters of the line on either side of the detection that go into its fingerprint.
+pub const WINDOW: usize = 8;
+
+/// The fingerprint of `d`, found among `context`.
+pub fn fingerprint(d: &Detection, context: &str) -> String {
+    let path = d.file.replace('\\', "/");
+    let path = path.strip_prefix("./").unwrap_or(&path);
+    let key = format!("{}\0{}\0{:X}\0{}", d.rule, path, d.code, context);
+    content_hash(key.as_bytes())
+}
+
+/// The characters of `line` around the 1-indexed `char_index`, the character itself included.
+/// `starts` is the byte offset in `line` of each of its characters, so the window is sliced
+/// without walking the line: a long line with many detections stays linear.
+pub fn window<'a>(line: &'a str, starts: &[usize], char_index: usize) -> &'a str {
+    let at = char_index.saturating_sub(1);
+    let byte = |i: usize| starts.get(i).copied().unwrap_or(line.len());
+    &line[byte(at.saturating_sub(WINDOW))..byte(at + 1 + WINDOW)]
+}
+
+/// Fingerprint the detections that have none yet by what was found alone: a name, an invalid
+/// byte sequence, or an identifier, which have no line around them to take a window of.
+pub fn fill(detections: &mut [Detection]) {
+    for d in detections.iter_mut().filter(|d| d.fingerprint.is_empty()) {
+        let context = d.invalid_bytes.as_deref().unwrap_or(&d.char);
+        d.fingerprint = fingerprint(d, context);
+    }
+}
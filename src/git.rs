@@ -0,0 +1,377 @@
+//! Git integration, implemented by running the `git` CLI.
+
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Run `git` with `args` in the current directory and return its stdout.
+pub fn run_git(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|e| format!("could not run git: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Absolute path of the root of the repository containing the current directory.
+pub fn repo_root() -> Result<PathBuf, String> {
+    let root = run_git(&["rev-parse", "--show-toplevel"])?;
+    PathBuf::from(root.trim())
+        .canonicalize()
+        .map_err(|e| format!("could not resolve repository root: {}", e))
+}
+
+/// Run `git command --stdin -z args` on `paths` and return its NUL-separated output and its
+/// exit code.
+fn query_paths(
+    command: &str,
+    args: &[&str],
+    paths: &[&Path],
+) -> Result<(Vec<u8>, Option<i32>), String> {
+    let mut child = Command::new("git")
+        .args([command, "--stdin", "-z"])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("could not run git: {}", e))?;
+
+    // Feed paths from a separate thread so a full stdout pipe can't deadlock us.
+    let mut requests = Vec::new();
+    for path in paths {
+        requests.extend_from_slice(path.to_string_lossy().as_bytes());
+        requests.push(0);
+    }
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| format!("git {} has no stdin", command))?;
+    let writer = std::thread::spawn(move || stdin.write_all(&requests));
+
+    let mut output = Vec::new();
+    child
+        .stdout
+        .take()
+        .ok_or_else(|| format!("git {} has no stdout", command))?
+        .read_to_end(&mut output)
+        .map_err(|e| format!("could not read from git {}: {}", command, e))?;
+    let _ = writer.join();
+    let status = child
+        .wait()
+        .map_err(|e| format!("git {} failed: {}", command, e))?;
+    Ok((output, status.code()))
+}
+
+/// The paths among `paths` that `.gitignore` (or another exclude file) ignores, as given.
+/// Tracked files are never ignored. Fails outside a repository.
+pub fn ignored_paths(paths: &[&Path]) -> Result<HashSet<PathBuf>, String> {
+    Ok(ignore_rules(paths)?.into_keys().collect())
+}
+
+/// Like [`ignored_paths`], with the rule that ignores each path as `source:line:pattern`, e.g.
+/// `.gitignore:3:*.log`.
+pub fn ignore_rules(paths: &[&Path]) -> Result<HashMap<PathBuf, String>, String> {
+    let (output, code) = query_paths("check-ignore", &["--verbose"], paths)?;
+    // Exit status 1 means nothing is ignored; 128 is an error, e.g. not in a repository
+    if !matches!(code, Some(0 | 1)) {
+        return Err("git check-ignore failed (not in a repository?)".to_string());
+    }
+    // Each answer is four fields: source, line number, pattern, and path. A path matching a
+    // negated pattern (`!keep.log`) is listed too, but not ignored.
+    let fields: Vec<&[u8]> = output.split(|&b| b == 0).collect();
+    Ok(fields
+        .chunks_exact(4)
+        .filter(|answer| !answer[2].starts_with(b"!"))
+        .map(|answer| {
+            let rule = format!(
+                "{}:{}:{}",
+                String::from_utf8_lossy(answer[0]),
+                String::from_utf8_lossy(answer[1]),
+                String::from_utf8_lossy(answer[2])
+            );
+            (PathBuf::from(String::from_utf8_lossy(answer[3]).into_owned()), rule)
+        })
+        .collect())
+}
+
+/// The paths among `paths` that `.gitattributes` marks `linguist-generated` or
+/// `linguist-vendored`, as given. Fails outside a repository.
+pub fn generated_paths(paths: &[&Path]) -> Result<HashSet<PathBuf>, String> {
+    let (output, code) = query_paths(
+        "check-attr",
+        &["linguist-generated", "linguist-vendored"],
+        paths,
+    )?;
+    if code != Some(0) {
+        return Err("git check-attr failed (not in a repository?)".to_string());
+    }
+    // Each answer is three fields: path, attribute, and `set`, `unset`, `unspecified`, or
+    // the value
+    let fields: Vec<&[u8]> = output.split(|&b| b == 0).collect();
+    Ok(fields
+        .chunks_exact(3)
+        .filter(|answer| matches!(answer[2], b"set" | b"true"))
+        .map(|answer| PathBuf::from(String::from_utf8_lossy(answer[0]).into_owned()))
+        .collect())
+}
+
+/// Undo git's C-style quoting of unusual paths (`"a\tb"`), leaving plain paths untouched.
+pub fn unquote_path(path: &str) -> String {
+    let Some(inner) = path.strip_prefix('"').and_then(|p| p.strip_suffix('"')) else {
+        return path.to_string();
+    };
+
+    let mut bytes = Vec::with_capacity(inner.len());
+    let mut chars = inner.bytes().peekable();
+    while let Some(b) = chars.next() {
+        if b != b'\\' {
+            bytes.push(b);
+            continue;
+        }
+        match chars.next() {
+            Some(b'n') => bytes.push(b'\n'),
+            Some(b't') => bytes.push(b'\t'),
+            Some(d @ b'0'..=b'7') => {
+                let mut value = u32::from(d - b'0');
+                for _ in 0..2 {
+                    if let Some(&next @ b'0'..=b'7') = chars.peek() {
+                        value = value * 8 + u32::from(next - b'0');
+                        chars.next();
+                    }
+                }
+                bytes.push(value as u8);
+            }
+            Some(other) => bytes.push(other),
+            None => {}
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Which lines of a file count as changed.
+#[derive(Debug)]
+enum LineSet {
+    /// Every line (new or untracked file).
+    All,
+
+    /// Inclusive 1-indexed line ranges.
+    Ranges(Vec<(usize, usize)>),
+}
+
+/// Lines added or modified relative to a base revision, keyed by repo-relative path.
+#[derive(Debug)]
+pub struct ChangedLines {
+    root: PathBuf,
+    files: HashMap<PathBuf, LineSet>,
+}
+
+impl ChangedLines {
+    /// Parse `git diff --unified=0` output into per-file added line ranges.
+    pub fn from_diff(root: PathBuf, diff: &str) -> Self {
+        let mut files: HashMap<PathBuf, LineSet> = HashMap::new();
+        let mut current: Option<PathBuf> = None;
+
+        for line in diff.lines() {
+            if let Some(target) = line.strip_prefix("+++ ") {
+                // git appends a tab to names containing spaces, for patch(1) compatibility
+                let target = unquote_path(target.trim_end_matches('\t'));
+                current = target.strip_prefix("b/").map(PathBuf::from);
+                continue;
+            }
+
+            let (Some(file), Some(hunk)) = (&current, line.strip_prefix("@@ ")) else {
+                continue;
+            };
+
+            // @@ -old[,len] +new[,len] @@
+            let Some(new_range) = hunk.split_whitespace().find_map(|t| t.strip_prefix('+')) else {
+                continue;
+            };
+            let mut parts = new_range.splitn(2, ',');
+            let start: usize = parts.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+            let len: usize = parts.next().and_then(|s| s.parse().ok()).unwrap_or(1);
+            if len == 0 {
+                continue;
+            }
+
+            if let LineSet::Ranges(ranges) = files
+                .entry(file.clone())
+                .or_insert_with(|| LineSet::Ranges(Vec::new()))
+            {
+                ranges.push((start, start + len - 1));
+            }
+        }
+
+        Self { root, files }
+    }
+
+    /// Collect lines changed between the merge base of `base` and HEAD, plus uncommitted
+    /// and untracked changes in the working tree.
+    ///
+    /// Using the merge base means commits that landed on `base` after this branch forked
+    /// are not mistaken for changes made here. With `staged`, the index is compared instead
+    /// of the working tree and untracked files are not included.
+    pub fn since(base: &str, staged: bool) -> Result<Self, String> {
+        let root = repo_root()?;
+        let merge_base = run_git(&["merge-base", base, "HEAD"])?;
+
+        let mut args = vec![
+            "-c",
+            "core.quotePath=false",
+            "diff",
+            "--unified=0",
+            "--no-color",
+            "--no-ext-diff",
+            "--no-renames",
+        ];
+        if staged {
+            args.push("--cached");
+        }
+        args.extend([merge_base.trim(), "--"]);
+        let diff = run_git(&args)?;
+
+        let mut changed = Self::from_diff(root, &diff);
+        if staged {
+            return Ok(changed);
+        }
+
+        let untracked = run_git(&[
+            "-c",
+            "core.quotePath=false",
+            "ls-files",
+            "--others",
+            "--exclude-standard",
+            "--full-name",
+        ])?;
+        for path in untracked.lines() {
+            changed.files.insert(PathBuf::from(path), LineSet::All);
+        }
+
+        Ok(changed)
+    }
+
+    /// Path of `path` relative to the repository root, if it lies inside the repository.
+    pub fn repo_relative(&self, path: &Path) -> Option<PathBuf> {
+        let absolute = path.canonicalize().ok()?;
+        absolute.strip_prefix(&self.root).ok().map(Path::to_path_buf)
+    }
+
+    /// True if the repo-relative file has any changed lines.
+    pub fn contains_file(&self, relative: &Path) -> bool {
+        self.files.contains_key(relative)
+    }
+
+    /// True if `line` (1-indexed) of the repo-relative file was added or modified.
+    pub fn contains_line(&self, relative: &Path, line: usize) -> bool {
+        match self.files.get(relative) {
+            Some(LineSet::All) => true,
+            Some(LineSet::Ranges(ranges)) => ranges
+                .iter()
+                .any(|&(start, end)| (start..=end).contains(&line)),
+            None => false,
+        }
+    }
+}
+
+/// A file as it currently exists in the git index.
+#[derive(Debug)]
+pub struct StagedFile {
+    /// Path relative to the current directory.
+    pub path: PathBuf,
+
+    /// Path relative to the repository root.
+    pub repo_path: PathBuf,
+
+    /// Blob content staged for the next commit.
+    pub content: Vec<u8>,
+}
+
+/// Read every added, copied, modified, or renamed file staged under the current directory.
+///
+/// Contents come from the index, not the working tree, so partially staged files are
+/// checked exactly as they will be committed. Blobs are fetched through a single
+/// `git cat-file --batch` process.
+pub fn staged_files() -> Result<Vec<StagedFile>, String> {
+    let prefix = run_git(&["rev-parse", "--show-prefix"])?;
+    let prefix = prefix.trim_end_matches('\n');
+
+    let names = run_git(&[
+        "diff",
+        "--cached",
+        "--name-only",
+        "--relative",
+        "--diff-filter=ACMR",
+        "-z",
+    ])?;
+    let paths: Vec<&str> = names.split('\0').filter(|p| !p.is_empty()).collect();
+    if paths.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut child = Command::new("git")
+        .args(["cat-file", "--batch"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("could not run git: {}", e))?;
+
+    // Feed requests from a separate thread so a full stdout pipe can't deadlock us.
+    let requests: String = paths
+        .iter()
+        .map(|p| format!(":{}{}\n", prefix, p))
+        .collect();
+    let mut stdin = child.stdin.take().ok_or("git cat-file has no stdin")?;
+    let writer = std::thread::spawn(move || stdin.write_all(requests.as_bytes()));
+
+    let mut output = Vec::new();
+    child
+        .stdout
+        .take()
+        .ok_or("git cat-file has no stdout")?
+        .read_to_end(&mut output)
+        .map_err(|e| format!("could not read from git cat-file: {}", e))?;
+    let _ = writer.join();
+    child
+        .wait()
+        .map_err(|e| format!("git cat-file failed: {}", e))?;
+
+    // Each response is "<oid> <type> <size>\n<content>\n".
+    let mut files = Vec::with_capacity(paths.len());
+    let mut rest = output.as_slice();
+    for path in paths {
+        let header_end = rest
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or("truncated git cat-file output")?;
+        let header = String::from_utf8_lossy(&rest[..header_end]).into_owned();
+        rest = &rest[header_end + 1..];
+
+        let size: usize = match header.split(' ').nth(2).and_then(|s| s.parse().ok()) {
+            Some(size) => size,
+            None => return Err(format!("could not read staged blob for {}: {}", path, header)),
+        };
+        if rest.len() < size + 1 {
+            return Err("truncated git cat-file output".to_string());
+        }
+
+        files.push(StagedFile {
+            path: PathBuf::from(path),
+            repo_path: PathBuf::from(format!("{}{}", prefix, path)),
+            content: rest[..size].to_vec(),
+        });
+        rest = &rest[size + 1..];
+    }
+
+    Ok(files)
+}
@@ -0,0 +1,101 @@
+//! `--hex-context N`: the raw bytes around each detection, as an `xxd`-style dump.
+//!
+//! A real U+200B and mojibake from a botched conversion can look the same once decoded; the
+//! bytes tell them apart (`e2 80 8b` versus, say, `c3 a2 c2 80 c2 8b`).
+
+use serde::{Deserialize, Serialize};
+
+use crate::render::{self, TextStyle};
+use crate::Detection;
+
+/// Bytes per dump row, as in `xxd`.
+const ROW_LEN: usize = 16;
+
+/// The bytes around a detection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HexContext {
+    /// 0-indexed file offset of the first byte, as `xxd` prints it.
+    pub offset: usize,
+
+    /// The bytes as lowercase hex, two digits per byte.
+    pub hex: String,
+}
+
+/// Number of bytes the detected character takes in its file, given the file's `bytes` from
+/// where it starts.
+fn char_len(d: &Detection, bytes: &[u8]) -> usize {
+    if let Some(hex) = &d.invalid_bytes {
+        return hex.split(' ').count();
+    }
+    d.encoding
+        .map_or(d.char.len(), |encoding| encoding.encoded_len(&d.char, bytes))
+}
+
+/// Set `hex_context` on every detection in the content of a file, with up to `radius` bytes
+/// on each side of the character.
+pub fn attach(bytes: &[u8], radius: usize, detections: &mut [Detection]) {
+    for d in detections.iter_mut() {
+        if d.line == 0 {
+            continue;
+        }
+        let start = (d.byte_offset - 1).min(bytes.len());
+        let end = (start + char_len(d, &bytes[start..])).min(bytes.len());
+        let from = start.saturating_sub(radius);
+        let to = end.saturating_add(radius).min(bytes.len());
+        d.hex_context = Some(HexContext {
+            offset: from,
+            hex: bytes[from..to]
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect(),
+        });
+    }
+}
+
+/// Format a detection's bytes as `xxd` rows, highlighting the character's own bytes when
+/// coloring.
+pub fn format_hex_context(d: &Detection, context: &HexContext, style: TextStyle) -> String {
+    let bytes: Vec<u8> = (0..context.hex.len() / 2)
+        .filter_map(|i| u8::from_str_radix(&context.hex[2 * i..2 * i + 2], 16).ok())
+        .collect();
+    let start = d.byte_offset - 1;
+    let end = start + char_len(d, bytes.get(start - context.offset..).unwrap_or_default());
+
+    let mut output = String::new();
+    for (row, chunk) in bytes.chunks(ROW_LEN).enumerate() {
+        let row_offset = context.offset + row * ROW_LEN;
+        let mut hex = String::new();
+        let mut ascii = String::new();
+        for (i, &byte) in chunk.iter().enumerate() {
+            if i > 0 && i % 2 == 0 {
+                hex.push(' ');
+            }
+            let offset = row_offset + i;
+            let highlight = |text: &str| {
+                if (start..end).contains(&offset) {
+                    style.paint(text, render::RED)
+                } else {
+                    text.to_string()
+                }
+            };
+            hex.push_str(&highlight(&format!("{:02x}", byte)));
+            let shown = if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '.'
+            };
+            ascii.push_str(&highlight(&shown.to_string()));
+        }
+        // Pad short rows so the ASCII column lines up: 2 digits per byte, a space per pair
+        let width = ROW_LEN * 2 + ROW_LEN / 2 - 1;
+        let visible = chunk.len() * 2 + chunk.len().saturating_sub(1) / 2;
+        output.push_str(&format!(
+            "      {:08x}: {}{}  {}\n",
+            row_offset,
+            hex,
+            " ".repeat(width - visible),
+            ascii
+        ));
+    }
+    output
+}
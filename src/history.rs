@@ -0,0 +1,298 @@
+//! `scan-history`: find the commits that introduced suspicious characters.
+//!
+//! Walks `git log` oldest-first. For every file a commit adds or modifies, the new blob is
+//! scanned in full (so line, column, and byte positions are exact) and only detections on
+//! lines that commit added are kept. A character is therefore attributed to the commit that
+//! introduced it, not to every later commit that touched the same file.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use serde::Serialize;
+
+use crate::git::{unquote_path, ChangedLines};
+use crate::{
+    detect_invisible_characters, should_ignore_path, sniff_binary_bytes, DetectOptions, Detection,
+    IgnoreDirs,
+};
+
+/// A detection attributed to the commit that introduced it.
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryDetection {
+    /// Full commit hash.
+    pub commit: String,
+
+    /// Commit author as `Name <email>`.
+    pub author: String,
+
+    /// Author date in ISO 8601 format.
+    pub date: String,
+
+    #[serde(flatten)]
+    pub detection: Detection,
+}
+
+/// A long-running `git cat-file --batch` process for fetching blobs one at a time.
+struct BlobReader {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl BlobReader {
+    fn spawn() -> Result<Self, String> {
+        let mut child = Command::new("git")
+            .args(["cat-file", "--batch"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("could not run git: {}", e))?;
+        let stdin = child.stdin.take().ok_or("git cat-file has no stdin")?;
+        let stdout = child.stdout.take().ok_or("git cat-file has no stdout")?;
+
+        Ok(Self {
+            child,
+            stdin: Some(stdin),
+            stdout: BufReader::new(stdout),
+        })
+    }
+
+    /// Fetch the content of blob `oid`, or `None` if git doesn't have it.
+    fn read(&mut self, oid: &str) -> Result<Option<Vec<u8>>, String> {
+        let io_err = |e: std::io::Error| format!("git cat-file failed: {}", e);
+        let stdin = self.stdin.as_mut().ok_or("git cat-file was closed")?;
+        writeln!(stdin, "{}", oid).map_err(io_err)?;
+        stdin.flush().map_err(io_err)?;
+
+        // "<oid> <type> <size>" or "<oid> missing"
+        let mut header = String::new();
+        self.stdout.read_line(&mut header).map_err(io_err)?;
+        let Some(size) = header
+            .split_whitespace()
+            .nth(2)
+            .and_then(|s| s.parse::<usize>().ok())
+        else {
+            return Ok(None);
+        };
+
+        let mut content = vec![0u8; size + 1];
+        self.stdout.read_exact(&mut content).map_err(io_err)?;
+        content.truncate(size);
+        Ok(Some(content))
+    }
+}
+
+impl Drop for BlobReader {
+    fn drop(&mut self) {
+        // Closing stdin makes cat-file exit.
+        self.stdin.take();
+        let _ = self.child.wait();
+    }
+}
+
+/// One commit from the log stream, accumulated until the next commit header.
+#[derive(Default)]
+struct PendingCommit {
+    hash: String,
+    author: String,
+    date: String,
+
+    /// (path, new blob id) for every added or modified file.
+    blobs: Vec<(String, String)>,
+
+    /// The `+++` and `@@` lines of the commit's patch; enough to recover added line ranges.
+    patch: String,
+}
+
+impl PendingCommit {
+    fn scan(
+        self,
+        blobs: &mut BlobReader,
+        scan_bundles: bool,
+        out: &mut Vec<HistoryDetection>,
+    ) -> Result<(), String> {
+        if self.blobs.is_empty() {
+            return Ok(());
+        }
+        let added = ChangedLines::from_diff(PathBuf::new(), &self.patch);
+
+        for (path, oid) in &self.blobs {
+            let relative = Path::new(path);
+            if should_ignore_path(path, scan_bundles, &IgnoreDirs::default())
+                || !added.contains_file(relative)
+            {
+                continue;
+            }
+
+            let Some(content) = blobs.read(oid)? else {
+                continue;
+            };
+            if sniff_binary_bytes(&content).is_some() {
+                continue;
+            }
+            let Ok(text) = String::from_utf8(content) else {
+                continue;
+            };
+
+            out.extend(
+                detect_invisible_characters(&text, path, &DetectOptions::default())
+                    .into_iter()
+                    .filter(|d| added.contains_line(relative, d.line))
+                    .map(|detection| HistoryDetection {
+                        commit: self.hash.clone(),
+                        author: self.author.clone(),
+                        date: self.date.clone(),
+                        detection,
+                    }),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Scan every commit reachable from HEAD (or only those after `since`), oldest first.
+pub fn scan_history(since: Option<&str>, scan_bundles: bool) -> Result<Vec<HistoryDetection>, String> {
+    let range = match since {
+        Some(rev) => format!("{}..HEAD", rev),
+        None => "HEAD".to_string(),
+    };
+
+    let mut log = Command::new("git")
+        .args([
+            "-c",
+            "core.quotePath=false",
+            "log",
+            "--reverse",
+            "--no-renames",
+            "--no-abbrev",
+            "--no-color",
+            "--no-ext-diff",
+            "--raw",
+            "-p",
+            "--unified=0",
+            "--format=%x01%H%x00%an <%ae>%x00%aI",
+            &range,
+            "--",
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("could not run git: {}", e))?;
+    let stdout = log.stdout.take().ok_or("git log has no stdout")?;
+
+    let mut blobs = BlobReader::spawn()?;
+    let mut detections = Vec::new();
+    let mut current: Option<PendingCommit> = None;
+
+    let mut reader = BufReader::new(stdout);
+    let mut raw_line = Vec::new();
+    loop {
+        raw_line.clear();
+        let n = reader
+            .read_until(b'\n', &mut raw_line)
+            .map_err(|e| format!("could not read git log: {}", e))?;
+        if n == 0 {
+            break;
+        }
+        let line = String::from_utf8_lossy(&raw_line);
+        let line = line.trim_end_matches('\n');
+
+        if let Some(header) = line.strip_prefix('\x01') {
+            if let Some(done) = current.take() {
+                done.scan(&mut blobs, scan_bundles, &mut detections)?;
+            }
+            let mut fields = header.split('\0');
+            current = Some(PendingCommit {
+                hash: fields.next().unwrap_or_default().to_string(),
+                author: fields.next().unwrap_or_default().to_string(),
+                date: fields.next().unwrap_or_default().to_string(),
+                ..PendingCommit::default()
+            });
+            continue;
+        }
+
+        let Some(commit) = current.as_mut() else {
+            continue;
+        };
+
+        if let Some(raw) = line.strip_prefix(':') {
+            // :old_mode new_mode old_oid new_oid status<TAB>path
+            let Some((meta, path)) = raw.split_once('\t') else {
+                continue;
+            };
+            let fields: Vec<&str> = meta.split_whitespace().collect();
+            if let [_, _, _, new_oid, status] = fields.as_slice() {
+                if status.starts_with(['A', 'M', 'T']) {
+                    commit.blobs.push((unquote_path(path), new_oid.to_string()));
+                }
+            }
+        } else if line.starts_with("+++ ") || line.starts_with("@@ ") {
+            commit.patch.push_str(line);
+            commit.patch.push('\n');
+        }
+    }
+
+    if let Some(done) = current.take() {
+        done.scan(&mut blobs, scan_bundles, &mut detections)?;
+    }
+
+    let output = log
+        .wait_with_output()
+        .map_err(|e| format!("git log failed: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git log {} failed: {}",
+            range,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(detections)
+}
+
+/// Format history detections as text, grouped by commit in the order they were made.
+pub fn format_history_text(detections: &[HistoryDetection]) -> String {
+    if detections.is_empty() {
+        return "No suspicious invisible characters found in history.".to_string();
+    }
+
+    let mut output = format!(
+        "Found {} suspicious character(s) introduced in history:\n\n",
+        detections.len()
+    );
+
+    let mut last_commit = "";
+    let mut last_file = "";
+    for h in detections {
+        if h.commit != last_commit {
+            if !last_commit.is_empty() {
+                output.push('\n');
+            }
+            output.push_str(&format!("commit {} ({}, {})\n", h.commit, h.author, h.date));
+            last_commit = &h.commit;
+            last_file = "";
+        }
+        let d = &h.detection;
+        if d.file != last_file {
+            output.push_str(&format!("  {}\n", d.file));
+            last_file = &d.file;
+        }
+        output.push_str(&format!(
+            "    Line {}:{} (byte {}) - {} (U+{:04X}, {}, {}) [{}, {}]\n",
+            d.line,
+            d.char_index,
+            d.byte_offset,
+            d.name,
+            d.code,
+            d.general_category,
+            d.category,
+            d.rule,
+            d.severity.as_str()
+        ));
+        output.push_str(&format!("  {}\n", d.description));
+    }
+
+    output
+}
@@ -0,0 +1,147 @@
+//! `install-hook` / `uninstall-hook`: manage a git pre-commit hook that runs the scanner.
+//!
+//! The hook is a marked block inserted right after the shebang of `pre-commit`, so an existing
+//! hook keeps working (and can't skip the check with an early `exit 0`). Reinstalling replaces
+//! the block; uninstalling removes it and deletes the file if nothing else is left. The block is
+//! shell, so an existing hook in another language is left alone and installing fails.
+
+use std::fs;
+use std::path::PathBuf;
+
+use crate::git::run_git;
+
+const BEGIN_MARKER: &str = "# >>> invisible-char-detector >>>";
+const END_MARKER: &str = "# <<< invisible-char-detector <<<";
+
+/// Path of the pre-commit hook, honoring `core.hooksPath`.
+fn pre_commit_path() -> Result<PathBuf, String> {
+    let hooks = run_git(&["rev-parse", "--git-path", "hooks"])?;
+    Ok(PathBuf::from(hooks.trim()).join("pre-commit"))
+}
+
+/// Quote `value` for a POSIX shell.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// The hook block. Before the first commit there is no HEAD to diff against, so the whole
+/// staged content is checked; afterwards only lines the commit adds.
+fn hook_block(binary: &str, pattern: &str) -> String {
+    let binary = shell_quote(binary);
+    let pattern = shell_quote(pattern);
+    format!(
+        "{BEGIN_MARKER}\n\
+         if git rev-parse --verify -q HEAD >/dev/null; then\n\
+         \x20   {binary} {pattern} --staged --diff HEAD || exit $?\n\
+         else\n\
+         \x20   {binary} {pattern} --staged || exit $?\n\
+         fi\n\
+         {END_MARKER}\n"
+    )
+}
+
+/// Shells the hook block runs in.
+const SHELLS: [&str; 4] = ["sh", "bash", "dash", "zsh"];
+
+/// The interpreter a `#!` line names: the program itself, or what `env` runs.
+fn interpreter(shebang: &str) -> Option<&str> {
+    let mut words = shebang.strip_prefix("#!")?.split_whitespace();
+    let program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        return words.find(|word| !word.starts_with('-') && !word.contains('='));
+    }
+    Some(program)
+}
+
+/// Remove our block from `script`, returning the rest unchanged.
+fn strip_block(script: &str) -> String {
+    let mut kept = String::with_capacity(script.len());
+    let mut inside = false;
+    for line in script.split_inclusive('\n') {
+        match line.trim_end() {
+            BEGIN_MARKER => inside = true,
+            END_MARKER => inside = false,
+            _ if !inside => kept.push_str(line),
+            _ => {}
+        }
+    }
+    kept
+}
+
+#[cfg(unix)]
+fn make_executable(path: &std::path::Path) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut perms = fs::metadata(path)?.permissions();
+    perms.set_mode(perms.mode() | 0o755);
+    fs::set_permissions(path, perms)
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &std::path::Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Install (or refresh) the hook block in the pre-commit hook. Returns the hook path.
+pub fn install(pattern: &str) -> Result<PathBuf, String> {
+    let path = pre_commit_path()?;
+    let binary = std::env::current_exe()
+        .map_err(|e| format!("could not locate the running binary: {}", e))?;
+    let block = hook_block(&binary.to_string_lossy(), pattern);
+
+    let existing = match fs::read_to_string(&path) {
+        Ok(text) => strip_block(&text),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("could not read {}: {}", path.display(), e)),
+    };
+
+    let script = match existing.split_once('\n').unwrap_or((&existing, "")) {
+        (shebang, rest) if shebang.starts_with("#!") => {
+            if !interpreter(shebang).is_some_and(|program| SHELLS.contains(&program)) {
+                return Err(format!(
+                    "{} is not a shell script ({}); run `invisible-char-detector {} --staged` \
+                     from it instead",
+                    path.display(),
+                    shebang.trim_end(),
+                    shell_quote(pattern)
+                ));
+            }
+            format!("{}\n{}{}", shebang, block, rest)
+        }
+        _ => format!("#!/bin/sh\n{}{}", block, existing),
+    };
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("could not create {}: {}", dir.display(), e))?;
+    }
+    fs::write(&path, script).map_err(|e| format!("could not write {}: {}", path.display(), e))?;
+    make_executable(&path).map_err(|e| format!("could not chmod {}: {}", path.display(), e))?;
+
+    Ok(path)
+}
+
+/// Remove the hook block. Returns the hook path, or `None` if no block was installed.
+pub fn uninstall() -> Result<Option<PathBuf>, String> {
+    let path = pre_commit_path()?;
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("could not read {}: {}", path.display(), e)),
+    };
+
+    let remaining = strip_block(&text);
+    if remaining == text {
+        return Ok(None);
+    }
+
+    let only_shebang = remaining
+        .lines()
+        .all(|line| line.trim().is_empty() || line.starts_with("#!"));
+    let result = if only_shebang {
+        fs::remove_file(&path)
+    } else {
+        fs::write(&path, remaining)
+    };
+    result.map_err(|e| format!("could not update {}: {}", path.display(), e))?;
+
+    Ok(Some(path))
+}
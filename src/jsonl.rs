@@ -0,0 +1,94 @@
+//! `--format jsonl`: one JSON object per line, for tools that read the report as a stream.
+//!
+//! The detections of each file follow a `file` record with its path and how many there are,
+//! so a consumer knows where a file's findings end without reading ahead, and an `end` record
+//! with the counts closes the report. The records of a file are formatted together and
+//! written to stdout with a single write, so nothing else can land in the middle of one, and
+//! files come in the same order as in the other formats however many `--jobs` scanned them.
+//! With `--show-suppressed`, a `suppressed` record for each detection settings dropped comes
+//! before the `end` record.
+
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::summary::SeverityCounts;
+use crate::Detection;
+
+/// One line of the report.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Record<'a> {
+    /// Starts the detections of `path`.
+    File {
+        path: &'a str,
+        detections: usize,
+    },
+
+    Detection(&'a Detection),
+
+    /// With `--show-suppressed`, after the files: a detection settings dropped, with why.
+    Suppressed(&'a Detection),
+
+    /// Closes the report.
+    End {
+        /// Files read and scanned.
+        scanned_files: usize,
+
+        /// Files with at least one detection.
+        files: usize,
+
+        /// Suspicious characters found, by severity.
+        findings: SeverityCounts,
+    },
+}
+
+/// `record` as a line of the report, appended to `out`.
+fn push(out: &mut Vec<u8>, record: &Record) -> io::Result<()> {
+    serde_json::to_writer(&mut *out, record)?;
+    out.push(b'\n');
+    Ok(())
+}
+
+/// Write `detections` (sorted, so each file's are together) as JSON lines to `out`, a file
+/// at a time, then any `suppressed` detections.
+pub fn write_jsonl(
+    out: &mut impl Write,
+    detections: &[Detection],
+    suppressed: &[Detection],
+    scanned_files: usize,
+    findings: SeverityCounts,
+) -> io::Result<()> {
+    let mut files = 0;
+    let mut block = Vec::new();
+    for file in detections.chunk_by(|a, b| a.file == b.file) {
+        files += 1;
+        block.clear();
+        let path = &file[0].file;
+        push(
+            &mut block,
+            &Record::File {
+                path,
+                detections: file.len(),
+            },
+        )?;
+        for d in file {
+            push(&mut block, &Record::Detection(d))?;
+        }
+        out.write_all(&block)?;
+    }
+    block.clear();
+    for d in suppressed {
+        push(&mut block, &Record::Suppressed(d))?;
+    }
+    push(
+        &mut block,
+        &Record::End {
+            scanned_files,
+            files,
+            findings,
+        },
+    )?;
+    out.write_all(&block)?;
+    out.flush()
+}
@@ -0,0 +1,341 @@
+//! Invisible characters in key position (`ICD-KEY001`): a `.env` or INI key, a YAML mapping
+//! key, or a shell variable name.
+//!
+//! A zero-width space or no-break space in a value is usually just odd data. In a key it
+//! silently makes a different name: `API_KEY<U+200B>=...` sets a variable nothing reads, a
+//! YAML key with a no-break space becomes a sibling of the one it imitates, and in a shell
+//! script `PATH<U+00A0>=/tmp` is run as a command instead of an assignment.
+//!
+//! Each format has a small line tokenizer that finds the byte ranges of keys; none is a full
+//! parser. The YAML tokenizer skips block scalars and the shell tokenizer skips here-documents
+//! and single-quoted text, which is all the state carried from one line to the next.
+
+use std::ops::Range;
+
+/// A file format whose keys are checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyFormat {
+    /// `.env` files: `KEY=value`, optionally after `export`.
+    Env,
+
+    /// INI and similar: `[section]`, `key = value`, `key: value`.
+    Ini,
+
+    /// YAML block mappings.
+    Yaml,
+
+    /// Shell scripts: assignments and `$NAME` / `${NAME}` references.
+    Shell,
+}
+
+impl KeyFormat {
+    /// The format of `path`, judged by its name.
+    fn for_path(path: &str) -> Option<Self> {
+        let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+        let name = name.to_ascii_lowercase();
+        if name == ".env" || name.starts_with(".env.") || name.ends_with(".env") {
+            return Some(KeyFormat::Env);
+        }
+        if matches!(
+            name.as_str(),
+            ".bashrc" | ".bash_profile" | ".profile" | ".zshrc" | ".zprofile" | ".envrc"
+        ) {
+            return Some(KeyFormat::Shell);
+        }
+        if matches!(name.as_str(), ".editorconfig" | ".gitconfig" | ".npmrc") {
+            return Some(KeyFormat::Ini);
+        }
+        let format = match name.rsplit_once('.')?.1 {
+            "ini" | "cfg" | "conf" | "properties" | "toml" => KeyFormat::Ini,
+            "yaml" | "yml" => KeyFormat::Yaml,
+            "sh" | "bash" | "zsh" | "ksh" => KeyFormat::Shell,
+            _ => return None,
+        };
+        Some(format)
+    }
+
+    /// What a key is called in the format, for descriptions.
+    fn noun(self) -> &'static str {
+        match self {
+            KeyFormat::Env => ".env key",
+            KeyFormat::Ini => "configuration key",
+            KeyFormat::Yaml => "YAML key",
+            KeyFormat::Shell => "shell variable name",
+        }
+    }
+}
+
+/// Finds the keys on each line of one file, fed the lines in order.
+#[derive(Debug)]
+pub struct KeyTokenizer {
+    format: KeyFormat,
+
+    /// YAML: indentation of the key whose block scalar (`|`, `>`) the lines below belong to.
+    block_indent: Option<usize>,
+
+    /// Shell: the word that ends the here-document the lines below belong to.
+    heredoc_end: Option<String>,
+
+    /// Shell: a single-quoted string continues from the line before.
+    in_single_quote: bool,
+}
+
+impl KeyTokenizer {
+    /// A tokenizer for `path`, or `None` if keys in its format aren't checked.
+    pub fn for_path(path: &str) -> Option<Self> {
+        KeyFormat::for_path(path).map(|format| KeyTokenizer {
+            format,
+            block_indent: None,
+            heredoc_end: None,
+            in_single_quote: false,
+        })
+    }
+
+    /// What a key is called in this file's format.
+    pub fn noun(&self) -> &'static str {
+        self.format.noun()
+    }
+
+    /// The byte ranges of `line` (without its newline) that are keys or variable names.
+    pub fn keys(&mut self, line: &str) -> Vec<Range<usize>> {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match self.format {
+            KeyFormat::Env => env_key(line).into_iter().collect(),
+            KeyFormat::Ini => ini_key(line).into_iter().collect(),
+            KeyFormat::Yaml => self.yaml_key(line).into_iter().collect(),
+            KeyFormat::Shell => self.shell_names(line),
+        }
+    }
+
+    fn yaml_key(&mut self, line: &str) -> Option<Range<usize>> {
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        if let Some(block) = self.block_indent {
+            if line.trim().is_empty() || indent > block {
+                return None;
+            }
+            self.block_indent = None;
+        }
+        let (key, value) = yaml_mapping(line, indent)?;
+        let value = value.trim_start();
+        let value = value.split(" #").next().unwrap_or(value).trim_end();
+        if value.starts_with(['|', '>']) {
+            self.block_indent = Some(indent);
+        }
+        Some(key)
+    }
+
+    fn shell_names(&mut self, line: &str) -> Vec<Range<usize>> {
+        if let Some(end) = &self.heredoc_end {
+            if line.trim_start_matches('\t') == end {
+                self.heredoc_end = None;
+            }
+            return Vec::new();
+        }
+
+        let mut names = Vec::new();
+        let mut quote = if self.in_single_quote {
+            Some('\'')
+        } else {
+            None
+        };
+        let mut at_word_start = true;
+        let mut command_start = quote.is_none();
+        let mut chars = line.char_indices().peekable();
+        while let Some((i, ch)) = chars.next() {
+            if quote == Some('\'') {
+                if ch == '\'' {
+                    quote = None;
+                }
+                continue;
+            }
+            if ch == '\\' {
+                chars.next();
+                at_word_start = false;
+                continue;
+            }
+            if quote.is_none() && at_word_start && command_start && !ch.is_ascii_whitespace() {
+                if let Some(name) = assignment_name(&line[i..]) {
+                    names.push(i + name.start..i + name.end);
+                }
+                command_start = false;
+            }
+            match ch {
+                '\'' if quote.is_none() => quote = Some('\''),
+                '"' if quote == Some('"') => quote = None,
+                '"' if quote.is_none() => quote = Some('"'),
+                '#' if quote.is_none() && at_word_start => break,
+                '$' => {
+                    if let Some(name) = parameter_name(&line[i + 1..]) {
+                        names.push(i + 1 + name.start..i + 1 + name.end);
+                    }
+                }
+                '<' if quote.is_none() && line[i..].starts_with("<<") => {
+                    chars.next();
+                    if let Some(end) = heredoc_word(&line[i + 2..]) {
+                        self.heredoc_end = Some(end);
+                    }
+                }
+                _ => {}
+            }
+            at_word_start = ch.is_ascii_whitespace() || (quote.is_none() && ";|&(".contains(ch));
+            if quote.is_none() && ";|&(".contains(ch) {
+                command_start = true;
+            }
+        }
+        self.in_single_quote = quote == Some('\'');
+        names
+    }
+}
+
+/// The key of a `.env` line: everything before the first `=`, after any `export`.
+fn env_key(line: &str) -> Option<Range<usize>> {
+    let start = line.len() - line.trim_start().len();
+    let rest = &line[start..];
+    if rest.starts_with('#') {
+        return None;
+    }
+    let start = match rest.strip_prefix("export") {
+        Some(after) if after.starts_with([' ', '\t']) => line.len() - after.trim_start().len(),
+        _ => start,
+    };
+    let eq = line[start..].find('=')?;
+    trimmed(line, start..start + eq)
+}
+
+/// The key of an INI line, or the name of a `[section]`.
+fn ini_key(line: &str) -> Option<Range<usize>> {
+    let start = line.len() - line.trim_start().len();
+    let rest = &line[start..];
+    if rest.is_empty() || rest.starts_with([';', '#']) {
+        return None;
+    }
+    if rest.starts_with('[') {
+        // `[[array]]` in TOML
+        let name = rest.trim_start_matches('[');
+        let open = start + rest.len() - name.len();
+        let end = name.find(']')?;
+        return trimmed(line, open..open + end);
+    }
+    let sep = rest.find(['=', ':'])?;
+    trimmed(line, start..start + sep)
+}
+
+/// The key of a YAML block mapping line indented by `indent`, and the value after it. Keys
+/// of mappings in sequences (`- name: x`) count; flow collections and scalars don't.
+fn yaml_mapping(line: &str, indent: usize) -> Option<(Range<usize>, &str)> {
+    let mut start = indent;
+    while line[start..].starts_with("- ") {
+        start += 2;
+        start += line[start..].len() - line[start..].trim_start_matches(' ').len();
+    }
+    let rest = &line[start..];
+    if rest.is_empty() || rest.starts_with(['#', '{', '[', '-', '%', '|', '>']) {
+        return None;
+    }
+    if let Some(q) = rest.chars().next().filter(|&c| c == '"' || c == '\'') {
+        let close = rest[1..].find(q)? + 1;
+        let value = rest[close + 1..].strip_prefix(':')?;
+        if !value.is_empty() && !value.starts_with([' ', '\t']) {
+            return None;
+        }
+        return Some((start + 1..start + close, value));
+    }
+    let mut search = 0;
+    loop {
+        let colon = search + rest[search..].find(':')?;
+        let after = &rest[colon + 1..];
+        if after.is_empty() || after.starts_with([' ', '\t']) {
+            if rest[..colon].contains(" #") {
+                return None;
+            }
+            return Some((trimmed(line, start..start + colon)?, after));
+        }
+        search = colon + 1;
+    }
+}
+
+/// The name assigned at the start of `word` (`NAME=value`, after any `export`, `local`,
+/// `readonly`, `declare`, or `typeset` and their options), as a range of `word`.
+fn assignment_name(word: &str) -> Option<Range<usize>> {
+    let mut start = 0;
+    loop {
+        let rest = &word[start..];
+        let end = rest.find([' ', '\t']).unwrap_or(rest.len());
+        let token = &rest[..end];
+        let is_keyword = matches!(
+            token,
+            "export" | "local" | "readonly" | "declare" | "typeset"
+        );
+        let is_option = start > 0 && token.starts_with('-');
+        if !is_keyword && !is_option {
+            break;
+        }
+        start += end;
+        start += word[start..].len() - word[start..].trim_start().len();
+    }
+    let rest = &word[start..];
+    let end = rest.find(|c: char| c.is_ascii_whitespace() || "=\"'$`;|&()<>".contains(c))?;
+    let name = &rest[..end];
+    let assigns = rest[end..].starts_with('=') || rest[end..].starts_with("+=");
+    let valid_start = name.chars().next().is_some_and(|c| !c.is_ascii_digit());
+    (assigns && valid_start).then(|| start..start + end)
+}
+
+/// The name of the parameter expanded by the text after a `$`: the inside of `${...}` up to
+/// any operator, or for `$NAME` the letters, digits, and underscores with any non-ASCII
+/// characters between them (which end the name for the shell, not for the reader).
+fn parameter_name(text: &str) -> Option<Range<usize>> {
+    let braced = text.starts_with('{');
+    let body = match text.strip_prefix('{') {
+        Some(body) => body.strip_prefix(['#', '!']).unwrap_or(body),
+        None => text,
+    };
+    let offset = text.len() - body.len();
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut end = body
+        .find(|c: char| c.is_ascii() && !is_name_char(c))
+        .unwrap_or(body.len());
+    if !braced {
+        end = body[..end]
+            .trim_end_matches(|c: char| !is_name_char(c))
+            .len();
+    }
+    (end > 0 && !body.starts_with(|c: char| c.is_ascii_digit())).then(|| offset..offset + end)
+}
+
+/// The word that ends a here-document started by `<<` followed by `text`.
+fn heredoc_word(text: &str) -> Option<String> {
+    // `<<<` is a here-string, which ends on the same line
+    if text.starts_with('<') {
+        return None;
+    }
+    let text = text.strip_prefix('-').unwrap_or(text).trim_start();
+    // Not `$((1 << 2))`
+    if !text.starts_with(|c: char| c.is_ascii_alphabetic() || "_'\"\\".contains(c)) {
+        return None;
+    }
+    let end = text
+        .find(|c: char| c.is_ascii_whitespace() || ";|&<>)".contains(c))
+        .unwrap_or(text.len());
+    let word: String = text[..end]
+        .chars()
+        .filter(|c| !"'\"\\".contains(*c))
+        .collect();
+    (!word.is_empty()).then_some(word)
+}
+
+/// `range` of `line` without ASCII whitespace at either end, which parsers drop; other
+/// whitespace stays, since that is what makes the key a different one.
+fn trimmed(line: &str, range: Range<usize>) -> Option<Range<usize>> {
+    let text = &line[range.clone()];
+    let start = range.start
+        + (text.len()
+            - text
+                .trim_start_matches(|c: char| c.is_ascii_whitespace())
+                .len());
+    let end = range.start
+        + text
+            .trim_end_matches(|c: char| c.is_ascii_whitespace())
+            .len();
+    (start < end).then_some(start..end)
+}
@@ -0,0 +1,4583 @@
+//! The scanner behind the `invisible-char-detector` binary. With the `ffi` feature the library
+//! also exports a C interface (see `include/icd.h`), and with `wasm` a JavaScript one.
+
+use std::borrow::Cow;
+use std::cell::OnceCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::{IsTerminal, Read};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use unicode_script::Script;
+use unicode_properties::{GeneralCategory, GeneralCategoryGroup, UnicodeGeneralCategory};
+
+mod archive;
+mod azure;
+mod blame;
+mod cache;
+mod clean;
+mod clipboard;
+mod cli;
+mod config;
+mod context;
+mod daemon;
+mod diff_reports;
+mod document;
+mod email;
+mod emoji;
+mod encoding;
+mod escapes;
+mod confusables;
+mod explain;
+#[cfg(feature = "ffi")]
+mod ffi;
+mod fetch;
+mod file_info;
+mod fingerprint;
+mod git;
+mod hexdump;
+mod history;
+mod hook;
+mod jsonl;
+mod keys;
+mod logging;
+mod list_files;
+mod lookalike;
+mod lsp;
+mod metadata;
+mod normalize;
+mod package;
+mod paste;
+mod patch;
+mod progress;
+mod redact;
+mod render;
+mod report;
+mod risk;
+mod roots;
+mod rules;
+mod sample;
+mod schema;
+mod scripts;
+mod serve;
+mod snippet;
+mod structure;
+mod step_summary;
+mod summary;
+mod suppressed;
+mod teamcity;
+mod timings;
+mod ucd;
+mod walk;
+mod watch;
+mod webhook;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+use cache::{CacheEntry, FileStamp, ScanCache};
+use clap::{CommandFactory, Parser};
+use cli::{Cli, Command};
+use normalize::NormalizationForm;
+use progress::Progress;
+use render::{EscapeStyle, GroupBy, OutputFormat, TextStyle};
+use walk::WalkEntry;
+
+/// A single detection record describing one suspicious code point occurrence.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Detection {
+    /// File path where the suspicious character was found.
+    file: String,
+
+    /// 1-indexed line number in the file, or 0 for a character in the file or directory name
+    /// `file` ends with (`ICD-NAME001`).
+    line: usize,
+
+    /// 1-indexed byte offset from start of file (unambiguous for all editors); for a name, from
+    /// the start of the name.
+    byte_offset: usize,
+
+    /// 0-indexed byte range of what was found, end exclusive, in the file's own bytes (or the
+    /// name's): the whole character however many bytes it takes, all of a run, the escape
+    /// sequence, or the identifier. `byte_start` is `byte_offset - 1`.
+    #[serde(default)]
+    byte_start: usize,
+    #[serde(default)]
+    byte_end: usize,
+
+    /// 1-indexed character index within the line (Unicode scalar count), or within the name.
+    char_index: usize,
+
+    /// 1-indexed column in UTF-16 code units, as editors and LSP count them: differs from
+    /// `char_index` after characters outside the Basic Multilingual Plane (emoji, ...).
+    #[serde(default)]
+    utf16_column: usize,
+
+    /// The character itself as a string (may be invisible in terminals/editors). For an
+    /// escaped character, the escape sequence as written (e.g. `\u200B`).
+    char: String,
+
+    /// Unicode code point value (scalar value) of the character.
+    code: u32,
+
+    /// Official Unicode name, or the formal alias or code point label when there is none.
+    name: String,
+
+    /// Unicode general category abbreviation (e.g. `Cf`, `Zs`, `Co`).
+    general_category: String,
+
+    /// A short explanation of why this code point is considered suspicious.
+    description: String,
+
+    /// ID of the rule that flagged the character (see `explain`).
+    rule: String,
+
+    /// Kind of character: `zero-width`, `bidi`, `variation-selector`, `whitespace`, `pua`,
+    /// `control`, ... (the rule's category; for a file name, that of the character in it).
+    category: String,
+
+    /// `error`, `warning`, or `info`, from the rule.
+    severity: rules::Severity,
+
+    /// True if the character is written as an escape sequence (`--include-escapes`).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    escaped: bool,
+
+    /// Where the character is in a JSON file (JSON Pointer, `/dependencies/lodash/resolved`)
+    /// or YAML file (`jobs.build.steps[2].run`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    structure_path: Option<String>,
+
+    /// Whether the character is in a string, comment, identifier, or other code (`--context`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    context: Option<context::SyntaxContext>,
+
+    /// The line holding the character and the lines around it (`--show-context`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    snippet: Option<Vec<snippet::SnippetLine>>,
+
+    /// The raw bytes around the character (`--hex-context`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hex_context: Option<hexdump::HexContext>,
+
+    /// Set when the character repeats back to back and the repeats are reported as this one
+    /// detection (on unless `--no-collapse-runs`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    run: Option<RepeatRun>,
+
+    /// On the last detection kept for a file, how many more were found after it and left out
+    /// (`--max-detections-per-file`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    more_in_file: Option<usize>,
+
+    /// The commit that last changed the line (`--blame`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    blame: Option<blame::Blame>,
+
+    /// The file's encoding when it isn't UTF-8 (`utf-16le`, `utf-16be`, or `windows-1252` with
+    /// `--lossy`). `byte_offset` counts the file's own bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encoding: Option<encoding::Encoding>,
+
+    /// The bytes of an invalid UTF-8 sequence in hex, e.g. `C0 AF` (`--report-invalid-utf8`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    invalid_bytes: Option<String>,
+
+    /// Other paths of the same file (hard links, bind mounts, followed symlinks), which were
+    /// not scanned again.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    also_at: Vec<String>,
+
+    /// Risk score of the file the detection is in; the higher, the sooner to review it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    file_risk: Option<u32>,
+
+    /// The edit a clean copy makes for this detection: a byte range of the text and what to
+    /// put there. Left out when the character needs a human decision.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fix: Option<clean::Fix>,
+
+    /// The `--root` the file was found under.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    root: Option<String>,
+
+    /// With `--show-suppressed`, the setting that kept the detection out of the report and
+    /// why, e.g. `allow_emoji: in an emoji sequence`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    suppressed: Option<String>,
+
+    /// Identity of the finding across runs, which lines added or removed elsewhere in the file
+    /// leave alone (see [`fingerprint`]).
+    #[serde(default)]
+    fingerprint: String,
+}
+
+impl Detection {
+    /// A detection of `code` in `file` at `line` by `rule`, with the categories and severity
+    /// that go with them. The caller says where it is and what it is.
+    fn new(file: &str, line: usize, code: u32, rule: &str) -> Self {
+        Self {
+            file: file.to_string(),
+            line,
+            byte_offset: 0,
+            byte_start: 0,
+            byte_end: 0,
+            char_index: 0,
+            utf16_column: 0,
+            char: char::from_u32(code).map_or_else(String::new, String::from),
+            code,
+            name: String::new(),
+            general_category: ucd::general_category(code).to_string(),
+            description: String::new(),
+            rule: rule.to_string(),
+            category: rules::category_of(rule, code),
+            severity: rules::severity_of(rule),
+            escaped: false,
+            structure_path: None,
+            context: None,
+            snippet: None,
+            hex_context: None,
+            run: None,
+            more_in_file: None,
+            blame: None,
+            encoding: None,
+            invalid_bytes: None,
+            also_at: Vec::new(),
+            file_risk: None,
+            fix: None,
+            root: None,
+            suppressed: None,
+            fingerprint: String::new(),
+        }
+    }
+}
+
+/// A character repeated back to back on one line, reported as a single detection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RepeatRun {
+    /// Number of characters in the run (at least 2).
+    count: usize,
+
+    /// 1-indexed byte offset of the last character in the run.
+    end_byte_offset: usize,
+
+    /// 1-indexed character index of the last character in the run within the line.
+    end_char_index: usize,
+}
+
+/// Whether `d` repeats the character of `prev`, or of the run `prev` already ends, right
+/// after it.
+fn continues_run(prev: &Detection, d: &Detection) -> bool {
+    let (end_byte_offset, end_char_index) = run_end(prev);
+    d.line == prev.line
+        && d.line > 0
+        && d.byte_offset == end_byte_offset + prev.char.len()
+        && d.char_index == end_char_index + 1
+        && d.code == prev.code
+        && d.char == prev.char
+        && d.rule == prev.rule
+        && d.description == prev.description
+        && !d.escaped
+        && !prev.escaped
+}
+
+/// Byte offset and character index of the last character `d` covers.
+fn run_end(d: &Detection) -> (usize, usize) {
+    d.run.as_ref().map_or((d.byte_offset, d.char_index), |run| {
+        (run.end_byte_offset, run.end_char_index)
+    })
+}
+
+/// Merge detections of the same character repeated back to back into one detection with a
+/// [`RepeatRun`], so 10,000 no-break spaces in a minified file are one finding.
+fn collapse_runs(detections: Vec<Detection>) -> Vec<Detection> {
+    let mut collapsed: Vec<Detection> = Vec::with_capacity(detections.len());
+    for d in detections {
+        if let Some(prev) = collapsed.last_mut() {
+            if continues_run(prev, &d) {
+                let (end_byte_offset, end_char_index) = run_end(prev);
+                let run = prev.run.get_or_insert(RepeatRun {
+                    count: 1,
+                    end_byte_offset,
+                    end_char_index,
+                });
+                run.count += 1;
+                run.end_byte_offset = d.byte_offset;
+                run.end_char_index = d.char_index;
+                prev.byte_end = d.byte_end;
+                continue;
+            }
+        }
+        collapsed.push(d);
+    }
+    collapsed
+}
+
+/// Options for heuristic checks that look at more than one character at a time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DetectOptions {
+    /// Flag runs of at least this many combining marks on one base character (0 = off)
+    combining_threshold: usize,
+
+    /// Flag text that is not in this normalization form (`--check-normalization`)
+    normalization: Option<NormalizationForm>,
+
+    /// Flag lookalikes of ASCII letters and digits from other scripts (`--confusables`)
+    confusables: bool,
+
+    /// Flag every format (Cf) character, not just the listed ones (`--all-format-chars`)
+    all_format_chars: bool,
+
+    /// Also flag the code points in these ranges that no rule covers (`--detect-range`)
+    detect_ranges: Vec<RangeInclusive<u32>>,
+
+    /// Flag identifier-like tokens that mix scripts (off with `--no-mixed-scripts`)
+    mixed_scripts: bool,
+
+    /// Flag identifiers that look the same as another in the file once invisible characters
+    /// are stripped (`--lookalike-identifiers`)
+    lookalike_identifiers: bool,
+
+    /// Flag non-ASCII characters from any other script (`--allow-scripts`)
+    allowed_scripts: Option<Vec<Script>>,
+
+    /// Don't flag ZWJ and VS16 inside well-formed emoji sequences (`--allow-emoji`)
+    allow_emoji: bool,
+
+    /// Flag every other non-ASCII character too, as info (`--ascii-only`)
+    ascii_only: bool,
+
+    /// Prose file: don't flag NBSP / NARROW NBSP next to punctuation (French typography)
+    prose: bool,
+
+    /// Also flag escape sequences that produce suspicious characters (`--include-escapes`)
+    include_escapes: bool,
+
+    /// Classify detections by syntactic context in supported languages (`--context`)
+    syntax_context: bool,
+
+    /// Drop detections by syntactic context (`--require-context`, `ignore_in_context`)
+    context_policy: context::ContextPolicy,
+
+    /// Keep the detections settings drop, marked with the reason (`--show-suppressed`)
+    show_suppressed: bool,
+
+    /// Attach the surrounding source lines to each detection (`--show-context`)
+    show_context: bool,
+
+    /// How those lines spell hidden characters (`--escape-style`)
+    escape_style: EscapeStyle,
+
+    /// Count U+2028, U+2029, and U+0085 as line breaks, as some editors display them
+    /// (`--treat-ls-as-newline`)
+    unicode_line_breaks: bool,
+
+    /// Report a character repeated back to back as one detection (off with
+    /// `--no-collapse-runs`)
+    collapse_runs: bool,
+
+    /// Keep at most this many detections per file (`--max-detections-per-file`)
+    max_detections: Option<usize>,
+
+    /// Attach this many bytes on each side of each detection as hex (`--hex-context`, 0 = off)
+    hex_context: usize,
+
+    /// Scan files that aren't valid UTF-8 with a best-effort decoding (`--lossy`)
+    lossy: bool,
+
+    /// Report a leading byte order mark as an error rather than info (`--forbid-bom`)
+    forbid_bom: bool,
+
+    /// Report the invalid sequences of files that aren't valid UTF-8 instead of skipping them
+    /// (`--report-invalid-utf8`)
+    report_invalid_utf8: bool,
+
+    /// Scan only these lines (1-indexed, inclusive) of each file (`--lines`)
+    lines: Option<RangeInclusive<usize>>,
+
+    /// Report lines longer than this many bytes, as info (`--warn-line-length`)
+    warn_line_length: Option<usize>,
+
+    /// Report files with at least this many suspicious characters, as info
+    /// (`--warn-file-detections`)
+    warn_file_detections: Option<usize>,
+}
+
+/// Default for `--combining-threshold`: above anything real text stacks on one letter.
+const DEFAULT_COMBINING_THRESHOLD: usize = 5;
+
+impl Default for DetectOptions {
+    fn default() -> Self {
+        Self {
+            combining_threshold: DEFAULT_COMBINING_THRESHOLD,
+            normalization: None,
+            confusables: false,
+            all_format_chars: false,
+            detect_ranges: Vec::new(),
+            mixed_scripts: true,
+            lookalike_identifiers: false,
+            allowed_scripts: None,
+            allow_emoji: false,
+            ascii_only: false,
+            prose: false,
+            include_escapes: false,
+            syntax_context: false,
+            context_policy: context::ContextPolicy::default(),
+            show_suppressed: false,
+            show_context: false,
+            escape_style: EscapeStyle::default(),
+            unicode_line_breaks: false,
+            collapse_runs: true,
+            max_detections: None,
+            hex_context: 0,
+            lossy: false,
+            forbid_bom: false,
+            report_invalid_utf8: false,
+            lines: None,
+            warn_line_length: None,
+            warn_file_detections: None,
+        }
+    }
+}
+
+/// Configuration for scan behavior.
+#[derive(Debug, Clone)]
+struct ScanConfig {
+    /// Pattern to match files (e.g., "**/*.rs")
+    pattern: String,
+
+    /// Trees scanned one by one with `pattern` under each, each with its own result (`--root`)
+    roots: Vec<String>,
+
+    /// Scan exactly these paths instead of matching `pattern` (`--hook`, or several paths)
+    files: Option<Vec<String>>,
+
+    /// The named set of options the scan was configured with (`--preset`)
+    preset: Option<config::ScanPreset>,
+
+    /// List what a scan would read and skip instead of scanning (`--list-files`)
+    list_files: bool,
+
+    /// Output format (`--format`, `--json`)
+    format: OutputFormat,
+
+    /// JSON layout: 1 = array of detections, 2 = object with scan metadata (`--json-version`)
+    json_version: u32,
+
+    /// Follow the detections with aggregate statistics (`--summary`)
+    summary: bool,
+
+    /// Order of files in the output (`--sort-by`)
+    sort_by: risk::SortBy,
+
+    /// How text output lists the detections (`--group-by`)
+    group_by: render::GroupBy,
+
+    /// Time each file and report this many of the slowest (`--timings`)
+    timings: Option<usize>,
+
+    /// Report the encoding, byte order mark, and line endings of each file (`--file-info`)
+    file_info: bool,
+
+    /// Only tally detections for aggregate statistics (`stats`, `--summary-only`)
+    census: bool,
+
+    /// Show warnings for ignored/unreadable files
+    verbose: bool,
+
+    /// Print only results (`--quiet`)
+    quiet: bool,
+
+    /// Show a progress line on stderr (a terminal, without `--no-progress` or `--quiet`)
+    progress: bool,
+
+    /// Fail with exit code 2 if any files cannot be read (strict mode)
+    fail_on_skip: bool,
+
+    /// Stop at the first file with detections and report only the first one (`--fail-fast`)
+    fail_fast: bool,
+
+    /// Attribute each detection to the commit that last changed its line (`--blame`)
+    blame: bool,
+
+    /// Report paths with `/` on Windows too (`--forward-slashes`)
+    forward_slashes: bool,
+
+    /// Rewrite paths in the output so they don't reveal where the scan ran (`--redact-paths`)
+    redact_paths: Option<redact::RedactMode>,
+
+    /// When true, scan dist/out/build directories (good for bundled extensions)
+    /// When false, ignore them (good for source repos)
+    scan_bundles: bool,
+
+    /// Follow symbolic links to files and directories (off by default)
+    follow_symlinks: bool,
+
+    /// Color text output (`--color`, resolved against the terminal and `NO_COLOR`)
+    color: bool,
+
+    /// Scan the files inside zip and tar archives (`--scan-archives`)
+    scan_archives: bool,
+
+    /// Scan the text of Office, OpenDocument, and PDF files and emails (`--scan-documents`)
+    scan_documents: bool,
+
+    /// Skip files larger than this many bytes (None = no limit)
+    max_file_size: Option<u64>,
+
+    /// Number of worker threads (0 = one per CPU)
+    jobs: usize,
+
+    /// Incremental scan cache file (`--cache`)
+    cache: Option<PathBuf>,
+
+    /// Directory for sanitized copies of the files with detections (`--clean-to`)
+    clean_to: Option<PathBuf>,
+
+    /// Where to POST a summary when the scan fails (`--notify-webhook`)
+    notify_webhook: Option<String>,
+
+    /// Body to POST instead of the default JSON (`--webhook-template`)
+    webhook_template: Option<webhook::Template>,
+
+    /// Include the detections in the default body (`--webhook-full-report`)
+    webhook_full_report: bool,
+
+    /// Append a Markdown report to the GitHub Actions job summary (`--step-summary`)
+    step_summary: bool,
+
+    /// Scan the lines added by a unified diff read from stdin instead of files (`--patch`)
+    patch: bool,
+
+    /// Scan the system clipboard instead of files (`--clipboard`)
+    clipboard: bool,
+
+    /// Scan stdin as the file of this name instead of files (`--stdin-filename`)
+    stdin_filename: Option<String>,
+
+    /// Put the clipboard back cleaned (`--fix`)
+    fix: bool,
+
+    /// Leave out files git ignores (`--respect-gitignore`, and the run without a pattern)
+    respect_gitignore: bool,
+
+    /// Only report lines added or modified since this git revision (`--diff`)
+    diff_base: Option<String>,
+
+    /// Scan the content staged in the git index instead of the working tree (`--staged`)
+    staged: bool,
+
+    /// Keep running and rescan files as they change (`--watch`)
+    watch: bool,
+
+    /// In watch mode, re-emit the full report after every change (`--watch-full`)
+    watch_full: bool,
+
+    /// Heuristic detection options
+    detect: DetectOptions,
+
+    /// Config file given with `--config` (default: `.icd.toml` if present)
+    config_file: Option<PathBuf>,
+
+    /// `prose` from the config file; unset means it follows the file's preset
+    prose: Option<bool>,
+
+    /// `--allow-emoji` or `allow_emoji` from the config file; unset means it follows the file's
+    /// preset
+    allow_emoji: Option<bool>,
+
+    /// `--ascii-only` or `ascii_only` from the config file
+    ascii_only: Option<bool>,
+
+    /// Per-path detection options from the config file
+    overrides: Vec<config::Override>,
+
+    /// Defaults by file extension, built in or from the config file
+    presets: BTreeMap<String, config::PathOptions>,
+
+    /// Directory names left out of the scan (`--ignore-dir`, `--no-default-ignore-dirs`, and
+    /// the config file)
+    ignore_dirs: IgnoreDirs,
+
+    /// Generated and vendored files (`--skip-generated`, `--no-downgrade-generated`)
+    generated: GeneratedFiles,
+}
+
+impl ScanConfig {
+    /// Merge in the config file. Options set on the command line keep their values.
+    fn apply_config_file(&mut self, file: config::Config) {
+        let cli_scripts = self.detect.allowed_scripts.is_some();
+        if !cli_scripts {
+            self.detect.allowed_scripts = file.options.allow_scripts;
+        }
+        let cli_contexts = self.detect.context_policy.require.is_some();
+        if !cli_contexts {
+            self.detect.context_policy.require = file.options.require_context;
+        }
+        if let Some(ignore) = file.options.ignore_in_context {
+            self.detect.context_policy.ignore = ignore;
+        }
+        self.prose = file.options.prose;
+        self.allow_emoji = self.allow_emoji.or(file.options.allow_emoji);
+        self.ascii_only = self.ascii_only.or(file.options.ascii_only);
+
+        self.overrides = file.overrides;
+        for o in &mut self.overrides {
+            if cli_scripts {
+                o.options.allow_scripts = None;
+            }
+            if cli_contexts {
+                o.options.require_context = None;
+            }
+        }
+        self.presets = file.presets;
+        self.ignore_dirs.defaults &= file.default_ignore_dirs.unwrap_or(true);
+        self.ignore_dirs.extra.extend(file.ignore_dirs);
+    }
+
+    /// Settings for text output.
+    fn text_style(&self) -> TextStyle {
+        TextStyle {
+            escapes: self.detect.escape_style,
+            color: self.color,
+            group_by: self.group_by,
+        }
+    }
+
+    /// Detection options for the file at `path`, after its preset and per-path overrides.
+    fn detect_options(&self, path: &str) -> Cow<'_, DetectOptions> {
+        let mut options = Cow::Borrowed(&self.detect);
+        let mut prose = self.prose;
+        let mut allow_emoji = self.allow_emoji;
+        let mut ascii_only = self.ascii_only;
+
+        // A preset only fills in what the command line and the config file's top level leave
+        // unset
+        let preset = config::preset_key(path).and_then(|ext| self.presets.get(&ext));
+        if let Some(preset) = preset {
+            if let (Some(scripts), None) = (&preset.allow_scripts, &options.allowed_scripts) {
+                options.to_mut().allowed_scripts = Some(scripts.clone());
+            }
+            if let (Some(require), None) = (&preset.require_context, &options.context_policy.require)
+            {
+                options.to_mut().context_policy.require = Some(require.clone());
+            }
+            if let Some(ignore) = &preset.ignore_in_context {
+                if options.context_policy.ignore.is_empty() {
+                    options.to_mut().context_policy.ignore = ignore.clone();
+                }
+            }
+            prose = prose.or(preset.prose);
+            allow_emoji = allow_emoji.or(preset.allow_emoji);
+            ascii_only = ascii_only.or(preset.ascii_only);
+        }
+
+        let mut prose = prose.unwrap_or(false);
+        let mut allow_emoji = allow_emoji.unwrap_or(false);
+        let mut ascii_only = ascii_only.unwrap_or(false);
+        for o in self.overrides.iter().filter(|o| o.matches(path)) {
+            if let Some(scripts) = &o.options.allow_scripts {
+                options.to_mut().allowed_scripts = Some(scripts.clone());
+            }
+            if let Some(require) = &o.options.require_context {
+                options.to_mut().context_policy.require = Some(require.clone());
+            }
+            if let Some(ignore) = &o.options.ignore_in_context {
+                options.to_mut().context_policy.ignore = ignore.clone();
+            }
+            prose = o.options.prose.unwrap_or(prose);
+            allow_emoji = o.options.allow_emoji.unwrap_or(allow_emoji);
+            ascii_only = o.options.ascii_only.unwrap_or(ascii_only);
+        }
+        if prose != options.prose {
+            options.to_mut().prose = prose;
+        }
+        if allow_emoji != options.allow_emoji {
+            options.to_mut().allow_emoji = allow_emoji;
+        }
+        if ascii_only != options.ascii_only {
+            options.to_mut().ascii_only = ascii_only;
+        }
+        options
+    }
+}
+
+/// Aggregate outcome of a scan.
+#[derive(Debug, Default)]
+struct ScanResult {
+    /// All detections across scanned files.
+    detections: Vec<Detection>,
+
+    /// Number of files read and scanned.
+    scanned: usize,
+
+    /// Number of files ignored or unreadable (counted by `--fail-on-skip`).
+    skipped: usize,
+
+    /// Number of binary files skipped (never counted by `--fail-on-skip`).
+    binary: usize,
+
+    /// The binary files skipped, by class.
+    binary_by_class: BTreeMap<BinaryClass, usize>,
+
+    /// Number of scanned files whose results came from `--cache`.
+    cached: usize,
+
+    /// For `stats`: the detections tallied instead of kept in `detections`.
+    census: Option<summary::SummaryBuilder>,
+
+    /// For `--json-version 2`: every file not scanned, and why.
+    skipped_files: Option<Vec<report::SkippedFile>>,
+
+    /// With `--timings`: the time and size of each scanned file.
+    timings: Option<timings::TimingsBuilder>,
+
+    /// With `--file-info`: the encoding and line endings of each scanned file.
+    file_info: Option<Vec<file_info::FileInfo>>,
+
+    /// With `--show-suppressed`: the detections settings dropped, each with the reason.
+    suppressed: Option<Vec<Detection>>,
+
+    /// With `--root`: the result of each root.
+    roots: Vec<roots::RootCounts>,
+}
+
+impl ScanResult {
+    /// Add a file's detections, or only count them for `stats`. Suppressed detections are set
+    /// apart, and never counted.
+    fn keep(&mut self, detections: Vec<Detection>) {
+        let (suppressed, detections): (Vec<_>, Vec<_>) =
+            detections.into_iter().partition(|d| d.suppressed.is_some());
+        if let Some(all) = &mut self.suppressed {
+            all.extend(suppressed);
+        }
+        match &mut self.census {
+            Some(census) => census.add(&detections),
+            None => self.detections.extend(detections),
+        }
+    }
+
+    /// Add the result of another root.
+    fn merge(&mut self, other: ScanResult) {
+        self.detections.extend(other.detections);
+        self.scanned += other.scanned;
+        self.skipped += other.skipped;
+        self.binary += other.binary;
+        for (class, count) in other.binary_by_class {
+            *self.binary_by_class.entry(class).or_insert(0) += count;
+        }
+        self.cached += other.cached;
+        if let (Some(census), Some(other)) = (&mut self.census, other.census) {
+            census.merge(other);
+        }
+        if let (Some(skipped_files), Some(other)) = (&mut self.skipped_files, other.skipped_files)
+        {
+            skipped_files.extend(other);
+        }
+        if let (Some(timings), Some(other)) = (&mut self.timings, other.timings) {
+            timings.merge(other);
+        }
+        if let (Some(files), Some(other)) = (&mut self.file_info, other.file_info) {
+            files.extend(other);
+        }
+        if let (Some(suppressed), Some(other)) = (&mut self.suppressed, other.suppressed) {
+            suppressed.extend(other);
+        }
+        self.roots.extend(other.roots);
+    }
+
+    /// Record a file that was not scanned, if the report lists them.
+    fn skip(&mut self, path: &str, reason: &'static str, detail: Option<String>) {
+        if let Some(skipped_files) = &mut self.skipped_files {
+            skipped_files.push(report::SkippedFile {
+                path: path.to_string(),
+                reason,
+                detail,
+                class: None,
+            });
+        }
+    }
+
+    /// Count a binary file that was not scanned, and record it if the report lists them.
+    fn skip_binary(&mut self, path: &str, kind: BinaryKind) {
+        self.binary += 1;
+        *self.binary_by_class.entry(kind.class).or_insert(0) += 1;
+        if let Some(skipped_files) = &mut self.skipped_files {
+            skipped_files.push(report::SkippedFile {
+                path: path.to_string(),
+                reason: "binary",
+                detail: Some(kind.label.to_string()),
+                class: Some(kind.class),
+            });
+        }
+    }
+}
+
+/// Pattern scanned when none is given: everything under the current directory.
+const DEFAULT_PATTERN: &str = "**/*";
+
+/// Default for `--max-file-size`: large enough for any hand-written source file.
+const DEFAULT_MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Default for `--max-detections-per-file`: enough to see what a file holds, few enough that
+/// one pathological file doesn't bury the rest of the report.
+const DEFAULT_MAX_DETECTIONS_PER_FILE: usize = 100;
+
+/// High-risk Unicode code points and why each is suspicious, as sorted, non-overlapping
+/// inclusive ranges, looked up by [`suspicious_description`].
+///
+/// Names are not listed here; detections take the official name from the UCD (see [`ucd`]).
+///
+/// This is intentionally focused on:
+/// - Zero-width and formatting characters used for obfuscation
+/// - Bidirectional controls (Trojan Source class)
+/// - Directional marks
+/// - Variation selectors (FE00-FE0F and the E0100-E01EF supplement)
+/// - Tag characters (E0001, E0020-E007F)
+/// - Line/paragraph separators and NEXT LINE (0085)
+/// - Deprecated invisible characters (17B4, 17B5, 180E, 206A-206F)
+/// - Arabic and Syriac prepended concatenation marks (0600-0605, 06DD, 070F, 0890-0891, 08E2)
+/// - Interlinear annotation characters (FFF9-FFFB)
+/// - Encoding artifacts (FFFC, FFFD)
+/// - A few frequently abused "blank" characters
+#[rustfmt::skip]
+const SUSPICIOUS_RANGES: &[(u32, u32, &str)] = &[
+    // NEXT LINE (NEL)
+    (0x0085, 0x0085, "Line break to some tools but not others; can split statements invisibly"),
+    // NO-BREAK SPACE
+    (0x00A0, 0x00A0, "Non-ASCII whitespace; may bypass naive filters"),
+    // SOFT HYPHEN
+    (0x00AD, 0x00AD, "Invisible in most contexts; used for obfuscation"),
+    // Arabic number sign, sanah, footnote marker, safha, samvat, number mark above
+    (0x0600, 0x0605, "Arabic number sign; can swallow or hide the digits after it"),
+    // ARABIC LETTER MARK
+    (0x061C, 0x061C, "Invisible directional marker"),
+    // ARABIC END OF AYAH
+    (0x06DD, 0x06DD, "Arabic number sign; can swallow or hide the digits after it"),
+    // SYRIAC ABBREVIATION MARK
+    (0x070F, 0x070F, "Syriac format mark; can hide or restyle the text after it"),
+    // ARABIC POUND MARK ABOVE, ARABIC PIASTRE MARK ABOVE
+    (0x0890, 0x0891, "Arabic number sign; can swallow or hide the digits after it"),
+    // ARABIC DISPUTED END OF AYAH
+    (0x08E2, 0x08E2, "Arabic number sign; can swallow or hide the digits after it"),
+    // HANGUL CHOSEONG FILLER, HANGUL JUNGSEONG FILLER
+    (0x115F, 0x1160, "Often renders as blank; used for obfuscation"),
+    // OGHAM SPACE MARK
+    (0x1680, 0x1680, "Non-ASCII whitespace; may bypass naive filters"),
+    // KHMER VOWEL INHERENT AQ, KHMER VOWEL INHERENT AA
+    (0x17B4, 0x17B5, "Deprecated invisible vowel sign"),
+    // MONGOLIAN VOWEL SEPARATOR
+    (0x180E, 0x180E, "Deprecated invisible format character"),
+    // EN QUAD through HAIR SPACE, including FIGURE SPACE (U+2007)
+    (0x2000, 0x200A, "Non-ASCII whitespace; may bypass naive filters"),
+    // ZERO WIDTH SPACE
+    (0x200B, 0x200B, "Invisible character used to hide code"),
+    // ZERO WIDTH NON-JOINER, ZERO WIDTH JOINER
+    (0x200C, 0x200D, "Can alter code logic invisibly"),
+    // LEFT-TO-RIGHT MARK, RIGHT-TO-LEFT MARK
+    (0x200E, 0x200F, "Invisible directional marker"),
+    // LINE SEPARATOR, PARAGRAPH SEPARATOR
+    (0x2028, 0x2029, "Can break parsing/tokenization"),
+    // LEFT-TO-RIGHT EMBEDDING, RIGHT-TO-LEFT EMBEDDING
+    (0x202A, 0x202B, "Bidi control; can mislead code review"),
+    // POP DIRECTIONAL FORMATTING
+    (0x202C, 0x202C, "Bidi control; terminates embeddings/overrides"),
+    // LEFT-TO-RIGHT OVERRIDE, RIGHT-TO-LEFT OVERRIDE
+    (0x202D, 0x202E, "Bidi override; can reorder displayed code"),
+    // NARROW NO-BREAK SPACE
+    (0x202F, 0x202F, "Non-ASCII whitespace; may bypass naive filters"),
+    // MEDIUM MATHEMATICAL SPACE
+    (0x205F, 0x205F, "Non-ASCII whitespace; may bypass naive filters"),
+    // WORD JOINER
+    (0x2060, 0x2060, "Invisible joiner; often used to hide payloads"),
+    // LEFT-TO-RIGHT, RIGHT-TO-LEFT, and FIRST STRONG ISOLATE
+    (0x2066, 0x2068, "Bidi isolate; can affect display order"),
+    // POP DIRECTIONAL ISOLATE
+    (0x2069, 0x2069, "Bidi isolate terminator"),
+    // Symmetric swapping, Arabic form shaping, and digit shape controls
+    (0x206A, 0x206F, "Deprecated invisible format character"),
+    // BRAILLE PATTERN BLANK
+    (0x2800, 0x2800, "Renders as empty space but is not whitespace; passes non-empty checks"),
+    // IDEOGRAPHIC SPACE
+    (0x3000, 0x3000, "Non-ASCII whitespace; may bypass naive filters"),
+    // HANGUL FILLER
+    (0x3164, 0x3164, "Often renders as blank; used for obfuscation"),
+    // VARIATION SELECTOR-1 through -16
+    (0xFE00, 0xFE0F, "Can modify character appearance"),
+    // ZERO WIDTH NO-BREAK SPACE
+    (0xFEFF, 0xFEFF, "Invisible space (not a leading byte order mark)"),
+    // HALFWIDTH HANGUL FILLER
+    (0xFFA0, 0xFFA0, "Often renders as blank; used for obfuscation"),
+    // INTERLINEAR ANNOTATION ANCHOR, SEPARATOR, TERMINATOR
+    (0xFFF9, 0xFFFB, "Interlinear annotation control; can hide annotation text"),
+    // OBJECT REPLACEMENT CHARACTER
+    (0xFFFC, 0xFFFC, "Placeholder for hidden embedded content"),
+    // REPLACEMENT CHARACTER
+    (0xFFFD, 0xFFFD, "Encoding corruption; original bytes were lost in a bad round-trip"),
+    // LANGUAGE TAG
+    (0xE0001, 0xE0001, "Invisible tag character; can encode hidden ASCII text"),
+    // TAG SPACE through CANCEL TAG
+    (0xE0020, 0xE007F, "Invisible tag character; can encode hidden ASCII text"),
+    // VARIATION SELECTOR-17 through -256
+    (0xE0100, 0xE01EF, "Invisible supplementary variation selector; can smuggle hidden data"),
+];
+
+/// Why `code` is suspicious, if it is in [`SUSPICIOUS_RANGES`].
+///
+/// Called for every character scanned, so everything below the first range (all of ASCII)
+/// is answered without a search, and the rest with a binary search of the ranges.
+fn suspicious_description(code: u32) -> Option<&'static str> {
+    if code < SUSPICIOUS_RANGES[0].0 {
+        return None;
+    }
+    let i = SUSPICIOUS_RANGES.partition_point(|&(_, last, _)| last < code);
+    match SUSPICIOUS_RANGES.get(i) {
+        Some(&(first, _, description)) if first <= code => Some(description),
+        _ => None,
+    }
+}
+
+/// Returns true if the code point is in one of the Unicode Private Use Area ranges.
+fn is_private_use_area(code: u32) -> bool {
+    (0xE000..=0xF8FF).contains(&code)
+        || (0xF0000..=0xFFFFD).contains(&code)
+        || (0x100000..=0x10FFFD).contains(&code)
+}
+
+/// Returns true for LANGUAGE TAG (U+E0001) and the tag characters U+E0020..U+E007F.
+fn is_tag_char(code: u32) -> bool {
+    code == 0xE0001 || (0xE0020..=0xE007F).contains(&code)
+}
+
+/// The ASCII character a tag character mirrors (U+E0041 TAG LATIN CAPITAL LETTER A -> `A`).
+///
+/// LANGUAGE TAG and CANCEL TAG only delimit sequences and decode to nothing.
+fn decode_tag_char(code: u32) -> Option<char> {
+    if (0xE0020..=0xE007E).contains(&code) {
+        char::from_u32(code - 0xE0000)
+    } else {
+        None
+    }
+}
+
+/// The byte a variation selector encodes in VS steganography: VS1–VS16 are 0–15 and
+/// VS17–VS256 are 16–255.
+fn decode_variation_selector(code: u32) -> Option<u8> {
+    match code {
+        0xFE00..=0xFE0F => Some((code - 0xFE00) as u8),
+        0xE0100..=0xE01EF => Some((code - 0xE0100 + 16) as u8),
+        _ => None,
+    }
+}
+
+/// Runs of at least this many variation selectors are decoded as a hidden payload.
+const VS_PAYLOAD_MIN_RUN: usize = 4;
+
+/// At most this many payload bytes are shown in a detection.
+const VS_PAYLOAD_PREVIEW_LEN: usize = 32;
+
+/// Hex dump and printable preview (`.` for other bytes) of a decoded payload.
+fn describe_payload(bytes: &[u8]) -> String {
+    let shown = &bytes[..bytes.len().min(VS_PAYLOAD_PREVIEW_LEN)];
+    let hex: Vec<String> = shown.iter().map(|b| format!("{:02x}", b)).collect();
+    let preview: String = shown
+        .iter()
+        .map(|&b| if b == b' ' || b.is_ascii_graphic() { b as char } else { '.' })
+        .collect();
+    let more = if bytes.len() > shown.len() { " ..." } else { "" };
+    format!("{}{} {:?}{}", hex.join(" "), more, preview, more)
+}
+
+/// Returns true if the code point is a suspicious C0/C1 control character.
+///
+/// Excludes TAB (U+0009), LF (U+000A), and CR (U+000D) since they are common in text.
+fn is_suspicious_control_char(code: u32) -> bool {
+    (code <= 0x001F && code != 0x0009 && code != 0x000A && code != 0x000D)
+        || (0x007F..=0x009F).contains(&code)
+}
+
+/// Directories ignored wherever they appear in a path, unless turned off with
+/// `--no-default-ignore-dirs` or `default_ignore_dirs = false`.
+const DEFAULT_IGNORE_DIRS: &[&str] = &["node_modules", ".git", ".cargo", "target", ".vscode"];
+
+/// The directory names a scan leaves out: the built-in ones and those added with
+/// `--ignore-dir` and `ignore_dirs` in the config file.
+#[derive(Debug, Clone)]
+struct IgnoreDirs {
+    /// Include [`DEFAULT_IGNORE_DIRS`].
+    defaults: bool,
+    extra: Vec<String>,
+}
+
+impl Default for IgnoreDirs {
+    fn default() -> Self {
+        Self {
+            defaults: true,
+            extra: Vec::new(),
+        }
+    }
+}
+
+impl IgnoreDirs {
+    /// Check if a path component matches an ignored directory.
+    fn contains(&self, component: &str) -> bool {
+        (self.defaults && DEFAULT_IGNORE_DIRS.contains(&component))
+            || self.extra.iter().any(|dir| dir == component)
+    }
+
+    /// All the ignored directory names, for the report.
+    fn names(&self) -> Vec<&str> {
+        let defaults = if self.defaults { DEFAULT_IGNORE_DIRS } else { &[] };
+        defaults
+            .iter()
+            .copied()
+            .chain(self.extra.iter().map(String::as_str))
+            .collect()
+    }
+}
+
+/// What a scan does with files `.gitattributes` marks `linguist-generated` or
+/// `linguist-vendored`, such as minified vendor bundles.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum GeneratedFiles {
+    /// Report their errors as warnings, so first-party findings stand out.
+    #[default]
+    Downgrade,
+
+    /// Leave them out (`--skip-generated`).
+    Skip,
+
+    /// Treat them like any other file (`--no-downgrade-generated`).
+    Scan,
+}
+
+impl GeneratedFiles {
+    fn as_str(self) -> &'static str {
+        match self {
+            GeneratedFiles::Downgrade => "downgrade",
+            GeneratedFiles::Skip => "skip",
+            GeneratedFiles::Scan => "scan",
+        }
+    }
+}
+
+/// The paths among `paths` that are generated or vendored, or none when `policy` doesn't
+/// need them or there is no repository to ask.
+fn generated_paths(policy: GeneratedFiles, paths: &[&Path]) -> HashSet<PathBuf> {
+    if policy == GeneratedFiles::Scan {
+        return HashSet::new();
+    }
+    git::generated_paths(paths).unwrap_or_default()
+}
+
+/// Check if a path should be ignored, using component-based matching to avoid false positives.
+///
+/// When `scan_bundles` is false, common build outputs are ignored. For VS Code extensions,
+/// consider enabling `--scan-bundles` because the shipped JS often lives in `dist/` or `out/`.
+fn should_ignore_path(path: &str, scan_bundles: bool, ignore_dirs: &IgnoreDirs) -> bool {
+    ignored_by(path, scan_bundles, ignore_dirs).is_some()
+}
+
+/// Why [`should_ignore_path`] ignores `path`: the ignored directory it is in.
+fn ignored_by(path: &str, scan_bundles: bool, ignore_dirs: &IgnoreDirs) -> Option<String> {
+    // Split by both / and \ for Windows compatibility
+    let components: Vec<&str> = path.split(['/', '\\']).collect();
+
+    for component in &components {
+        if ignore_dirs.contains(component) {
+            return Some(format!("in {}/", component));
+        }
+    }
+
+    if !scan_bundles {
+        for component in &components {
+            if matches!(*component, "dist" | "build" | "out" | ".next" | ".nuxt") {
+                return Some(format!("in build output {}/ (see --scan-bundles)", component));
+            }
+        }
+    }
+
+    None
+}
+
+/// Name and description for a suspicious code point, or `None` if it isn't flagged.
+fn classify(code: u32) -> Option<(String, String)> {
+    let description = if let Some(description) = suspicious_description(code) {
+        description.to_string()
+    } else if is_private_use_area(code) {
+        format!(
+            "Private use character (U+{:04X}) - commonly used for payload hiding",
+            code
+        )
+    } else if is_suspicious_control_char(code) {
+        format!("Suspicious control character (U+{:04X})", code)
+    } else {
+        return None;
+    };
+
+    Some((ucd::char_name(code), description))
+}
+
+/// True for format characters (general category Cf), which render as nothing or change how
+/// their neighbours render.
+fn is_format_char(ch: char) -> bool {
+    ch.general_category() == GeneralCategory::Format
+}
+
+/// True for combining marks (general categories Mn, Mc, Me).
+fn is_combining_mark(ch: char) -> bool {
+    ch.general_category_group() == GeneralCategoryGroup::Mark
+}
+
+/// A run of adjacent combining marks, reported as one detection if it is long enough.
+struct MarkRun {
+    /// Index in `detections` where the run's detection goes, ahead of any per-mark ones.
+    insert_at: usize,
+    line: usize,
+    char_index: usize,
+    byte_offset: usize,
+    marks: String,
+}
+
+/// A bidi embedding, override, or isolate that has not been closed yet on the current line.
+struct BidiOpener {
+    /// Index in `detections` where an "unterminated" detection goes, ahead of the opener's own.
+    insert_at: usize,
+    code: u32,
+    char_index: usize,
+    byte_offset: usize,
+}
+
+/// True for LRI, RLI, and FSI, which are closed by PDI rather than PDF.
+fn is_bidi_isolate(code: u32) -> bool {
+    (0x2066..=0x2068).contains(&code)
+}
+
+/// Why a detection is waiting for the next character.
+enum Lookahead {
+    /// A ZWJ right after an emoji: dropped if another emoji follows (`--allow-emoji`).
+    EmojiJoiner,
+
+    /// A no-break space in prose: dropped if punctuation follows (`« mot »`, `Quoi ?`).
+    ProseSpace,
+}
+
+/// True for the line terminators outside ASCII: NEXT LINE, LINE SEPARATOR, and PARAGRAPH
+/// SEPARATOR.
+fn is_unicode_line_break(code: u32) -> bool {
+    matches!(code, 0x0085 | 0x2028 | 0x2029)
+}
+
+/// Byte offset at which each line of `content` starts, under the same line model as the
+/// scanner.
+fn line_starts(content: &str, unicode_line_breaks: bool) -> Vec<usize> {
+    let mut starts = vec![0];
+    let mut chars = content.char_indices().peekable();
+    while let Some((i, ch)) = chars.next() {
+        let next = i + ch.len_utf8();
+        match ch {
+            '\r' if chars.next_if(|&(_, c)| c == '\n').is_some() => starts.push(next + 1),
+            '\n' | '\r' => starts.push(next),
+            _ if unicode_line_breaks && is_unicode_line_break(ch as u32) => starts.push(next),
+            _ => {}
+        }
+    }
+    starts
+}
+
+/// `line` without the terminator `line_starts` ended it at.
+fn strip_line_terminator(line: &str) -> &str {
+    if let Some(line) = line.strip_suffix("\r\n") {
+        return line;
+    }
+    match line.chars().next_back() {
+        Some(ch) if matches!(ch, '\n' | '\r') || is_unicode_line_break(ch as u32) => {
+            &line[..line.len() - ch.len_utf8()]
+        }
+        _ => line,
+    }
+}
+
+/// True for NO-BREAK SPACE and NARROW NO-BREAK SPACE, which French typography puts next to
+/// punctuation.
+fn is_typographic_space(code: u32) -> bool {
+    code == 0x00A0 || code == 0x202F
+}
+
+/// True for punctuation (general categories Pc, Pd, Ps, Pe, Pi, Pf, Po).
+fn is_punctuation(ch: char) -> bool {
+    ch.general_category_group() == GeneralCategoryGroup::Punctuation
+}
+
+/// An identifier-like token, checked for mixed scripts once it ends.
+struct Token {
+    /// Index in `detections` where the token's detection goes, ahead of any per-char ones.
+    insert_at: usize,
+    line: usize,
+    char_index: usize,
+    byte_offset: usize,
+    text: String,
+}
+
+/// Incremental scanner that carries line/column state across chunks of text.
+///
+/// Shared by the in-memory path and the streaming path so both report identical positions.
+struct CharScanner<'a> {
+    options: &'a DetectOptions,
+    file_path: &'a str,
+    line: usize,       // 1-indexed
+    char_index: usize, // resets per line; incremented on non-newline chars
+    detections: Vec<Detection>,
+
+    /// The current run of adjacent tag characters: each code point, with the index of its
+    /// detection unless `--max-detections-per-file` left it out.
+    tag_run: Vec<(u32, Option<usize>)>,
+
+    /// The current run of adjacent variation selectors, the same way as `tag_run`.
+    vs_run: Vec<(u32, Option<usize>)>,
+
+    /// The current run of combining marks, while `--combining-threshold` is on.
+    mark_run: Option<MarkRun>,
+
+    /// The current identifier-like token, while mixed-script detection is on.
+    token: Option<Token>,
+
+    /// The current identifier-like token with any invisible characters in it, while
+    /// `--lookalike-identifiers` is on.
+    identifier: Option<lookalike::Spot>,
+
+    /// The identifiers seen so far, while `--lookalike-identifiers` is on.
+    identifiers: Option<lookalike::Identifiers>,
+
+    /// Bidi controls opened on the current line and not closed yet, innermost last.
+    bidi_stack: Vec<BidiOpener>,
+
+    /// The previous character, for `--allow-emoji`.
+    prev_char: char,
+
+    /// True if the text so far ends with an emoji (plus any modifiers or VS16).
+    after_emoji: bool,
+
+    /// A detection held back until the next character shows whether to report it.
+    pending: Option<(Detection, Lookahead)>,
+
+    /// Detections settings dropped, kept for `--show-suppressed`.
+    suppressed: Vec<Detection>,
+
+    /// Text of the current line (without the newline).
+    line_text: String,
+
+    /// 1-indexed byte offset at which the current line starts.
+    line_offset: usize,
+
+    /// Index in `detections` of the first detection on the current line.
+    line_first_detection: usize,
+
+    /// `char_index` of each character on the current line that takes two UTF-16 code units.
+    line_astral: Vec<usize>,
+
+    /// Byte offset in `line_text` of each character on the current line.
+    line_chars: Vec<usize>,
+
+    /// The previous character was a Unicode line break (`--treat-ls-as-newline`); the line
+    /// ends once it has been reported.
+    unicode_break: bool,
+
+    /// Detections left out past `--max-detections-per-file`.
+    omitted: usize,
+
+    /// Detections on the current line that repeat the one before, so collapse into it.
+    line_repeats: usize,
+
+    /// The last detection left out past `--max-detections-per-file`, so a run of it is
+    /// counted once.
+    overflow: Option<Detection>,
+
+    /// Finds the keys on each line, in `.env`, INI, YAML, and shell files.
+    keys: Option<keys::KeyTokenizer>,
+}
+
+impl<'a> CharScanner<'a> {
+    fn new(file_path: &'a str, options: &'a DetectOptions) -> Self {
+        Self {
+            options,
+            file_path,
+            line: 1,
+            char_index: 0,
+            detections: Vec::new(),
+            tag_run: Vec::new(),
+            vs_run: Vec::new(),
+            mark_run: None,
+            token: None,
+            identifier: None,
+            identifiers: options
+                .lookalike_identifiers
+                .then(lookalike::Identifiers::default),
+            bidi_stack: Vec::new(),
+            prev_char: '\n',
+            after_emoji: false,
+            pending: None,
+            suppressed: Vec::new(),
+            line_text: String::new(),
+            line_offset: 1,
+            line_first_detection: 0,
+            line_astral: Vec::new(),
+            line_chars: Vec::new(),
+            unicode_break: false,
+            omitted: 0,
+            line_repeats: 0,
+            overflow: None,
+            keys: keys::KeyTokenizer::for_path(file_path),
+        }
+    }
+
+    /// Close the current token, flagging it if it mixes scripts.
+    fn end_token(&mut self) {
+        let Some(token) = self.token.take() else {
+            return;
+        };
+        if !scripts::is_mixed_script(&token.text) {
+            return;
+        }
+
+        let scripts = scripts::scripts_in(&token.text);
+        let first = scripts::first_foreign_char(&token.text).map_or(0, |c| c as u32);
+        let detection = Detection {
+            byte_offset: token.byte_offset,
+            byte_start: token.byte_offset - 1,
+            byte_end: token.byte_offset - 1 + token.text.len(),
+            char_index: token.char_index,
+            name: "MIXED-SCRIPT IDENTIFIER".to_string(),
+            description: format!(
+                "{:?} mixes {} scripts; it can imitate a different name",
+                token.text,
+                scripts.join(" and ")
+            ),
+            char: token.text,
+            ..Detection::new(self.file_path, token.line, first, "ICD-MIX001")
+        };
+        self.detections.insert(token.insert_at, detection);
+    }
+
+    /// Close the current identifier for `--lookalike-identifiers`.
+    fn end_identifier(&mut self) {
+        if let (Some(spot), Some(identifiers)) = (self.identifier.take(), &mut self.identifiers) {
+            identifiers.add(spot);
+        }
+    }
+
+    /// Report the identifiers that look the same as another in the file once invisible
+    /// characters are stripped, each where it first appears.
+    fn report_lookalikes(&mut self) {
+        let Some(identifiers) = self.identifiers.take() else {
+            return;
+        };
+        for lookalike in identifiers.lookalikes() {
+            let spot = lookalike.spot;
+            let first = spot
+                .text
+                .chars()
+                .find(|&c| lookalike::is_invisible(c))
+                .map_or(0, |c| c as u32);
+            let detection = Detection {
+                byte_offset: spot.byte_offset,
+                byte_start: spot.byte_offset - 1,
+                byte_end: spot.byte_offset - 1 + spot.text.len(),
+                char_index: spot.char_index,
+                utf16_column: spot.utf16_column,
+                name: "LOOKALIKE IDENTIFIER".to_string(),
+                description: format!(
+                    "{:?} looks the same as {:?} (line {}) but is a different identifier",
+                    spot.text, lookalike.other, lookalike.other_line
+                ),
+                char: spot.text,
+                ..Detection::new(self.file_path, spot.line, first, "ICD-LOOK001")
+            };
+            // Ahead of the identifier's own characters
+            let at = self
+                .detections
+                .partition_point(|d| (d.line, d.byte_offset) < (spot.line, spot.byte_offset));
+            self.detections.insert(at, detection);
+        }
+        if let Some(max) = self.options.max_detections {
+            if self.detections.len() > max {
+                self.omitted += self.detections.len() - max;
+                self.detections.truncate(max);
+            }
+        }
+    }
+
+    /// Track bidi embedding/override/isolate nesting the way the Unicode bidi algorithm does.
+    fn track_bidi(&mut self, code: u32, char_index: usize, byte_offset: usize) {
+        match code {
+            0x202A | 0x202B | 0x202D | 0x202E | 0x2066..=0x2068 => {
+                self.bidi_stack.push(BidiOpener {
+                    insert_at: self.detections.len(),
+                    code,
+                    char_index,
+                    byte_offset,
+                })
+            }
+            // PDF closes the innermost embedding, but never one outside an open isolate
+            0x202C if self.bidi_stack.last().is_some_and(|o| !is_bidi_isolate(o.code)) => {
+                self.bidi_stack.pop();
+            }
+            // PDI closes the innermost isolate and any embeddings opened inside it
+            0x2069 => {
+                let isolate = self.bidi_stack.iter().rposition(|o| is_bidi_isolate(o.code));
+                self.bidi_stack.truncate(isolate.unwrap_or(self.bidi_stack.len()));
+            }
+            _ => {}
+        }
+    }
+
+    /// Report the bidi controls still open at the end of the line (CVE-2021-42574).
+    fn end_bidi_line(&mut self) {
+        let open = std::mem::take(&mut self.bidi_stack);
+        let Some(first) = open.first() else {
+            return;
+        };
+
+        let names: Vec<String> = open
+            .iter()
+            .map(|o| format!("U+{:04X} {}", o.code, ucd::char_name(o.code)))
+            .collect();
+        let detection = Detection {
+            byte_offset: first.byte_offset,
+            byte_start: first.byte_offset - 1,
+            byte_end: first.byte_offset - 1 + char::from_u32(first.code).map_or(0, char::len_utf8),
+            char_index: first.char_index,
+            name: "UNTERMINATED BIDI CONTROL".to_string(),
+            description: format!(
+                "Not closed before the end of the line: {}; the rest of the line is displayed \
+                 reordered (Trojan Source, CVE-2021-42574)",
+                names.join(", ")
+            ),
+            ..Detection::new(self.file_path, self.line, first.code, "ICD-BIDI004")
+        };
+        self.detections.insert(first.insert_at, detection);
+    }
+
+    /// Finish the current line: report unterminated bidi controls, normalization mismatches,
+    /// and escaped characters, keeping the line's detections in column order, and work out
+    /// their UTF-16 columns. Then collapse repeats and apply `--max-detections-per-file`; every
+    /// run and token is closed at a line break, so nothing refers to the line's detections
+    /// any more.
+    fn end_line(&mut self) {
+        self.end_bidi_line();
+
+        if let Some(form) = self.options.normalization {
+            self.end_normalization_line(form);
+        }
+        if self.options.include_escapes {
+            self.end_escapes_line();
+        }
+        if self.options.normalization.is_some() || self.options.include_escapes {
+            self.detections[self.line_first_detection..].sort_by_key(|d| d.byte_offset);
+        }
+        if let Some(limit) = self.options.warn_line_length {
+            if self.line_text.len() > limit {
+                self.report_long_line(limit);
+            }
+        }
+        if self.keys.is_some() {
+            self.report_keys();
+        }
+
+        // Collapse and cap first, so only the detections kept are fingerprinted
+        let mut line = self.detections.split_off(self.line_first_detection);
+        if self.options.collapse_runs {
+            line = collapse_runs(line);
+        }
+        if let Some(max) = self.options.max_detections {
+            let room = max.saturating_sub(self.detections.len());
+            if line.len() > room {
+                self.omitted += line.len() - room;
+                line.truncate(room);
+            }
+        }
+        for d in &mut line {
+            self.finalize_line_detection(d);
+        }
+        self.detections.append(&mut line);
+
+        let first = self
+            .suppressed
+            .iter()
+            .rposition(|d| d.line != self.line)
+            .map_or(0, |i| i + 1);
+        let mut line = self.suppressed.split_off(first);
+        for d in &mut line {
+            self.finalize_line_detection(d);
+        }
+        self.suppressed.append(&mut line);
+
+        self.line_text.clear();
+        self.line_astral.clear();
+        self.line_chars.clear();
+        self.line_first_detection = self.detections.len();
+        self.line_repeats = 0;
+    }
+
+    /// Fill in what a detection on the current line needs the whole line for: its UTF-16
+    /// column and its fingerprint.
+    fn finalize_line_detection(&self, d: &mut Detection) {
+        let astral = self.line_astral.partition_point(|&i| i < d.char_index);
+        d.utf16_column = d.char_index + astral;
+        let window = fingerprint::window(&self.line_text, &self.line_chars, d.char_index);
+        d.fingerprint = fingerprint::fingerprint(d, window);
+    }
+
+    /// Report the invisible characters on the current line that are in a key or variable name
+    /// under `ICD-KEY001`, where they make a different name than the one the reader sees.
+    fn report_keys(&mut self) {
+        let Some(tokenizer) = &mut self.keys else {
+            return;
+        };
+        let keys = tokenizer.keys(&self.line_text);
+        if keys.is_empty() {
+            return;
+        }
+        let noun = tokenizer.noun();
+        for d in &mut self.detections[self.line_first_detection..] {
+            let at = d.byte_offset - self.line_offset;
+            let plain = !d.escaped && rules::rule_for(d.code).is_some_and(|rule| rule.id == d.rule);
+            if plain && keys.iter().any(|key| key.contains(&at)) {
+                d.description = format!("In a {}: {}", noun, d.description);
+                d.rule = "ICD-KEY001".to_string();
+                d.category = rules::category_of("ICD-KEY001", d.code);
+                d.severity = rules::severity_of("ICD-KEY001");
+            }
+        }
+    }
+
+    /// Report the current line as longer than `limit` bytes, ahead of the line's other
+    /// detections. The detection spans the whole line and names its first character.
+    fn report_long_line(&mut self, limit: usize) {
+        let first = self.line_text.chars().next().map_or(0, |c| c as u32);
+        let chars = self.line_text.chars().count();
+        let detection = Detection {
+            byte_offset: self.line_offset,
+            char_index: 1,
+            char: String::new(),
+            name: "LONG LINE".to_string(),
+            description: format!(
+                "Line is {} bytes ({} characters) long, over the limit of {}; minified or \
+                 obfuscated code hides what it does from review",
+                self.line_text.len(),
+                chars,
+                limit
+            ),
+            byte_start: self.line_offset - 1,
+            byte_end: self.line_offset - 1 + self.line_text.len(),
+            ..Detection::new(self.file_path, self.line, first, "ICD-LONG001")
+        };
+        self.detections.insert(self.line_first_detection, detection);
+    }
+
+    /// Report the file as saturated with suspicious characters if it has at least `limit` of
+    /// them, counting repeats and those left out past `--max-detections-per-file`. The
+    /// detection goes first, at the start of the file, and names the most frequent character.
+    fn report_saturated_file(&mut self, limit: usize) {
+        let mut counts: HashMap<u32, usize> = HashMap::new();
+        for d in self.detections.iter().filter(|d| d.rule != "ICD-LONG001") {
+            *counts.entry(d.code).or_default() += d.run.as_ref().map_or(1, |run| run.count);
+        }
+        let total = counts.values().sum::<usize>() + self.omitted;
+        if total < limit {
+            return;
+        }
+        let (code, most) = counts
+            .into_iter()
+            .max_by_key(|&(code, count)| (count, std::cmp::Reverse(code)))
+            .unwrap_or((0, 0));
+        let detection = Detection {
+            byte_offset: 1,
+            char_index: 1,
+            utf16_column: 1,
+            char: String::new(),
+            name: "SATURATED FILE".to_string(),
+            description: format!(
+                "{} suspicious characters in the file, at least the limit of {} ({} of them \
+                 U+{:04X}); a file this full of hidden characters is likely an obfuscated payload",
+                total, limit, most, code
+            ),
+            ..Detection::new(self.file_path, 1, code, "ICD-DENSE001")
+        };
+        self.detections.insert(0, detection);
+    }
+
+    /// Report the sequences on the current line that are not in normalization form `form`.
+    fn end_normalization_line(&mut self, form: NormalizationForm) {
+        for mismatch in normalize::find_mismatches(&self.line_text, form) {
+            let sequence = &self.line_text[mismatch.start..mismatch.end];
+            // NFKC folds e.g. NO-BREAK SPACE, which is already reported on its own
+            if mismatch.rule == "ICD-NORM002"
+                && sequence
+                    .chars()
+                    .all(|c| classify(c as u32).is_some())
+            {
+                continue;
+            }
+            let first = sequence.chars().next().map_or(0, |c| c as u32);
+            let (name, description) = if mismatch.rule == "ICD-NORM001" {
+                (
+                    "NON-NFC SEQUENCE",
+                    format!(
+                        "Not in NFC: {} normalizes to {}; identical-looking text can compare unequal",
+                        code_points(sequence),
+                        code_points(&mismatch.normalized)
+                    ),
+                )
+            } else {
+                (
+                    "COMPATIBILITY CHARACTER",
+                    format!(
+                        "Changes under NFKC: {} becomes {}; some tools fold it and others don't",
+                        code_points(sequence),
+                        code_points(&mismatch.normalized)
+                    ),
+                )
+            };
+
+            self.detections.push(Detection {
+                byte_offset: self.line_offset + mismatch.start,
+                byte_start: self.line_offset - 1 + mismatch.start,
+                byte_end: self.line_offset - 1 + mismatch.start + sequence.len(),
+                char_index: self.line_text[..mismatch.start].chars().count() + 1,
+                char: sequence.to_string(),
+                name: name.to_string(),
+                description,
+                ..Detection::new(self.file_path, self.line, first, mismatch.rule)
+            });
+        }
+    }
+
+    /// Report the escape sequences on the current line that produce a suspicious character.
+    ///
+    /// Escapes are the intended way to write control characters (`\u001B`) and no-break
+    /// spaces (`&#160;`), so those are not reported.
+    fn end_escapes_line(&mut self) {
+        for escape in escapes::find_escapes(&self.line_text) {
+            if is_suspicious_control_char(escape.code) || escape.code == 0x00A0 {
+                continue;
+            }
+            let Some((name, description)) = classify(escape.code) else {
+                continue;
+            };
+            let text = &self.line_text[escape.start..escape.end];
+            let rule = rules::rule_for(escape.code).map_or("", |r| r.id);
+            self.detections.push(Detection {
+                byte_offset: self.line_offset + escape.start,
+                byte_start: self.line_offset - 1 + escape.start,
+                byte_end: self.line_offset - 1 + escape.end,
+                char_index: self.line_text[..escape.start].chars().count() + 1,
+                char: text.to_string(),
+                name,
+                description: format!("{} (written as the escape {})", description, text),
+                escaped: true,
+                ..Detection::new(self.file_path, self.line, escape.code, rule)
+            });
+        }
+    }
+
+    /// Close the current run of combining marks, flagging it if it reaches the threshold.
+    fn end_mark_run(&mut self) {
+        let Some(run) = self.mark_run.take() else {
+            return;
+        };
+        let count = run.marks.chars().count();
+        if count < self.options.combining_threshold {
+            return;
+        }
+
+        let first = run.marks.chars().next().map_or(0, |c| c as u32);
+        let detection = Detection {
+            byte_offset: run.byte_offset,
+            byte_start: run.byte_offset - 1,
+            byte_end: run.byte_offset - 1 + run.marks.len(),
+            char_index: run.char_index,
+            char: run.marks,
+            name: "COMBINING MARK FLOOD".to_string(),
+            description: format!(
+                "{} combining marks stacked on one character (zalgo); can hide content and break rendering",
+                count
+            ),
+            ..Detection::new(self.file_path, run.line, first, "ICD-ZALGO001")
+        };
+        self.detections.insert(run.insert_at, detection);
+    }
+
+    /// Close the current run of tag characters, decoding the ASCII text it spells.
+    ///
+    /// The first detection of the run reports the payload; the rest point back to it.
+    fn end_tag_run(&mut self) {
+        let run = std::mem::take(&mut self.tag_run);
+        let payload: String = run
+            .iter()
+            .filter_map(|&(code, _)| decode_tag_char(code))
+            .collect();
+        let kept: Vec<usize> = run.iter().filter_map(|&(_, i)| i).collect();
+        let Some((&first, rest)) = kept.split_first() else {
+            return;
+        };
+        if payload.is_empty() {
+            return;
+        }
+
+        let first = &mut self.detections[first];
+        first.description = format!("Tag characters hide ASCII text: {:?}", payload);
+        let anchor = format!("{}:{}", first.line, first.char_index);
+        for &i in rest {
+            self.detections[i].description =
+                format!("Tag character; part of the hidden text decoded at {}", anchor);
+        }
+    }
+
+    /// Close the current run of variation selectors, decoding the bytes it hides if it is
+    /// long enough to be a payload rather than a glyph variant.
+    ///
+    /// The first detection of the run reports the payload; the rest point back to it.
+    fn end_vs_run(&mut self) {
+        let run = std::mem::take(&mut self.vs_run);
+        if run.len() < VS_PAYLOAD_MIN_RUN {
+            return;
+        }
+        let payload: Vec<u8> = run
+            .iter()
+            .filter_map(|&(code, _)| decode_variation_selector(code))
+            .collect();
+        let kept: Vec<usize> = run.iter().filter_map(|&(_, i)| i).collect();
+        let Some((&first, rest)) = kept.split_first() else {
+            return;
+        };
+
+        let first = &mut self.detections[first];
+        first.description = format!(
+            "{} variation selectors hide {} bytes: {}",
+            run.len(),
+            payload.len(),
+            describe_payload(&payload)
+        );
+        let anchor = format!("{}:{}", first.line, first.char_index);
+        for &i in rest {
+            self.detections[i].description = format!(
+                "Variation selector; part of the hidden payload decoded at {}",
+                anchor
+            );
+        }
+    }
+
+    /// Count the next text fed as the start of line `line`, at 1-indexed byte offset `offset`,
+    /// to scan part of a file (`--lines`).
+    fn start_at(&mut self, line: usize, offset: usize) {
+        self.line = line;
+        self.line_offset = offset;
+    }
+
+    /// Start a new line at 1-indexed byte offset `offset`.
+    fn next_line(&mut self, offset: usize) {
+        self.end_line();
+        self.line += 1;
+        self.char_index = 0;
+        self.line_offset = offset;
+    }
+
+    /// Scan the next piece of text, which starts `base_offset` bytes into the file.
+    ///
+    /// Lines end at `\n`, `\r\n`, or a lone `\r`, and with `--treat-ls-as-newline` also after
+    /// U+2028, U+2029, and U+0085.
+    fn feed(&mut self, text: &str, base_offset: usize) {
+        for (byte_i, ch) in text.char_indices() {
+            let code = ch as u32;
+            if std::mem::take(&mut self.unicode_break) {
+                self.next_line(base_offset + byte_i + 1);
+            }
+            if let Some((detection, lookahead)) = self.pending.take() {
+                let legitimate = match lookahead {
+                    Lookahead::EmojiJoiner => emoji::is_emoji_base(ch),
+                    Lookahead::ProseSpace => is_punctuation(ch),
+                };
+                match lookahead {
+                    _ if !legitimate => {
+                        if let Some(detection) = self.under_cap(detection) {
+                            self.detections.push(detection);
+                        }
+                    }
+                    Lookahead::EmojiJoiner => {
+                        self.suppress(detection, "allow_emoji", "in an emoji sequence")
+                    }
+                    Lookahead::ProseSpace => {
+                        self.suppress(detection, "prose", "next to punctuation")
+                    }
+                }
+            }
+            let prev_char = std::mem::replace(&mut self.prev_char, ch);
+            let after_emoji = self.after_emoji;
+            self.after_emoji =
+                emoji::is_emoji_base(ch) || (after_emoji && emoji::is_emoji_extender(ch));
+
+            if !self.tag_run.is_empty() && !is_tag_char(code) {
+                self.end_tag_run();
+            }
+            if !self.vs_run.is_empty() && decode_variation_selector(code).is_none() {
+                self.end_vs_run();
+            }
+
+            let is_mark = self.options.combining_threshold > 0 && is_combining_mark(ch);
+            if self.mark_run.is_some() && !is_mark {
+                self.end_mark_run();
+            }
+
+            let in_token = self.options.mixed_scripts && scripts::is_identifier_char(ch);
+            if self.token.is_some() && !in_token {
+                self.end_token();
+            }
+            let in_identifier = self.identifiers.is_some()
+                && (scripts::is_identifier_char(ch) || lookalike::is_invisible(ch));
+            if self.identifier.is_some() && !in_identifier {
+                self.end_identifier();
+            }
+
+            if ch == '\n' && prev_char == '\r' {
+                // The \r already ended the line
+                self.line_offset = base_offset + byte_i + 2;
+                continue;
+            }
+            if ch == '\n' || ch == '\r' {
+                self.next_line(base_offset + byte_i + 2);
+                continue;
+            }
+            if self.options.unicode_line_breaks && is_unicode_line_break(code) {
+                self.unicode_break = true;
+            }
+
+            self.char_index += 1;
+            if ch.len_utf16() == 2 {
+                self.line_astral.push(self.char_index);
+            }
+            self.line_chars.push(self.line_text.len());
+            self.line_text.push(ch);
+
+            if is_mark {
+                let run = self.mark_run.get_or_insert_with(|| MarkRun {
+                    insert_at: self.detections.len(),
+                    line: self.line,
+                    char_index: self.char_index,
+                    byte_offset: base_offset + byte_i + 1,
+                    marks: String::new(),
+                });
+                run.marks.push(ch);
+            }
+
+            if in_token {
+                let token = self.token.get_or_insert_with(|| Token {
+                    insert_at: self.detections.len(),
+                    line: self.line,
+                    char_index: self.char_index,
+                    byte_offset: base_offset + byte_i + 1,
+                    text: String::new(),
+                });
+                token.text.push(ch);
+            }
+            if in_identifier {
+                let utf16_column = self.char_index
+                    + self
+                        .line_astral
+                        .partition_point(|&i| i < self.char_index);
+                let spot = self.identifier.get_or_insert_with(|| lookalike::Spot {
+                    line: self.line,
+                    char_index: self.char_index,
+                    utf16_column,
+                    byte_offset: base_offset + byte_i + 1,
+                    text: String::new(),
+                });
+                spot.text.push(ch);
+            }
+
+            let flagged = match classify(code) {
+                Some((name, description)) => {
+                    Some((name, description, rules::rule_for(code).map_or("", |r| r.id)))
+                }
+                None if self.options.detect_ranges.iter().any(|r| r.contains(&code)) => {
+                    let description = format!(
+                        "Code point (U+{:04X}) in a range given with --detect-range",
+                        code
+                    );
+                    Some((ucd::char_name(code), description, "ICD-RANGE001"))
+                }
+                None if self.options.all_format_chars && is_format_char(ch) => {
+                    let description = format!(
+                        "Format character (U+{:04X}) with no specific rule; may render as nothing",
+                        code
+                    );
+                    Some((ucd::char_name(code), description, "ICD-FMT001"))
+                }
+                None if self.options.confusables => confusables::ascii_lookalike(ch).map(|ascii| {
+                    let description = format!(
+                        "{} character that looks like ASCII {:?}; text can read as something it isn't",
+                        scripts::script_name(ch),
+                        ascii
+                    );
+                    (ucd::char_name(code), description, "ICD-CONF001")
+                }),
+                None => None,
+            };
+            let flagged = flagged.or_else(|| {
+                let allowed = self.options.allowed_scripts.as_deref()?;
+                if scripts::is_allowed(ch, allowed) {
+                    return None;
+                }
+                let names: Vec<&str> = allowed.iter().map(|s| s.full_name()).collect();
+                let description = format!(
+                    "{} character outside the allowed scripts ({})",
+                    scripts::script_name(ch),
+                    names.join(", ")
+                );
+                Some((ucd::char_name(code), description, "ICD-SCRIPT001"))
+            });
+            let flagged = flagged.or_else(|| {
+                if !self.options.ascii_only || ch.is_ascii() {
+                    return None;
+                }
+                let description = format!(
+                    "{} character in a file that must be ASCII-only",
+                    scripts::script_name(ch)
+                );
+                Some((ucd::char_name(code), description, "ICD-ASCII001"))
+            });
+            let Some((name, mut description, mut rule)) = flagged else {
+                continue;
+            };
+            // U+FEFF as the very first character is a byte order mark, not a hidden character
+            let leading_bom = code == 0xFEFF && base_offset + byte_i == 0;
+            if leading_bom {
+                description = "Byte order mark at the start of the file".to_string();
+                rule = "ICD-BOM001";
+            }
+            self.track_bidi(code, self.char_index, base_offset + byte_i + 1);
+
+            let detection = Detection {
+                byte_offset: base_offset + byte_i + 1, // 1-indexed
+                byte_start: base_offset + byte_i,
+                byte_end: base_offset + byte_i + ch.len_utf8(),
+                char_index: self.char_index,
+                name,
+                description,
+                severity: if leading_bom && self.options.forbid_bom {
+                    rules::Severity::Error
+                } else {
+                    rules::severity_of(rule)
+                },
+                ..Detection::new(self.file_path, self.line, code, rule)
+            };
+            if self.options.allow_emoji {
+                if code == 0xFE0F && emoji::is_emoji(prev_char) {
+                    self.suppress(detection, "allow_emoji", "in an emoji sequence");
+                    continue;
+                }
+                if code == 0x200D && after_emoji {
+                    self.pending = Some((detection, Lookahead::EmojiJoiner));
+                    continue;
+                }
+            }
+            if self.options.prose && is_typographic_space(code) {
+                if is_punctuation(prev_char) {
+                    self.suppress(detection, "prose", "next to punctuation");
+                    continue;
+                }
+                self.pending = Some((detection, Lookahead::ProseSpace));
+                continue;
+            }
+            let kept = self.under_cap(detection).map(|detection| {
+                self.detections.push(detection);
+                self.detections.len() - 1
+            });
+            if is_tag_char(code) {
+                self.tag_run.push((code, kept));
+            }
+            if decode_variation_selector(code).is_some() {
+                self.vs_run.push((code, kept));
+            }
+        }
+    }
+
+    /// Pass `detection` back to be kept, or count it as omitted once the file has
+    /// `--max-detections-per-file` detections, so a file full of one character does not hold
+    /// every one of them until the line ends. A repeat of the last detection kept still is,
+    /// since it collapses into it.
+    fn under_cap(&mut self, detection: Detection) -> Option<Detection> {
+        let Some(max) = self.options.max_detections else {
+            return Some(detection);
+        };
+        let collapse = self.options.collapse_runs;
+        if collapse
+            && self
+                .detections
+                .last()
+                .is_some_and(|prev| continues_run(prev, &detection))
+        {
+            self.line_repeats += 1;
+            return Some(detection);
+        }
+        if self.detections.len() - self.line_repeats < max {
+            return Some(detection);
+        }
+        let repeat = collapse
+            && self
+                .overflow
+                .as_ref()
+                .is_some_and(|prev| continues_run(prev, &detection));
+        if !repeat {
+            self.omitted += 1;
+        }
+        self.overflow = Some(detection);
+        None
+    }
+
+    /// Keep `detection`, which `setting` drops, for `--show-suppressed`, up to
+    /// `--max-detections-per-file` of them.
+    fn suppress(&mut self, mut detection: Detection, setting: &str, why: &str) {
+        let full = self
+            .options
+            .max_detections
+            .is_some_and(|max| self.suppressed.len() >= max);
+        if self.options.show_suppressed && !full {
+            detection.suppressed = Some(format!("{}: {}", setting, why));
+            self.suppressed.push(detection);
+        }
+    }
+
+    fn finish(mut self) -> Vec<Detection> {
+        if let Some((detection, _)) = self.pending.take() {
+            if let Some(detection) = self.under_cap(detection) {
+                self.detections.push(detection);
+            }
+        }
+        self.end_tag_run();
+        self.end_vs_run();
+        self.end_mark_run();
+        self.end_token();
+        self.end_identifier();
+        self.end_line();
+        self.report_lookalikes();
+        if let Some(limit) = self.options.warn_file_detections {
+            self.report_saturated_file(limit);
+        }
+        if self.omitted > 0 {
+            if let Some(last) = self.detections.last_mut() {
+                last.more_in_file = Some(self.omitted);
+            }
+        }
+        self.detections.append(&mut self.suppressed);
+        for d in &mut self.detections {
+            d.fix = clean::fix_for(d);
+        }
+        fingerprint::fill(&mut self.detections);
+        self.detections
+    }
+}
+
+/// Check the names along `path` for suspicious characters, reporting each file or directory
+/// under its own path (`ICD-NAME001`). Directories already in `seen` are not reported again.
+fn detect_in_names(path: &str, seen: &mut HashSet<String>) -> Vec<Detection> {
+    let mut detections = Vec::new();
+    let mut end = 0;
+    for name in path.split(['/', '\\']) {
+        end += name.len();
+        let prefix = &path[..end];
+        end += 1;
+        if !seen.insert(prefix.to_string()) {
+            continue;
+        }
+
+        for (char_i, (byte_i, ch)) in name.char_indices().enumerate() {
+            let code = ch as u32;
+            let Some((char_name, description)) = classify(code) else {
+                continue;
+            };
+            detections.push(Detection {
+                byte_offset: byte_i + 1,
+                byte_start: byte_i,
+                byte_end: byte_i + ch.len_utf8(),
+                char_index: char_i + 1,
+                utf16_column: name[..byte_i].encode_utf16().count() + 1,
+                name: char_name,
+                description: format!("In the name {:?}: {}", name, description),
+                ..Detection::new(prefix, 0, code, "ICD-NAME001")
+            });
+        }
+    }
+    fingerprint::fill(&mut detections);
+    detections
+}
+
+/// Space-separated `U+XXXX` code points of `text`.
+fn code_points(text: &str) -> String {
+    text.chars()
+        .map(|c| format!("U+{:04X}", c as u32))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Scan file content for suspicious invisible/formatting characters.
+///
+/// Uses `char_indices()` so `byte_offset` is always correct (no newline guessing).
+/// `line` and `char_index` count lines ending in `\n`, `\r\n`, or a lone `\r`, and also in
+/// U+0085, U+2028, and U+2029 with `--treat-ls-as-newline`.
+fn detect_invisible_characters(
+    content: &str,
+    file_path: &str,
+    options: &DetectOptions,
+) -> Vec<Detection> {
+    let mut scanner = CharScanner::new(file_path, options);
+    match &options.lines {
+        Some(lines) => {
+            let starts = line_starts(content, options.unicode_line_breaks);
+            let start = starts.get(*lines.start() - 1).copied().unwrap_or(content.len());
+            let end = starts.get(*lines.end()).copied().unwrap_or(content.len());
+            scanner.start_at(*lines.start(), start + 1);
+            scanner.feed(&content[start..end], start);
+        }
+        None => scanner.feed(content, 0),
+    }
+    let mut detections = scanner.finish();
+    annotate_detections(content, file_path, options, &mut detections);
+    detections
+}
+
+/// Add to `detections` what needs the whole of `content`: structural paths, syntax context
+/// (dropping what the context policy rules out), `--show-context` snippets, and
+/// `--hex-context` dumps.
+fn annotate_detections(
+    content: &str,
+    file_path: &str,
+    options: &DetectOptions,
+    detections: &mut Vec<Detection>,
+) {
+    structure::annotate(
+        file_path,
+        content,
+        &line_starts(content, options.unicode_line_breaks),
+        detections,
+    );
+    if options.syntax_context || options.context_policy.is_active() {
+        context::classify(file_path, content, detections);
+        options
+            .context_policy
+            .apply(detections, options.show_suppressed);
+    }
+    if options.show_context {
+        let starts = line_starts(content, options.unicode_line_breaks);
+        snippet::attach(content, &starts, options.escape_style, detections);
+    }
+    if options.hex_context > 0 {
+        hexdump::attach(content.as_bytes(), options.hex_context, detections);
+    }
+}
+
+/// What [`annotate_detections`] adds for `file_path` under `options`, for saying what a
+/// streamed file goes without.
+fn annotations(file_path: &str, options: &DetectOptions) -> Vec<&'static str> {
+    let mut names = Vec::new();
+    if structure::applies(file_path) {
+        names.push("structure paths");
+    }
+    if options.context_policy.is_active() {
+        names.push("syntax context (so no --require-context/--ignore-context)");
+    } else if options.syntax_context {
+        names.push("syntax context");
+    }
+    if options.show_context {
+        names.push("--show-context");
+    }
+    if options.hex_context > 0 {
+        names.push("--hex-context");
+    }
+    names
+}
+
+/// Files at least this large are scanned in chunks instead of being read into memory.
+const STREAMING_THRESHOLD: u64 = 8 * 1024 * 1024;
+
+/// Size of each read in the streaming path.
+const STREAM_CHUNK_LEN: usize = 64 * 1024;
+
+/// Scan UTF-8 text from a reader in fixed-size chunks.
+///
+/// A code point split across two reads is held back (at most 3 bytes) and completed by the
+/// next read, so positions match `detect_invisible_characters` exactly. Invalid UTF-8 fails
+/// the whole file with `InvalidData`, like `fs::read_to_string`.
+fn scan_reader<R: Read>(
+    mut reader: R,
+    file_path: &str,
+    options: &DetectOptions,
+) -> std::io::Result<Vec<Detection>> {
+    let mut scanner = CharScanner::new(file_path, options);
+    let mut buf = vec![0u8; STREAM_CHUNK_LEN];
+    let mut pending: Vec<u8> = Vec::with_capacity(STREAM_CHUNK_LEN + 4);
+    let mut offset = 0usize;
+
+    let invalid = || {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "stream did not contain valid UTF-8",
+        )
+    };
+
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        pending.extend_from_slice(&buf[..n]);
+
+        let valid = match std::str::from_utf8(&pending) {
+            Ok(text) => text.len(),
+            // Incomplete sequence at the end: wait for the rest of the code point.
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => return Err(invalid()),
+        };
+
+        let text = std::str::from_utf8(&pending[..valid]).map_err(|_| invalid())?;
+        scanner.feed(text, offset);
+        offset += valid;
+        pending.drain(..valid);
+    }
+
+    if !pending.is_empty() {
+        return Err(invalid());
+    }
+
+    Ok(scanner.finish())
+}
+
+/// Number of leading bytes inspected when deciding whether a file is binary.
+const BINARY_SNIFF_LEN: usize = 8192;
+
+/// Broad class of a binary file, for counting skipped files by what they are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum BinaryClass {
+    Image,
+    Archive,
+    Executable,
+    Document,
+    Media,
+    Font,
+    Database,
+
+    /// No known signature, but NUL bytes.
+    Other,
+}
+
+impl BinaryClass {
+    /// Plural noun for counts, e.g. "images".
+    fn plural(self) -> &'static str {
+        match self {
+            BinaryClass::Image => "images",
+            BinaryClass::Archive => "archives",
+            BinaryClass::Executable => "executables",
+            BinaryClass::Document => "documents",
+            BinaryClass::Media => "audio/video files",
+            BinaryClass::Font => "fonts",
+            BinaryClass::Database => "databases",
+            BinaryClass::Other => "other",
+        }
+    }
+}
+
+/// A binary format recognised by [`sniff_binary_bytes`].
+#[derive(Debug, Clone, Copy)]
+struct BinaryKind {
+    /// Format name, e.g. "PNG image".
+    label: &'static str,
+    class: BinaryClass,
+}
+
+/// Magic numbers of common binary formats and their offset in the file. Formats at offset 0
+/// are recognised even when the sniffed prefix has no NUL; the others only tell what a file
+/// with NUL bytes is, so text can't be mistaken for them.
+const BINARY_SIGNATURES: &[(usize, &[u8], &str, BinaryClass)] = &[
+    (0, b"\x89PNG\r\n\x1a\n", "PNG image", BinaryClass::Image),
+    (0, b"\xFF\xD8\xFF", "JPEG image", BinaryClass::Image),
+    (0, b"GIF87a", "GIF image", BinaryClass::Image),
+    (0, b"GIF89a", "GIF image", BinaryClass::Image),
+    (8, b"WEBP", "WebP image", BinaryClass::Image),
+    (0, b"II*\x00", "TIFF image", BinaryClass::Image),
+    (0, b"MM\x00*", "TIFF image", BinaryClass::Image),
+    (0, b"\x00\x00\x01\x00", "ICO image", BinaryClass::Image),
+    (0, b"8BPS\x00\x01", "Photoshop image", BinaryClass::Image),
+    (4, b"ftypheic", "HEIC image", BinaryClass::Image),
+    (4, b"ftypavif", "AVIF image", BinaryClass::Image),
+    (0, b"%PDF-", "PDF document", BinaryClass::Document),
+    (0, b"\xD0\xCF\x11\xE0\xA1\xB1\x1A\xE1", "OLE document", BinaryClass::Document),
+    (0, b"PK\x03\x04", "ZIP archive", BinaryClass::Archive),
+    (0, b"\x1F\x8B", "gzip archive", BinaryClass::Archive),
+    (0, b"\xFD7zXZ\x00", "xz archive", BinaryClass::Archive),
+    (0, b"7z\xBC\xAF\x27\x1C", "7z archive", BinaryClass::Archive),
+    (0, b"\x28\xB5\x2F\xFD", "zstd archive", BinaryClass::Archive),
+    (0, b"Rar!\x1A\x07", "RAR archive", BinaryClass::Archive),
+    (257, b"ustar", "tar archive", BinaryClass::Archive),
+    (0, b"\x7FELF", "ELF executable", BinaryClass::Executable),
+    (0, b"\xCA\xFE\xBA\xBE", "Mach-O/Java class", BinaryClass::Executable),
+    (0, b"\xCF\xFA\xED\xFE", "Mach-O executable", BinaryClass::Executable),
+    (0, b"\xCE\xFA\xED\xFE", "Mach-O executable", BinaryClass::Executable),
+    (0, b"\x00asm", "WebAssembly module", BinaryClass::Executable),
+    (0, b"dex\n0", "Android DEX", BinaryClass::Executable),
+    (0, b"ID3\x03\x00", "MP3 audio", BinaryClass::Media),
+    (0, b"ID3\x04\x00", "MP3 audio", BinaryClass::Media),
+    (0, b"OggS", "Ogg media", BinaryClass::Media),
+    (0, b"fLaC", "FLAC audio", BinaryClass::Media),
+    (8, b"WAVE", "WAV audio", BinaryClass::Media),
+    (8, b"AVI ", "AVI video", BinaryClass::Media),
+    (4, b"ftyp", "MP4/QuickTime media", BinaryClass::Media),
+    (0, b"\x1A\x45\xDF\xA3", "Matroska/WebM video", BinaryClass::Media),
+    (0, b"wOFF", "WOFF font", BinaryClass::Font),
+    (0, b"wOF2", "WOFF2 font", BinaryClass::Font),
+    (0, b"OTTO\x00", "OpenType font", BinaryClass::Font),
+    (0, b"\x00\x01\x00\x00\x00", "TrueType font", BinaryClass::Font),
+    (0, b"SQLite format 3\x00", "SQLite database", BinaryClass::Database),
+];
+
+/// The first few KB of a file, for [`sniff_binary_bytes`].
+fn read_head(path: &Path) -> std::io::Result<Vec<u8>> {
+    let mut head = Vec::with_capacity(BINARY_SNIFF_LEN);
+    fs::File::open(path)?
+        .take(BINARY_SNIFF_LEN as u64)
+        .read_to_end(&mut head)?;
+    Ok(head)
+}
+
+/// Classify content as binary by sniffing its first few KB.
+///
+/// Returns the format (or "contains NUL bytes") for binary files and `None` for files that
+/// look like text. UTF-16 text, full of NUL bytes, is recognized by its BOM.
+fn sniff_binary_bytes(content: &[u8]) -> Option<BinaryKind> {
+    let head = &content[..content.len().min(BINARY_SNIFF_LEN)];
+    if encoding::utf16_bom(head).is_some() {
+        return None;
+    }
+
+    let has_nul = head.contains(&0);
+    if let Some(&(_, _, label, class)) = BINARY_SIGNATURES.iter().find(|(offset, magic, _, _)| {
+        (*offset == 0 || has_nul) && head.get(*offset..).is_some_and(|at| at.starts_with(magic))
+    }) {
+        return Some(BinaryKind { label, class });
+    }
+
+    if has_nul {
+        // Windows executables start with "MZ" and are full of NULs
+        let (label, class) = if head.starts_with(b"MZ") {
+            ("Windows executable", BinaryClass::Executable)
+        } else {
+            ("contains NUL bytes", BinaryClass::Other)
+        };
+        return Some(BinaryKind { label, class });
+    }
+
+    None
+}
+
+/// What happened to a single path produced by the walker.
+enum FileOutcome {
+    /// The file was scanned (or its unchanged results were taken from the cache).
+    Scanned {
+        detections: Vec<Detection>,
+        /// True when the detections were reused from the cache.
+        from_cache: bool,
+        /// New or refreshed cache entry to record for this file.
+        cache_update: Option<CacheEntry>,
+    },
+
+    /// Symlink not followed (`--follow-symlinks` off).
+    Symlink,
+
+    /// Directory already reached through another path; neither scanned nor skipped.
+    AlreadyVisited,
+
+    /// The same file as the one at this path, already scanned; its detections list both paths.
+    Duplicate(String),
+
+    /// Path is inside an ignored directory.
+    Ignored,
+
+    /// File exceeds `--max-file-size`.
+    TooLarge(u64),
+
+    /// File was recognised as binary.
+    Binary(BinaryKind),
+
+    /// File could not be read or is not valid UTF-8.
+    Unreadable(std::io::Error),
+}
+
+impl FileOutcome {
+    /// True if the file was scanned and something was found in it.
+    fn has_detections(&self) -> bool {
+        matches!(self, FileOutcome::Scanned { detections, .. } if !detections.is_empty())
+    }
+
+    /// True if the file has a detection that fails the scan.
+    fn has_failures(&self) -> bool {
+        matches!(self, FileOutcome::Scanned { detections, .. }
+            if detections.iter().any(|d| d.severity.fails()))
+    }
+}
+
+/// Bump whenever the detections produced for the same input change (new characters, new
+/// fields), so caches written by an older build of the same version are discarded.
+const DETECTOR_REVISION: u32 = 34;
+
+/// Identifies everything that affects which detections a file produces.
+///
+/// Cached results are only reused when this matches the run that produced them.
+fn detection_settings(config: &ScanConfig) -> String {
+    format!(
+        "v{}.r{}.{:?}.{:?}.{:?}.{:?}.{:?}.a{}.d{}",
+        env!("CARGO_PKG_VERSION"),
+        DETECTOR_REVISION,
+        config.detect,
+        config.prose,
+        config.allow_emoji,
+        config.overrides,
+        config.presets,
+        config.scan_archives,
+        config.scan_documents
+    )
+}
+
+/// Decode file content as UTF-8 (or UTF-16, given its BOM) and scan it.
+fn decode_and_scan(
+    bytes: Vec<u8>,
+    path_str: &str,
+    options: &DetectOptions,
+) -> std::io::Result<Vec<Detection>> {
+    if let Some(encoding) = encoding::utf16_bom(&bytes) {
+        let content = encoding::decode_utf16(&bytes, encoding);
+        let mut detections = detect_invisible_characters(&content, path_str, options);
+        encoding::remap_utf16(&content, encoding, &mut detections);
+        // Dump the file's own bytes, not those of the transcoded text
+        if options.hex_context > 0 {
+            hexdump::attach(&bytes, options.hex_context, &mut detections);
+        }
+        return Ok(detections);
+    }
+    let bytes = match String::from_utf8(bytes) {
+        Ok(content) => return Ok(detect_invisible_characters(&content, path_str, options)),
+        Err(e) if options.lossy || options.report_invalid_utf8 => e.into_bytes(),
+        Err(_) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "stream did not contain valid UTF-8",
+            ))
+        }
+    };
+    // The rest of the text is scanned either way; --report-invalid-utf8 adds the bad bytes
+    let lossy = encoding::Lossy::decode(&bytes);
+    let mut detections = detect_invisible_characters(&lossy.text, path_str, options);
+    lossy.remap(&mut detections);
+    if options.report_invalid_utf8 {
+        let mut invalid = detect_invalid_utf8(&bytes, path_str);
+        if let Some(lines) = &options.lines {
+            invalid.retain(|d| lines.contains(&d.line));
+        }
+        // Without --lossy, what an invalid sequence decodes to (a lone A0 byte is a no-break
+        // space in Windows-1252) is only a stand-in for the bytes reported as ICD-UTF8001
+        if !options.lossy {
+            detections.retain(|d| {
+                let i = invalid.partition_point(|bad| bad.byte_end <= d.byte_start);
+                invalid.get(i).is_none_or(|bad| d.byte_end <= bad.byte_start)
+            });
+        }
+        detections.extend(invalid);
+        detections.sort_by_key(|d| (d.line != 0, d.byte_offset));
+    }
+    if options.hex_context > 0 {
+        hexdump::attach(&bytes, options.hex_context, &mut detections);
+    }
+    Ok(detections)
+}
+
+/// Report each invalid UTF-8 sequence in `bytes` (`ICD-UTF8001`).
+fn detect_invalid_utf8(bytes: &[u8], file_path: &str) -> Vec<Detection> {
+    let mut detections: Vec<Detection> = encoding::invalid_utf8(bytes)
+        .into_iter()
+        .map(|sequence| {
+            let hex = sequence.hex();
+            let (name, description) = match (sequence.malformation, sequence.decoded) {
+                (encoding::Malformation::Overlong, Some(value)) => (
+                    "OVERLONG UTF-8 ENCODING",
+                    format!(
+                        "Invalid UTF-8 bytes {}: overlong encoding of U+{:04X}, which lenient \
+                         decoders accept and filters looking for the short form miss",
+                        hex, value
+                    ),
+                ),
+                (encoding::Malformation::Surrogate, Some(value)) if value > 0xFFFF => (
+                    "UTF-8 ENCODED SURROGATE PAIR",
+                    format!(
+                        "Invalid UTF-8 bytes {}: U+{:04X} encoded as a surrogate pair (CESU-8)",
+                        hex, value
+                    ),
+                ),
+                (encoding::Malformation::Surrogate, value) => (
+                    "UTF-8 ENCODED SURROGATE",
+                    format!(
+                        "Invalid UTF-8 bytes {}: lone surrogate U+{:04X}",
+                        hex,
+                        value.unwrap_or_default()
+                    ),
+                ),
+                (encoding::Malformation::OutOfRange, _) => (
+                    "UTF-8 BEYOND U+10FFFF",
+                    format!("Invalid UTF-8 bytes {}: decodes beyond U+10FFFF", hex),
+                ),
+                (encoding::Malformation::Truncated, _) => (
+                    "TRUNCATED UTF-8 SEQUENCE",
+                    format!(
+                        "Invalid UTF-8 bytes {}: sequence cut short before its continuation bytes",
+                        hex
+                    ),
+                ),
+                _ => (
+                    "INVALID UTF-8 BYTE",
+                    format!(
+                        "Invalid UTF-8 bytes {}: bytes that can't start a UTF-8 sequence",
+                        hex
+                    ),
+                ),
+            };
+            Detection {
+                byte_offset: sequence.offset + 1,
+                byte_start: sequence.offset,
+                byte_end: sequence.offset + sequence.bytes.len(),
+                char_index: sequence.char_index,
+                utf16_column: sequence.utf16_column,
+                // What a decoder puts in place of the bytes
+                char: char::REPLACEMENT_CHARACTER.to_string(),
+                name: name.to_string(),
+                description,
+                invalid_bytes: Some(hex),
+                ..Detection::new(file_path, sequence.line, 0xFFFD, "ICD-UTF8001")
+            }
+        })
+        .collect();
+    fingerprint::fill(&mut detections);
+    detections
+}
+
+/// Scan the text files inside an archive, reporting them as `archive.zip!path/inside.js`.
+///
+/// Entries are not subject to the ignored-directory rules: a `node_modules/` inside a package
+/// is part of what ships.
+fn scan_archive(
+    bytes: &[u8],
+    kind: archive::ArchiveKind,
+    path_str: &str,
+    config: &ScanConfig,
+) -> std::io::Result<Vec<Detection>> {
+    let entries = archive::read_entries(bytes, kind, config.max_file_size).map_err(|e| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("invalid archive: {}", e),
+        )
+    })?;
+    Ok(scan_archive_entries(entries, path_str, |inner| {
+        config.detect_options(inner)
+    }))
+}
+
+/// Scan the names and text files of archive entries with the options `options` gives for
+/// each entry's reported path.
+fn scan_archive_entries<'a>(
+    entries: Vec<archive::ArchiveEntry>,
+    path_str: &str,
+    options: impl Fn(&str) -> Cow<'a, DetectOptions>,
+) -> Vec<Detection> {
+    let mut seen_names = HashSet::new();
+    let mut detections = Vec::new();
+    for entry in entries {
+        let inner = format!("{}!{}", path_str, entry.path);
+        for mut d in detect_in_names(&entry.path, &mut seen_names) {
+            d.file = format!("{}!{}", path_str, d.file);
+            detections.push(d);
+        }
+        if sniff_binary_bytes(&entry.content).is_some() {
+            continue;
+        }
+        // Entries that aren't UTF-8 text are skipped like binary files
+        if let Ok(found) = decode_and_scan(entry.content, &inner, &options(&inner)) {
+            detections.extend(found);
+        }
+    }
+    detections
+}
+
+/// Scan the text of an Office, OpenDocument, or PDF file or an email, one paragraph per line,
+/// reporting each part as `report.docx!word/document.xml` (or `spec.pdf!page-3`,
+/// `mail.eml!headers/Subject`).
+fn scan_document(
+    bytes: &[u8],
+    kind: document::DocumentKind,
+    path_str: &str,
+    config: &ScanConfig,
+) -> std::io::Result<Vec<Detection>> {
+    let parts = document::extract_text(bytes, kind, config.max_file_size).map_err(|e| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("invalid document: {}", e),
+        )
+    })?;
+
+    let mut detections = Vec::new();
+    for part in parts {
+        let inner = format!("{}!{}", path_str, part.name);
+        detections.extend(detect_invisible_characters(
+            &part.text,
+            &inner,
+            &config.detect_options(&inner),
+        ));
+    }
+    Ok(detections)
+}
+
+/// A file's path, what became of it, and with `--timings` and `--file-info`, its time and
+/// what its bytes are.
+type EntryResult = (
+    String,
+    FileOutcome,
+    Option<FileTime>,
+    Option<file_info::FileInfo>,
+);
+
+/// Decide what to do with content that is already in memory (e.g. a staged blob).
+fn process_blob(content: Vec<u8>, path_str: &str, config: &ScanConfig) -> FileOutcome {
+    if should_ignore_path(path_str, config.scan_bundles, &config.ignore_dirs) {
+        return FileOutcome::Ignored;
+    }
+
+    let size = content.len() as u64;
+    if config.max_file_size.is_some_and(|limit| size > limit) {
+        return FileOutcome::TooLarge(size);
+    }
+
+    if let Some(kind) = sniff_binary_bytes(&content) {
+        return FileOutcome::Binary(kind);
+    }
+
+    match decode_and_scan(content, path_str, &config.detect_options(path_str)) {
+        Ok(detections) => FileOutcome::Scanned {
+            detections,
+            from_cache: false,
+            cache_update: None,
+        },
+        Err(e) => FileOutcome::Unreadable(e),
+    }
+}
+
+/// The time a file took to read and scan and the bytes it had.
+type FileTime = (Duration, u64);
+
+/// With `--timings`, the time a scanned file took since `started` and the bytes it had (none
+/// when its results came from the cache).
+fn file_time(
+    config: &ScanConfig,
+    started: Instant,
+    outcome: &FileOutcome,
+    bytes: impl FnOnce() -> u64,
+) -> Option<FileTime> {
+    match outcome {
+        FileOutcome::Scanned { from_cache, .. } if config.timings.is_some() => {
+            Some((started.elapsed(), if *from_cache { 0 } else { bytes() }))
+        }
+        _ => None,
+    }
+}
+
+/// With `--file-info`, what the bytes of a scanned file are. Archives and documents, whose
+/// entries are scanned rather than their bytes, have none.
+fn inspect_file(
+    config: &ScanConfig,
+    path_str: &str,
+    outcome: &FileOutcome,
+) -> Option<file_info::FileInfo> {
+    if !config.file_info
+        || !matches!(outcome, FileOutcome::Scanned { .. })
+        || archive::archive_kind(path_str).is_some_and(|_| config.scan_archives)
+        || document::document_kind(path_str).is_some_and(|_| config.scan_documents)
+    {
+        return None;
+    }
+    match fs::File::open(path_str).and_then(|file| file_info::inspect(file, path_str)) {
+        Ok(info) => Some(info),
+        Err(e) => {
+            logging::debug!("could not inspect {}: {}", path_str, e);
+            None
+        }
+    }
+}
+
+/// Decide what to do with one walked path and, if appropriate, scan it.
+///
+/// Pure with respect to shared state so it can run on any worker thread.
+fn process_entry(
+    walked: WalkEntry,
+    config: &ScanConfig,
+    cache: Option<&ScanCache>,
+) -> (String, FileOutcome) {
+    let entry = match walked {
+        WalkEntry::Matched(path) => path,
+        WalkEntry::SymlinkNotFollowed(path) => {
+            return (path.to_string_lossy().into_owned(), FileOutcome::Symlink)
+        }
+        WalkEntry::AlreadyVisited(path) => {
+            return (path.to_string_lossy().into_owned(), FileOutcome::AlreadyVisited)
+        }
+        WalkEntry::Duplicate { path, first } => {
+            let first = first.to_string_lossy().into_owned();
+            return (path.to_string_lossy().into_owned(), FileOutcome::Duplicate(first));
+        }
+    };
+    let path_str = entry.to_string_lossy().into_owned();
+    let detect = config.detect_options(&path_str);
+
+    // Skip ignored paths, unless they were named one by one
+    if config.files.is_none()
+        && should_ignore_path(&path_str, config.scan_bundles, &config.ignore_dirs)
+    {
+        return (path_str, FileOutcome::Ignored);
+    }
+
+    // Skip oversized files before reading them into memory
+    let meta = fs::metadata(&entry).ok();
+    let size = meta.as_ref().map_or(0, |m| m.len());
+    if config.max_file_size.is_some_and(|limit| size > limit) {
+        return (path_str, FileOutcome::TooLarge(size));
+    }
+
+    // Unchanged size and mtime: reuse the previous results without reading the file
+    let stamp = meta.as_ref().and_then(FileStamp::from_metadata);
+    let previous = cache.and_then(|c| c.get(&path_str));
+    if let (Some(prev), Some(stamp)) = (previous, stamp) {
+        if prev.stamp == stamp {
+            let outcome = FileOutcome::Scanned {
+                detections: prev.detections.clone(),
+                from_cache: true,
+                cache_update: None,
+            };
+            return (path_str, outcome);
+        }
+    }
+
+    // Archives and documents are read whole and their entries scanned in memory
+    let archive = archive::archive_kind(&path_str).filter(|_| config.scan_archives);
+    let document = document::document_kind(&path_str).filter(|_| config.scan_documents);
+    let scanned = match (archive, document) {
+        (Some(kind), _) => {
+            Some(fs::read(&entry).and_then(|bytes| scan_archive(&bytes, kind, &path_str, config)))
+        }
+        (None, Some(kind)) => {
+            Some(fs::read(&entry).and_then(|bytes| scan_document(&bytes, kind, &path_str, config)))
+        }
+        (None, None) => None,
+    };
+    if let Some(scanned) = scanned {
+        let outcome = match scanned {
+            Ok(detections) => FileOutcome::Scanned {
+                cache_update: stamp.filter(|_| cache.is_some()).map(|stamp| CacheEntry {
+                    stamp,
+                    hash: None,
+                    detections: detections.clone(),
+                }),
+                detections,
+                from_cache: false,
+            },
+            Err(e) => FileOutcome::Unreadable(e),
+        };
+        return (path_str, outcome);
+    }
+
+    // Skip binary files explicitly rather than via UTF-8 decode failures
+    let head = match read_head(&entry) {
+        Ok(head) => head,
+        Err(e) => return (path_str, FileOutcome::Unreadable(e)),
+    };
+    if let Some(kind) = sniff_binary_bytes(&head) {
+        return (path_str, FileOutcome::Binary(kind));
+    }
+
+    // Very large files are streamed instead of loaded whole (and not content-hashed); the
+    // streaming scanner only reads UTF-8 (and gives up on invalid UTF-8, which --lossy and
+    // --report-invalid-utf8 read). --lines reads the whole file to scan only part of it.
+    // What needs the whole file around a detection is left out rather than read back
+    if size >= STREAMING_THRESHOLD
+        && encoding::utf16_bom(&head).is_none()
+        && !detect.lossy
+        && !detect.report_invalid_utf8
+        && detect.lines.is_none()
+    {
+        let file = fs::File::open(&entry);
+        let scanned = file.and_then(|file| scan_reader(file, &path_str, &detect));
+        let skipped = annotations(&path_str, &detect);
+        if scanned.as_ref().is_ok_and(|d| !d.is_empty()) && !skipped.is_empty() {
+            logging::warning!(
+                "{}: skipped {} for this file, which is too large to read whole",
+                path_str,
+                skipped.join(", ")
+            );
+        }
+        let outcome = match scanned {
+            Ok(detections) => FileOutcome::Scanned {
+                cache_update: stamp.filter(|_| cache.is_some()).map(|stamp| CacheEntry {
+                    stamp,
+                    hash: None,
+                    detections: detections.clone(),
+                }),
+                detections,
+                from_cache: false,
+            },
+            Err(e) => FileOutcome::Unreadable(e),
+        };
+        return (path_str, outcome);
+    }
+
+    let bytes = match fs::read(&entry) {
+        Ok(bytes) => bytes,
+        Err(e) => return (path_str, FileOutcome::Unreadable(e)),
+    };
+
+    // Touched but identical content: reuse the previous results under the new mtime
+    let hash = cache.map(|_| cache::content_hash(&bytes));
+    if let (Some(prev), Some(stamp)) = (previous, stamp) {
+        if prev.hash.is_some() && prev.hash == hash {
+            let outcome = FileOutcome::Scanned {
+                detections: prev.detections.clone(),
+                from_cache: true,
+                cache_update: Some(CacheEntry {
+                    stamp,
+                    hash,
+                    detections: prev.detections.clone(),
+                }),
+            };
+            return (path_str, outcome);
+        }
+    }
+
+    let detections = match decode_and_scan(bytes, &path_str, &detect) {
+        Ok(detections) => detections,
+        Err(e) => return (path_str, FileOutcome::Unreadable(e)),
+    };
+    let cache_update = stamp.filter(|_| cache.is_some()).map(|stamp| CacheEntry {
+        stamp,
+        hash,
+        detections: detections.clone(),
+    });
+    let outcome = FileOutcome::Scanned {
+        detections,
+        from_cache: false,
+        cache_update,
+    };
+    (path_str, outcome)
+}
+
+/// Scan the lines added by the unified diff on stdin (`--patch`).
+///
+/// Only the added lines are seen, so what needs the whole file (`--context`, `--show-context`,
+/// JSON and YAML paths) is left out.
+fn scan_patch(config: &ScanConfig) -> std::io::Result<ScanResult> {
+    let mut bytes = Vec::new();
+    std::io::stdin().read_to_end(&mut bytes)?;
+    let patch = String::from_utf8(bytes).map_err(|_| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "the patch on stdin is not valid UTF-8",
+        )
+    })?;
+
+    let mut result = ScanResult {
+        census: config.census.then(summary::SummaryBuilder::default),
+        skipped_files: (config.format == OutputFormat::Json
+            && config.json_version >= report::SCHEMA_VERSION)
+            .then(Vec::new),
+        suppressed: config.detect.show_suppressed.then(Vec::new),
+        ..ScanResult::default()
+    };
+    let mut seen_names = HashSet::new();
+
+    for file in patch::added_lines(&patch) {
+        if should_ignore_path(&file.path, config.scan_bundles, &config.ignore_dirs) {
+            result.skipped += 1;
+            result.skip(&file.path, "ignored", None);
+            logging::debug!("(ignored) {}", file.path);
+            continue;
+        }
+        result.keep(detect_in_names(&file.path, &mut seen_names));
+
+        let options = config.detect_options(&file.path);
+        let mut scanner = CharScanner::new(&file.path, &options);
+        for added in &file.lines {
+            scanner.start_at(added.line, added.offset + 1);
+            scanner.feed(&patch[added.offset..added.offset + added.len], added.offset);
+        }
+        let mut detections = scanner.finish();
+        if options.hex_context > 0 {
+            hexdump::attach(patch.as_bytes(), options.hex_context, &mut detections);
+        }
+        result.scanned += 1;
+        result.keep(detections);
+        if config.fail_fast && result.detections.iter().any(|d| d.severity.fails()) {
+            break;
+        }
+    }
+
+    if result.scanned == 0 && result.skipped == 0 {
+        logging::info!("The patch on stdin adds no lines");
+    }
+    sort_detections(&mut result.detections);
+    if let Some(suppressed) = &mut result.suppressed {
+        sort_detections(suppressed);
+    }
+    Ok(result)
+}
+
+/// Scan the text on the clipboard.
+fn scan_clipboard(config: &ScanConfig) -> std::io::Result<ScanResult> {
+    let text = clipboard::read().map_err(std::io::Error::other)?;
+    let detections = detect_invisible_characters(&text, clipboard::CLIPBOARD_NAME, &config.detect);
+    let mut result = ScanResult {
+        census: config.census.then(summary::SummaryBuilder::default),
+        skipped_files: (config.format == OutputFormat::Json
+            && config.json_version >= report::SCHEMA_VERSION)
+            .then(Vec::new),
+        scanned: 1,
+        suppressed: config.detect.show_suppressed.then(Vec::new),
+        ..ScanResult::default()
+    };
+    result.keep(detections);
+    Ok(result)
+}
+
+/// Scan the text on stdin as the file `name`, with the options that file would get.
+fn scan_stdin(config: &ScanConfig, name: &str) -> std::io::Result<ScanResult> {
+    let mut bytes = Vec::new();
+    std::io::stdin().read_to_end(&mut bytes)?;
+    let detections = decode_and_scan(bytes, name, &config.detect_options(name))?;
+    let mut result = ScanResult {
+        census: config.census.then(summary::SummaryBuilder::default),
+        skipped_files: (config.format == OutputFormat::Json
+            && config.json_version >= report::SCHEMA_VERSION)
+            .then(Vec::new),
+        scanned: 1,
+        suppressed: config.detect.show_suppressed.then(Vec::new),
+        ..ScanResult::default()
+    };
+    result.keep(detections);
+    Ok(result)
+}
+
+/// Scan each `--root` in turn, or just the pattern when there are none.
+fn scan_roots(config: &ScanConfig) -> std::io::Result<ScanResult> {
+    if config.roots.is_empty() {
+        return scan_files(config);
+    }
+    let mut merged: Option<ScanResult> = None;
+    for root in &config.roots {
+        let mut root_config = config.clone();
+        root_config.pattern = roots::pattern_under(root, &config.pattern);
+        let mut result = scan_files(&root_config)?;
+        for d in result
+            .detections
+            .iter_mut()
+            .chain(result.suppressed.iter_mut().flatten())
+        {
+            d.root = Some(root.clone());
+        }
+        let findings = match &result.census {
+            Some(census) => census.total(),
+            None => result
+                .detections
+                .iter()
+                .map(|d| d.run.as_ref().map_or(1, |run| run.count) + d.more_in_file.unwrap_or(0))
+                .sum(),
+        };
+        let failed = result.detections.iter().any(|d| d.severity.fails())
+            || result.census.as_ref().is_some_and(|c| c.has_failures());
+        result.roots.push(roots::RootCounts {
+            root: root.clone(),
+            scanned: result.scanned,
+            findings,
+            failed,
+        });
+        match &mut merged {
+            Some(merged) => merged.merge(result),
+            None => merged = Some(result),
+        }
+        // The roots after a failing one are never scanned
+        if config.fail_fast && failed {
+            break;
+        }
+    }
+    // Detections stay grouped by root, in the order the roots were given
+    Ok(merged.unwrap_or_default())
+}
+
+/// Scan all files matched by a glob pattern.
+///
+/// Files are processed in parallel on a rayon pool sized by `--jobs`, but outcomes are
+/// collected and reported in traversal (path) order, so output is identical for any job count.
+fn scan_files(config: &ScanConfig) -> std::io::Result<ScanResult> {
+    if config.patch {
+        return scan_patch(config);
+    }
+    if config.clipboard {
+        return scan_clipboard(config);
+    }
+    if let Some(name) = &config.stdin_filename {
+        return scan_stdin(config, name);
+    }
+
+    let invalid_pattern = |e: glob::PatternError| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Invalid glob pattern: {}", e),
+        )
+    };
+
+    // In --diff mode only files with changed lines are scanned at all
+    let changed = match &config.diff_base {
+        Some(base) => Some(
+            git::ChangedLines::since(base, config.staged).map_err(std::io::Error::other)?,
+        ),
+        None => None,
+    };
+    let mut relative_paths: HashMap<String, PathBuf> = HashMap::new();
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(config.jobs)
+        .build()
+        .map_err(std::io::Error::other)?;
+
+    // Staged blobs never touch the working tree, so the cache does not apply to them
+    let mut cache = config.cache.as_ref().filter(|_| !config.staged).map(|path| {
+        let (cache, discarded) = ScanCache::load(path, &detection_settings(config));
+        if let Some(reason) = discarded {
+            logging::debug!("(cache) {}: {}", path.display(), reason);
+        }
+        cache
+    });
+
+    // --fail-fast: files not started once one has detections are never scanned. In --diff mode
+    // detections outside changed lines are dropped later, so only the loop below can stop.
+    let stop = AtomicBool::new(false);
+    let stop_if_found = |outcome: &FileOutcome| {
+        if config.fail_fast && changed.is_none() && outcome.has_failures() {
+            stop.store(true, Ordering::Relaxed);
+        }
+    };
+
+    // Generated and vendored files among those to scan
+    let generated;
+    let outcomes: Vec<EntryResult> = if config.staged {
+        let pattern = glob::Pattern::new(&config.pattern).map_err(invalid_pattern)?;
+        let mut staged = git::staged_files().map_err(std::io::Error::other)?;
+        staged.retain(|file| {
+            walk::matches(&pattern, &file.path)
+                && changed
+                    .as_ref()
+                    .is_none_or(|changed| changed.contains_file(&file.repo_path))
+        });
+        let paths: Vec<&Path> = staged.iter().map(|file| file.path.as_path()).collect();
+        generated = generated_paths(config.generated, &paths);
+        if config.generated == GeneratedFiles::Skip {
+            staged.retain(|file| !generated.contains(&file.path));
+        }
+        for file in &staged {
+            let key = file.path.to_string_lossy().into_owned();
+            relative_paths.insert(key, file.repo_path.clone());
+        }
+
+        let progress = Progress::new(staged.len(), config.progress);
+        let outcomes = pool.install(|| {
+            staged
+                .into_par_iter()
+                .map(|file| {
+                    if stop.load(Ordering::Relaxed) {
+                        return None;
+                    }
+                    let started = Instant::now();
+                    let path_str = file.path.to_string_lossy().into_owned();
+                    let bytes = file.content.len() as u64;
+                    let info = config
+                        .file_info
+                        .then(|| file_info::inspect(&file.content[..], &path_str).ok())
+                        .flatten();
+                    let outcome = process_blob(file.content, &path_str, config);
+                    let time = file_time(config, started, &outcome, || bytes);
+                    let info = info.filter(|_| matches!(outcome, FileOutcome::Scanned { .. }));
+                    progress.file_done(&path_str, outcome.has_detections());
+                    stop_if_found(&outcome);
+                    Some((path_str, outcome, time, info))
+                })
+                .while_some()
+                .collect()
+        });
+        progress.finish();
+        outcomes
+    } else {
+        let mut walk_results = match &config.files {
+            // Given by the pre-commit framework, which has already picked what to check; a
+            // name that is not there is a mistake, not an empty match
+            Some(files) => files
+                .iter()
+                .map(|file| match fs::symlink_metadata(file) {
+                    Ok(_) => Ok(WalkEntry::Matched(PathBuf::from(file))),
+                    Err(e) => Err(std::io::Error::new(e.kind(), format!("{}: {}", file, e))),
+                })
+                .collect::<std::io::Result<_>>()?,
+            None => walk::walk(&config.pattern, config.follow_symlinks).map_err(invalid_pattern)?,
+        };
+        if config.respect_gitignore && config.files.is_none() {
+            let paths: Vec<&Path> = walk_results.iter().map(WalkEntry::path).collect();
+            // Outside a repository there is no .gitignore to respect
+            if let Ok(ignored) = git::ignored_paths(&paths) {
+                walk_results.retain(|walked| !ignored.contains(walked.path()));
+            }
+        }
+        let paths: Vec<&Path> = walk_results.iter().map(WalkEntry::path).collect();
+        generated = generated_paths(config.generated, &paths);
+        if config.generated == GeneratedFiles::Skip {
+            walk_results.retain(|walked| !generated.contains(walked.path()));
+        }
+        if let Some(changed) = &changed {
+            walk_results.retain(|walked| match changed.repo_relative(walked.path()) {
+                Some(relative) if changed.contains_file(&relative) => {
+                    let key = walked.path().to_string_lossy().into_owned();
+                    relative_paths.insert(key, relative);
+                    true
+                }
+                _ => false,
+            });
+        }
+
+        let progress = Progress::new(walk_results.len(), config.progress);
+        let outcomes = pool.install(|| {
+            walk_results
+                .into_par_iter()
+                .map(|walked| {
+                    if stop.load(Ordering::Relaxed) {
+                        return None;
+                    }
+                    let started = Instant::now();
+                    let (path_str, outcome) = process_entry(walked, config, cache.as_ref());
+                    let time = file_time(config, started, &outcome, || {
+                        fs::metadata(&path_str).map_or(0, |m| m.len())
+                    });
+                    let info = inspect_file(config, &path_str, &outcome);
+                    progress.file_done(&path_str, outcome.has_detections());
+                    stop_if_found(&outcome);
+                    Some((path_str, outcome, time, info))
+                })
+                .while_some()
+                .collect()
+        });
+        progress.finish();
+        outcomes
+    };
+
+    let mut result = ScanResult {
+        census: config.census.then(summary::SummaryBuilder::default),
+        skipped_files: (config.format == OutputFormat::Json
+            && config.json_version >= report::SCHEMA_VERSION)
+            .then(Vec::new),
+        timings: config.timings.map(|_| timings::TimingsBuilder::default()),
+        file_info: config.file_info.then(Vec::new),
+        suppressed: config.detect.show_suppressed.then(Vec::new),
+        ..ScanResult::default()
+    };
+    let mut seen_names = HashSet::new();
+    let mut duplicates: HashMap<String, Vec<String>> = HashMap::new();
+
+    for (path_str, outcome, time, info) in outcomes {
+        if let (Some(timings), Some((duration, bytes))) = (&mut result.timings, time) {
+            timings.add(&path_str, duration, bytes);
+        }
+        if let (Some(files), Some(info)) = (&mut result.file_info, info) {
+            files.push(info);
+        }
+        // --lines is about the content of a file, not its name
+        if !matches!(outcome, FileOutcome::Ignored | FileOutcome::AlreadyVisited)
+            && config.detect.lines.is_none()
+        {
+            let names = detect_in_names(&path_str, &mut seen_names);
+            result.keep(names);
+        }
+        match outcome {
+            FileOutcome::Scanned {
+                mut detections,
+                from_cache,
+                cache_update,
+            } => {
+                result.scanned += 1;
+                if from_cache {
+                    result.cached += 1;
+                }
+                if let (Some(changed), Some(relative)) = (&changed, relative_paths.get(&path_str)) {
+                    if config.detect.show_suppressed {
+                        let base = config.diff_base.as_deref().unwrap_or_default();
+                        for d in detections.iter_mut().filter(|d| d.suppressed.is_none()) {
+                            if !changed.contains_line(relative, d.line) {
+                                d.suppressed =
+                                    Some(format!("--diff: on a line not changed since {}", base));
+                            }
+                        }
+                    } else {
+                        detections.retain(|d| changed.contains_line(relative, d.line));
+                    }
+                }
+                if config.generated == GeneratedFiles::Downgrade
+                    && generated.contains(Path::new(&path_str))
+                {
+                    for d in &mut detections {
+                        if d.severity == rules::Severity::Error {
+                            d.severity = rules::Severity::Warning;
+                        }
+                    }
+                }
+                if let (Some(cache), Some(update)) = (cache.as_mut(), cache_update) {
+                    cache.insert(path_str, update);
+                }
+                result.keep(detections);
+            }
+            FileOutcome::Symlink => {
+                result.skipped += 1;
+                result.skip(&path_str, "symlink", None);
+                logging::debug!("(symlink) {}", path_str);
+            }
+            FileOutcome::AlreadyVisited => {
+                logging::debug!("(already visited) {}", path_str);
+            }
+            FileOutcome::Duplicate(first) => {
+                logging::debug!("(same file as {}) {}", first, path_str);
+                duplicates.entry(first).or_default().push(path_str);
+            }
+            FileOutcome::Ignored => {
+                result.skipped += 1;
+                result.skip(&path_str, "ignored", None);
+                logging::debug!("(ignored) {}", path_str);
+            }
+            FileOutcome::TooLarge(size) => {
+                result.skipped += 1;
+                result.skip(&path_str, "too-large", Some(format!("{} bytes", size)));
+                logging::debug!("(too large: {} bytes) {}", size, path_str);
+            }
+            FileOutcome::Binary(kind) => {
+                result.skip_binary(&path_str, kind);
+                logging::debug!("(binary: {}) {}", kind.label, path_str);
+            }
+            FileOutcome::Unreadable(e) => {
+                result.skipped += 1;
+                result.skip(&path_str, "unreadable", Some(e.to_string()));
+                logging::debug!("could not read {}: {}", path_str, e);
+            }
+        }
+        if config.fail_fast && result.detections.iter().any(|d| d.severity.fails()) {
+            break;
+        }
+    }
+
+    // A file reached by several paths is scanned once and reported with all of them
+    for d in result.detections.iter_mut().filter(|d| d.line > 0) {
+        if let Some(paths) = duplicates.get(&d.file) {
+            d.also_at = paths.clone();
+        }
+    }
+
+    if result.scanned == 0 && result.skipped == 0 && result.binary == 0 {
+        match (&config.diff_base, config.staged) {
+            (Some(base), _) => logging::warning!(
+                "no files changed since {} match pattern: {}",
+                base, config.pattern
+            ),
+            (None, true) => logging::warning!("no staged files match pattern: {}", config.pattern),
+            (None, false) if config.files.is_some() => logging::warning!("no files given to scan"),
+            (None, false) => logging::warning!("no files matched pattern: {}", config.pattern),
+        }
+    }
+
+    if let (Some(cache), Some(path)) = (cache, config.cache.as_ref()) {
+        if let Err(e) = cache.save(path) {
+            logging::warning!("could not write cache {}: {}", path.display(), e);
+        }
+    }
+
+    sort_detections(&mut result.detections);
+    if let Some(suppressed) = &mut result.suppressed {
+        sort_detections(suppressed);
+    }
+    Ok(result)
+}
+
+/// Put detections in report order: by file path, a file's name before its content, then by
+/// byte offset. The sort is stable, so an "unterminated" bidi finding stays ahead of the
+/// opener it shares an offset with.
+fn sort_detections(detections: &mut [Detection]) {
+    detections.sort_by(|a, b| {
+        a.file
+            .cmp(&b.file)
+            .then_with(|| (a.line != 0).cmp(&(b.line != 0)))
+            .then_with(|| a.byte_offset.cmp(&b.byte_offset))
+    });
+}
+
+/// Format detections as human-readable text, grouped by file in the order the files first
+/// appear ([`sort_detections`] order unless sorted by risk).
+fn format_text_output(detections: &[Detection], style: TextStyle) -> String {
+    if detections.is_empty() {
+        return "No suspicious invisible characters detected.".to_string();
+    }
+
+    // Counted like the verdict, a run as its characters; those left out are counted below
+    let omitted: usize = detections.iter().filter_map(|d| d.more_in_file).sum();
+    let mut output = format!(
+        "Found {} suspicious character(s):\n\n",
+        summary::SeverityCounts::new(detections).total()
+    );
+    if style.group_by != GroupBy::File {
+        output.push_str(&format_groups(detections, style));
+        if omitted > 0 {
+            output.push_str(&format!(
+                "... and {} more not listed (see --max-detections-per-file)\n",
+                omitted
+            ));
+        }
+        return output;
+    }
+    let mut grouped: HashMap<&str, Vec<&Detection>> = HashMap::new();
+    let mut files = Vec::new();
+
+    for detection in detections {
+        let dets = grouped.entry(&detection.file).or_default();
+        if dets.is_empty() {
+            files.push(detection.file.as_str());
+        }
+        dets.push(detection);
+    }
+
+    for file in files {
+        let dets = &grouped[file];
+        // The name itself may hold the bidi controls being reported
+        let header = visualize_as(file, style.escapes);
+        output.push_str(&style.paint(&header, render::BOLD));
+        if let Some(score) = dets[0].file_risk {
+            output.push_str(&format!(" (risk {})", score));
+        }
+        output.push('\n');
+        if let Some(d) = dets.iter().find(|d| !d.also_at.is_empty()) {
+            let paths: Vec<String> = d
+                .also_at
+                .iter()
+                .map(|path| visualize_as(path, style.escapes))
+                .collect();
+            output.push_str(&format!("  (also at {})\n", paths.join(", ")));
+        }
+
+        for d in dets {
+            let mut notes = String::new();
+            if d.escaped {
+                notes.push_str(", escaped form");
+            }
+            if let Some(context) = d.context {
+                notes.push_str(&format!(", in {}", context.as_str()));
+            }
+            if let Some(run) = &d.run {
+                notes.push_str(&format!(
+                    ", {} in a row through column {}",
+                    run.count, run.end_char_index
+                ));
+            }
+            let mut position = if d.line == 0 {
+                format!("Name:{}", d.char_index)
+            } else {
+                format!("Line {}:{}", d.line, d.char_index)
+            };
+            if let Some(path) = &d.structure_path {
+                position.push_str(&format!(" at {}", visualize_as(path, style.escapes)));
+            }
+            let rule_color = match d.severity {
+                rules::Severity::Warning => render::YELLOW,
+                rules::Severity::Error => render::RED,
+                rules::Severity::Info => render::DIM,
+            };
+            output.push_str(&format!(
+                "    {} (byte {}) - {} (U+{:04X}, {}, {}{}) [{}]\n",
+                position,
+                d.byte_offset,
+                d.name,
+                d.code,
+                d.general_category,
+                d.category,
+                notes,
+                style.paint(&format!("{}, {}", d.rule, d.severity.as_str()), rule_color)
+            ));
+            output.push_str(&format!("  {}\n", d.description));
+            if let Some(blame) = &d.blame {
+                output.push_str(&format!(
+                    "      last changed in {} by {} on {}\n",
+                    &blame.commit[..blame.commit.len().min(12)],
+                    blame.author,
+                    blame.date
+                ));
+            }
+            if let Some(lines) = &d.snippet {
+                output.push_str(&snippet::format_snippet(lines, style));
+            }
+            if let Some(context) = &d.hex_context {
+                output.push_str(&hexdump::format_hex_context(d, context, style));
+            }
+            if let Some(more) = d.more_in_file {
+                output.push_str(&format!(
+                    "    ... and {} more in this file (see --max-detections-per-file)\n",
+                    more
+                ));
+            }
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+/// The detections grouped by code point or category (`--group-by`), the largest group first,
+/// each with its count and the lines it is on in each file.
+fn format_groups(detections: &[Detection], style: TextStyle) -> String {
+    /// Lines listed per file before the rest are left out.
+    const MAX_LINES: usize = 10;
+
+    let mut groups: HashMap<String, Vec<&Detection>> = HashMap::new();
+    for d in detections {
+        let key = match style.group_by {
+            GroupBy::Category => d.category.clone(),
+            _ => format!("U+{:04X} {}", d.code, d.name),
+        };
+        groups.entry(key).or_default().push(d);
+    }
+    let mut groups: Vec<(String, Vec<&Detection>)> = groups.into_iter().collect();
+    groups.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(&b.0)));
+
+    let mut output = String::new();
+    for (key, dets) in groups {
+        let mut files: Vec<(&str, Vec<usize>)> = Vec::new();
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        for d in &dets {
+            let i = *index.entry(&d.file).or_insert_with(|| {
+                files.push((&d.file, Vec::new()));
+                files.len() - 1
+            });
+            files[i].1.push(d.line);
+        }
+        let mut code_points: Vec<u32> = dets.iter().map(|d| d.code).collect();
+        code_points.sort_unstable();
+        code_points.dedup();
+
+        output.push_str(&style.paint(&key, render::BOLD));
+        output.push_str(&format!(": {} occurrence(s)", dets.len()));
+        if style.group_by == GroupBy::Category {
+            output.push_str(&format!(" of {} code point(s)", code_points.len()));
+        }
+        output.push_str(&format!(" across {} file(s)\n", files.len()));
+
+        for (file, mut lines) in files {
+            let count = lines.len();
+            lines.dedup();
+            let mut places = Vec::new();
+            // Line 0 is the file's own name
+            if lines.first() == Some(&0) {
+                lines.remove(0);
+                places.push("file name".to_string());
+            }
+            if !lines.is_empty() {
+                let mut numbers: Vec<String> = lines
+                    .iter()
+                    .take(MAX_LINES)
+                    .map(|line| line.to_string())
+                    .collect();
+                if lines.len() > MAX_LINES {
+                    numbers.push("...".to_string());
+                }
+                let label = if lines.len() == 1 { "line" } else { "lines" };
+                places.push(format!("{} {}", label, numbers.join(", ")));
+            }
+            output.push_str(&format!(
+                "    {} ({}): {}\n",
+                visualize_as(file, style.escapes),
+                count,
+                places.join(", ")
+            ));
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// Format detections one per line as
+/// `file:line:col: error: U+200B ZERO WIDTH SPACE [ICD-ZW001, zero-width]`, the form compilers
+/// use, so editors' quickfix lists and problem matchers can jump to them. Columns are in UTF-16
+/// code units, as editors count them.
+fn format_short_output(detections: &[Detection]) -> String {
+    let mut output = String::new();
+    for d in detections {
+        output.push_str(&format!(
+            "{}:{}:{}: {}: U+{:04X} {} [{}, {}]\n",
+            visualize(&d.file),
+            d.line,
+            d.utf16_column,
+            d.severity.as_str(),
+            d.code,
+            d.name,
+            d.rule,
+            d.category
+        ));
+    }
+    output
+}
+
+/// Rewrite `text` with every suspicious character replaced by a visible `<U+XXXX>` marker.
+fn visualize(text: &str) -> String {
+    visualize_as(text, EscapeStyle::default())
+}
+
+/// Rewrite `text` with every suspicious character spelled in `style`.
+fn visualize_as(text: &str, style: EscapeStyle) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if ch != '\n' && (classify(ch as u32).is_some() || is_format_char(ch)) {
+            render::push_escaped(ch, style, &mut out);
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
+
+/// Decode a hex string such as `e2808b` (whitespace allowed) into bytes.
+fn decode_hex(hex: &str) -> Result<Vec<u8>, String> {
+    let digits: Vec<u8> = hex.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    let pairs = digits.chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return Err("hex input has an odd number of digits".to_string());
+    }
+    pairs
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|p| u8::from_str_radix(p, 16).ok())
+                .ok_or_else(|| format!("invalid hex byte: {}", String::from_utf8_lossy(pair)))
+        })
+        .collect()
+}
+
+/// Run `check` on a command-line string and exit with the usual exit codes.
+fn run_check(args: &cli::CheckArgs) -> ! {
+    let text = match (&args.text, &args.hex) {
+        (_, Some(hex)) => match decode_hex(hex).and_then(|bytes| {
+            String::from_utf8(bytes).map_err(|_| "hex input is not valid UTF-8".to_string())
+        }) {
+            Ok(text) => text,
+            Err(e) => {
+                logging::error!("{}", e);
+                process::exit(2);
+            }
+        },
+        (Some(text), None) => text.clone(),
+        (None, None) => unreachable!("clap requires text or --hex"),
+    };
+
+    let detections = detect_invisible_characters(&text, "<input>", &DetectOptions::default());
+
+    if args.json {
+        match serde_json::to_string_pretty(&detections) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                logging::error!("could not serialize to JSON: {}", e);
+                process::exit(2);
+            }
+        }
+    } else {
+        println!("Input: {}\n", visualize(&text));
+        let style = TextStyle {
+            color: args.color.enabled(),
+            ..TextStyle::default()
+        };
+        println!("{}", format_text_output(&detections, style));
+    }
+
+    let found = detections.iter().any(|d| d.severity.fails());
+    process::exit(if found { 1 } else { 0 });
+}
+
+/// Run `check-paste` on stdin or the clipboard and exit with the usual exit codes.
+fn run_check_paste(args: &cli::CheckPasteArgs) -> ! {
+    let text = if args.clipboard {
+        clipboard::read()
+    } else {
+        let mut text = String::new();
+        std::io::stdin()
+            .read_to_string(&mut text)
+            .map(|_| text)
+            .map_err(|e| format!("could not read stdin: {}", e))
+    };
+    let text = match text {
+        Ok(text) => text,
+        Err(e) => {
+            logging::error!("{}", e);
+            process::exit(2);
+        }
+    };
+
+    let detections = detect_invisible_characters(&text, paste::PASTE_NAME, &paste::options());
+    let found = detections.iter().any(|d| d.severity.fails());
+    let exit_code = if found { 1 } else { 0 };
+
+    if args.json {
+        let verdict = serde_json::json!({
+            "verdict": if detections.is_empty() { "clean" } else { "suspicious" },
+            "detections": detections,
+        });
+        match serde_json::to_string_pretty(&verdict) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                logging::error!("could not serialize to JSON: {}", e);
+                process::exit(2);
+            }
+        }
+        process::exit(exit_code);
+    }
+
+    let verdict = paste::format_verdict(&text, &detections);
+    let clean = if args.clean {
+        eprint!("{}", verdict);
+        true
+    } else {
+        print!("{}", verdict);
+        // One keystroke (and Enter) away from the cleaned text, when there is someone to ask
+        let fixable = detections.iter().any(|d| d.fix.is_some());
+        fixable && std::io::stdin().is_terminal() && std::io::stderr().is_terminal() && {
+            let target = if args.clipboard { "the clipboard" } else { "stdout" };
+            eprint!("\nClean it and write it to {}? [y/N] ", target);
+            let mut answer = String::new();
+            std::io::stdin().read_line(&mut answer).is_ok()
+                && answer.trim().eq_ignore_ascii_case("y")
+        }
+    };
+    if !clean {
+        process::exit(exit_code);
+    }
+
+    let cleaned = match clean::clean_text(&text, &detections) {
+        Ok((cleaned, _)) => cleaned,
+        Err(e) => {
+            logging::error!("could not clean the text: {}", e);
+            process::exit(2);
+        }
+    };
+    if args.clipboard {
+        if let Err(e) = clipboard::write(&cleaned) {
+            logging::error!("could not write the clipboard: {}", e);
+            process::exit(2);
+        }
+        logging::info!("Cleaned text is on the clipboard");
+    } else {
+        print!("{}", cleaned);
+    }
+    process::exit(exit_code);
+}
+
+/// Run `scan-url` and exit with the usual exit codes, or 2 if the document can't be fetched.
+fn run_scan_url(args: &cli::ScanUrlArgs) -> ! {
+    let max_size = match args.max_size {
+        Some(0) => None,
+        Some(size) => Some(size),
+        None => Some(DEFAULT_MAX_FILE_SIZE),
+    };
+    let allowed: Vec<String> = fetch::DEFAULT_ALLOWED_TYPES
+        .iter()
+        .map(|t| t.to_string())
+        .chain(args.allow_type.iter().cloned())
+        .collect();
+    let detections = match fetch::fetch(&args.url, max_size, &allowed).and_then(|bytes| {
+        decode_and_scan(bytes, &args.url, &DetectOptions::default())
+            .map_err(|_| format!("{}: not UTF-8 or UTF-16 text", args.url))
+    }) {
+        Ok(detections) => detections,
+        Err(e) => {
+            logging::error!("{}", e);
+            process::exit(2);
+        }
+    };
+
+    if args.json {
+        match serde_json::to_string_pretty(&detections) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                logging::error!("could not serialize to JSON: {}", e);
+                process::exit(2);
+            }
+        }
+    } else {
+        let style = TextStyle {
+            color: args.color.enabled(),
+            ..TextStyle::default()
+        };
+        println!("{}", format_text_output(&detections, style));
+    }
+
+    let found = detections.iter().any(|d| d.severity.fails());
+    process::exit(if found { 1 } else { 0 });
+}
+
+/// Run `scan-package` and exit with the usual exit codes, or 2 if the package can't be
+/// downloaded or read.
+fn run_scan_package(args: &cli::ScanPackageArgs) -> ! {
+    let max_size = match args.max_size {
+        Some(0) => None,
+        Some(size) => Some(size),
+        None => Some(DEFAULT_MAX_FILE_SIZE),
+    };
+    let scanned = package::download(&args.package, max_size).and_then(|download| {
+        let name = download.spec.to_string();
+        let entries = archive::read_entries(&download.bytes, download.kind, max_size)
+            .map_err(|e| format!("{}: invalid archive: {}", name, e))?;
+        let options = DetectOptions::default();
+        let detections = scan_archive_entries(entries, &name, |_| Cow::Borrowed(&options));
+        Ok((name, detections))
+    });
+    let (name, detections) = match scanned {
+        Ok(scanned) => scanned,
+        Err(e) => {
+            logging::error!("{}", e);
+            process::exit(2);
+        }
+    };
+
+    if args.json {
+        match serde_json::to_string_pretty(&detections) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                logging::error!("could not serialize to JSON: {}", e);
+                process::exit(2);
+            }
+        }
+    } else {
+        println!("Package: {}\n", name);
+        let style = TextStyle {
+            color: args.color.enabled(),
+            ..TextStyle::default()
+        };
+        println!("{}", format_text_output(&detections, style));
+    }
+
+    let found = detections.iter().any(|d| d.severity.fails());
+    process::exit(if found { 1 } else { 0 });
+}
+
+/// Run `scan-history` and exit with the usual exit codes.
+fn run_scan_history(args: &cli::HistoryArgs) -> ! {
+    let detections = match history::scan_history(args.since.as_deref(), args.scan_bundles) {
+        Ok(detections) => detections,
+        Err(e) => {
+            logging::error!("could not scan the history: {}", e);
+            process::exit(2);
+        }
+    };
+
+    if args.json {
+        match serde_json::to_string_pretty(&detections) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                logging::error!("could not serialize to JSON: {}", e);
+                process::exit(2);
+            }
+        }
+    } else {
+        println!("{}", history::format_history_text(&detections));
+    }
+
+    let found = detections.iter().any(|h| h.detection.severity.fails());
+    process::exit(if found { 1 } else { 0 });
+}
+
+/// Run `scan-git-metadata` and exit with the usual exit codes.
+fn run_scan_git_metadata(args: &cli::MetadataArgs) -> ! {
+    let detections = match metadata::scan_git_metadata(args.range.as_deref()) {
+        Ok(detections) => detections,
+        Err(e) => {
+            logging::error!("could not scan git metadata: {}", e);
+            process::exit(2);
+        }
+    };
+
+    if args.json {
+        match serde_json::to_string_pretty(&detections) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                logging::error!("could not serialize to JSON: {}", e);
+                process::exit(2);
+            }
+        }
+    } else {
+        println!("{}", metadata::format_metadata_text(&detections));
+    }
+
+    process::exit(if detections.is_empty() { 0 } else { 1 });
+}
+
+/// Run `diff-reports` and exit: 1 if the new report has detections the old one doesn't.
+fn run_diff_reports(args: &cli::DiffReportsArgs) -> ! {
+    let diff = match diff_reports::diff_reports(&args.old, &args.new) {
+        Ok(diff) => diff,
+        Err(e) => {
+            logging::error!("{}", e);
+            process::exit(2);
+        }
+    };
+
+    if args.json {
+        match serde_json::to_string_pretty(&diff_reports::to_json(&diff)) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                logging::error!("could not serialize to JSON: {}", e);
+                process::exit(2);
+            }
+        }
+    } else {
+        print!("{}", diff_reports::format_diff_text(&diff));
+    }
+
+    process::exit(if diff.new.is_empty() { 0 } else { 1 });
+}
+
+/// Run `install-hook` / `uninstall-hook` and exit.
+/// `pattern` is `Some` for install.
+fn run_hook_command(install_pattern: Option<&str>) -> ! {
+    let result = if let Some(pattern) = install_pattern {
+        hook::install(pattern).map(|path| println!("Installed pre-commit hook: {}", path.display()))
+    } else {
+        hook::uninstall().map(|removed| match removed {
+            Some(path) => println!("Removed pre-commit hook from {}", path.display()),
+            None => println!("No pre-commit hook installed by invisible-char-detector"),
+        })
+    };
+
+    if let Err(e) = result {
+        logging::error!("{}", e);
+        process::exit(2);
+    }
+    process::exit(0);
+}
+
+/// Run `serve` until killed, exiting with code 2 if the server can't start.
+fn run_serve(args: cli::ServeArgs) -> ! {
+    let config = serve::ServeConfig {
+        addr: format!("{}:{}", args.bind, args.port),
+        max_body_size: args.max_body_size.unwrap_or(DEFAULT_MAX_FILE_SIZE),
+        threads: args
+            .jobs
+            .unwrap_or_else(|| std::thread::available_parallelism().map_or(4, |n| n.get())),
+    };
+
+    if let Err(e) = serve::serve(&config) {
+        logging::error!("{}", e);
+        process::exit(2);
+    }
+    process::exit(0);
+}
+
+/// Run `daemon` until a client stops it.
+fn run_daemon(args: cli::DaemonArgs) -> ! {
+    if let Some(pattern) = &args.scan.pattern {
+        logging::error!("the daemon scans the paths clients send, not a pattern ({})", pattern);
+        process::exit(2);
+    }
+    let socket = args.socket.unwrap_or_else(daemon::default_socket);
+    if let Err(e) = daemon::run(args.scan.into_config(), &socket) {
+        logging::error!("{}", e);
+        process::exit(2);
+    }
+    process::exit(0);
+}
+
+/// Run `lsp`: publish diagnostics for the documents an editor opens.
+fn run_lsp(args: cli::ScanArgs) -> ! {
+    if let Some(pattern) = &args.pattern {
+        logging::error!(
+            "the language server scans the documents the editor opens, not a pattern ({})",
+            pattern
+        );
+        process::exit(2);
+    }
+    if let Err(e) = lsp::run(args.into_config()) {
+        logging::error!("{}", e);
+        process::exit(2);
+    }
+    process::exit(0);
+}
+
+/// Run `client`: send its request to the daemon and print the response. A scan exits with the
+/// usual exit codes.
+fn run_client(args: cli::ClientArgs) -> ! {
+    let socket = args.socket.unwrap_or_else(daemon::default_socket);
+    let send = |request: &daemon::Request| match daemon::request(&socket, request) {
+        Ok(response) if response.status == "error" && response.path.is_none() => {
+            logging::error!("{}", response.reason.unwrap_or_default());
+            process::exit(2);
+        }
+        Ok(response) => response,
+        Err(e) => {
+            logging::error!("{}", e);
+            process::exit(2);
+        }
+    };
+
+    let cli::ClientCommand::Scan {
+        paths,
+        stdin,
+        json,
+        color,
+    } = args.command
+    else {
+        let request = match args.command {
+            cli::ClientCommand::Ping => daemon::Request::Ping,
+            cli::ClientCommand::Reload => daemon::Request::Reload,
+            _ => daemon::Request::Stop,
+        };
+        send(&request);
+        logging::info!("ok");
+        process::exit(0);
+    };
+
+    if stdin && paths.len() > 1 {
+        logging::error!("--stdin scans one file, not {}", paths.len());
+        process::exit(2);
+    }
+    let text = if stdin {
+        let mut text = String::new();
+        if let Err(e) = std::io::stdin().read_to_string(&mut text) {
+            logging::error!("could not read stdin: {}", e);
+            process::exit(2);
+        }
+        Some(text)
+    } else {
+        None
+    };
+    let mut responses = Vec::new();
+    for path in paths {
+        // The daemon may run elsewhere; it reports paths under its own directory relative
+        let path = std::path::absolute(&path).unwrap_or(path);
+        let request = daemon::Request::Scan {
+            path: path.to_string_lossy().into_owned(),
+            text: text.clone(),
+        };
+        responses.push(send(&request));
+    }
+
+    let found = responses
+        .iter()
+        .flat_map(|response| &response.detections)
+        .any(|d| d.severity.fails());
+    if json {
+        match serde_json::to_string_pretty(&responses) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                logging::error!("could not serialize to JSON: {}", e);
+                process::exit(2);
+            }
+        }
+    } else {
+        let mut detections = Vec::new();
+        for response in responses {
+            if let (Some(path), Some(reason)) = (&response.path, &response.reason) {
+                logging::warning!("{}: {} ({})", path, reason, response.status);
+            }
+            detections.extend(response.detections);
+        }
+        let style = TextStyle {
+            color: color.enabled(),
+            ..TextStyle::default()
+        };
+        println!("{}", format_text_output(&detections, style));
+    }
+    process::exit(if found { 1 } else { 0 });
+}
+
+/// Run the command line tool (the `invisible-char-detector` binary).
+pub fn main() {
+    // The GitHub Action passes an empty argument for every option that is turned off.
+    let cli = Cli::parse_from(std::env::args_os().filter(|a| !a.is_empty()));
+    let (log_level, log_json) = (cli.log_level, cli.log_json);
+    logging::init(log_level.unwrap_or(logging::Level::Info), log_json);
+
+    let (scan_args, stats) = match cli.command {
+        None => (cli.scan, false),
+        Some(Command::Scan(args)) => (args, false),
+        Some(Command::Stats(args)) => (args, true),
+        Some(Command::ScanHistory(args)) => run_scan_history(&args),
+        Some(Command::ScanGitMetadata(args)) => run_scan_git_metadata(&args),
+        Some(Command::DiffReports(args)) => run_diff_reports(&args),
+        Some(Command::InstallHook { pattern }) => run_hook_command(Some(&pattern)),
+        Some(Command::UninstallHook) => run_hook_command(None),
+        Some(Command::Lsp(args)) => run_lsp(args),
+        Some(Command::Serve(args)) => run_serve(args),
+        Some(Command::Daemon(args)) => run_daemon(args),
+        Some(Command::Client(args)) => run_client(args),
+        Some(Command::Check(args)) => run_check(&args),
+        Some(Command::CheckPaste(args)) => run_check_paste(&args),
+        Some(Command::ScanUrl(args)) => run_scan_url(&args),
+        Some(Command::ScanPackage(args)) => run_scan_package(&args),
+        Some(Command::GenerateSample { path, force }) => {
+            match sample::write_sample(&path, force) {
+                Ok(count) => {
+                    println!(
+                        "Wrote {} (scanning it should report {} detections, covering every rule)",
+                        path.display(),
+                        count
+                    );
+                    process::exit(0);
+                }
+                Err(e) => {
+                    logging::error!("{}", e);
+                    process::exit(2);
+                }
+            }
+        }
+        Some(Command::Explain { query }) => match explain::explain(&query) {
+            Ok(text) => {
+                print!("{}", text);
+                process::exit(0);
+            }
+            Err(e) => {
+                logging::error!("{}", e);
+                process::exit(2);
+            }
+        },
+        Some(Command::Schema { json_version }) => {
+            let schema = serde_json::to_string_pretty(&schema::schema(json_version))
+                .unwrap_or_default()
+                + "\n";
+            let _ = std::io::Write::write_all(&mut std::io::stdout(), schema.as_bytes());
+            process::exit(0);
+        }
+        Some(Command::Completions { shell }) => {
+            let mut command = Cli::command();
+            let name = command.get_name().to_string();
+            // Generate into a buffer: clap_complete panics if stdout is closed early (`| head`).
+            let mut script = Vec::new();
+            clap_complete::generate(shell, &mut command, name, &mut script);
+            let _ = std::io::Write::write_all(&mut std::io::stdout(), &script);
+            process::exit(0);
+        }
+    };
+
+    let mut config = scan_args.into_config();
+    config.census |= stats;
+    if log_level.is_none() && (config.verbose || config.quiet) {
+        let level = if config.verbose {
+            logging::Level::Debug
+        } else {
+            logging::Level::Warn
+        };
+        logging::init(level, log_json);
+    }
+    if config.census {
+        // Exact counts: nothing is listed, so nothing needs capping
+        config.summary = true;
+        config.detect.max_detections = None;
+    }
+
+    match config::Config::load(config.config_file.as_deref()) {
+        Ok(file) => config.apply_config_file(file),
+        Err(e) => {
+            logging::error!("{}", e);
+            process::exit(2);
+        }
+    }
+
+    if !config.roots.is_empty() && Path::new(&config.pattern).is_absolute() {
+        logging::error!(
+            "with --root the pattern is relative to each root, not {}",
+            config.pattern
+        );
+        process::exit(2);
+    }
+    if config.detect.lines.is_some()
+        && config.stdin_filename.is_none()
+        && !Path::new(&config.pattern).is_file()
+    {
+        logging::error!(
+            "--lines needs a single file or --stdin-filename, not {}",
+            config.pattern
+        );
+        process::exit(2);
+    }
+
+    if config.list_files {
+        let mut listed = match list_files::list_files(&config) {
+            Ok(listed) => listed,
+            Err(e) => {
+                logging::error!("{}", e);
+                process::exit(2);
+            }
+        };
+        if cfg!(windows) {
+            for file in &mut listed {
+                file.path = walk::display_path(&file.path, config.forward_slashes);
+            }
+        }
+        if config.format == OutputFormat::Json {
+            match serde_json::to_string_pretty(&listed) {
+                Ok(json) => println!("{}", json),
+                Err(e) => {
+                    logging::error!("could not serialize to JSON: {}", e);
+                    process::exit(2);
+                }
+            }
+        } else {
+            print!("{}", list_files::format_list(&listed));
+        }
+        process::exit(0);
+    }
+
+    // Short, TeamCity, Azure, and JSON lines output are parsed line by line; keep them to
+    // detections
+    if !config.quiet
+        && !config.census
+        && !matches!(
+            config.format,
+            OutputFormat::Short
+                | OutputFormat::Teamcity
+                | OutputFormat::Azure
+                | OutputFormat::Jsonl
+        )
+    {
+        if config.patch {
+            println!("Scanning the lines added by the patch on stdin");
+        } else if config.clipboard {
+            println!("Scanning the clipboard");
+        } else if let Some(name) = &config.stdin_filename {
+            println!("Scanning {} from stdin", name);
+        } else if let Some(files) = &config.files {
+            println!("Scanning {} file(s) given on the command line", files.len());
+        } else if !config.roots.is_empty() {
+            let patterns: Vec<String> = config
+                .roots
+                .iter()
+                .map(|root| roots::pattern_under(root, &config.pattern))
+                .collect();
+            println!("Scanning files matching: {}", patterns.join(", "));
+        } else {
+            println!("Scanning files matching: {}", config.pattern);
+        }
+    }
+    if config.verbose {
+        println!(
+            "Options: format={:?}, scan_bundles={}, fail_on_skip={}, follow_symlinks={}",
+            config.format, config.scan_bundles, config.fail_on_skip, config.follow_symlinks
+        );
+    }
+
+    let started = Instant::now();
+    let ScanResult {
+        mut detections,
+        scanned,
+        skipped,
+        binary,
+        binary_by_class,
+        cached,
+        census,
+        mut skipped_files,
+        timings,
+        file_info,
+        mut suppressed,
+        mut roots,
+    } = match scan_roots(&config) {
+        Ok(result) => result,
+        Err(e) => {
+            logging::error!("could not scan files: {}", e);
+            process::exit(2);
+        }
+    };
+
+    if config.verbose {
+        let by_class: Vec<String> = binary_by_class
+            .iter()
+            .map(|(class, count)| format!("{} {}", count, class.plural()))
+            .collect();
+        let by_class = if by_class.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", by_class.join(", "))
+        };
+        println!(
+            "Scanned: {} files ({} from cache), Skipped: {} files, Binary: {} files{}\n",
+            scanned, cached, skipped, binary, by_class
+        );
+    }
+
+    if config.fail_fast {
+        detections.retain(|d| d.severity.fails());
+        detections.truncate(1);
+    }
+    if config.blame {
+        blame::attach(&mut detections);
+    }
+    if let Some(dir) = &config.clean_to {
+        match clean::write_clean_copies(dir, &detections) {
+            Ok(copies) => logging::info!("Wrote {} clean copies to {}", copies, dir.display()),
+            Err(e) => {
+                logging::error!("could not write clean copies: {}", e);
+                process::exit(2);
+            }
+        }
+    }
+    if cfg!(windows) {
+        for d in detections.iter_mut().chain(suppressed.iter_mut().flatten()) {
+            d.file = walk::display_path(&d.file, config.forward_slashes);
+            for path in &mut d.also_at {
+                *path = walk::display_path(path, config.forward_slashes);
+            }
+        }
+        for file in skipped_files.iter_mut().flatten() {
+            file.path = walk::display_path(&file.path, config.forward_slashes);
+        }
+    }
+    let redactor = config.redact_paths.map(redact::Redactor::new);
+    if let Some(redactor) = &redactor {
+        for d in detections.iter_mut().chain(suppressed.iter_mut().flatten()) {
+            d.file = redactor.redact(&d.file);
+            for path in &mut d.also_at {
+                *path = redactor.redact(path);
+            }
+        }
+        for file in skipped_files.iter_mut().flatten() {
+            file.path = redactor.redact(&file.path);
+        }
+        config.pattern = redactor.strip(&config.pattern);
+        for root in &mut config.roots {
+            *root = redactor.strip(root);
+        }
+        for r in &mut roots {
+            r.root = redactor.strip(&r.root);
+        }
+        for d in detections.iter_mut().chain(suppressed.iter_mut().flatten()) {
+            d.root = d.root.as_deref().map(|root| redactor.strip(root));
+        }
+        config.config_file = config
+            .config_file
+            .map(|path| PathBuf::from(redactor.strip(&path.to_string_lossy())));
+        config.clean_to = config
+            .clean_to
+            .map(|path| PathBuf::from(redactor.strip(&path.to_string_lossy())));
+    }
+    if config.sort_by == risk::SortBy::Risk
+        || matches!(config.format, OutputFormat::Json | OutputFormat::Jsonl)
+    {
+        risk::attach(&mut detections);
+    }
+    if config.sort_by == risk::SortBy::Risk {
+        risk::sort(&mut detections);
+    }
+    let duration = started.elapsed();
+    let timings = config.timings.map(|slowest| {
+        let mut timings = timings.unwrap_or_default().finish(duration, slowest);
+        for file in &mut timings.slowest {
+            if cfg!(windows) {
+                file.path = walk::display_path(&file.path, config.forward_slashes);
+            }
+            if let Some(redactor) = &redactor {
+                file.path = redactor.redact(&file.path);
+            }
+        }
+        timings
+    });
+    let file_info = file_info.map(|mut files| {
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        for file in &mut files {
+            if cfg!(windows) {
+                file.path = walk::display_path(&file.path, config.forward_slashes);
+            }
+            if let Some(redactor) = &redactor {
+                file.path = redactor.redact(&file.path);
+            }
+        }
+        files
+    });
+    // Info findings (a leading byte order mark) are reported but don't fail the scan
+    let found = detections.iter().any(|d| d.severity.fails())
+        || census.as_ref().is_some_and(|c| c.has_failures());
+    let summary = match census {
+        Some(census) => {
+            let mut summary = census.finish(scanned, duration);
+            // Tallied from the scanned paths; detections were redacted before --summary counts them
+            if let Some(redactor) = &redactor {
+                for file in &mut summary.top_files {
+                    file.key = redactor.redact(&file.key);
+                }
+            }
+            Some(summary)
+        }
+        None if config.summary => Some(summary::Summary::new(&detections, scanned, duration)),
+        None => None,
+    };
+    let findings = match &summary {
+        Some(summary) => summary::SeverityCounts::from_summary(summary),
+        None => summary::SeverityCounts::new(&detections),
+    };
+    match config.format {
+        OutputFormat::Json => {
+            let json = if config.json_version >= report::SCHEMA_VERSION {
+                let counts = report::Counts {
+                    scanned,
+                    skipped,
+                    binary,
+                    binary_by_class,
+                    cached,
+                    findings,
+                    roots: roots.clone(),
+                };
+                let report = report::Report::new(
+                    &config,
+                    counts,
+                    duration.as_millis(),
+                    (!config.census).then_some(&detections[..]),
+                    skipped_files.as_deref().unwrap_or_default(),
+                    summary.as_ref(),
+                    timings.as_ref(),
+                )
+                .with_files(file_info.as_deref())
+                .with_suppressed(suppressed.as_deref());
+                serde_json::to_string_pretty(&report)
+            } else {
+                match (&summary, &timings) {
+                    // `stats`: the statistics alone
+                    (Some(summary), None)
+                        if config.census && roots.is_empty() && file_info.is_none() =>
+                    {
+                        serde_json::to_string_pretty(summary)
+                    }
+                    // With --summary, --timings, --file-info, or --show-suppressed, the
+                    // detections and the rest together
+                    (None, None)
+                        if roots.is_empty() && file_info.is_none() && suppressed.is_none() =>
+                    {
+                        serde_json::to_string_pretty(&detections)
+                    }
+                    _ => {
+                        let mut envelope = serde_json::json!({ "detections": detections });
+                        if !roots.is_empty() {
+                            envelope["roots"] = serde_json::json!(roots);
+                        }
+                        if let Some(summary) = &summary {
+                            envelope["summary"] = serde_json::json!(summary);
+                        }
+                        if let Some(timings) = &timings {
+                            envelope["timings"] = serde_json::json!(timings);
+                        }
+                        if let Some(files) = &file_info {
+                            envelope["files"] = serde_json::json!(files);
+                        }
+                        if let Some(suppressed) = &suppressed {
+                            envelope["suppressed"] = serde_json::json!(suppressed);
+                        }
+                        serde_json::to_string_pretty(&envelope)
+                    }
+                }
+            };
+            match json {
+                Ok(json) => println!("{}", json),
+                Err(e) => {
+                    logging::error!("could not serialize to JSON: {}", e);
+                    process::exit(2);
+                }
+            }
+        }
+        _ if config.census => {
+            if let Some(summary) = &summary {
+                print!("{}", summary::format_summary(summary));
+            }
+            if !roots.is_empty() {
+                print!("{}", roots::format_roots(&roots));
+            }
+            if let Some(timings) = &timings {
+                print!("{}", timings::format_timings(timings));
+            }
+            if let Some(files) = &file_info {
+                print!("{}", file_info::format_file_info(files, config.verbose));
+            }
+            if let Some(suppressed) = &suppressed {
+                print!("{}", suppressed::format_suppressed(suppressed));
+            }
+        }
+        OutputFormat::Short => print!("{}", format_short_output(&detections)),
+        OutputFormat::Teamcity => print!("{}", teamcity::format_teamcity(&detections)),
+        OutputFormat::Azure => print!("{}", azure::format_azure(&detections)),
+        OutputFormat::Jsonl => {
+            let written = jsonl::write_jsonl(
+                &mut std::io::stdout().lock(),
+                &detections,
+                suppressed.as_deref().unwrap_or_default(),
+                scanned,
+                findings,
+            );
+            if let Err(e) = written {
+                logging::error!("could not write the report: {}", e);
+                process::exit(2);
+            }
+        }
+        OutputFormat::Text => {
+            if !(config.quiet && detections.is_empty()) {
+                println!(
+                    "{}",
+                    format_text_output(&detections, config.text_style())
+                );
+            }
+            if let Some(summary) = &summary {
+                println!("{}", summary::format_summary(summary));
+            }
+            if !roots.is_empty() {
+                println!("{}", roots::format_roots(&roots));
+            }
+            if let Some(timings) = &timings {
+                println!("{}", timings::format_timings(timings));
+            }
+            if let Some(files) = &file_info {
+                println!("{}", file_info::format_file_info(files, config.verbose));
+            }
+            if let Some(suppressed) = &suppressed {
+                println!("{}", suppressed::format_suppressed(suppressed));
+            }
+        }
+    }
+    // Machine-readable formats keep stdout to themselves
+    if !roots.is_empty()
+        && !config.census
+        && matches!(
+            config.format,
+            OutputFormat::Short
+                | OutputFormat::Teamcity
+                | OutputFormat::Azure
+                | OutputFormat::Jsonl
+        )
+    {
+        eprint!("{}", roots::format_roots(&roots));
+    }
+    if let (
+        Some(timings),
+        OutputFormat::Short | OutputFormat::Teamcity | OutputFormat::Azure | OutputFormat::Jsonl,
+    ) = (&timings, config.format)
+    {
+        if !config.census {
+            eprint!("{}", timings::format_timings(timings));
+        }
+    }
+    if let (
+        Some(files),
+        OutputFormat::Short | OutputFormat::Teamcity | OutputFormat::Azure | OutputFormat::Jsonl,
+    ) = (&file_info, config.format)
+    {
+        if !config.census {
+            eprint!("{}", file_info::format_file_info(files, config.verbose));
+        }
+    }
+    // JSON lines has its own records for them
+    if let (Some(suppressed), OutputFormat::Short | OutputFormat::Teamcity | OutputFormat::Azure) =
+        (&suppressed, config.format)
+    {
+        if !config.census {
+            eprint!("{}", suppressed::format_suppressed(suppressed));
+        }
+    }
+    logging::info!("{}", summary::format_verdict(findings, scanned, duration));
+
+    if config.fix {
+        // The scan doesn't keep the text; edits are checked against what is there now
+        let fixed = clipboard::read()
+            .and_then(|text| clean::clean_text(&text, &detections))
+            .and_then(|(cleaned, fixed)| {
+                if fixed > 0 {
+                    clipboard::write(&cleaned)?;
+                }
+                Ok(fixed)
+            });
+        match fixed {
+            Ok(0) => logging::info!("Nothing on the clipboard to fix"),
+            Ok(fixed) => logging::info!("Fixed {} finding(s) on the clipboard", fixed),
+            Err(e) => {
+                logging::error!("could not fix the clipboard: {}", e);
+                process::exit(2);
+            }
+        }
+    }
+
+    // The step summary and the webhook report the counts even without --summary
+    let built = OnceCell::new();
+    let summary = || {
+        summary.as_ref().unwrap_or_else(|| {
+            built.get_or_init(|| summary::Summary::new(&detections, scanned, duration))
+        })
+    };
+
+    if config.step_summary {
+        let markdown =
+            step_summary::format_step_summary(&config.pattern, summary(), &detections);
+        match step_summary::append(&markdown) {
+            Ok(true) => {}
+            Ok(false) => logging::info!(
+                "--step-summary: {} is not set, so there is no job summary to write",
+                step_summary::ENV
+            ),
+            Err(e) => {
+                logging::error!("could not write the step summary: {}", e);
+                process::exit(2);
+            }
+        }
+    }
+
+    if let (true, Some(url)) = (found, &config.notify_webhook) {
+        let sent = webhook::payload(
+            config.webhook_template.as_ref(),
+            config.webhook_full_report,
+            &config.pattern,
+            summary(),
+            &detections,
+        )
+        .and_then(|body| webhook::notify(url, &body));
+        if let Err(e) = sent {
+            logging::error!("could not notify the webhook: {}", e);
+            process::exit(2);
+        }
+    }
+
+    if config.watch {
+        if let Err(e) = watch::watch(&config, detections) {
+            logging::error!("could not watch files: {}", e);
+        }
+        process::exit(2);
+    }
+
+    // Strict mode: treat any unreadable/ignored files as an operational failure.
+    if config.fail_on_skip && skipped > 0 {
+        logging::error!("{} files were skipped (--fail-on-skip enabled)", skipped);
+        process::exit(2);
+    }
+
+    if found {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,213 @@
+//! `--list-files`: walk the tree as a scan would, without scanning anything, and say of each
+//! file whether it would be scanned and, if not, why.
+//!
+//! The answer to "why wasn't this file scanned?": a file under the pattern's directory that
+//! the glob doesn't match, a `.gitignore` rule (with the file and line it is on), an ignored
+//! directory, the size cap, a binary format, a generated file, or one left alone by `--diff`.
+//! Only the first few kilobytes of a file are read, to recognise binary formats.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::walk::{self, WalkEntry};
+use crate::{
+    generated_paths, git, ignored_by, read_head, roots, sniff_binary_bytes, visualize,
+    GeneratedFiles, ScanConfig,
+};
+
+/// What a scan would do with one file.
+#[derive(Debug, Serialize)]
+pub struct ListedFile {
+    pub path: String,
+
+    /// `scan` or `skip`.
+    pub action: &'static str,
+
+    /// Why the file is skipped: `glob-miss`, `gitignore`, `generated`, `unchanged`, `ignored`,
+    /// `symlink`, `duplicate`, `already-visited`, `too-large`, `binary`, or `unreadable`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<&'static str>,
+
+    /// The rule, directory, size, or format behind the reason.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl ListedFile {
+    fn scan(path: &Path, detail: Option<String>) -> Self {
+        ListedFile {
+            path: path.to_string_lossy().into_owned(),
+            action: "scan",
+            reason: None,
+            detail,
+        }
+    }
+
+    fn skip(path: &Path, reason: &'static str, detail: Option<String>) -> Self {
+        ListedFile {
+            path: path.to_string_lossy().into_owned(),
+            action: "skip",
+            reason: Some(reason),
+            detail,
+        }
+    }
+}
+
+/// The pattern that walks everything under the directory `pattern` starts in.
+fn everything_under(pattern: &str) -> String {
+    let root = walk::literal_root(pattern);
+    if root == Path::new(".") && !pattern.starts_with("./") {
+        "**/*".to_string()
+    } else {
+        root.join("**").join("*").to_string_lossy().into_owned()
+    }
+}
+
+/// What a scan of `config.pattern` (under each `--root`) would do with each file.
+pub fn list_files(config: &ScanConfig) -> Result<Vec<ListedFile>, String> {
+    let patterns = if config.roots.is_empty() {
+        vec![config.pattern.clone()]
+    } else {
+        config
+            .roots
+            .iter()
+            .map(|root| roots::pattern_under(root, &config.pattern))
+            .collect()
+    };
+    let changed = match &config.diff_base {
+        Some(base) => Some(git::ChangedLines::since(base, config.staged)?),
+        None => None,
+    };
+
+    let mut listed = Vec::new();
+    for pattern in &patterns {
+        let invalid = |e: glob::PatternError| format!("invalid glob pattern: {}", e);
+        let mut entries = walk::walk(pattern, config.follow_symlinks).map_err(invalid)?;
+        let matched: HashSet<PathBuf> = entries.iter().map(|e| e.path().to_path_buf()).collect();
+        // A pattern without wildcards names one file, which nothing else can miss
+        if pattern.contains(['*', '?', '[']) {
+            let everything =
+                walk::walk(&everything_under(pattern), config.follow_symlinks).map_err(invalid)?;
+            for entry in everything {
+                if !matched.contains(entry.path()) {
+                    let detail = format!("does not match {}", pattern);
+                    listed.push(ListedFile::skip(entry.path(), "glob-miss", Some(detail)));
+                }
+            }
+        }
+
+        if config.respect_gitignore {
+            let paths: Vec<&Path> = entries.iter().map(WalkEntry::path).collect();
+            // Outside a repository there is no .gitignore to respect
+            if let Ok(rules) = git::ignore_rules(&paths) {
+                entries.retain(|entry| match rules.get(entry.path()) {
+                    Some(rule) => {
+                        let detail = Some(rule.clone());
+                        listed.push(ListedFile::skip(entry.path(), "gitignore", detail));
+                        false
+                    }
+                    None => true,
+                });
+            }
+        }
+
+        let paths: Vec<&Path> = entries.iter().map(WalkEntry::path).collect();
+        let generated = generated_paths(config.generated, &paths);
+        for entry in entries {
+            listed.push(classify(entry, config, &generated, changed.as_ref()));
+        }
+    }
+    listed.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(listed)
+}
+
+/// What a scan would do with a file the walk found.
+fn classify(
+    entry: WalkEntry,
+    config: &ScanConfig,
+    generated: &HashSet<PathBuf>,
+    changed: Option<&git::ChangedLines>,
+) -> ListedFile {
+    let path = match entry {
+        WalkEntry::Matched(path) => path,
+        WalkEntry::SymlinkNotFollowed(path) => {
+            let detail = Some("not followed (see --follow-symlinks)".to_string());
+            return ListedFile::skip(&path, "symlink", detail);
+        }
+        WalkEntry::AlreadyVisited(path) => {
+            return ListedFile::skip(&path, "already-visited", None);
+        }
+        WalkEntry::Duplicate { path, first } => {
+            let detail = Some(format!("same file as {}", first.display()));
+            return ListedFile::skip(&path, "duplicate", detail);
+        }
+    };
+
+    let is_generated = generated.contains(&path);
+    if is_generated && config.generated == GeneratedFiles::Skip {
+        let detail = Some("linguist-generated or linguist-vendored".to_string());
+        return ListedFile::skip(&path, "generated", detail);
+    }
+    if let Some(changed) = changed {
+        let in_diff = changed
+            .repo_relative(&path)
+            .is_some_and(|relative| changed.contains_file(&relative));
+        if !in_diff {
+            return ListedFile::skip(&path, "unchanged", Some("not in the diff".to_string()));
+        }
+    }
+    let path_str = path.to_string_lossy();
+    if let Some(dir) = ignored_by(&path_str, config.scan_bundles, &config.ignore_dirs) {
+        return ListedFile::skip(&path, "ignored", Some(dir));
+    }
+
+    let size = match fs::metadata(&path) {
+        Ok(meta) => meta.len(),
+        Err(e) => return ListedFile::skip(&path, "unreadable", Some(e.to_string())),
+    };
+    if config.max_file_size.is_some_and(|limit| size > limit) {
+        let detail = Some(format!("{} bytes (see --max-file-size)", size));
+        return ListedFile::skip(&path, "too-large", detail);
+    }
+    let archive = crate::archive::archive_kind(&path_str).filter(|_| config.scan_archives);
+    let document = crate::document::document_kind(&path_str).filter(|_| config.scan_documents);
+    if archive.is_some() || document.is_some() {
+        return ListedFile::scan(&path, Some("entries read in memory".to_string()));
+    }
+    match read_head(&path) {
+        Ok(head) => match sniff_binary_bytes(&head) {
+            Some(kind) => ListedFile::skip(&path, "binary", Some(kind.label.to_string())),
+            None if is_generated => ListedFile::scan(
+                &path,
+                Some("generated: errors reported as warnings".to_string()),
+            ),
+            None => ListedFile::scan(&path, None),
+        },
+        Err(e) => ListedFile::skip(&path, "unreadable", Some(e.to_string())),
+    }
+}
+
+/// Format the list for text output: one file per line, then the totals.
+pub fn format_list(listed: &[ListedFile]) -> String {
+    let mut output = String::new();
+    for file in listed {
+        output.push_str(&format!("{}  {}", file.action, visualize(&file.path)));
+        match (file.reason, &file.detail) {
+            (Some(reason), Some(detail)) => output.push_str(&format!("  ({}: {})", reason, detail)),
+            (Some(reason), None) => output.push_str(&format!("  ({})", reason)),
+            (None, Some(detail)) => output.push_str(&format!("  ({})", detail)),
+            (None, None) => {}
+        }
+        output.push('\n');
+    }
+    let scanned = listed.iter().filter(|file| file.action == "scan").count();
+    output.push_str(&format!(
+        "\n{} file(s) to scan, {} skipped\n",
+        scanned,
+        listed.len() - scanned
+    ));
+    output
+}
@@ -0,0 +1,102 @@
+//! Operational messages on stderr: skipped files, notes, warnings, and errors, kept apart
+//! from the report on stdout.
+//!
+//! `--log-level` sets the least severe level printed (`debug` lists every file left out, as
+//! `--verbose` does) and `--log-json` writes each message as a JSON object on a line of its
+//! own for log collectors. A message is written with a single call under the stderr lock, so
+//! messages from the scanning threads never interleave mid-line.
+
+use std::fmt;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::ValueEnum;
+
+/// How much a message matters, least first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum Level {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    fn as_str(self) -> &'static str {
+        match self {
+            Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Warn => "warn",
+            Level::Error => "error",
+        }
+    }
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// Print messages of `level` and above, as JSON lines if `json`.
+pub fn init(level: Level, json: bool) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+    JSON.store(json, Ordering::Relaxed);
+}
+
+/// True if messages of `level` are printed.
+pub fn enabled(level: Level) -> bool {
+    level as u8 >= LEVEL.load(Ordering::Relaxed)
+}
+
+/// Write `message` to stderr if its level is enabled.
+pub fn log(level: Level, message: fmt::Arguments) {
+    if !enabled(level) {
+        return;
+    }
+    let line = if JSON.load(Ordering::Relaxed) {
+        let time_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_millis());
+        serde_json::json!({
+            "time_ms": time_ms,
+            "level": level.as_str(),
+            "message": message.to_string(),
+        })
+        .to_string()
+    } else {
+        match level {
+            Level::Debug => format!("  {}", message),
+            Level::Info => message.to_string(),
+            Level::Warn => format!("Warning: {}", message),
+            Level::Error => format!("Error: {}", message),
+        }
+    };
+    let _ = std::io::stderr()
+        .lock()
+        .write_all(format!("{}\n", line).as_bytes());
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Debug, format_args!($($arg)*))
+    };
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Info, format_args!($($arg)*))
+    };
+}
+
+macro_rules! warning {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Warn, format_args!($($arg)*))
+    };
+}
+
+macro_rules! error {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Error, format_args!($($arg)*))
+    };
+}
+
+pub(crate) use {debug, error, info, warning};
@@ -0,0 +1,91 @@
+//! `--lookalike-identifiers`: identifiers that differ only by invisible characters.
+//!
+//! The identifier-like tokens of a file are collected with any invisible characters inside
+//! them and compared once those are stripped. A per-character report shows the ZWSP in
+//! `is_adm<U+200B>in`, but not that `is_admin` is right there too: two names that look the
+//! same and are different identifiers is what the attack relies on, so each spelling with
+//! invisible characters is reported along with the lookalike it imitates.
+
+use std::collections::HashMap;
+
+use crate::{is_format_char, rules};
+
+/// True for the characters stripped before comparing: format characters (zero-width, bidi,
+/// tags, ...), variation selectors, and characters that render as blank.
+pub fn is_invisible(ch: char) -> bool {
+    is_format_char(ch)
+        || rules::rule_for(ch as u32)
+            .is_some_and(|rule| matches!(rule.category, "variation-selector" | "filler" | "blank"))
+}
+
+/// Where a spelling of an identifier first appears.
+pub struct Spot {
+    pub line: usize,
+    pub char_index: usize,
+    pub utf16_column: usize,
+    pub byte_offset: usize,
+    pub text: String,
+}
+
+/// A spelling with invisible characters and the lookalike it was found next to.
+pub struct Lookalike {
+    pub spot: Spot,
+
+    /// The spelling it looks like: the one without invisible characters when there is one.
+    pub other: String,
+    pub other_line: usize,
+}
+
+/// The distinct spellings of the identifiers in a file, by how they look.
+#[derive(Default)]
+pub struct Identifiers {
+    spellings: HashMap<String, Vec<Spot>>,
+}
+
+impl Identifiers {
+    /// Record an identifier-like token, keeping only the first occurrence of each spelling.
+    pub fn add(&mut self, spot: Spot) {
+        let visible: String = spot.text.chars().filter(|&c| !is_invisible(c)).collect();
+        if !visible.chars().any(char::is_alphabetic) {
+            return;
+        }
+        let spellings = self.spellings.entry(visible).or_default();
+        if !spellings.iter().any(|s| s.text == spot.text) {
+            spellings.push(spot);
+        }
+    }
+
+    /// Each spelling with invisible characters that looks the same as another spelling.
+    pub fn lookalikes(self) -> Vec<Lookalike> {
+        let mut lookalikes = Vec::new();
+        for (visible, spellings) in self.spellings {
+            if spellings.len() < 2 {
+                continue;
+            }
+            let (plain, hidden): (Vec<Spot>, Vec<Spot>) =
+                spellings.into_iter().partition(|s| s.text == visible);
+            for (i, spot) in hidden.iter().enumerate() {
+                let other = plain.first().or_else(|| {
+                    hidden
+                        .iter()
+                        .enumerate()
+                        .find(|&(j, _)| j != i)
+                        .map(|(_, s)| s)
+                });
+                let Some(other) = other else {
+                    continue;
+                };
+                lookalikes.push(Lookalike {
+                    other: other.text.clone(),
+                    other_line: other.line,
+                    spot: Spot {
+                        text: spot.text.clone(),
+                        ..*spot
+                    },
+                });
+            }
+        }
+        lookalikes.sort_by_key(|l| l.spot.byte_offset);
+        lookalikes
+    }
+}
@@ -0,0 +1,302 @@
+//! `lsp`: a language server over stdio that publishes diagnostics for suspicious characters.
+//!
+//! Documents are synced in full on every change and rescanned with the same detector and
+//! options as a scan of their path: the command line given to `lsp`, the config file (read
+//! once at startup), its presets, and its overrides. A diagnostic has a quick fix when `--clean-to` would fix its character, making the same
+//! edit (deleting it, or turning an unusual space or line separator into a plain one), and a
+//! second action applies every fix in the document at once.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use lsp_server::{Connection, ErrorCode, Message, Notification, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+    Notification as NotificationTrait, PublishDiagnostics,
+};
+use lsp_types::request::{CodeActionRequest, Request as RequestTrait};
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams,
+    CodeActionProviderCapability, Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, NumberOrString, Position,
+    PublishDiagnosticsParams, Range, ServerCapabilities, TextDocumentSyncCapability,
+    TextDocumentSyncKind, TextEdit, Uri, WorkspaceEdit,
+};
+
+use crate::rules::Severity;
+use crate::{clean, config, logging};
+use crate::{detect_invisible_characters, Detection, ScanConfig};
+
+const SOURCE: &str = "invisible-char-detector";
+
+/// Convert a detection to an LSP range, counting columns in UTF-16 code units.
+fn detection_range(d: &Detection) -> Range {
+    let column = (d.utf16_column - 1) as u32;
+    let repeats = d.run.as_ref().map_or(1, |run| run.count);
+    let width = (d.char.encode_utf16().count() * repeats) as u32;
+    let line = (d.line - 1) as u32;
+    Range::new(
+        Position::new(line, column),
+        Position::new(line, column + width),
+    )
+}
+
+/// How documents are scanned.
+struct Workspace {
+    /// The options given to `lsp`, with the config file applied.
+    config: ScanConfig,
+
+    /// The directory the server started in, which paths are relative to.
+    cwd: PathBuf,
+}
+
+impl Workspace {
+    /// The path of the document at `uri`, relative to the working directory as a scan from
+    /// there reports it and overrides match it; the URI itself for a document that isn't a
+    /// file.
+    fn path(&self, uri: &Uri) -> String {
+        if !uri
+            .scheme()
+            .is_some_and(|scheme| scheme.as_str().eq_ignore_ascii_case("file"))
+        {
+            return uri.to_string();
+        }
+        let path = uri.path().as_estr().decode().into_string_lossy();
+        // file:///C:/src/app.js on Windows
+        let path = match path.strip_prefix('/') {
+            Some(rest) if rest.get(1..2) == Some(":") => rest,
+            _ => &path,
+        };
+        Path::new(path)
+            .strip_prefix(&self.cwd)
+            .map_or_else(|_| path.to_string(), |p| p.to_string_lossy().into_owned())
+    }
+}
+
+/// Scan a document and return each detection with its diagnostic.
+fn diagnose(workspace: &Workspace, uri: &Uri, text: &str) -> Vec<(Detection, Diagnostic)> {
+    let path = workspace.path(uri);
+    let options = workspace.config.detect_options(&path);
+    detect_invisible_characters(text, &path, &options)
+        .into_iter()
+        .map(|d| {
+            let diagnostic = Diagnostic {
+                range: detection_range(&d),
+                severity: Some(match d.severity {
+                    Severity::Warning => DiagnosticSeverity::WARNING,
+                    Severity::Error => DiagnosticSeverity::ERROR,
+                    Severity::Info => DiagnosticSeverity::INFORMATION,
+                }),
+                code: Some(NumberOrString::String(d.rule.clone())),
+                source: Some(SOURCE.to_string()),
+                message: format!("{} (U+{:04X}): {}", d.name, d.code, d.description),
+                ..Diagnostic::default()
+            };
+            (d, diagnostic)
+        })
+        .collect()
+}
+
+fn publish(connection: &Connection, uri: Uri, diagnostics: Vec<Diagnostic>) -> Result<(), String> {
+    let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+    let notification = Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+    connection
+        .sender
+        .send(Message::Notification(notification))
+        .map_err(|e| format!("could not send diagnostics: {}", e))
+}
+
+fn overlaps(a: &Range, b: &Range) -> bool {
+    a.start <= b.end && b.start <= a.end
+}
+
+/// The edit that fixes `d` the way `--clean-to` does, or `None` when it needs a human decision.
+fn fix_edit(d: &Detection) -> Option<TextEdit> {
+    let fix = clean::fix_for(d)?;
+    Some(TextEdit::new(detection_range(d), fix.replacement))
+}
+
+/// Title of the quick fix that applies `edit` to `d`.
+fn fix_title(d: &Detection, edit: &TextEdit) -> String {
+    let character = format!("{} (U+{:04X})", d.name, d.code);
+    let plural = if edit.new_text.chars().count() > 1 {
+        "s"
+    } else {
+        ""
+    };
+    match edit.new_text.chars().next() {
+        None => format!("Remove {}", character),
+        Some('\n') => format!("Replace {} with line break{}", character, plural),
+        Some(_) => format!("Replace {} with space{}", character, plural),
+    }
+}
+
+fn fix_action(
+    title: String,
+    uri: &Uri,
+    edits: Vec<TextEdit>,
+    diagnostics: Vec<Diagnostic>,
+) -> CodeActionOrCommand {
+    CodeActionOrCommand::CodeAction(CodeAction {
+        title,
+        kind: Some(CodeActionKind::QUICKFIX),
+        edit: Some(WorkspaceEdit::new(HashMap::from([(uri.clone(), edits)]))),
+        is_preferred: Some(diagnostics.len() == 1),
+        diagnostics: Some(diagnostics),
+        ..CodeAction::default()
+    })
+}
+
+/// Quick fixes for the characters inside the requested range, plus a fix-all action.
+fn code_actions(
+    workspace: &Workspace,
+    documents: &HashMap<String, String>,
+    params: &CodeActionParams,
+) -> Vec<CodeActionOrCommand> {
+    let uri = &params.text_document.uri;
+    let Some(text) = documents.get(uri.as_str()) else {
+        return Vec::new();
+    };
+
+    let all = diagnose(workspace, uri, text);
+    let mut actions: Vec<CodeActionOrCommand> = all
+        .iter()
+        .filter(|(_, diag)| overlaps(&diag.range, &params.range))
+        .filter_map(|(d, diag)| {
+            let edit = fix_edit(d)?;
+            let title = fix_title(d, &edit);
+            Some(fix_action(title, uri, vec![edit], vec![diag.clone()]))
+        })
+        .collect();
+
+    let mut fixes: Vec<(TextEdit, Diagnostic)> = all
+        .into_iter()
+        .filter_map(|(d, diag)| Some((fix_edit(&d)?, diag)))
+        .collect();
+    // Text edits in one workspace edit must not overlap, and a character can have several
+    // diagnostics
+    fixes.sort_by_key(|(edit, _)| (edit.range.start, edit.range.end));
+    fixes.dedup_by(|(later, _), (kept, _)| later.range.start < kept.range.end);
+
+    if fixes.len() > 1 && !actions.is_empty() {
+        let title = format!("Fix all {} suspicious characters in this file", fixes.len());
+        let (edits, diagnostics) = fixes.into_iter().unzip();
+        actions.push(fix_action(title, uri, edits, diagnostics));
+    }
+
+    actions
+}
+
+/// Serve LSP over stdin/stdout until the client shuts the server down, scanning with `base`
+/// and the config file.
+pub fn run(base: ScanConfig) -> Result<(), String> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+        ..ServerCapabilities::default()
+    };
+    let capabilities = serde_json::to_value(capabilities).map_err(|e| e.to_string())?;
+    connection
+        .initialize(capabilities)
+        .map_err(|e| format!("LSP initialization failed: {}", e))?;
+
+    let mut config = base;
+    config.apply_config_file(config::Config::load(config.config_file.as_deref())?);
+    let workspace = Workspace {
+        config,
+        cwd: std::env::current_dir().map_err(|e| e.to_string())?,
+    };
+
+    // Keyed by URI string: `Uri` caches its parse internally, which makes it a poor map key.
+    let mut documents: HashMap<String, String> = HashMap::new();
+
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection
+                    .handle_shutdown(&request)
+                    .map_err(|e| e.to_string())?
+                {
+                    break;
+                }
+
+                let response = match request.method.as_str() {
+                    CodeActionRequest::METHOD => {
+                        match request.extract::<CodeActionParams>(CodeActionRequest::METHOD) {
+                            Ok((id, params)) => {
+                                Response::new_ok(id, code_actions(&workspace, &documents, &params))
+                            }
+                            Err(e) => {
+                                logging::warning!("bad codeAction request: {:?}", e);
+                                continue;
+                            }
+                        }
+                    }
+                    _ => Response::new_err(
+                        request.id,
+                        ErrorCode::MethodNotFound as i32,
+                        format!("unsupported method: {}", request.method),
+                    ),
+                };
+                connection
+                    .sender
+                    .send(Message::Response(response))
+                    .map_err(|e| format!("could not send response: {}", e))?;
+            }
+            Message::Notification(notification) => match notification.method.as_str() {
+                DidOpenTextDocument::METHOD => {
+                    let Ok(params) = notification
+                        .extract::<DidOpenTextDocumentParams>(DidOpenTextDocument::METHOD)
+                    else {
+                        continue;
+                    };
+                    let uri = params.text_document.uri;
+                    let diagnostics = diagnose(&workspace, &uri, &params.text_document.text);
+                    documents.insert(uri.to_string(), params.text_document.text);
+                    publish(
+                        &connection,
+                        uri,
+                        diagnostics.into_iter().map(|(_, d)| d).collect(),
+                    )?;
+                }
+                DidChangeTextDocument::METHOD => {
+                    let Ok(mut params) = notification
+                        .extract::<DidChangeTextDocumentParams>(DidChangeTextDocument::METHOD)
+                    else {
+                        continue;
+                    };
+                    // Full sync: the last change holds the whole document.
+                    let Some(change) = params.content_changes.pop() else {
+                        continue;
+                    };
+                    let uri = params.text_document.uri;
+                    let diagnostics = diagnose(&workspace, &uri, &change.text);
+                    documents.insert(uri.to_string(), change.text);
+                    publish(
+                        &connection,
+                        uri,
+                        diagnostics.into_iter().map(|(_, d)| d).collect(),
+                    )?;
+                }
+                DidCloseTextDocument::METHOD => {
+                    let Ok(params) = notification
+                        .extract::<DidCloseTextDocumentParams>(DidCloseTextDocument::METHOD)
+                    else {
+                        continue;
+                    };
+                    documents.remove(params.text_document.uri.as_str());
+                    publish(&connection, params.text_document.uri, Vec::new())?;
+                }
+                _ => {}
+            },
+            Message::Response(_) => {}
+        }
+    }
+
+    drop(connection);
+    io_threads
+        .join()
+        .map_err(|e| format!("LSP I/O error: {}", e))
+}
@@ -0,0 +1,114 @@
+//! `scan-git-metadata`: suspicious characters in commit messages, tag annotations, and ref
+//! names.
+//!
+//! None of these are files, so `scan` never sees them, yet changelog generators, release
+//! pages, and `git log` in a terminal all render them. A bidi control in a commit message can
+//! reorder how a changelog line reads; one in a branch name can make it look like another.
+
+use serde::Serialize;
+
+use crate::git::run_git;
+use crate::{detect_invisible_characters, visualize, DetectOptions, Detection};
+
+/// A detection in a piece of git metadata. `detection.file` holds the commit hash or the
+/// full ref name.
+#[derive(Debug, Clone, Serialize)]
+pub struct MetadataDetection {
+    /// What was scanned: `commit` (message), `tag` (annotation), or `ref` (name).
+    pub kind: &'static str,
+
+    #[serde(flatten)]
+    pub detection: Detection,
+}
+
+/// Scan `text` as one document labeled `label`, tagging its detections with `kind`.
+fn scan_text(kind: &'static str, label: &str, text: &str, out: &mut Vec<MetadataDetection>) {
+    // Messages are prose: no-break spaces next to punctuation are typography, not tricks
+    let options = DetectOptions {
+        prose: kind != "ref",
+        ..DetectOptions::default()
+    };
+    out.extend(
+        detect_invisible_characters(text, label, &options)
+            .into_iter()
+            .map(|detection| MetadataDetection { kind, detection }),
+    );
+}
+
+/// Scan the messages of the commits in `range` (every commit reachable from HEAD by default),
+/// the annotations of all tags, and the names of all refs.
+pub fn scan_git_metadata(range: Option<&str>) -> Result<Vec<MetadataDetection>, String> {
+    let range = range.unwrap_or("HEAD");
+    let mut detections = Vec::new();
+
+    let log = run_git(&["log", "--reverse", "--format=%x01%H%x00%B", range, "--"])?;
+    for record in log.split('\x01').skip(1) {
+        let Some((hash, message)) = record.split_once('\0') else {
+            continue;
+        };
+        scan_text("commit", hash, message.trim_end_matches('\n'), &mut detections);
+    }
+
+    let refs = run_git(&[
+        "for-each-ref",
+        "--format=%01%(refname)%00%(objecttype)%00%(contents)",
+    ])?;
+    for record in refs.split('\x01').skip(1) {
+        let mut fields = record.splitn(3, '\0');
+        let (Some(name), Some(object_type)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        scan_text("ref", name, name, &mut detections);
+        if object_type == "tag" {
+            let annotation = fields.next().unwrap_or_default();
+            scan_text("tag", name, annotation.trim_end_matches('\n'), &mut detections);
+        }
+    }
+
+    Ok(detections)
+}
+
+/// Format metadata detections as text, grouped by the commit or ref they were found in.
+pub fn format_metadata_text(detections: &[MetadataDetection]) -> String {
+    if detections.is_empty() {
+        return "No suspicious invisible characters found in git metadata.".to_string();
+    }
+
+    let mut output = format!(
+        "Found {} suspicious character(s) in git metadata:\n\n",
+        detections.len()
+    );
+
+    let mut last = ("", "");
+    for m in detections {
+        let d = &m.detection;
+        if (m.kind, d.file.as_str()) != last {
+            if !last.0.is_empty() {
+                output.push('\n');
+            }
+            let what = match m.kind {
+                "commit" => "message",
+                "tag" => "annotation",
+                _ => "name",
+            };
+            // Ref names may hold the very bidi controls being reported
+            output.push_str(&format!("{} {} ({})\n", m.kind, visualize(&d.file), what));
+            last = (m.kind, &d.file);
+        }
+        output.push_str(&format!(
+            "    Line {}:{} (byte {}) - {} (U+{:04X}, {}, {}) [{}, {}]\n",
+            d.line,
+            d.char_index,
+            d.byte_offset,
+            d.name,
+            d.code,
+            d.general_category,
+            d.category,
+            d.rule,
+            d.severity.as_str()
+        ));
+        output.push_str(&format!("  {}\n", d.description));
+    }
+
+    output
+}
@@ -0,0 +1,89 @@
+//! `--check-normalization`: find text that is not in Unicode normalization form NFC (or,
+//! optionally, NFKC).
+//!
+//! A line is split into segments that each start at a character nothing before it can combine
+//! with, and each segment is normalized on its own. That way a finding covers only the
+//! offending sequence (`e` + U+0301) rather than the whole line.
+
+use clap::ValueEnum;
+use unicode_normalization::char::canonical_combining_class;
+use unicode_normalization::{is_nfc, is_nfkc, UnicodeNormalization};
+use unicode_properties::{GeneralCategoryGroup, UnicodeGeneralCategory};
+
+/// Which normalization form text is expected to be in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum NormalizationForm {
+    /// Canonical composition: flags decomposed or unordered sequences
+    Nfc,
+
+    /// NFC plus compatibility characters such as ligatures and fullwidth letters
+    Nfkc,
+}
+
+/// A sequence that changes under normalization.
+#[derive(Debug)]
+pub struct Mismatch {
+    /// Byte range of the sequence within the line.
+    pub start: usize,
+    pub end: usize,
+
+    /// The sequence after normalization.
+    pub normalized: String,
+
+    /// ID of the rule the mismatch is reported under.
+    pub rule: &'static str,
+}
+
+/// True for Hangul vowel and trailing consonant jamo, which compose with the preceding syllable.
+fn is_conjoining_jamo(ch: char) -> bool {
+    matches!(ch, '\u{1161}'..='\u{11FF}' | '\u{D7B0}'..='\u{D7FF}')
+}
+
+/// True if no character before `ch` can combine with it under normalization.
+fn starts_segment(ch: char) -> bool {
+    canonical_combining_class(ch) == 0
+        && ch.general_category_group() != GeneralCategoryGroup::Mark
+        && !is_conjoining_jamo(ch)
+}
+
+/// Sequences in `line` that are not in `form`.
+pub fn find_mismatches(line: &str, form: NormalizationForm) -> Vec<Mismatch> {
+    let compat = form == NormalizationForm::Nfkc;
+    if is_nfc(line) && (!compat || is_nfkc(line)) {
+        return Vec::new();
+    }
+
+    let mut starts: Vec<usize> = line
+        .char_indices()
+        .filter(|&(i, ch)| i == 0 || starts_segment(ch))
+        .map(|(i, _)| i)
+        .collect();
+    starts.push(line.len());
+
+    let mut mismatches = Vec::new();
+    for bounds in starts.windows(2) {
+        let (start, end) = (bounds[0], bounds[1]);
+        let segment = &line[start..end];
+
+        let nfc: String = segment.nfc().collect();
+        let (normalized, rule) = if nfc != segment {
+            (nfc, "ICD-NORM001")
+        } else if compat {
+            let nfkc: String = segment.nfkc().collect();
+            if nfkc == segment {
+                continue;
+            }
+            (nfkc, "ICD-NORM002")
+        } else {
+            continue;
+        };
+
+        mismatches.push(Mismatch {
+            start,
+            end,
+            normalized,
+            rule,
+        });
+    }
+    mismatches
+}
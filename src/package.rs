@@ -0,0 +1,186 @@
+//! `scan-package`: download a package from its registry and scan what it ships, for
+//! screening a third-party dependency before adding it.
+//!
+//! `npm:left-pad@1.3.0`, `crates:serde@1.0.0`, and `pypi:requests@2.31.0` name a package and
+//! version; without `@version` the latest release is scanned. The package's archive (the npm
+//! tarball, the .crate, or the PyPI source distribution, or a wheel when there is none) is
+//! read in memory like with `--scan-archives`, and its files are reported as
+//! `npm:left-pad@1.3.0!package/index.js`.
+
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::archive::{self, ArchiveKind};
+use crate::fetch;
+
+/// Largest registry metadata document read while resolving a package.
+const MAX_METADATA_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Where a package is published.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Registry {
+    Npm,
+    Crates,
+    Pypi,
+}
+
+impl Registry {
+    fn scheme(self) -> &'static str {
+        match self {
+            Registry::Npm => "npm",
+            Registry::Crates => "crates",
+            Registry::Pypi => "pypi",
+        }
+    }
+}
+
+/// A package named on the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageSpec {
+    pub registry: Registry,
+    pub name: String,
+
+    /// `None` for the latest release.
+    pub version: Option<String>,
+}
+
+impl fmt::Display for PackageSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.registry.scheme(), self.name)?;
+        if let Some(version) = &self.version {
+            write!(f, "@{}", version)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse a `scan-package` argument such as `npm:@scope/name@1.0.0`.
+pub fn parse_spec(value: &str) -> Result<PackageSpec, String> {
+    let (scheme, package) = value
+        .split_once(':')
+        .ok_or("expected REGISTRY:NAME[@VERSION], e.g. npm:left-pad@1.3.0")?;
+    let registry = match scheme {
+        "npm" => Registry::Npm,
+        "crates" => Registry::Crates,
+        "pypi" => Registry::Pypi,
+        _ => return Err(format!("unknown registry {:?} (npm, crates, pypi)", scheme)),
+    };
+    // An npm scope starts with @ too
+    let (name, version) = match package.rfind('@').filter(|&at| at > 0) {
+        Some(at) => (&package[..at], Some(&package[at + 1..])),
+        None => (package, None),
+    };
+    let valid = |s: &str| {
+        !s.is_empty()
+            && s.chars()
+                .all(|c| c.is_ascii_alphanumeric() || "@/._-+~".contains(c))
+    };
+    if !valid(name) || version.is_some_and(|version| !valid(version)) {
+        return Err(format!("{:?} is not a package name and version", package));
+    }
+    Ok(PackageSpec {
+        registry,
+        name: name.to_string(),
+        version: version.map(str::to_string),
+    })
+}
+
+/// A downloaded package archive.
+pub struct Download {
+    /// The package with the version that was downloaded.
+    pub spec: PackageSpec,
+    pub bytes: Vec<u8>,
+    pub kind: ArchiveKind,
+}
+
+/// Fetch and parse a registry's JSON metadata.
+fn metadata(url: &str) -> Result<Value, String> {
+    let bytes = fetch::fetch(
+        url,
+        Some(MAX_METADATA_SIZE),
+        &["application/json".to_string()],
+    )?;
+    serde_json::from_slice(&bytes).map_err(|e| format!("{}: {}", url, e))
+}
+
+/// The string at `pointer` in a metadata document.
+fn field<'a>(document: &'a Value, pointer: &str, url: &str) -> Result<&'a str, String> {
+    document
+        .pointer(pointer)
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("{}: no {} in the registry's answer", url, pointer))
+}
+
+/// The version to download and the URL of its archive.
+fn resolve(spec: &PackageSpec) -> Result<(String, String), String> {
+    match spec.registry {
+        Registry::Npm => {
+            let url = format!(
+                "https://registry.npmjs.org/{}/{}",
+                spec.name,
+                spec.version.as_deref().unwrap_or("latest")
+            );
+            let document = metadata(&url)?;
+            Ok((
+                field(&document, "/version", &url)?.to_string(),
+                field(&document, "/dist/tarball", &url)?.to_string(),
+            ))
+        }
+        Registry::Crates => {
+            let version = match &spec.version {
+                Some(version) => version.clone(),
+                None => {
+                    let url = format!("https://crates.io/api/v1/crates/{}", spec.name);
+                    let document = metadata(&url)?;
+                    field(&document, "/crate/max_stable_version", &url)
+                        .or_else(|_| field(&document, "/crate/max_version", &url))?
+                        .to_string()
+                }
+            };
+            let url = format!(
+                "https://static.crates.io/crates/{0}/{0}-{1}.crate",
+                spec.name, version
+            );
+            Ok((version, url))
+        }
+        Registry::Pypi => {
+            let url = match &spec.version {
+                Some(version) => format!("https://pypi.org/pypi/{}/{}/json", spec.name, version),
+                None => format!("https://pypi.org/pypi/{}/json", spec.name),
+            };
+            let document = metadata(&url)?;
+            let version = field(&document, "/info/version", &url)?.to_string();
+            let files = document
+                .get("urls")
+                .and_then(Value::as_array)
+                .map_or(&[][..], Vec::as_slice);
+            let of_type = |kind: &str| {
+                files
+                    .iter()
+                    .find(|file| file.get("packagetype").and_then(Value::as_str) == Some(kind))
+            };
+            let file = of_type("sdist")
+                .or_else(|| of_type("bdist_wheel"))
+                .ok_or_else(|| format!("{}: version {} has no files", url, version))?;
+            Ok((version, field(file, "/url", &url)?.to_string()))
+        }
+    }
+}
+
+/// Download the package `spec`, refusing archives larger than `max_size` bytes.
+pub fn download(spec: &PackageSpec, max_size: Option<u64>) -> Result<Download, String> {
+    let (version, url) = resolve(spec)?;
+    let file_name = url.rsplit('/').next().unwrap_or_default();
+    let kind = archive::archive_kind(file_name)
+        .ok_or_else(|| format!("{}: not an archive format this tool reads", url))?;
+    let bytes = fetch::fetch(&url, max_size, &["*/*".to_string()])?;
+    Ok(Download {
+        spec: PackageSpec {
+            version: Some(version),
+            ..spec.clone()
+        },
+        bytes,
+        kind,
+    })
+}
@@ -0,0 +1,77 @@
+//! `check-paste`: screen text about to be pasted, such as code from an LLM or a chat, in one
+//! command instead of a temp file and a handful of flags.
+//!
+//! The text comes from stdin or the clipboard and is checked with every opt-in check that
+//! suits code: escaped forms, confusables, lookalike identifiers, and all format characters,
+//! on top of the default rules (tag characters included). The verdict is a few lines: each
+//! line with findings, its hidden characters made visible, and what they are. `--clean`
+//! prints (or puts back on the clipboard) the text without them.
+
+use crate::{visualize, DetectOptions, Detection};
+
+/// Name detections in the pasted text are reported under.
+pub const PASTE_NAME: &str = "<paste>";
+
+/// The strict profile pasted text is checked with.
+pub fn options() -> DetectOptions {
+    DetectOptions {
+        confusables: true,
+        all_format_chars: true,
+        lookalike_identifiers: true,
+        include_escapes: true,
+        forbid_bom: true,
+        ..DetectOptions::default()
+    }
+}
+
+/// Characters a detection stands for, counting repeats.
+fn count(d: &Detection) -> usize {
+    d.run.as_ref().map_or(1, |run| run.count)
+}
+
+/// The verdict on `text`: one line when it is clean, otherwise each line with findings,
+/// made visible, followed by what was found on it.
+pub fn format_verdict(text: &str, detections: &[Detection]) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    if detections.is_empty() {
+        return format!("CLEAN: nothing hidden in {} line(s)\n", lines.len());
+    }
+
+    let total: usize = detections.iter().map(count).sum();
+    let mut flagged: Vec<usize> = detections.iter().map(|d| d.line).collect();
+    flagged.dedup();
+    let mut output = format!(
+        "SUSPICIOUS: {} character(s) on {} of {} line(s)\n",
+        total,
+        flagged.len(),
+        lines.len()
+    );
+    let width = flagged.last().map_or(1, |line| line.to_string().len());
+    let mut current = 0;
+    for d in detections {
+        if d.line != current {
+            current = d.line;
+            let line = lines.get(d.line.wrapping_sub(1)).copied().unwrap_or("");
+            output.push_str(&format!("\n  {:>width$}: {}\n", d.line, visualize(line)));
+        }
+        let repeat = match count(d) {
+            1 => String::new(),
+            n => format!(" x{}", n),
+        };
+        let escaped = if d.escaped { " (escaped)" } else { "" };
+        output.push_str(&format!(
+            "  {:>width$}  col {}: U+{:04X} {}{}{} [{}]\n",
+            "", d.char_index, d.code, d.name, repeat, escaped, d.rule
+        ));
+    }
+
+    let fixable = detections.iter().filter(|d| d.fix.is_some()).count();
+    if fixable < detections.len() {
+        output.push_str(&format!(
+            "\n{} finding(s) need fixing by hand: escapes, lookalike identifiers, and confusables \
+             aren't removed automatically.\n",
+            detections.len() - fixable
+        ));
+    }
+    output
+}
@@ -0,0 +1,129 @@
+//! `--patch`: scan the lines a unified diff adds, read from stdin.
+//!
+//! `git diff | invisible-char-detector --patch` gates a change without a working tree, and
+//! works the same with the output of `svn diff`, `hg diff`, or `diff -u`. Only added lines
+//! are scanned; each detection is reported at its file and line in the new version, from the
+//! hunk headers. `byte_offset` counts bytes of the patch, since the file itself may not exist.
+
+use crate::git::unquote_path;
+
+/// A line the patch adds.
+#[derive(Debug)]
+pub struct AddedLine {
+    /// 1-indexed line number in the new version of the file.
+    pub line: usize,
+
+    /// 0-indexed offset in the patch of the line's text (after its `+`).
+    pub offset: usize,
+
+    /// Length of the text in bytes, with its line terminator.
+    pub len: usize,
+}
+
+/// A file the patch adds lines to.
+#[derive(Debug)]
+pub struct PatchedFile {
+    /// Path of the new version, without git's `b/` prefix.
+    pub path: String,
+
+    pub lines: Vec<AddedLine>,
+}
+
+/// The path in a `+++ ` header, or `None` for a deleted file.
+fn target_path(header: &str) -> Option<String> {
+    // diff -u follows the name with a tab and a timestamp; git adds a tab to names with spaces
+    let name = header
+        .split('\t')
+        .next()
+        .unwrap_or(header)
+        .trim_end_matches(['\r', '\n']);
+    let name = unquote_path(name);
+    if name == "/dev/null" {
+        return None;
+    }
+    Some(match name.strip_prefix("b/") {
+        Some(path) => path.to_string(),
+        None => name,
+    })
+}
+
+/// Start and length of a hunk header range (`12,3`, or `12` for one line).
+fn range_len(range: &str) -> Option<(usize, usize)> {
+    let (start, len) = range.split_once(',').unwrap_or((range, "1"));
+    Some((start.parse().ok()?, len.parse().ok()?))
+}
+
+/// The files `patch` adds lines to and the lines it adds, in patch order.
+pub fn added_lines(patch: &str) -> Vec<PatchedFile> {
+    let mut files: Vec<PatchedFile> = Vec::new();
+    let mut current: Option<String> = None;
+    // Lines of the current hunk still to come, in the old and new versions
+    let mut old_left = 0;
+    let mut new_left = 0;
+    let mut new_line = 0;
+    let mut offset = 0;
+
+    for row in patch.split_inclusive('\n') {
+        let row_offset = offset;
+        offset += row.len();
+
+        if old_left > 0 || new_left > 0 {
+            match row.as_bytes().first() {
+                Some(b'+') => {
+                    if let Some(path) = &current {
+                        if files.last().is_none_or(|file| &file.path != path) {
+                            files.push(PatchedFile {
+                                path: path.clone(),
+                                lines: Vec::new(),
+                            });
+                        }
+                        if let Some(file) = files.last_mut() {
+                            file.lines.push(AddedLine {
+                                line: new_line,
+                                offset: row_offset + 1,
+                                len: row.len() - 1,
+                            });
+                        }
+                    }
+                    new_line += 1;
+                    new_left -= 1;
+                }
+                Some(b'-') => old_left -= 1,
+                // "\ No newline at end of file"
+                Some(b'\\') => {}
+                // Context, or a blank context line some tools strip to nothing
+                _ => {
+                    new_line += 1;
+                    old_left -= 1;
+                    new_left -= 1;
+                }
+            }
+            // A malformed hunk can't run on past its header's counts
+            if old_left < 0 || new_left < 0 {
+                (old_left, new_left) = (0, 0);
+            }
+            continue;
+        }
+
+        if let Some(header) = row.strip_prefix("+++ ") {
+            current = target_path(header);
+        } else if let Some(hunk) = row.strip_prefix("@@ ") {
+            // @@ -old[,len] +new[,len] @@
+            let mut ranges = hunk.split_whitespace();
+            let old = ranges
+                .next()
+                .and_then(|r| r.strip_prefix('-'))
+                .and_then(range_len);
+            let new = ranges
+                .next()
+                .and_then(|r| r.strip_prefix('+'))
+                .and_then(range_len);
+            if let (Some((_, old_len)), Some((new_start, new_len))) = (old, new) {
+                old_left = old_len as isize;
+                new_left = new_len as isize;
+                new_line = new_start;
+            }
+        }
+    }
+    files
+}
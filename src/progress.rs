@@ -0,0 +1,95 @@
+//! A progress line on stderr while files are scanned, so a long scan doesn't look hung.
+//!
+//! Shown only when stderr is a terminal (never in CI logs or redirected output), and turned off
+//! with `--no-progress` or `--quiet`. The line is redrawn at most ten times a second and
+//! cleared before any results are printed.
+
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::visualize;
+
+/// Minimum time between redraws.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Longest path shown; longer ones keep their end.
+const MAX_PATH_CHARS: usize = 60;
+
+/// True if a progress line may be drawn: stderr is a terminal.
+pub fn available() -> bool {
+    std::io::stderr().is_terminal()
+}
+
+/// Progress of a scan over a known number of files, updated from any thread.
+pub struct Progress {
+    enabled: bool,
+    total: usize,
+    done: AtomicUsize,
+    matched: AtomicUsize,
+    last_draw: Mutex<Option<Instant>>,
+    drawn: AtomicBool,
+}
+
+impl Progress {
+    pub fn new(total: usize, enabled: bool) -> Self {
+        Progress {
+            enabled,
+            total,
+            done: AtomicUsize::new(0),
+            matched: AtomicUsize::new(0),
+            last_draw: Mutex::new(None),
+            drawn: AtomicBool::new(false),
+        }
+    }
+
+    /// Record that `path` has been processed, with `found` if it had detections.
+    pub fn file_done(&self, path: &str, found: bool) {
+        if !self.enabled {
+            return;
+        }
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        if found {
+            self.matched.fetch_add(1, Ordering::Relaxed);
+        }
+
+        // Another thread drawing means this update can be skipped
+        let Ok(mut last_draw) = self.last_draw.try_lock() else {
+            return;
+        };
+        if last_draw.is_some_and(|last| last.elapsed() < REDRAW_INTERVAL) {
+            return;
+        }
+        *last_draw = Some(Instant::now());
+
+        let path = visualize(path);
+        let chars = path.chars().count();
+        let path = if chars > MAX_PATH_CHARS {
+            let tail: String = path.chars().skip(chars - (MAX_PATH_CHARS - 3)).collect();
+            format!("...{}", tail)
+        } else {
+            path
+        };
+        let mut stderr = std::io::stderr().lock();
+        let _ = write!(
+            stderr,
+            "\r\x1b[K{}/{} files, {} with findings  {}",
+            done,
+            self.total,
+            self.matched.load(Ordering::Relaxed),
+            path
+        );
+        let _ = stderr.flush();
+        self.drawn.store(true, Ordering::Relaxed);
+    }
+
+    /// Erase the progress line, if one was drawn.
+    pub fn finish(&self) {
+        if self.drawn.load(Ordering::Relaxed) {
+            let mut stderr = std::io::stderr().lock();
+            let _ = write!(stderr, "\r\x1b[K");
+            let _ = stderr.flush();
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! `--redact-paths`: paths in reports that can be shared outside the organization.
+//!
+//! An absolute path tells an external auditor or a public issue tracker where a repository
+//! is checked out and whose home directory it is in. `strip` makes paths inside the
+//! repository relative to its root and replaces the home directory (or any
+//! `/home/<user>`-style prefix) with `~`; `hash` also replaces each path by a hash of its
+//! stripped form, keeping only the extension. Both give the same path for the same file on
+//! every run and every machine, so `diff-reports` and baselines still match findings.
+
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+
+use crate::cache::content_hash;
+use crate::git;
+
+/// How much of a path `--redact-paths` keeps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RedactMode {
+    /// Repository-relative paths, with `~` for the home directory elsewhere
+    Strip,
+
+    /// A hash of the stripped path plus its extension (`3f09c1d27a4e8b65.rs`)
+    Hash,
+}
+
+/// Directories whose next component is a user name.
+const USER_PARENTS: &[&str] = &["home", "Users", "users"];
+
+/// Rewrites paths per a [`RedactMode`].
+#[derive(Debug)]
+pub struct Redactor {
+    mode: RedactMode,
+
+    /// Root of the repository being scanned, or the current directory outside one.
+    root: Option<PathBuf>,
+
+    home: Option<PathBuf>,
+}
+
+impl Redactor {
+    pub fn new(mode: RedactMode) -> Self {
+        let root = git::repo_root()
+            .ok()
+            .or_else(|| std::env::current_dir().ok()?.canonicalize().ok());
+        let home = std::env::var_os("HOME")
+            .or_else(|| std::env::var_os("USERPROFILE"))
+            .filter(|home| !home.is_empty())
+            .map(PathBuf::from);
+        Redactor { mode, root, home }
+    }
+
+    /// The path with the repository root, home directory, and user name taken out.
+    pub fn strip(&self, path: &str) -> String {
+        let as_path = Path::new(path);
+        if !as_path.is_absolute() {
+            return path.strip_prefix("./").unwrap_or(path).to_string();
+        }
+        if let Some(relative) = self
+            .root
+            .as_deref()
+            .and_then(|root| as_path.strip_prefix(root).ok())
+        {
+            return relative.to_string_lossy().replace('\\', "/");
+        }
+        if let Some(rest) = self
+            .home
+            .as_deref()
+            .and_then(|home| as_path.strip_prefix(home).ok())
+        {
+            return format!("~/{}", rest.to_string_lossy().replace('\\', "/"));
+        }
+
+        // Someone else's home directory, or the same one through another mount
+        let components: Vec<_> = as_path.components().collect();
+        for (i, pair) in components.windows(2).enumerate() {
+            if USER_PARENTS.iter().any(|parent| pair[0].as_os_str() == *parent) {
+                let rest: PathBuf = components[i + 2..].iter().collect();
+                return format!("~/{}", rest.to_string_lossy().replace('\\', "/"));
+            }
+        }
+        path.to_string()
+    }
+
+    /// `path` as the report should show it.
+    pub fn redact(&self, path: &str) -> String {
+        let stripped = self.strip(path);
+        match self.mode {
+            RedactMode::Strip => stripped,
+            RedactMode::Hash => {
+                // A member of an archive or document keeps the extension of the member
+                let name = stripped.rsplit(['/', '!']).next().unwrap_or_default();
+                let hash = content_hash(stripped.as_bytes());
+                match name.rsplit_once('.') {
+                    Some((stem, extension)) if !stem.is_empty() => {
+                        format!("{}.{}", hash, extension)
+                    }
+                    _ => hash,
+                }
+            }
+        }
+    }
+}
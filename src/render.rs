@@ -0,0 +1,205 @@
+//! How results are printed: `--format`, `--escape-style`, `--group-by`, and `--color`.
+//!
+//! Printing a bidi control raw can reorder the report itself, and a zero-width character
+//! prints as nothing. File names, structure paths, and `--show-context` lines spell every
+//! suspicious character in one of three visible forms instead.
+//!
+//! Color follows the `NO_COLOR` convention (<https://no-color.org>): `--color auto` colors
+//! only a terminal, and only when `NO_COLOR` (or `ICD_NO_COLOR`) is unset or empty.
+
+use std::io::IsTerminal;
+
+use clap::ValueEnum;
+
+use crate::ucd;
+
+/// A visible spelling for hidden characters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum EscapeStyle {
+    /// <U+200B>
+    #[default]
+    UnicodeNotation,
+
+    /// ⟨ZWSP⟩, or the full name for characters without a common abbreviation
+    BracketedName,
+
+    /// M-bM-^@M-^K: the UTF-8 bytes in `cat -v` caret notation
+    Caret,
+}
+
+/// How text output lists the detections.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum GroupBy {
+    /// Each file with its detections
+    #[default]
+    File,
+
+    /// Each code point with how often it occurs and in which files and lines, most frequent
+    /// first
+    Codepoint,
+
+    /// Each category (zero-width, bidi, ...) the same way
+    Category,
+}
+
+/// Common abbreviations of the characters the scanner reports.
+const ABBREVIATIONS: &[(u32, &str)] = &[
+    (0x0085, "NEL"),
+    (0x00A0, "NBSP"),
+    (0x00AD, "SHY"),
+    (0x061C, "ALM"),
+    (0x180E, "MVS"),
+    (0x200B, "ZWSP"),
+    (0x200C, "ZWNJ"),
+    (0x200D, "ZWJ"),
+    (0x200E, "LRM"),
+    (0x200F, "RLM"),
+    (0x2028, "LS"),
+    (0x2029, "PS"),
+    (0x202A, "LRE"),
+    (0x202B, "RLE"),
+    (0x202C, "PDF"),
+    (0x202D, "LRO"),
+    (0x202E, "RLO"),
+    (0x202F, "NNBSP"),
+    (0x205F, "MMSP"),
+    (0x2060, "WJ"),
+    (0x2066, "LRI"),
+    (0x2067, "RLI"),
+    (0x2068, "FSI"),
+    (0x2069, "PDI"),
+    (0x3000, "IDSP"),
+    (0xFEFF, "BOM"),
+    (0xFFF9, "IAA"),
+    (0xFFFA, "IAS"),
+    (0xFFFB, "IAT"),
+    (0xFFFC, "ORC"),
+];
+
+/// Caret notation of one byte, as `cat -v` prints it.
+fn push_caret_byte(byte: u8, out: &mut String) {
+    let low = if byte >= 0x80 {
+        out.push_str("M-");
+        byte - 0x80
+    } else {
+        byte
+    };
+    match low {
+        0x00..=0x1F => {
+            out.push('^');
+            out.push((low + 0x40) as char);
+        }
+        0x7F => out.push_str("^?"),
+        _ => out.push(low as char),
+    }
+}
+
+/// Append the visible spelling of `ch` in `style` to `out`.
+pub fn push_escaped(ch: char, style: EscapeStyle, out: &mut String) {
+    let code = ch as u32;
+    match style {
+        EscapeStyle::UnicodeNotation => out.push_str(&format!("<U+{:04X}>", code)),
+        EscapeStyle::BracketedName => {
+            let name = match ABBREVIATIONS.binary_search_by_key(&code, |&(c, _)| c) {
+                Ok(i) => ABBREVIATIONS[i].1.to_string(),
+                Err(_) => match code {
+                    0xFE00..=0xFE0F => format!("VS{}", code - 0xFE00 + 1),
+                    0xE0100..=0xE01EF => format!("VS{}", code - 0xE0100 + 17),
+                    _ => ucd::char_name(code),
+                },
+            };
+            out.push('⟨');
+            out.push_str(&name);
+            out.push('⟩');
+        }
+        EscapeStyle::Caret => {
+            let mut buf = [0u8; 4];
+            for &byte in ch.encode_utf8(&mut buf).as_bytes() {
+                push_caret_byte(byte, out);
+            }
+        }
+    }
+}
+
+/// How scan results are printed (`--format`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Grouped by file, for reading
+    #[default]
+    Text,
+
+    /// An array of detections (same as --json)
+    Json,
+
+    /// One `file:line:col: error: U+200B ZERO WIDTH SPACE [ICD-ZW001, zero-width]` line per
+    /// detection, for quickfix lists, problem matchers, and grep-style tools
+    Short,
+
+    /// TeamCity service messages: an inspection per detection and a build problem
+    Teamcity,
+
+    /// Azure Pipelines logging commands: an issue per detection, and the task marked failed
+    Azure,
+
+    /// JSON lines: a `file` record before each file's detections and an `end` record with the
+    /// counts, for reading the report as a stream
+    Jsonl,
+}
+
+/// When to color text output (`--color`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorMode {
+    /// When stdout is a terminal and neither `NO_COLOR` nor `ICD_NO_COLOR` is set
+    #[default]
+    Auto,
+
+    Always,
+
+    Never,
+}
+
+impl ColorMode {
+    /// Whether output to stdout should be colored.
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => {
+                ["NO_COLOR", "ICD_NO_COLOR"]
+                    .iter()
+                    .all(|name| std::env::var_os(name).is_none_or(|value| value.is_empty()))
+                    && std::io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+/// SGR parameters for the colors text output uses.
+pub const BOLD: &str = "1";
+pub const RED: &str = "1;31";
+pub const YELLOW: &str = "33";
+pub const DIM: &str = "2";
+
+/// Settings for text output.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextStyle {
+    /// How hidden characters are spelled.
+    pub escapes: EscapeStyle,
+
+    /// Emit ANSI colors.
+    pub color: bool,
+
+    /// How the detections are listed.
+    pub group_by: GroupBy,
+}
+
+impl TextStyle {
+    /// `text` wrapped in the SGR sequence `sgr` (one of the constants above), if coloring.
+    pub fn paint(self, text: &str, sgr: &str) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", sgr, text)
+        } else {
+            text.to_string()
+        }
+    }
+}
@@ -0,0 +1,319 @@
+//! `--json-version 2`: JSON output as one object that records how the scan was run.
+//!
+//! Version 1 (the default) is a bare array of detections. Version 2 wraps them with a
+//! `schema_version`, the tool and its version, the settings the scan used, file counts, the
+//! files that were not scanned and why, and the scan duration, so an archived report can be
+//! audited on its own and the format can grow without breaking consumers that check the
+//! version.
+
+use std::collections::BTreeMap;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::file_info::FileInfo;
+use crate::roots::RootCounts;
+use crate::rules;
+use crate::summary::{SeverityCounts, Summary};
+use crate::timings::Timings;
+use crate::{BinaryClass, Detection, ScanConfig};
+
+/// The `schema_version` of the object this module writes.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// The program that wrote the report.
+#[derive(Debug, Serialize)]
+struct Tool {
+    name: &'static str,
+    version: &'static str,
+}
+
+/// The settings a scan used, after merging in the config file.
+#[derive(Debug, Serialize)]
+struct ReportConfig<'a> {
+    pattern: &'a str,
+    roots: &'a [String],
+    sort_by: String,
+    group_by: String,
+    timings: Option<usize>,
+    file_info: bool,
+    config_file: Option<String>,
+    patch: bool,
+    diff_base: Option<&'a str>,
+    staged: bool,
+    preset: Option<&'static str>,
+    scan_bundles: bool,
+    ignore_dirs: Vec<&'a str>,
+    generated: &'static str,
+    scan_archives: bool,
+    scan_documents: bool,
+    follow_symlinks: bool,
+    max_file_size: Option<u64>,
+    max_detections_per_file: Option<usize>,
+    combining_threshold: usize,
+    warn_line_length: Option<usize>,
+    warn_file_detections: Option<usize>,
+    check_normalization: Option<String>,
+    confusables: bool,
+    all_format_chars: bool,
+    detect_ranges: Vec<String>,
+    mixed_scripts: bool,
+    lookalike_identifiers: bool,
+    allow_scripts: Option<Vec<&'static str>>,
+    allow_emoji: bool,
+    ascii_only: bool,
+    include_escapes: bool,
+    context: bool,
+    require_context: Option<Vec<&'static str>>,
+    show_suppressed: bool,
+    treat_ls_as_newline: bool,
+    collapse_runs: bool,
+    lossy: bool,
+    forbid_bom: bool,
+    report_invalid_utf8: bool,
+    lines: Option<String>,
+    path_overrides: usize,
+    clean_to: Option<String>,
+    notify_webhook: bool,
+    step_summary: bool,
+    forward_slashes: bool,
+    redact_paths: Option<String>,
+}
+
+impl<'a> ReportConfig<'a> {
+    fn new(config: &'a ScanConfig) -> Self {
+        let detect = &config.detect;
+        ReportConfig {
+            pattern: &config.pattern,
+            roots: &config.roots,
+            sort_by: config
+                .sort_by
+                .to_possible_value()
+                .map(|value| value.get_name().to_string())
+                .unwrap_or_default(),
+            group_by: config
+                .group_by
+                .to_possible_value()
+                .map(|value| value.get_name().to_string())
+                .unwrap_or_default(),
+            timings: config.timings,
+            file_info: config.file_info,
+            config_file: config
+                .config_file
+                .as_ref()
+                .map(|path| path.display().to_string()),
+            patch: config.patch,
+            diff_base: config.diff_base.as_deref(),
+            staged: config.staged,
+            preset: config.preset.map(|preset| preset.as_str()),
+            scan_bundles: config.scan_bundles,
+            ignore_dirs: config.ignore_dirs.names(),
+            generated: config.generated.as_str(),
+            scan_archives: config.scan_archives,
+            scan_documents: config.scan_documents,
+            follow_symlinks: config.follow_symlinks,
+            max_file_size: config.max_file_size,
+            max_detections_per_file: detect.max_detections,
+            combining_threshold: detect.combining_threshold,
+            warn_line_length: detect.warn_line_length,
+            warn_file_detections: detect.warn_file_detections,
+            check_normalization: detect
+                .normalization
+                .and_then(|form| form.to_possible_value())
+                .map(|value| value.get_name().to_string()),
+            confusables: detect.confusables,
+            all_format_chars: detect.all_format_chars,
+            detect_ranges: detect
+                .detect_ranges
+                .iter()
+                .map(|range| format!("U+{:04X}..U+{:04X}", range.start(), range.end()))
+                .collect(),
+            mixed_scripts: detect.mixed_scripts,
+            lookalike_identifiers: detect.lookalike_identifiers,
+            allow_scripts: detect
+                .allowed_scripts
+                .as_ref()
+                .map(|scripts| scripts.iter().map(|s| s.full_name()).collect()),
+            allow_emoji: detect.allow_emoji,
+            ascii_only: config.ascii_only.unwrap_or(false),
+            include_escapes: detect.include_escapes,
+            context: detect.syntax_context,
+            require_context: detect
+                .context_policy
+                .require
+                .as_ref()
+                .map(|contexts| contexts.iter().map(|c| c.as_str()).collect()),
+            show_suppressed: detect.show_suppressed,
+            treat_ls_as_newline: detect.unicode_line_breaks,
+            collapse_runs: detect.collapse_runs,
+            lossy: detect.lossy,
+            forbid_bom: detect.forbid_bom,
+            report_invalid_utf8: detect.report_invalid_utf8,
+            lines: detect
+                .lines
+                .as_ref()
+                .map(|lines| format!("{}-{}", lines.start(), lines.end())),
+            path_overrides: config.overrides.len(),
+            clean_to: config
+                .clean_to
+                .as_ref()
+                .map(|path| path.display().to_string()),
+            // The URL itself is often a secret
+            notify_webhook: config.notify_webhook.is_some(),
+            step_summary: config.step_summary,
+            forward_slashes: config.forward_slashes,
+            redact_paths: config
+                .redact_paths
+                .and_then(|mode| mode.to_possible_value())
+                .map(|value| value.get_name().to_string()),
+        }
+    }
+}
+
+/// How many files the scan looked at, and what became of them.
+#[derive(Debug, Serialize)]
+pub struct Counts {
+    pub scanned: usize,
+    pub skipped: usize,
+    pub binary: usize,
+
+    /// Binary files by class, e.g. `{"image": 1204, "archive": 3}`.
+    pub binary_by_class: BTreeMap<BinaryClass, usize>,
+
+    /// Scanned files whose results came from `--cache`.
+    pub cached: usize,
+
+    /// Suspicious characters found, by severity.
+    pub findings: SeverityCounts,
+
+    /// With `--root`: the result of each root.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub roots: Vec<RootCounts>,
+}
+
+/// A file that was not scanned.
+#[derive(Debug, Serialize)]
+pub struct SkippedFile {
+    pub path: String,
+
+    /// `ignored` (in an ignored directory such as `node_modules/`), `symlink` (not followed),
+    /// `too-large`, `binary`, or `unreadable`. All but `binary` count for `--fail-on-skip`.
+    pub reason: &'static str,
+
+    /// The size of a large file, the format of a binary one, or the read error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+
+    /// For a binary file: `image`, `archive`, `executable`, `document`, `media`, `font`,
+    /// `database`, or `other`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class: Option<BinaryClass>,
+}
+
+/// Reference information on a rule that fired, for mapping findings to tickets.
+#[derive(Debug, Serialize)]
+struct RuleInfo {
+    title: &'static str,
+
+    /// `CWE-451`, ...
+    cwe: Vec<String>,
+    references: &'static [&'static str],
+}
+
+/// The rules of `detections` and of the counts in `summary`, by ID.
+fn rules_info(
+    detections: Option<&[Detection]>,
+    summary: Option<&Summary>,
+) -> BTreeMap<&'static str, RuleInfo> {
+    let ids = detections
+        .into_iter()
+        .flatten()
+        .map(|d| d.rule.as_str())
+        .chain(summary.into_iter().flat_map(|s| s.by_rule.iter().map(|c| c.key.as_str())));
+    let mut info = BTreeMap::new();
+    for rule in ids.filter_map(rules::find) {
+        info.entry(rule.id).or_insert_with(|| RuleInfo {
+            title: rule.title,
+            cwe: rule.cwe.iter().map(|cwe| format!("CWE-{}", cwe)).collect(),
+            references: rule.references,
+        });
+    }
+    info
+}
+
+/// The version 2 JSON report.
+#[derive(Debug, Serialize)]
+pub struct Report<'a> {
+    schema_version: u32,
+    tool: Tool,
+    config: ReportConfig<'a>,
+    counts: Counts,
+    duration_ms: u128,
+
+    /// Left out by `stats` and `--summary-only`, which keep no detections.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detections: Option<&'a [Detection]>,
+
+    skipped: &'a [SkippedFile],
+
+    /// With `--summary`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<&'a Summary>,
+
+    /// With `--timings`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timings: Option<&'a Timings>,
+
+    /// With `--file-info`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    files: Option<&'a [FileInfo]>,
+
+    /// With `--show-suppressed`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suppressed: Option<&'a [Detection]>,
+
+    /// The rules that fired, with their CWE IDs and references.
+    rules: BTreeMap<&'static str, RuleInfo>,
+}
+
+impl<'a> Report<'a> {
+    pub fn new(
+        config: &'a ScanConfig,
+        counts: Counts,
+        duration_ms: u128,
+        detections: Option<&'a [Detection]>,
+        skipped: &'a [SkippedFile],
+        summary: Option<&'a Summary>,
+        timings: Option<&'a Timings>,
+    ) -> Self {
+        Report {
+            schema_version: SCHEMA_VERSION,
+            tool: Tool {
+                name: env!("CARGO_PKG_NAME"),
+                version: env!("CARGO_PKG_VERSION"),
+            },
+            config: ReportConfig::new(config),
+            counts,
+            duration_ms,
+            detections,
+            skipped,
+            summary,
+            timings,
+            files: None,
+            suppressed: None,
+            rules: rules_info(detections, summary),
+        }
+    }
+
+    /// Add the `--file-info` census.
+    pub fn with_files(mut self, files: Option<&'a [FileInfo]>) -> Self {
+        self.files = files;
+        self
+    }
+
+    /// Add the detections `--show-suppressed` keeps.
+    pub fn with_suppressed(mut self, suppressed: Option<&'a [Detection]>) -> Self {
+        self.suppressed = suppressed;
+        self
+    }
+}
@@ -0,0 +1,49 @@
+//! `--root`: scan several independent trees in one run, such as the `frontend/` and `backend/`
+//! of a monorepo, each with a result of its own.
+//!
+//! Every root is scanned with the pattern taken relative to it and its detections are tagged
+//! with the root. The report gives each root its counts and whether it passed; the run fails
+//! if any root does.
+
+use serde::Serialize;
+
+/// The result of one root.
+#[derive(Debug, Clone, Serialize)]
+pub struct RootCounts {
+    pub root: String,
+    pub scanned: usize,
+
+    /// Findings, counting those left out past `--max-detections`.
+    pub findings: usize,
+
+    /// True if a finding under the root fails the scan.
+    pub failed: bool,
+}
+
+/// The pattern to scan under `root`.
+pub fn pattern_under(root: &str, pattern: &str) -> String {
+    let root = root.trim_end_matches(['/', '\\']);
+    let pattern = pattern.strip_prefix("./").unwrap_or(pattern);
+    format!("{}/{}", root, pattern)
+}
+
+/// Format the per-root results for text output.
+pub fn format_roots(roots: &[RootCounts]) -> String {
+    let mut output = String::from("Roots:\n");
+    let width = roots
+        .iter()
+        .map(|r| crate::visualize(&r.root).chars().count())
+        .max()
+        .unwrap_or(0);
+    for r in roots {
+        let status = if r.failed { "FAILED" } else { "passed" };
+        output.push_str(&format!(
+            "  {:<width$}  {}  {} finding(s) in {} scanned file(s)\n",
+            crate::visualize(&r.root),
+            status,
+            r.findings,
+            r.scanned
+        ));
+    }
+    output
+}
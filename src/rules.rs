@@ -0,0 +1,801 @@
+//! Detection rules: stable IDs for each class of suspicious character, with the reference
+//! text `explain` prints for reviewers triaging a finding.
+
+use serde::{Deserialize, Serialize};
+
+/// How serious a finding is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Almost certainly malicious or broken; should fail CI.
+    Error,
+
+    /// Usually an accident (e.g. a bad encoding round-trip) rather than an attack.
+    Warning,
+
+    /// Usually benign; reported so it can be seen, but doesn't fail the scan.
+    Info,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        }
+    }
+
+    /// True if a finding of this severity makes the scan fail (exit status 1).
+    pub fn fails(self) -> bool {
+        self != Severity::Info
+    }
+}
+
+/// A class of suspicious characters reported under one ID.
+#[derive(Debug)]
+pub struct Rule {
+    /// Stable identifier, e.g. `ICD-BIDI001`.
+    pub id: &'static str,
+
+    /// Short title of the class.
+    pub title: &'static str,
+
+    /// Kind of character, shared by related rules: `zero-width`, `bidi`, `whitespace`, ...
+    /// (the `category` of detections).
+    pub category: &'static str,
+
+    /// The code points the rule covers, for display.
+    pub covers: &'static str,
+
+    /// Text that triggers exactly one detection under this rule, used by `generate-sample`.
+    /// Empty for rules that file content can't trigger.
+    pub sample: &'static str,
+
+    /// How serious a finding under this rule is.
+    pub severity: Severity,
+
+    /// The option that turns the rule on, for rules that are off by default.
+    pub opt_in: Option<&'static str>,
+
+    /// Why the characters are dangerous in source code.
+    pub risk: &'static str,
+
+    /// A concrete attack using them.
+    pub example: &'static str,
+
+    /// What to do about a finding.
+    pub remediation: &'static str,
+
+    /// The CWE weaknesses a finding is an instance of, for mapping findings to tickets.
+    pub cwe: &'static [u32],
+
+    /// Where to read more: papers, Unicode reports, and the code charts of the characters.
+    pub references: &'static [&'static str],
+}
+
+/// The Trojan Source paper (Boucher and Anderson, 2021).
+const TROJAN_SOURCE: &str = "https://trojansource.codes/trojan-source.pdf";
+
+/// UAX #9, the Unicode Bidirectional Algorithm.
+const UAX9: &str = "https://www.unicode.org/reports/tr9/";
+
+/// UAX #15, Unicode Normalization Forms.
+const UAX15: &str = "https://www.unicode.org/reports/tr15/";
+
+/// UTR #36, Unicode Security Considerations.
+const UTR36: &str = "https://www.unicode.org/reports/tr36/";
+
+/// UTS #39, Unicode Security Mechanisms (confusables and mixed scripts).
+const UTS39: &str = "https://www.unicode.org/reports/tr39/";
+
+/// UTS #55, Unicode Source Code Handling.
+const UTS55: &str = "https://www.unicode.org/reports/tr55/";
+
+/// Names of the CWE weaknesses the rules map to.
+const CWE_NAMES: &[(u32, &str)] = &[
+    (150, "Improper Neutralization of Escape, Meta, or Control Sequences"),
+    (172, "Encoding Error"),
+    (176, "Improper Handling of Unicode Encoding"),
+    (451, "User Interface (UI) Misrepresentation of Critical Information"),
+    (506, "Embedded Malicious Code"),
+    (1007, "Insufficient Visual Distinction of Homoglyphs Presented to User"),
+    (1289, "Improper Validation of Unsafe Equivalence in Input"),
+];
+
+/// The name of the weakness `CWE-id`.
+pub fn cwe_name(id: u32) -> &'static str {
+    CWE_NAMES
+        .iter()
+        .find(|&&(cwe, _)| cwe == id)
+        .map_or("", |&(_, name)| name)
+}
+
+/// All rules. Examples spell hidden characters as `<U+XXXX>` so `explain` never prints them raw.
+pub const RULES: &[Rule] = &[
+    Rule {
+        id: "ICD-ZW001",
+        title: "Zero-width characters",
+        category: "zero-width",
+        covers: "U+200B, U+200C, U+200D, U+2060, U+FEFF (except a leading byte order mark)",
+        sample: "\u{200B}",
+        severity: Severity::Error,
+        opt_in: None,
+        risk: "They take up no space when rendered, so two identifiers or strings that look \
+               identical can differ. Compilers and interpreters treat them as real characters.",
+        example: "`if (isAdmin<U+2060>) {}` reads the undeclared global `isAdmin<U+2060>` instead of \
+                  the local `isAdmin`; a zero-width space in a URL or key makes a comparison fail \
+                  in a way nobody can see.",
+        remediation: "Delete the character. U+FEFF is only legitimate as a byte order mark at the \
+                      very start of a file.",
+        cwe: &[1007, 451],
+        references: &[UTR36, UTS55, "https://www.unicode.org/charts/PDF/U2000.pdf"],
+    },
+    Rule {
+        id: "ICD-BOM001",
+        title: "Leading byte order mark",
+        category: "byte-order-mark",
+        covers: "U+FEFF as the first character of a file",
+        sample: "",
+        severity: Severity::Info,
+        opt_in: None,
+        risk: "A byte order mark at the start of a file is usually harmless: Windows editors \
+               write one to mark UTF-8. Some tools still choke on it, such as shells reading a \
+               `#!` line, PHP emitting it before headers, or parsers that take it as part of the \
+               first token. It is reported as info and doesn't fail the scan unless \
+               `--forbid-bom` is given.",
+        example: "`<U+FEFF>#!/bin/sh` fails to run with `not found`, because the kernel no longer \
+                  sees `#!` as the first two bytes.",
+        remediation: "Re-save the file as UTF-8 without BOM, or leave it if the repository \
+                      allows BOMs. Use `--forbid-bom` to enforce a no-BOM policy.",
+        cwe: &[176],
+        references: &["https://www.unicode.org/faq/utf_bom.html"],
+    },
+    Rule {
+        id: "ICD-BIDI001",
+        title: "Bidirectional embeddings and overrides",
+        category: "bidi",
+        covers: "U+202A–U+202E",
+        sample: "\u{202E}\u{202C}",
+        severity: Severity::Error,
+        opt_in: None,
+        risk: "They reorder how the following text is displayed without changing the order the \
+               compiler reads it in, so reviewers see different code than what runs.",
+        example: "Trojan Source (CVE-2021-42574): a RIGHT-TO-LEFT OVERRIDE inside a comment makes \
+                  `/* } if (isAdmin) { */` display as if the check were commented out, while the \
+                  compiler executes it.",
+        remediation: "Delete the character. If right-to-left text is genuinely needed in a string, \
+                      write it with an escape sequence so it is visible in review.",
+        cwe: &[451],
+        references: &[TROJAN_SOURCE, UAX9, "https://www.unicode.org/charts/PDF/U2000.pdf"],
+    },
+    Rule {
+        id: "ICD-BIDI002",
+        title: "Bidirectional isolates",
+        category: "bidi",
+        covers: "U+2066–U+2069",
+        sample: "\u{2066}\u{2069}",
+        severity: Severity::Error,
+        opt_in: None,
+        risk: "Like overrides, isolates change the displayed order of the text around them, and an \
+               unterminated isolate can swallow the rest of the line.",
+        example: "Trojan Source (CVE-2021-42574): `\"user<U+2067> <U+2066>// Check if admin<U+2069> \
+                  <U+2066>\"` hides the end of a string literal so code after it looks like part of \
+                  a comment.",
+        remediation: "Delete the character, or replace it with an escape sequence if the isolate is \
+                      intentional inside a string.",
+        cwe: &[451],
+        references: &[TROJAN_SOURCE, UAX9, "https://www.unicode.org/charts/PDF/U2000.pdf"],
+    },
+    Rule {
+        id: "ICD-BIDI004",
+        title: "Unterminated bidirectional controls",
+        category: "bidi",
+        covers: "Embeddings, overrides, and isolates (U+202A–U+202E, U+2066–U+2068) still open at \
+                 the end of a line",
+        sample: "\u{202E}",
+        severity: Severity::Error,
+        opt_in: None,
+        risk: "An embedding or override without its PDF, or an isolate without its PDI, keeps \
+               reordering everything after it on the line. That is the pattern that actually \
+               exploits CVE-2021-42574: a control opened inside a comment or string literal \
+               spills over the closing delimiter and rearranges the code that follows.",
+        example: "`/*<U+202E> } <U+2066>if (isAdmin)<U+2069> <U+2066> begin admins only */` leaves \
+                  the override open, so the `if` displays inside the comment while the compiler \
+                  sees it outside.",
+        remediation: "Delete the controls. If the text really needs them, close every one on the \
+                      same line (PDF U+202C for embeddings and overrides, PDI U+2069 for \
+                      isolates) and write them as escapes.",
+        cwe: &[451],
+        references: &[TROJAN_SOURCE, UAX9],
+    },
+    Rule {
+        id: "ICD-BIDI003",
+        title: "Directional marks",
+        category: "bidi",
+        covers: "U+200E, U+200F, U+061C",
+        sample: "\u{200F}",
+        severity: Severity::Error,
+        opt_in: None,
+        risk: "Invisible marks that change the direction of neighbouring neutral characters such \
+               as punctuation and operators, and make otherwise identical tokens distinct.",
+        example: "A RIGHT-TO-LEFT MARK after an identifier creates a second, visually identical \
+                  name that shadows or bypasses the first.",
+        remediation: "Delete the character outside of natural-language text.",
+        cwe: &[451],
+        references: &[UAX9, "https://www.unicode.org/charts/PDF/U2000.pdf"],
+    },
+    Rule {
+        id: "ICD-VS001",
+        title: "Variation selectors",
+        category: "variation-selector",
+        covers: "U+FE00–U+FE0F",
+        sample: "\u{FE01}",
+        severity: Severity::Error,
+        opt_in: None,
+        risk: "They are meant to pick a glyph variant for the preceding character, but most have \
+               no visible effect and can be appended to any character.",
+        example: "Glassworm-style smuggling encodes a payload as a run of variation selectors after \
+                  an ordinary character; a decoder in the same file reassembles and executes it.",
+        remediation: "Delete the character. U+FE0F after an emoji is usually harmless; check that \
+                      it is not part of a longer run.",
+        cwe: &[506, 451],
+        references: &[UTS55, "https://www.unicode.org/charts/PDF/UFE00.pdf"],
+    },
+    Rule {
+        id: "ICD-VS002",
+        title: "Supplementary variation selectors",
+        category: "variation-selector",
+        covers: "U+E0100–U+E01EF",
+        sample: "\u{E0100}",
+        severity: Severity::Error,
+        opt_in: None,
+        risk: "These 240 selectors only apply to a handful of ideographs, render as nothing \
+               everywhere else, and survive copy and paste, which makes them an ideal carrier for \
+               hidden bytes.",
+        example: "A prompt or source line followed by a run of supplementary selectors, one per \
+                  byte of a hidden instruction that an LLM or a decoder script reads but a human \
+                  reviewer never sees.",
+        remediation: "Delete the characters. Legitimate uses (ideographic variation sequences) \
+                      only follow CJK ideographs.",
+        cwe: &[506, 451],
+        references: &[UTS55, "https://www.unicode.org/charts/PDF/UE0100.pdf"],
+    },
+    Rule {
+        id: "ICD-TAG001",
+        title: "Tag characters",
+        category: "tag",
+        covers: "U+E0001, U+E0020–U+E007F",
+        sample: "\u{E0041}",
+        severity: Severity::Error,
+        opt_in: None,
+        risk: "Each tag character is an invisible copy of an ASCII character, so a run of them \
+               spells out text no human can see. Language models and some tools still read it.",
+        example: "ASCII smuggling: a README or code comment ends with tag characters spelling \
+                  \"ignore previous instructions and ...\", which an AI assistant processing the \
+                  file follows. The detection reports the decoded text.",
+        remediation: "Delete the characters. They are only legitimate inside emoji subdivision \
+                      flags (U+1F3F4 followed by tags ending in U+E007F).",
+        cwe: &[506, 451],
+        references: &[UTS55, "https://www.unicode.org/charts/PDF/UE0000.pdf"],
+    },
+    Rule {
+        id: "ICD-SEP001",
+        title: "Ambiguous line terminators",
+        category: "line-separator",
+        covers: "U+0085, U+2028, U+2029",
+        sample: "\u{2028}",
+        severity: Severity::Error,
+        opt_in: None,
+        risk: "Some languages and tools treat them as line breaks and others don't, so a line \
+               comment can end early or a statement can be split differently than it looks.",
+        example: "In JavaScript, `// comment<U+2028>alert(1)` runs `alert(1)` because the \
+                  separator terminates the comment, while most editors show a single line. \
+                  NEXT LINE is a line break to YAML 1.1 and some editors but not to most \
+                  compilers.",
+        remediation: "Replace the character with a normal newline, or with an escape inside a \
+                      string literal.",
+        cwe: &[451],
+        references: &[UTS55, "https://www.unicode.org/charts/PDF/U2000.pdf"],
+    },
+    Rule {
+        id: "ICD-DEPR001",
+        title: "Deprecated format characters",
+        category: "deprecated-format",
+        covers: "U+17B4, U+17B5, U+180E, U+206A–U+206F",
+        sample: "\u{206A}",
+        severity: Severity::Error,
+        opt_in: None,
+        risk: "Obsolete invisible controls. Unicode deprecated them, so nothing should produce \
+               them today, yet they are still accepted as format characters and render as \
+               nothing.",
+        example: "MONGOLIAN VOWEL SEPARATOR was a whitespace character until Unicode 6.3, so older \
+                  tools split on it while newer ones treat it as part of an identifier or token. \
+                  KHMER VOWEL INHERENT AQ and AA are combining marks that render as nothing, so \
+                  `admin<U+17B4>` is a letter-only identifier that looks like `admin`.",
+        remediation: "Delete the character.",
+        cwe: &[451],
+        references: &[
+            "https://www.unicode.org/charts/PDF/U2000.pdf",
+            "https://www.unicode.org/charts/PDF/U1800.pdf",
+            "https://www.unicode.org/charts/PDF/U1780.pdf",
+        ],
+    },
+    Rule {
+        id: "ICD-ANNOT001",
+        title: "Interlinear annotation characters",
+        category: "annotation",
+        covers: "U+FFF9–U+FFFB",
+        sample: "\u{FFF9}",
+        severity: Severity::Error,
+        opt_in: None,
+        risk: "They mark up annotation text (like ruby text) inside a string. Some tools hide \
+               the annotation, some show it inline, and some drop the markers, so the same bytes \
+               read differently depending on where they are viewed.",
+        example: "`<U+FFF9>rm -rf ~<U+FFFA>ls<U+FFFB>` can display as just `ls` in a tool that \
+                  renders annotations above the text, while the whole sequence is what gets \
+                  executed or compared.",
+        remediation: "Delete the characters and any annotation text between them.",
+        cwe: &[451],
+        references: &["https://www.unicode.org/charts/PDF/UFFF0.pdf"],
+    },
+    Rule {
+        id: "ICD-PCM001",
+        title: "Prepended concatenation marks",
+        category: "prepended-mark",
+        covers: "U+0600–U+0605, U+06DD, U+070F, U+0890, U+0891, U+08E2 (Arabic number signs, \
+                 END OF AYAH, SYRIAC ABBREVIATION MARK)",
+        sample: "\u{0600}",
+        severity: Severity::Warning,
+        opt_in: None,
+        risk: "These format characters are drawn as a sign spanning the digits or letters after \
+               them. Renderers without the right font or shaping show nothing at all, or a sign \
+               that covers what follows, so text that compares and parses one way displays \
+               another. Outside Arabic or Syriac text they have no reason to appear.",
+        example: "`amount = <U+0600>100` displays as `amount = 100` in many editors, but the \
+                  literal no longer parses as the number a reviewer sees, or a string check for \
+                  `100` no longer matches.",
+        remediation: "Delete the mark, unless the text is Arabic or Syriac that needs it (for \
+                      example Quranic verse numbers); there, add an override for the path.",
+        cwe: &[451],
+        references: &[
+            "https://www.unicode.org/charts/PDF/U0600.pdf",
+            "https://www.unicode.org/charts/PDF/U0700.pdf",
+        ],
+    },
+    Rule {
+        id: "ICD-WS001",
+        title: "Non-ASCII whitespace",
+        category: "whitespace",
+        covers: "U+00A0, U+1680, U+2000–U+200A, U+202F, U+205F, U+3000",
+        sample: "\u{00A0}",
+        severity: Severity::Error,
+        opt_in: None,
+        risk: "They look like an ordinary space but are not treated as one by tokenizers, \
+               validators, or naive filters, and parsers that don't accept them fail with \
+               confusing errors.",
+        example: "A NO-BREAK SPACE inside a shell command (`rm -rf<U+00A0>/tmp/x`) changes how the \
+                  arguments are split; in an identifier it creates a name that looks like two \
+                  tokens.",
+        remediation: "Replace the character with an ASCII space (U+0020).",
+        cwe: &[1007],
+        references: &[UTR36, "https://www.unicode.org/charts/PDF/U2000.pdf"],
+    },
+    Rule {
+        id: "ICD-BLANK001",
+        title: "Blank-rendering characters",
+        category: "blank",
+        covers: "U+00AD, U+2800",
+        sample: "\u{00AD}",
+        severity: Severity::Error,
+        opt_in: None,
+        risk: "They render as nothing or as empty space in most contexts but are real characters \
+               in identifiers and strings, and are not whitespace to trimming or validation.",
+        example: "A SOFT HYPHEN inside `ad<U+00AD>min` makes a username that displays as `admin` \
+                  but fails (or bypasses) an exact comparison against it; a string of BRAILLE \
+                  PATTERN BLANK passes a non-empty check while showing nothing in review.",
+        remediation: "Delete the character.",
+        cwe: &[1007],
+        references: &[
+            UTR36,
+            "https://www.unicode.org/charts/PDF/U0080.pdf",
+            "https://www.unicode.org/charts/PDF/U2800.pdf",
+        ],
+    },
+    Rule {
+        id: "ICD-FILL001",
+        title: "Hangul fillers",
+        category: "filler",
+        covers: "U+115F, U+1160, U+3164, U+FFA0",
+        sample: "\u{3164}",
+        severity: Severity::Error,
+        opt_in: None,
+        risk: "Fillers are letters (so they are valid in identifiers and pass non-empty checks) \
+               that render as blank space.",
+        example: "HANGUL FILLER is a valid identifier character in JavaScript, so \
+                  `const <U+3164> = require('child_process')` declares a variable whose name \
+                  looks empty; the same trick produces blank usernames.",
+        remediation: "Delete the character, or rename the identifier.",
+        cwe: &[1007],
+        references: &[UTR36, "https://www.unicode.org/charts/PDF/U3130.pdf"],
+    },
+    Rule {
+        id: "ICD-ENC001",
+        title: "Encoding artifacts",
+        category: "encoding",
+        covers: "U+FFFC, U+FFFD",
+        sample: "\u{FFFD}",
+        severity: Severity::Warning,
+        opt_in: None,
+        risk: "REPLACEMENT CHARACTER is what decoders substitute for bytes they could not read, \
+               so it almost always means the file went through a broken encoding round-trip and \
+               some of its original content is gone. OBJECT REPLACEMENT CHARACTER stands in for \
+               embedded content that is not shown.",
+        example: "A Windows-1252 file saved as UTF-8 by an editor that guessed wrong: every `é` \
+                  or smart quote becomes U+FFFD, silently changing string literals and test \
+                  fixtures.",
+        remediation: "Recover the original bytes from version control or the source of the text \
+                      and re-save it with the correct encoding; don't just delete the character.",
+        cwe: &[176],
+        references: &["https://www.unicode.org/charts/PDF/UFFF0.pdf"],
+    },
+    Rule {
+        id: "ICD-UTF8001",
+        title: "Invalid UTF-8",
+        category: "invalid-utf8",
+        covers: "Byte sequences that aren't valid UTF-8: overlong encodings, encoded surrogates \
+                 (CESU-8), sequences beyond U+10FFFF, truncated sequences, and stray bytes",
+        sample: "",
+        severity: Severity::Error,
+        opt_in: Some("--report-invalid-utf8"),
+        risk: "A filter that checks decoded text never sees bytes a strict decoder rejects, but a \
+               lenient decoder downstream may still turn them into characters. Overlong forms \
+               and CESU-8 surrogates are classic ways to smuggle a `/`, a quote, or a hidden \
+               character past validation. Scanners that skip undecodable files leave the same \
+               gap.",
+        example: "`C0 AF` is an overlong `/`: a path check on the UTF-8 text finds no `../`, while \
+                  a decoder that accepts overlong forms reads `..<C0 AF>` as `../`.",
+        remediation: "Find out what wrote the bytes. Re-encode the file as valid UTF-8, or delete \
+                      the sequence if it isn't meant to be there.",
+        cwe: &[172],
+        references: &[UTR36, "https://www.rfc-editor.org/rfc/rfc3629"],
+    },
+    Rule {
+        id: "ICD-ZALGO001",
+        title: "Combining mark floods",
+        category: "combining-marks",
+        covers: "Runs of --combining-threshold or more combining marks (default 5)",
+        sample: "e\u{0300}\u{0301}\u{0302}\u{0303}\u{0304}",
+        severity: Severity::Error,
+        opt_in: None,
+        risk: "Stacking many combining marks on one character (zalgo text) makes it spill over \
+               neighbouring lines, so it can cover up code in review, and breaks diff and \
+               rendering tools. Each mark is harmless on its own, so per-character checks miss it.",
+        example: "A comment line of zalgo text whose marks render on top of the line below, hiding \
+                  a changed condition from a reviewer looking at the diff.",
+        remediation: "Delete the marks. Real text rarely needs more than two or three on a single \
+                      letter; raise --combining-threshold for scripts that do.",
+        cwe: &[1007],
+        references: &[UTR36, "https://www.unicode.org/charts/PDF/U0300.pdf"],
+    },
+    Rule {
+        id: "ICD-NORM001",
+        title: "Text not in NFC",
+        category: "normalization",
+        covers: "Decomposed or misordered sequences that change under NFC",
+        sample: "e\u{0301}",
+        severity: Severity::Warning,
+        opt_in: Some("--check-normalization"),
+        risk: "The same visible text can be encoded precomposed (U+00E9) or decomposed (e + \
+               U+0301). Compilers compare code points, so two identifiers or keys that look \
+               identical are different names, while editors and humans treat them as the same.",
+        example: "A second `café` variable spelled with a combining accent that shadows the real \
+                  one in review but is a separate binding to the compiler.",
+        remediation: "Normalize the file to NFC (most editors and `uconv -x nfc` can do this) and \
+                      configure the editor to save NFC.",
+        cwe: &[1289],
+        references: &[UAX15, UTR36],
+    },
+    Rule {
+        id: "ICD-NORM002",
+        title: "Compatibility characters",
+        category: "normalization",
+        covers: "Characters that change under NFKC (ligatures, fullwidth and styled letters)",
+        sample: "\u{FB01}",
+        severity: Severity::Warning,
+        opt_in: Some("--check-normalization=nfkc"),
+        risk: "Compatibility characters such as the ligature U+FB01 or fullwidth letters look like \
+               ordinary text, but some languages fold them (Python identifiers are NFKC) and \
+               others don't, so the same name can refer to different things in different tools.",
+        example: "A Python function named with a U+FB01 ligature that the interpreter folds into \
+                  an existing `fi...` name, silently replacing it.",
+        remediation: "Replace the character with its NFKC form, which the finding shows.",
+        cwe: &[1289],
+        references: &[UAX15, UTR36],
+    },
+    Rule {
+        id: "ICD-MIX001",
+        title: "Mixed-script identifiers",
+        category: "mixed-script",
+        covers: "Letter/digit runs mixing scripts that no writing system combines (UTS #39)",
+        sample: "p\u{0430}ypal",
+        severity: Severity::Error,
+        opt_in: None,
+        risk: "A name that is mostly Latin but contains one Cyrillic or Greek lookalike reads as \
+               the familiar name while being a different identifier, domain, or key. Genuine text \
+               rarely mixes scripts inside one word, so this is a strong spoofing signal.",
+        example: "A function `p<U+0430>ypal_client` (Cyrillic a) defined next to the real \
+                  `paypal_client`, so call sites that look identical reach the attacker's code.",
+        remediation: "Retype the identifier in a single script. If the mix is intended, scan with \
+                      --no-mixed-scripts.",
+        cwe: &[1007],
+        references: &[UTS39, UTS55],
+    },
+    Rule {
+        id: "ICD-LOOK001",
+        title: "Lookalike identifiers",
+        category: "lookalike-identifier",
+        covers: "Identifiers that match another one in the same file once format characters, \
+                 variation selectors, and blank characters are stripped",
+        sample: "is_admin is_adm\u{200B}in",
+        severity: Severity::Error,
+        opt_in: Some("--lookalike-identifiers"),
+        risk: "Each invisible character is reported on its own, but the exploit is the pair: an \
+               identifier that renders exactly like one already in the file while being a \
+               different name, so a reviewer reading the code sees one variable where there are \
+               two.",
+        example: "`is_adm<U+200B>in = true` next to `if is_admin:` assigns a new variable; the \
+                  check still reads the real `is_admin`.",
+        remediation: "Delete the invisible characters so both spellings are the same identifier, \
+                      then check what the hidden one was used for.",
+        cwe: &[1007],
+        references: &[UTS39, UTS55],
+    },
+    Rule {
+        id: "ICD-CONF001",
+        title: "Confusable characters",
+        category: "confusable",
+        covers: "Characters whose UTS #39 skeleton is ASCII letters or digits",
+        sample: "\u{0430}",
+        severity: Severity::Warning,
+        opt_in: Some("--confusables"),
+        risk: "Letters from other scripts such as CYRILLIC SMALL LETTER A (U+0430) are drawn \
+               exactly like their Latin counterparts, so an identifier, URL, or string can look \
+               like a familiar one while naming something else entirely.",
+        example: "A dependency URL `https://gith<U+0443>b.com/...` with a Cyrillic u that points \
+                  to an attacker-controlled domain while reading as github.com.",
+        remediation: "Replace the character with the ASCII it imitates (shown in the finding), or \
+                      confirm the text is genuinely written in that script.",
+        cwe: &[1007],
+        references: &[UTS39],
+    },
+    Rule {
+        id: "ICD-SCRIPT001",
+        title: "Characters outside the allowed scripts",
+        category: "script",
+        covers: "Non-ASCII characters whose script is not in --allow-scripts or allow_scripts",
+        sample: "\u{0436}",
+        severity: Severity::Error,
+        opt_in: Some("--allow-scripts"),
+        risk: "A project that is written in a known set of scripts has no legitimate use for \
+               characters from any other one, so every such character is either a mistake or a \
+               lookalike smuggled into an identifier, string, or comment.",
+        example: "With `--allow-scripts Latin,Common`, a Cyrillic <U+0430> slipped into `admin` \
+                  is reported even though it looks exactly like a Latin a.",
+        remediation: "Replace the character, or add its script to the allowed list, for all files \
+                      or for the affected paths with an [[override]] in .icd.toml.",
+        cwe: &[1007],
+        references: &[UTS39],
+    },
+    Rule {
+        id: "ICD-ASCII001",
+        title: "Non-ASCII characters",
+        category: "non-ascii",
+        covers: "Any character above U+007F that no other rule covers",
+        sample: "\u{00E9}",
+        severity: Severity::Info,
+        opt_in: Some("--ascii-only"),
+        risk: "In a repository whose code and configuration are meant to be plain ASCII, any \
+               other character is out of policy: letters from other scripts can imitate ASCII \
+               ones, and typographic quotes or dashes pasted from documents break parsers.",
+        example: "A shell script with `<U+2013>force` (an EN DASH pasted from a wiki) passes no \
+                  `--force` flag; the command runs without it.",
+        remediation: "Replace the character with its ASCII equivalent, or write it as an escape \
+                      sequence.",
+        cwe: &[176],
+        references: &[UTR36],
+    },
+    Rule {
+        id: "ICD-FMT001",
+        title: "Other format characters",
+        category: "format",
+        covers: "Characters of general category Cf that no other rule covers",
+        sample: "\u{2062}",
+        severity: Severity::Warning,
+        opt_in: Some("--all-format-chars"),
+        risk: "Format characters affect layout or shaping without a glyph of their own. The rules \
+               above list the ones known to be abused; new Unicode versions and new techniques add \
+               others that render as nothing in an editor just the same.",
+        example: "`is<U+2062>Admin` with an INVISIBLE TIMES reads as `isAdmin` but is a different \
+                  identifier, one a later definition can shadow.",
+        remediation: "Delete the character, or write it as an escape sequence if the text needs it.",
+        cwe: &[451],
+        references: &[UTS55],
+    },
+    Rule {
+        id: "ICD-RANGE001",
+        title: "Code points in a range given on the command line",
+        category: "range",
+        covers: "Code points in a --detect-range range that no other rule covers",
+        sample: "\u{1D173}",
+        severity: Severity::Warning,
+        opt_in: Some("--detect-range"),
+        risk: "The built-in rules cover the characters known to be abused. An investigation into \
+               a new technique, or a specification that forbids a block of its own, needs to \
+               find other code points today, before a release adds them as a rule.",
+        example: "With `--detect-range U+1D173..U+1D17A`, the musical symbol beam and tie format \
+                  controls, which render as nothing outside music notation, are reported.",
+        remediation: "Delete the character, or write it as an escape sequence if the text needs it.",
+        cwe: &[176],
+        references: &[UTR36],
+    },
+    Rule {
+        id: "ICD-PUA001",
+        title: "Private Use Area characters",
+        category: "pua",
+        covers: "U+E000–U+F8FF, U+F0000–U+FFFFD, U+100000–U+10FFFD",
+        sample: "\u{E000}",
+        severity: Severity::Error,
+        opt_in: None,
+        risk: "Private use code points have no standard meaning or glyph. Outside icon fonts they \
+               are a common carrier for hidden data.",
+        example: "Glassworm: malware in published VS Code extensions hid its loader as private use \
+                  characters that editors render as nothing.",
+        remediation: "Delete the character. If an icon font needs it, use an escape sequence so \
+                      the code point is visible in review.",
+        cwe: &[506],
+        references: &["https://www.unicode.org/charts/PDF/UE000.pdf"],
+    },
+    Rule {
+        id: "ICD-CTRL001",
+        title: "Control characters",
+        category: "control",
+        covers: "U+0000–U+001F except TAB, LF, CR; U+007F–U+009F",
+        sample: "\u{001B}",
+        severity: Severity::Error,
+        opt_in: None,
+        risk: "Control characters are invisible in most editors and can truncate strings, change \
+               terminal output, or confuse parsers.",
+        example: "An ESC (U+001B) sequence in a source file can rewrite what `cat` or `git diff` \
+                  shows in a terminal, hiding the surrounding code.",
+        remediation: "Delete the character, or write it as an escape sequence.",
+        cwe: &[150],
+        references: &["https://www.unicode.org/charts/PDF/U0000.pdf"],
+    },
+    Rule {
+        id: "ICD-NAME001",
+        title: "Suspicious characters in file names",
+        category: "file-name",
+        covers: "Any character above in a file or directory name",
+        sample: "",
+        severity: Severity::Error,
+        opt_in: None,
+        risk: "File browsers, archive tools, and download dialogs display names with the same \
+               bidi reordering and invisible characters as any other text, so a name can show a \
+               different extension or imitate another file.",
+        example: "`invoice<U+202E>gpj.exe` is displayed as `invoiceexe.jpg`: a program that looks \
+                  like an image.",
+        remediation: "Rename the file or directory without the character (`git mv` in a \
+                      repository).",
+        cwe: &[451],
+        references: &[UTR36],
+    },
+    Rule {
+        id: "ICD-KEY001",
+        title: "Invisible characters in keys and variable names",
+        category: "key-name",
+        covers: "Any character above in a .env, INI, or YAML key, or a shell variable name",
+        sample: "",
+        severity: Severity::Error,
+        opt_in: None,
+        risk: "In a key, an invisible character doesn't just look odd: it makes a different \
+               name. The application reads the key it expects and finds it unset, a YAML \
+               mapping gains a sibling of the key it imitates, and a shell line that looks like \
+               an assignment runs as a command.",
+        example: "`PATH<U+00A0>=/opt/tools` in a shell script doesn't set PATH: the shell looks \
+                  for a command of that name. `DEBUG<U+200B>=false` in a `.env` file leaves \
+                  DEBUG at its default.",
+        remediation: "Retype the key or name in plain ASCII, and check that the value is the one \
+                      the program actually reads.",
+        cwe: &[176, 1289],
+        references: &[UTR36],
+    },
+    Rule {
+        id: "ICD-LONG001",
+        title: "Overlong lines",
+        category: "advisory",
+        covers: "Lines longer than the --warn-line-length limit, in bytes",
+        sample: "",
+        severity: Severity::Info,
+        opt_in: Some("--warn-line-length"),
+        risk: "Nobody reviews a line thousands of bytes long: diff views and editors cut it off \
+               or wrap it into a wall of text. Minified bundles and obfuscated droppers are \
+               written that way, and anything appended past the edge of the screen goes unseen.",
+        example: "A one-line `setup.js` of 40 000 bytes whose last few hundred decode and run a \
+                  second stage, out of sight in every diff.",
+        remediation: "Check what the line is. Replace generated code with its source, or exclude \
+                      the file from the scan if it is a vendored build output.",
+        cwe: &[506],
+        references: &[UTS55],
+    },
+    Rule {
+        id: "ICD-DENSE001",
+        title: "Files saturated with suspicious characters",
+        category: "advisory",
+        covers: "Files with at least the --warn-file-detections number of suspicious characters",
+        sample: "",
+        severity: Severity::Info,
+        opt_in: Some("--warn-file-detections"),
+        risk: "A handful of hidden characters is usually an accident; hundreds in one file are \
+               data. Payloads encoded as variation selectors, tag characters, or zero-width \
+               characters show up as a flood of itemized findings that is easy to dismiss as \
+               noise.",
+        example: "A line of JavaScript followed by thousands of variation selectors that decode \
+                  to a script, which the code then passes to `eval`.",
+        remediation: "Look at the file as a whole rather than at each finding: decode the \
+                      characters to see what they carry, and remove the file if it is a payload.",
+        cwe: &[506],
+        references: &[UTR36],
+    },
+];
+
+/// The rule a detected code point is reported under.
+///
+/// Every code point the scanner flags maps to exactly one rule.
+pub fn rule_for(code: u32) -> Option<&'static Rule> {
+    let id = match code {
+        0x200B | 0x200C | 0x200D | 0x2060 | 0xFEFF => "ICD-ZW001",
+        0x202A..=0x202E => "ICD-BIDI001",
+        0x2066..=0x2069 => "ICD-BIDI002",
+        0x200E | 0x200F | 0x061C => "ICD-BIDI003",
+        0xFE00..=0xFE0F => "ICD-VS001",
+        0xE0100..=0xE01EF => "ICD-VS002",
+        0xE0001 | 0xE0020..=0xE007F => "ICD-TAG001",
+        0x0085 | 0x2028 | 0x2029 => "ICD-SEP001",
+        0x17B4 | 0x17B5 | 0x180E | 0x206A..=0x206F => "ICD-DEPR001",
+        0xFFF9..=0xFFFB => "ICD-ANNOT001",
+        0x0600..=0x0605 | 0x06DD | 0x070F | 0x0890 | 0x0891 | 0x08E2 => "ICD-PCM001",
+        0xFFFC | 0xFFFD => "ICD-ENC001",
+        0x00A0 | 0x1680 | 0x2000..=0x200A | 0x202F | 0x205F | 0x3000 => "ICD-WS001",
+        0x00AD | 0x2800 => "ICD-BLANK001",
+        0x115F | 0x1160 | 0x3164 | 0xFFA0 => "ICD-FILL001",
+        _ if crate::is_private_use_area(code) => "ICD-PUA001",
+        _ if crate::is_suspicious_control_char(code) => "ICD-CTRL001",
+        _ => return None,
+    };
+    find(id)
+}
+
+/// The `category` of a detection of `code` under the rule `id`. A character in a file name
+/// or a key is categorized by what it is (`bidi` for U+202E in a name), not as `file-name`
+/// or `key-name`.
+pub fn category_of(id: &str, code: u32) -> String {
+    let rule = match find(id) {
+        Some(rule) if rule.id == "ICD-NAME001" || rule.id == "ICD-KEY001" => {
+            rule_for(code).unwrap_or(rule)
+        }
+        Some(rule) => rule,
+        None => return "other".to_string(),
+    };
+    rule.category.to_string()
+}
+
+/// The `severity` of a detection under the rule `id`.
+pub fn severity_of(id: &str) -> Severity {
+    find(id).map_or(Severity::Error, |rule| rule.severity)
+}
+
+/// Look up a rule by ID, ignoring case.
+pub fn find(id: &str) -> Option<&'static Rule> {
+    RULES.iter().find(|rule| rule.id.eq_ignore_ascii_case(id))
+}
@@ -0,0 +1,69 @@
+//! `generate-sample`: a canary file with one suspicious character per detection rule.
+//!
+//! Scanning the file should report every rule, which makes it easy to check that CI wiring,
+//! editor integrations, and baselines catch findings end to end.
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use crate::rules::RULES;
+use crate::{detect_invisible_characters, DetectOptions};
+
+/// Content of the sample file.
+pub fn sample_text() -> String {
+    let mut text = String::from(
+        "invisible-char-detector sample file\n\
+         \n\
+         Each line below holds one suspicious character (or sequence) between the\n\
+         brackets, labeled with the rule that should report it. Scanning this file must\n\
+         report every rule listed; if it doesn't, the scan is not wired up correctly.\n\
+         Rules that are off by default or only apply to file names are left out.\n\
+         \n",
+    );
+
+    for rule in RULES
+        .iter()
+        .filter(|rule| rule.opt_in.is_none() && !rule.sample.is_empty())
+    {
+        let code_points: Vec<String> = rule
+            .sample
+            .chars()
+            .map(|c| format!("U+{:04X}", c as u32))
+            .collect();
+        text.push_str(&format!(
+            "{} {} ({}): [{}]\n",
+            rule.id,
+            rule.title,
+            code_points.join(" "),
+            rule.sample
+        ));
+    }
+
+    text
+}
+
+/// Write the sample to `path`, refusing to replace an existing file unless `force` is set.
+/// Returns the number of detections the file should produce.
+pub fn write_sample(path: &Path, force: bool) -> Result<usize, String> {
+    let text = sample_text();
+    let count = detect_invisible_characters(&text, "", &DetectOptions::default()).len();
+    let mut options = fs::OpenOptions::new();
+    options.write(true);
+    if force {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+
+    let mut file = options.open(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::AlreadyExists => {
+            format!("{} already exists (use --force to overwrite)", path.display())
+        }
+        _ => format!("could not create {}: {}", path.display(), e),
+    })?;
+    file.write_all(text.as_bytes())
+        .map_err(|e| format!("could not write {}: {}", path.display(), e))?;
+
+    Ok(count)
+}
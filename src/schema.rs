@@ -0,0 +1,365 @@
+//! `schema`: the JSON Schema of the `--json` output, for validating reports and generating
+//! types in other languages.
+//!
+//! Written by hand next to the types it describes; a field added to [`crate::Detection`] or
+//! the version 2 report needs a line here too.
+
+use serde_json::{json, Value};
+
+use crate::report;
+
+/// The JSON Schema dialect the schemas are written in.
+const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Definitions shared by both layouts.
+fn defs() -> Value {
+    let count =
+        |description: &str| json!({"type": "integer", "minimum": 0, "description": description});
+    let position =
+        |description: &str| json!({"type": "integer", "minimum": 1, "description": description});
+    let fix = json!({
+        "type": "object",
+        "description": "The edit a clean copy makes: replace the bytes start..end (0-indexed, end exclusive) with replacement",
+        "required": ["start", "end", "replacement"],
+        "properties": {
+            "start": count("First byte to replace"),
+            "end": count("Byte just past the last to replace"),
+            "replacement": {"type": "string", "description": "Empty to delete the character"}
+        },
+        "additionalProperties": false
+    });
+    json!({
+        "severity": {
+            "enum": ["error", "warning", "info"],
+            "description": "How serious the finding is; `info` findings don't fail the scan"
+        },
+        "detection": {
+            "type": "object",
+            "description": "One suspicious code point occurrence",
+            "required": [
+                "file", "line", "byte_offset", "byte_start", "byte_end", "char_index", "utf16_column",
+                "char", "code",
+                "name", "general_category", "description", "rule", "category", "severity",
+                "fingerprint"
+            ],
+            "properties": {
+                "file": {"type": "string", "description": "File path"},
+                "line": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "1-indexed line, or 0 for a character in the file name"
+                },
+                "byte_offset": position("1-indexed byte offset from the start of the file (or name)"),
+                "byte_start": count("0-indexed first byte of what was found, in the file's own bytes (or the name's)"),
+                "byte_end": count("Byte just past what was found: the whole character, run, escape sequence, or identifier"),
+                "char_index": position("1-indexed character index within the line (or name)"),
+                "utf16_column": position("1-indexed column in UTF-16 code units"),
+                "char": {
+                    "type": "string",
+                    "description": "The character, or the escape sequence as written"
+                },
+                "code": {"type": "integer", "minimum": 0, "maximum": 1114111},
+                "name": {"type": "string", "description": "Unicode name, alias, or label"},
+                "general_category": {"type": "string", "description": "e.g. `Cf`, `Zs`"},
+                "description": {"type": "string"},
+                "rule": {"type": "string", "description": "Rule ID, e.g. `ICD-BIDI001`"},
+                "category": {"type": "string", "description": "e.g. `zero-width`, `bidi`"},
+                "severity": {"$ref": "#/$defs/severity"},
+                "escaped": {"const": true, "description": "Written as an escape sequence"},
+                "structure_path": {
+                    "type": "string",
+                    "description": "JSON Pointer or YAML path of the value holding the character"
+                },
+                "context": {"enum": ["string", "comment", "identifier", "code"]},
+                "snippet": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["line", "text"],
+                        "properties": {
+                            "line": position("1-indexed line"),
+                            "text": {"type": "string"},
+                            "column": position("Where the character starts, on its own line")
+                        },
+                        "additionalProperties": false
+                    }
+                },
+                "hex_context": {
+                    "type": "object",
+                    "required": ["offset", "hex"],
+                    "properties": {
+                        "offset": count("0-indexed file offset of the first byte"),
+                        "hex": {"type": "string", "pattern": "^([0-9a-f]{2})*$"}
+                    },
+                    "additionalProperties": false
+                },
+                "run": {
+                    "type": "object",
+                    "description": "The character repeats back to back; this detection stands for all of them",
+                    "required": ["count", "end_byte_offset", "end_char_index"],
+                    "properties": {
+                        "count": {"type": "integer", "minimum": 2},
+                        "end_byte_offset": position("Byte offset of the last character"),
+                        "end_char_index": position("Character index of the last character")
+                    },
+                    "additionalProperties": false
+                },
+                "more_in_file": count("Detections left out of this file after this one"),
+                "blame": {
+                    "type": "object",
+                    "required": ["commit", "author", "date"],
+                    "properties": {
+                        "commit": {"type": "string"},
+                        "author": {"type": "string", "description": "`Name <email>`"},
+                        "date": {"type": "string", "description": "ISO 8601"}
+                    },
+                    "additionalProperties": false
+                },
+                "encoding": {"enum": ["utf-16le", "utf-16be", "windows-1252"]},
+                "invalid_bytes": {
+                    "type": "string",
+                    "description": "The invalid UTF-8 sequence in hex, e.g. `C0 AF`"
+                },
+                "also_at": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "Other paths of the same file, which were not scanned again"
+                },
+                "file_risk": count("Risk score of the detection's file; the higher, the sooner to review it"),
+                "fix": fix,
+                "root": {"type": "string", "description": "The --root the file was found under"},
+                "suppressed": {
+                    "type": "string",
+                    "description": "With --show-suppressed, in the suppressed list: the setting that dropped the detection and why"
+                },
+                "fingerprint": {
+                    "type": "string",
+                    "pattern": "^[0-9a-f]{16}$",
+                    "description": "Identity of the finding across runs: a hash of the rule, path, code point, and the characters around it, not of its line"
+                }
+            },
+            "additionalProperties": false
+        },
+        "count": {
+            "type": "object",
+            "required": ["key", "count"],
+            "properties": {
+                "key": {"type": "string"},
+                "count": count("Characters"),
+                "files": count("Distinct files")
+            },
+            "additionalProperties": false
+        },
+        "summary": {
+            "type": "object",
+            "required": [
+                "total", "files", "scanned_files", "by_code_point", "by_rule", "by_category",
+                "by_severity", "by_extension", "top_files", "duration_ms"
+            ],
+            "properties": {
+                "total": count("Suspicious characters found"),
+                "files": count("Files with at least one detection"),
+                "scanned_files": count("Files read and scanned"),
+                "by_code_point": {"type": "array", "items": {"$ref": "#/$defs/count"}},
+                "by_rule": {"type": "array", "items": {"$ref": "#/$defs/count"}},
+                "by_category": {"type": "array", "items": {"$ref": "#/$defs/count"}},
+                "by_severity": {"type": "array", "items": {"$ref": "#/$defs/count"}},
+                "by_extension": {"type": "array", "items": {"$ref": "#/$defs/count"}},
+                "top_files": {"type": "array", "items": {"$ref": "#/$defs/count"}},
+                "duration_ms": count("Wall-clock time of the scan")
+            },
+            "additionalProperties": false
+        },
+        "timings": {
+            "type": "object",
+            "required": ["wall_ms", "files", "bytes", "bytes_per_second", "slowest"],
+            "properties": {
+                "wall_ms": count("Wall-clock time of the scan"),
+                "files": count("Files scanned"),
+                "bytes": count("Bytes read and scanned"),
+                "bytes_per_second": count("Throughput over the wall-clock time"),
+                "slowest": {
+                    "type": "array",
+                    "description": "The slowest files, slowest first",
+                    "items": {
+                        "type": "object",
+                        "required": ["path", "duration_us", "bytes"],
+                        "properties": {
+                            "path": {"type": "string"},
+                            "duration_us": count("Time to read and scan the file, in microseconds"),
+                            "bytes": count("Bytes read and scanned")
+                        },
+                        "additionalProperties": false
+                    }
+                }
+            },
+            "additionalProperties": false
+        },
+        "files": {
+            "type": "array",
+            "description": "With --file-info: the encoding and line endings of each scanned file",
+            "items": {
+                "type": "object",
+                "required": ["path", "encoding", "line_endings", "lf", "crlf", "cr"],
+                "properties": {
+                    "path": {"type": "string"},
+                    "encoding": {"enum": ["ascii", "utf-8", "utf-16le", "utf-16be", "other"]},
+                    "bom": {"enum": ["utf-8", "utf-16le", "utf-16be"]},
+                    "line_endings": {"enum": ["lf", "crlf", "cr", "mixed", "none"]},
+                    "lf": count("LF line breaks"),
+                    "crlf": count("CR LF line breaks"),
+                    "cr": count("Lone CR line breaks")
+                },
+                "additionalProperties": false
+            }
+        },
+        "suppressed": {
+            "type": "array",
+            "description": "With --show-suppressed: the detections settings dropped, not counted in the findings",
+            "items": {"$ref": "#/$defs/detection"}
+        },
+        "roots": {
+            "type": "array",
+            "description": "With --root: the result of each root, in the order given",
+            "items": {
+                "type": "object",
+                "required": ["root", "scanned", "findings", "failed"],
+                "properties": {
+                    "root": {"type": "string"},
+                    "scanned": count("Files read and scanned under the root"),
+                    "findings": count("Suspicious characters found under the root"),
+                    "failed": {"type": "boolean", "description": "True if a finding under the root fails the scan"}
+                },
+                "additionalProperties": false
+            }
+        }
+    })
+}
+
+/// Version 1: an array of detections; with `--summary`, `--timings`, `--file-info`,
+/// `--show-suppressed`, or `--root`, `{"detections", "summary", "timings", "files",
+/// "suppressed", "roots"}`; from `stats`, the summary alone.
+fn version_1() -> Value {
+    json!({
+        "$schema": DIALECT,
+        "title": "invisible-char-detector report (--json-version 1)",
+        "oneOf": [
+            {"type": "array", "items": {"$ref": "#/$defs/detection"}},
+            {
+                "type": "object",
+                "required": ["detections"],
+                "properties": {
+                    "detections": {"type": "array", "items": {"$ref": "#/$defs/detection"}},
+                    "summary": {"$ref": "#/$defs/summary"},
+                    "timings": {"$ref": "#/$defs/timings"},
+                    "files": {"$ref": "#/$defs/files"},
+                    "suppressed": {"$ref": "#/$defs/suppressed"},
+                    "roots": {"$ref": "#/$defs/roots"}
+                },
+                "additionalProperties": false
+            },
+            {"$ref": "#/$defs/summary"}
+        ],
+        "$defs": defs()
+    })
+}
+
+/// Version 2: the object written by [`report::Report`].
+fn version_2() -> Value {
+    let count = json!({"type": "integer", "minimum": 0});
+    json!({
+        "$schema": DIALECT,
+        "title": "invisible-char-detector report (--json-version 2)",
+        "type": "object",
+        "required": ["schema_version", "tool", "config", "counts", "duration_ms", "skipped"],
+        "properties": {
+            "schema_version": {"const": report::SCHEMA_VERSION},
+            "tool": {
+                "type": "object",
+                "required": ["name", "version"],
+                "properties": {
+                    "name": {"type": "string"},
+                    "version": {"type": "string"}
+                }
+            },
+            "config": {
+                "type": "object",
+                "description": "The settings the scan used, after merging in the config file; new settings are added over time"
+            },
+            "counts": {
+                "type": "object",
+                "required": [
+                    "scanned", "skipped", "binary", "binary_by_class", "cached", "findings"
+                ],
+                "properties": {
+                    "scanned": count,
+                    "skipped": count,
+                    "binary": count,
+                    "binary_by_class": {"type": "object", "additionalProperties": count},
+                    "cached": count,
+                    "findings": {
+                        "type": "object",
+                        "description": "Suspicious characters found, by severity",
+                        "required": ["error", "warning", "info"],
+                        "properties": {"error": count, "warning": count, "info": count},
+                        "additionalProperties": false
+                    },
+                    "roots": {"$ref": "#/$defs/roots"}
+                }
+            },
+            "duration_ms": count,
+            "detections": {
+                "type": "array",
+                "description": "Left out by `stats` and `--summary-only`",
+                "items": {"$ref": "#/$defs/detection"}
+            },
+            "skipped": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["path", "reason"],
+                    "properties": {
+                        "path": {"type": "string"},
+                        "reason": {"enum": ["ignored", "symlink", "too-large", "binary", "unreadable"]},
+                        "detail": {"type": "string"},
+                        "class": {
+                            "enum": [
+                                "image", "archive", "executable", "document", "media", "font",
+                                "database", "other"
+                            ]
+                        }
+                    },
+                    "additionalProperties": false
+                }
+            },
+            "summary": {"$ref": "#/$defs/summary"},
+            "timings": {"$ref": "#/$defs/timings"},
+            "files": {"$ref": "#/$defs/files"},
+            "suppressed": {"$ref": "#/$defs/suppressed"},
+            "rules": {
+                "type": "object",
+                "description": "The rules that fired, by ID",
+                "additionalProperties": {
+                    "type": "object",
+                    "required": ["title", "cwe", "references"],
+                    "properties": {
+                        "title": {"type": "string"},
+                        "cwe": {"type": "array", "items": {"type": "string", "pattern": "^CWE-[0-9]+$"}},
+                        "references": {"type": "array", "items": {"type": "string"}}
+                    },
+                    "additionalProperties": false
+                }
+            }
+        },
+        "$defs": defs()
+    })
+}
+
+/// The schema of `--json-version` `version` output.
+pub fn schema(version: u32) -> Value {
+    if version >= report::SCHEMA_VERSION {
+        version_2()
+    } else {
+        version_1()
+    }
+}
@@ -0,0 +1,79 @@
+//! Unicode script analysis: mixed-script identifiers (UTS #39).
+//!
+//! Identifier-like tokens (runs of letters, digits, marks, and `_`) are checked with the UTS #39
+//! restriction levels. Tokens a real writing system produces, such as Latin mixed with Han and
+//! Kana, pass; Latin mixed with Cyrillic or Greek, the classic homoglyph spoof, does not.
+
+use unicode_properties::{GeneralCategoryGroup, UnicodeGeneralCategory};
+use unicode_script::{Script, UnicodeScript};
+use unicode_security::{MixedScript, RestrictionLevel, RestrictionLevelDetection};
+
+/// Full name of the script `ch` belongs to (`Cyrillic`, `Greek`, `Common`, ...).
+pub fn script_name(ch: char) -> &'static str {
+    ch.script().full_name()
+}
+
+/// True for characters that can be part of an identifier-like token.
+pub fn is_identifier_char(ch: char) -> bool {
+    ch == '_' || ch.is_alphanumeric() || ch.general_category_group() == GeneralCategoryGroup::Mark
+}
+
+/// True if `token` mixes scripts in a way no single writing system does.
+pub fn is_mixed_script(token: &str) -> bool {
+    if token.is_ascii() || token.is_single_script() {
+        return false;
+    }
+    token.detect_restriction_level() > RestrictionLevel::ModeratelyRestrictive
+}
+
+/// Scripts used in `token`, in order of first appearance, ignoring Common and Inherited.
+pub fn scripts_in(token: &str) -> Vec<&'static str> {
+    let mut names = Vec::new();
+    for ch in token.chars() {
+        let script = ch.script();
+        if matches!(script, Script::Common | Script::Inherited | Script::Unknown) {
+            continue;
+        }
+        if !names.contains(&script.full_name()) {
+            names.push(script.full_name());
+        }
+    }
+    names
+}
+
+/// The first character that is not in the script most of `token` is written in, ignoring
+/// Common and Inherited characters.
+pub fn first_foreign_char(token: &str) -> Option<char> {
+    let letters = || {
+        token
+            .chars()
+            .filter(|c| !matches!(c.script(), Script::Common | Script::Inherited | Script::Unknown))
+    };
+    let majority = scripts_in(token)
+        .into_iter()
+        .max_by_key(|&name| letters().filter(|c| c.script().full_name() == name).count())?;
+    letters().find(|c| c.script().full_name() != majority)
+}
+
+/// Parse a script name such as `Latin`, `latin`, or the ISO 15924 code `Latn`.
+pub fn parse_script(name: &str) -> Result<Script, String> {
+    let name = name.trim();
+    let mut capitalized: String = name.to_ascii_lowercase();
+    if let Some(first) = capitalized.get_mut(..1) {
+        first.make_ascii_uppercase();
+    }
+
+    Script::from_full_name(name)
+        .or_else(|| Script::from_short_name(name))
+        .or_else(|| Script::from_full_name(&capitalized))
+        .or_else(|| Script::from_short_name(&capitalized))
+        .ok_or_else(|| format!("unknown script: {}", name))
+}
+
+/// True if `ch` belongs to one of `allowed`. ASCII is always allowed, and characters used by
+/// several scripts (Script_Extensions) are allowed if any of them is.
+pub fn is_allowed(ch: char, allowed: &[Script]) -> bool {
+    ch.is_ascii()
+        || allowed.contains(&ch.script())
+        || ch.script_extension().iter().any(|s| allowed.contains(&s))
+}
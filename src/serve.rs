@@ -0,0 +1,172 @@
+//! `serve`: a small HTTP API for scanning text without spawning a process per request.
+//!
+//! `POST /scan` takes the raw request body (text or file bytes) and answers with the JSON
+//! detections; `?name=` sets the file name reported in them. `GET /health` is a liveness check.
+//! Requests are handled by a fixed pool of threads sharing one listener.
+
+use std::io::Read;
+use std::sync::Arc;
+
+use serde::Serialize;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::logging;
+use crate::{decode_and_scan, sniff_binary_bytes, DetectOptions, Detection};
+
+/// Settings for `serve`.
+#[derive(Debug)]
+pub struct ServeConfig {
+    /// Address to listen on, e.g. `127.0.0.1:8080`.
+    pub addr: String,
+
+    /// Largest request body accepted, in bytes.
+    pub max_body_size: u64,
+
+    /// Number of request-handling threads.
+    pub threads: usize,
+}
+
+#[derive(Serialize)]
+struct ScanResponse<'a> {
+    name: &'a str,
+    clean: bool,
+    detections: Vec<Detection>,
+}
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+fn json_response<T: Serialize>(status: u16, body: &T) -> Response<std::io::Cursor<Vec<u8>>> {
+    let json = serde_json::to_vec(body).unwrap_or_default();
+    let header =
+        Header::from_bytes("Content-Type", "application/json").expect("static header is valid");
+    Response::from_data(json)
+        .with_status_code(status)
+        .with_header(header)
+}
+
+fn error_response(status: u16, message: impl Into<String>) -> Response<std::io::Cursor<Vec<u8>>> {
+    json_response(
+        status,
+        &ErrorResponse {
+            error: message.into(),
+        },
+    )
+}
+
+/// Value of query parameter `key` in `url`, with `+` and `%XX` escapes decoded.
+fn query_param(url: &str, key: &str) -> Option<String> {
+    let (_, query) = url.split_once('?')?;
+    query.split('&').find_map(|pair| {
+        let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+        (k == key).then(|| percent_decode(v))
+    })
+}
+
+fn percent_decode(value: &str) -> String {
+    let hex = |b: u8| (b as char).to_digit(16).map(|d| d as u8);
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => match (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                (Some(hi), Some(lo)) => {
+                    out.push(hi << 4 | lo);
+                    i += 2;
+                }
+                _ => out.push(b'%'),
+            },
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn handle_scan(request: &mut Request, max_body_size: u64) -> Response<std::io::Cursor<Vec<u8>>> {
+    let name = query_param(request.url(), "name").unwrap_or_else(|| "<request>".to_string());
+
+    if request
+        .body_length()
+        .is_some_and(|len| len as u64 > max_body_size)
+    {
+        return error_response(413, format!("body exceeds {} bytes", max_body_size));
+    }
+
+    // Read one byte past the limit so chunked bodies can't slip through.
+    let mut body = Vec::new();
+    if let Err(e) = request
+        .as_reader()
+        .take(max_body_size + 1)
+        .read_to_end(&mut body)
+    {
+        return error_response(400, format!("could not read body: {}", e));
+    }
+    if body.len() as u64 > max_body_size {
+        return error_response(413, format!("body exceeds {} bytes", max_body_size));
+    }
+
+    if let Some(kind) = sniff_binary_bytes(&body) {
+        return error_response(422, format!("binary content ({})", kind.label));
+    }
+    match decode_and_scan(body, &name, &DetectOptions::default()) {
+        Ok(detections) => json_response(
+            200,
+            &ScanResponse {
+                name: &name,
+                clean: detections.is_empty(),
+                detections,
+            },
+        ),
+        Err(e) => error_response(422, e.to_string()),
+    }
+}
+
+fn handle(mut request: Request, max_body_size: u64) {
+    let path = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let response = match (request.method(), path.as_str()) {
+        (Method::Post, "/scan") => handle_scan(&mut request, max_body_size),
+        (Method::Get, "/health") => json_response(
+            200,
+            &serde_json::json!({ "status": "ok", "version": env!("CARGO_PKG_VERSION") }),
+        ),
+        (_, "/scan") | (_, "/health") => error_response(405, "method not allowed"),
+        _ => error_response(404, "not found"),
+    };
+    // The client may have gone away; nothing useful to do about it.
+    let _ = request.respond(response);
+}
+
+/// Listen on `config.addr` and serve requests until the process is killed.
+pub fn serve(config: &ServeConfig) -> Result<(), String> {
+    let server = Server::http(&config.addr)
+        .map_err(|e| format!("could not listen on {}: {}", config.addr, e))?;
+    let server = Arc::new(server);
+    logging::info!("Listening on http://{}", config.addr);
+
+    let workers: Vec<_> = (0..config.threads.max(1))
+        .map(|_| {
+            let server = Arc::clone(&server);
+            let max_body_size = config.max_body_size;
+            std::thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    handle(request, max_body_size);
+                }
+            })
+        })
+        .collect();
+
+    for worker in workers {
+        let _ = worker.join();
+    }
+    Ok(())
+}
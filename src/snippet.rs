@@ -0,0 +1,91 @@
+//! `--show-context`: the source lines around each detection, so a finding can be triaged
+//! without opening the file and counting bytes.
+//!
+//! Each detection gets its line and the lines on either side, with every suspicious character
+//! spelled `<U+XXXX>` (or as `--escape-style` says); the marked line says at which column the
+//! detected character starts.
+
+use serde::{Deserialize, Serialize};
+
+use crate::render::{self, EscapeStyle, TextStyle};
+use crate::{strip_line_terminator, visualize_as, Detection};
+
+/// Lines shown on each side of the detection's line.
+const SNIPPET_RADIUS: usize = 1;
+
+/// One line of a snippet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnippetLine {
+    /// 1-indexed line number.
+    pub line: usize,
+
+    /// The line, with suspicious characters spelled `<U+XXXX>` (or per `--escape-style`) and no
+    /// line terminator.
+    pub text: String,
+
+    /// On the detection's own line, the 1-indexed column within `text` where the detected
+    /// character (or its `<U+XXXX>` marker) starts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+}
+
+/// Set `snippet` on every detection in the content of a file (not on those in its name).
+/// `starts` holds the byte offset of each line, from [`crate::line_starts`].
+pub fn attach(content: &str, starts: &[usize], style: EscapeStyle, detections: &mut [Detection]) {
+    // A final line terminator ends the last line rather than starting an empty one
+    let lines = match starts.last() {
+        Some(&last) if last == content.len() && starts.len() > 1 => starts.len() - 1,
+        _ => starts.len(),
+    };
+    let line_text = |line: usize| {
+        let end = starts.get(line).copied().unwrap_or(content.len());
+        strip_line_terminator(&content[starts[line - 1]..end])
+    };
+
+    for d in detections.iter_mut() {
+        if d.line == 0 || d.line > lines {
+            continue;
+        }
+        let first = d.line.saturating_sub(SNIPPET_RADIUS).max(1);
+        let last = (d.line + SNIPPET_RADIUS).min(lines);
+        let lines = (first..=last)
+            .map(|line| {
+                let column = (line == d.line).then(|| {
+                    let start = starts[line - 1];
+                    let offset = (d.byte_offset - 1).clamp(start, content.len());
+                    content
+                        .get(start..offset)
+                        .map_or(0, |before| visualize_as(before, style).chars().count())
+                        + 1
+                });
+                SnippetLine {
+                    line,
+                    text: visualize_as(line_text(line), style),
+                    column,
+                }
+            })
+            .collect();
+        d.snippet = Some(lines);
+    }
+}
+
+/// Format a snippet for text output, with a caret under the detected character.
+pub fn format_snippet(lines: &[SnippetLine], style: TextStyle) -> String {
+    let width = lines.last().map_or(1, |l| l.line.to_string().len());
+    let mut output = String::new();
+    for l in lines {
+        let gutter = style.paint(&format!("{:>width$} |", l.line), render::DIM);
+        let row = format!("      {} {}", gutter, l.text);
+        output.push_str(row.trim_end_matches(' '));
+        output.push('\n');
+        if let Some(column) = l.column {
+            output.push_str(&format!(
+                "      {} {}{}\n",
+                style.paint(&format!("{:>width$} |", ""), render::DIM),
+                " ".repeat(column - 1),
+                style.paint("^", render::RED)
+            ));
+        }
+    }
+    output
+}
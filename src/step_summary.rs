@@ -0,0 +1,111 @@
+//! `--step-summary`: the findings as Markdown on the job summary page of a GitHub Actions run,
+//! so reviewers see them without digging through the log.
+//!
+//! The report is appended to the file named by `$GITHUB_STEP_SUMMARY`, next to the normal
+//! output: the counts, a table per code point, and a table of the detections. GitHub caps a
+//! step summary at 1 MiB, so past [`MAX_ROWS`] detections the rest are only counted.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use crate::rules;
+use crate::summary::Summary;
+use crate::{visualize, Detection};
+
+/// The variable GitHub Actions sets to the summary file of the current step.
+pub const ENV: &str = "GITHUB_STEP_SUMMARY";
+
+/// Detections listed in the table.
+const MAX_ROWS: usize = 500;
+
+/// `text` as a code span that is safe in a table cell.
+fn code(text: &str) -> String {
+    let text = text.replace(['\r', '\n'], " ").replace('|', "\\|");
+    // The fence must be longer than any run of backticks inside
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest + 1);
+    format!("{} {} {}", fence, text, fence)
+}
+
+/// The Markdown report of a scan of `pattern`.
+pub fn format_step_summary(pattern: &str, summary: &Summary, detections: &[Detection]) -> String {
+    let mut output = format!("### Invisible character scan of {}\n\n", code(pattern));
+    if summary.total == 0 {
+        output.push_str(&format!(
+            ":white_check_mark: No suspicious invisible characters in {} scanned file(s).\n",
+            summary.scanned_files
+        ));
+        return output;
+    }
+    output.push_str(&format!(
+        ":x: Found **{}** suspicious character(s) in **{}** of {} scanned file(s).\n\n",
+        summary.total, summary.files, summary.scanned_files
+    ));
+
+    output.push_str("| Character | Count | Files |\n| --- | ---: | ---: |\n");
+    for count in &summary.by_code_point {
+        output.push_str(&format!(
+            "| {} | {} | {} |\n",
+            count.key,
+            count.count,
+            count.files.unwrap_or(0)
+        ));
+    }
+
+    if detections.is_empty() {
+        return output;
+    }
+    output.push_str("\n| File | Line | Column | Character | Rule | Severity | CWE |\n");
+    output.push_str("| --- | ---: | ---: | --- | --- | --- | --- |\n");
+    for d in detections.iter().take(MAX_ROWS) {
+        // A character in a name has no line in the file
+        let line = if d.line == 0 {
+            "name".to_string()
+        } else {
+            d.line.to_string()
+        };
+        let cwe: Vec<String> = rules::find(&d.rule)
+            .map_or(&[][..], |rule| rule.cwe)
+            .iter()
+            .map(|cwe| {
+                format!(
+                    "[CWE-{0}](https://cwe.mitre.org/data/definitions/{0}.html)",
+                    cwe
+                )
+            })
+            .collect();
+        output.push_str(&format!(
+            "| {} | {} | {} | U+{:04X} {} | {} | {} | {} |\n",
+            code(&visualize(&d.file)),
+            line,
+            d.char_index,
+            d.code,
+            d.name,
+            d.rule,
+            d.severity.as_str(),
+            cwe.join(", ")
+        ));
+    }
+    if detections.len() > MAX_ROWS {
+        output.push_str(&format!(
+            "\n... and {} more detection(s), see the log.\n",
+            detections.len() - MAX_ROWS
+        ));
+    }
+    output
+}
+
+/// Append `markdown` to the step summary file. False when not running under GitHub Actions,
+/// so a setting shared with local runs does no harm.
+pub fn append(markdown: &str) -> Result<bool, String> {
+    let Some(path) = std::env::var_os(ENV).filter(|path| !path.is_empty()) else {
+        return Ok(false);
+    };
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("{}: {}", path.to_string_lossy(), e))?;
+    writeln!(file, "{}", markdown).map_err(|e| format!("{}: {}", path.to_string_lossy(), e))?;
+    Ok(true)
+}
@@ -0,0 +1,314 @@
+//! Structural locations in JSON and YAML files.
+//!
+//! In a minified one-line `package-lock.json`, a line and column say little; the key whose
+//! value holds the character says a lot. Detections in `.json` files get a JSON Pointer
+//! (`/dependencies/lodash/resolved`) and detections in `.yaml`/`.yml` files a dotted path
+//! (`jobs.build.steps[2].run`).
+//!
+//! Neither format is fully parsed: the JSON walk tolerates comments and trailing garbage, and
+//! the YAML walk follows block indentation, which covers configuration files in practice.
+
+use crate::{strip_line_terminator, Detection};
+
+/// Set `structure_path` on `detections` if `file_path` is a JSON or YAML file. `starts` holds
+/// the byte offset of each line, from [`crate::line_starts`].
+pub fn annotate(file_path: &str, content: &str, starts: &[usize], detections: &mut [Detection]) {
+    match extension(file_path).as_deref() {
+        Some("json") => annotate_json(content, detections),
+        Some("yaml" | "yml") => annotate_yaml(content, starts, detections),
+        _ => {}
+    }
+}
+
+/// Whether [`annotate`] gives detections in `file_path` a structural location.
+pub fn applies(file_path: &str) -> bool {
+    matches!(extension(file_path).as_deref(), Some("json" | "yaml" | "yml"))
+}
+
+/// Lowercased extension of `file_path`.
+fn extension(file_path: &str) -> Option<String> {
+    file_path
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+}
+
+/// One open JSON object or array.
+enum JsonFrame {
+    /// The member currently being read (`None` before its key is complete), and whether the
+    /// next string is a key.
+    Object {
+        key: Option<String>,
+        expect_key: bool,
+    },
+
+    /// Index of the element currently being read, `None` before the first one.
+    Array { index: Option<usize> },
+}
+
+/// JSON Pointer (RFC 6901) of the value being read.
+fn json_pointer(stack: &[JsonFrame]) -> String {
+    let mut pointer = String::new();
+    for frame in stack {
+        match frame {
+            JsonFrame::Object { key: Some(key), .. } => {
+                pointer.push('/');
+                pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+            }
+            JsonFrame::Array { index: Some(index) } => {
+                pointer.push_str(&format!("/{}", index));
+            }
+            _ => break,
+        }
+    }
+    pointer
+}
+
+/// A JSON value starts: advance the index of the enclosing array.
+fn start_json_value(stack: &mut [JsonFrame]) {
+    if let Some(JsonFrame::Array { index }) = stack.last_mut() {
+        *index = Some(index.map_or(0, |i| i + 1));
+    }
+}
+
+fn annotate_json(content: &str, detections: &mut [Detection]) {
+    // Detections in byte order, each resolved when the walk reaches it
+    let mut order: Vec<usize> = (0..detections.len()).collect();
+    order.sort_by_key(|&i| detections[i].byte_offset);
+    let mut next = 0;
+
+    let mut stack: Vec<JsonFrame> = Vec::new();
+    // Set while inside a string; `Some` buffer for object keys
+    let mut string: Option<Option<String>> = None;
+    // Detections inside a key, resolved once the key is complete
+    let mut in_key: Vec<usize> = Vec::new();
+    let mut in_scalar = false;
+    let mut chars = content.char_indices().peekable();
+
+    while let Some((i, ch)) = chars.next() {
+        while next < order.len() && detections[order[next]].byte_offset <= i + 1 {
+            match &string {
+                Some(Some(_)) => in_key.push(order[next]),
+                _ => detections[order[next]].structure_path = Some(json_pointer(&stack)),
+            }
+            next += 1;
+        }
+
+        if let Some(key) = &mut string {
+            match ch {
+                '\\' => {
+                    let escaped = match chars.next() {
+                        Some((_, 'n')) => Some('\n'),
+                        Some((_, 't')) => Some('\t'),
+                        Some((_, 'u')) => {
+                            let hex: String = (0..4)
+                                .filter_map(|_| chars.next())
+                                .map(|(_, c)| c)
+                                .collect();
+                            u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32)
+                        }
+                        Some((_, other)) => Some(other),
+                        None => None,
+                    };
+                    if let (Some(key), Some(escaped)) = (key.as_mut(), escaped) {
+                        key.push(escaped);
+                    }
+                }
+                '"' => {
+                    if let Some(key) = key.take() {
+                        if let Some(JsonFrame::Object { key: current, .. }) = stack.last_mut() {
+                            *current = Some(key);
+                        }
+                        for d in in_key.drain(..) {
+                            detections[d].structure_path = Some(json_pointer(&stack));
+                        }
+                    }
+                    string = None;
+                }
+                _ => {
+                    if let Some(key) = key.as_mut() {
+                        key.push(ch);
+                    }
+                }
+            }
+            continue;
+        }
+
+        let scalar_char =
+            !matches!(ch, '{' | '}' | '[' | ']' | ',' | ':' | '"' | '/') && !ch.is_whitespace();
+        if scalar_char && !in_scalar {
+            start_json_value(&mut stack);
+        }
+        in_scalar = scalar_char;
+
+        match ch {
+            '{' => {
+                start_json_value(&mut stack);
+                stack.push(JsonFrame::Object {
+                    key: None,
+                    expect_key: true,
+                });
+            }
+            '[' => {
+                start_json_value(&mut stack);
+                stack.push(JsonFrame::Array { index: None });
+            }
+            '}' | ']' => {
+                stack.pop();
+            }
+            ':' => {
+                if let Some(JsonFrame::Object { expect_key, .. }) = stack.last_mut() {
+                    *expect_key = false;
+                }
+            }
+            ',' => {
+                if let Some(JsonFrame::Object { key, expect_key }) = stack.last_mut() {
+                    *key = None;
+                    *expect_key = true;
+                }
+            }
+            '"' => match stack.last() {
+                Some(JsonFrame::Object {
+                    expect_key: true, ..
+                }) => string = Some(Some(String::new())),
+                _ => {
+                    start_json_value(&mut stack);
+                    string = Some(None);
+                }
+            },
+            // Comments, as in tsconfig.json and VS Code settings
+            '/' => match chars.peek() {
+                Some((_, '/')) => while chars.next_if(|&(_, c)| c != '\n').is_some() {},
+                Some((_, '*')) => {
+                    chars.next();
+                    let mut prev = ' ';
+                    for (_, c) in chars.by_ref() {
+                        if prev == '*' && c == '/' {
+                            break;
+                        }
+                        prev = c;
+                    }
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    for &d in &order[next..] {
+        detections[d].structure_path = Some(json_pointer(&stack));
+    }
+    for d in in_key {
+        detections[d].structure_path = Some(json_pointer(&stack));
+    }
+    for d in detections.iter_mut() {
+        if d.structure_path.as_deref() == Some("") {
+            d.structure_path = None;
+        }
+    }
+}
+
+/// One level of the YAML path: a mapping key or a sequence index.
+enum YamlSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// Dotted path of the YAML node being read (`jobs.build.steps[2].run`).
+fn yaml_path(stack: &[(usize, YamlSegment)]) -> Option<String> {
+    let mut path = String::new();
+    for (_, segment) in stack {
+        match segment {
+            YamlSegment::Key(key) => {
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(key);
+            }
+            YamlSegment::Index(index) => path.push_str(&format!("[{}]", index)),
+        }
+    }
+    (!path.is_empty()).then_some(path)
+}
+
+/// The key of a `key: value` or `key:` line, with quotes removed.
+fn yaml_key(text: &str) -> Option<&str> {
+    let colon = text
+        .find(": ")
+        .or_else(|| text.strip_suffix(':').map(str::len))?;
+    let key = text[..colon].trim();
+    let key = key
+        .strip_prefix('"')
+        .and_then(|k| k.strip_suffix('"'))
+        .or_else(|| key.strip_prefix('\'').and_then(|k| k.strip_suffix('\'')))
+        .unwrap_or(key);
+    (!key.is_empty() && !key.starts_with(['#', '{', '['])).then_some(key)
+}
+
+fn annotate_yaml(content: &str, starts: &[usize], detections: &mut [Detection]) {
+    let mut stack: Vec<(usize, YamlSegment)> = Vec::new();
+    // Indentation of the key whose block scalar (`|`, `>`) is being read
+    let mut block_scalar: Option<usize> = None;
+    let mut paths: Vec<Option<String>> = Vec::new();
+
+    for (i, &start) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).copied().unwrap_or(content.len());
+        let line = strip_line_terminator(&content[start..end]);
+        let text = line.trim_start_matches(' ');
+        let mut indent = line.len() - text.len();
+        let text = text.trim_end();
+
+        if let Some(block) = block_scalar {
+            if text.is_empty() || indent > block {
+                paths.push(yaml_path(&stack));
+                continue;
+            }
+            block_scalar = None;
+        }
+        if text == "---" || text == "..." {
+            stack.clear();
+        }
+        if text.is_empty() || text.starts_with('#') || text == "---" || text == "..." {
+            paths.push(yaml_path(&stack));
+            continue;
+        }
+
+        let mut text = text;
+        while stack.last().is_some_and(|(i, _)| *i > indent) {
+            stack.pop();
+        }
+        if let Some(item) = text.strip_prefix("- ").or((text == "-").then_some("")) {
+            let index = match stack.last() {
+                Some((i, YamlSegment::Index(index))) if *i == indent => {
+                    let index = index + 1;
+                    stack.pop();
+                    index
+                }
+                _ => 0,
+            };
+            stack.push((indent, YamlSegment::Index(index)));
+            let content = item.trim_start_matches(' ');
+            indent += text.len() - content.len();
+            text = content;
+        } else {
+            // A key ends the sibling key (or sequence) at the same indentation
+            while stack.last().is_some_and(|(i, _)| *i == indent) {
+                stack.pop();
+            }
+        }
+
+        if let Some(key) = yaml_key(text) {
+            stack.push((indent, YamlSegment::Key(key.to_string())));
+            let value = text[text.find(':').map_or(text.len(), |c| c + 1)..].trim();
+            if value.starts_with(['|', '>']) {
+                block_scalar = Some(indent);
+            }
+        }
+        paths.push(yaml_path(&stack));
+    }
+
+    for d in detections.iter_mut() {
+        if d.line > 0 {
+            d.structure_path = paths.get(d.line - 1).cloned().flatten();
+        }
+    }
+}
@@ -0,0 +1,349 @@
+//! `--summary` and `stats`: aggregate statistics of a scan.
+//!
+//! "How bad is it and where" in one screen: characters per code point, rule, category,
+//! severity, and file extension, the files with the most findings, and how long the scan took. Counts are of
+//! characters, so a collapsed run of 10,000 no-break spaces counts 10,000.
+//!
+//! `--summary` prints this after the per-file listing; the `stats` subcommand prints only
+//! this, tallying each file's detections as it goes instead of keeping them.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::{rules, Detection};
+
+/// Files listed under "top files".
+const TOP_FILES: usize = 10;
+
+/// One row of a breakdown.
+#[derive(Debug, Serialize)]
+pub struct Count {
+    /// What is counted: `U+200B ZERO WIDTH SPACE`, a rule ID, an extension, or a file.
+    pub key: String,
+
+    pub count: usize,
+
+    /// Number of distinct files the characters are in (not given for top files).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub files: Option<usize>,
+}
+
+/// Aggregate statistics of a scan.
+#[derive(Debug, Serialize)]
+pub struct Summary {
+    /// Suspicious characters found, including those left out by `--max-detections-per-file`.
+    pub total: usize,
+
+    /// Files with at least one detection.
+    pub files: usize,
+
+    /// Files read and scanned.
+    pub scanned_files: usize,
+
+    pub by_code_point: Vec<Count>,
+    pub by_rule: Vec<Count>,
+
+    /// By detection `category` (`zero-width`, `bidi`, ...).
+    pub by_category: Vec<Count>,
+
+    /// By `severity`.
+    pub by_severity: Vec<Count>,
+
+    /// By file extension (`(none)` for files without one).
+    pub by_extension: Vec<Count>,
+
+    /// The files with the most suspicious characters, at most ten.
+    pub top_files: Vec<Count>,
+
+    /// Wall-clock time of the scan in milliseconds.
+    pub duration_ms: u128,
+}
+
+/// Counts of one breakdown, with the files each key was seen in.
+#[derive(Debug, Default)]
+struct Tally {
+    counts: HashMap<String, usize>,
+    files: HashMap<String, HashSet<String>>,
+}
+
+impl Tally {
+    fn add(&mut self, key: String, file: &str, count: usize) {
+        self.files
+            .entry(key.clone())
+            .or_default()
+            .insert(file.to_string());
+        *self.counts.entry(key).or_insert(0) += count;
+    }
+
+    fn merge(&mut self, other: Tally) {
+        for (key, files) in other.files {
+            self.files.entry(key).or_default().extend(files);
+        }
+        for (key, count) in other.counts {
+            *self.counts.entry(key).or_insert(0) += count;
+        }
+    }
+
+    /// Rows sorted by count, highest first, then by key.
+    fn rows(self) -> Vec<Count> {
+        let files = self.files;
+        let mut rows: Vec<Count> = self
+            .counts
+            .into_iter()
+            .map(|(key, count)| Count {
+                files: files.get(&key).map(HashSet::len),
+                key,
+                count,
+            })
+            .collect();
+        rows.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+        rows
+    }
+}
+
+/// Aggregate statistics being collected, one file's detections at a time.
+#[derive(Debug, Default)]
+pub struct SummaryBuilder {
+    total: usize,
+    by_code_point: Tally,
+    by_rule: Tally,
+    by_category: Tally,
+    by_severity: Tally,
+    by_extension: Tally,
+    by_file: HashMap<String, usize>,
+
+    /// True once a detection that fails the scan has been counted.
+    failing: bool,
+}
+
+impl SummaryBuilder {
+    /// Count `detections`.
+    pub fn add(&mut self, detections: &[Detection]) {
+        for d in detections {
+            let count = d.run.as_ref().map_or(1, |run| run.count);
+            // Detections left out past the cap are only known by number
+            let with_omitted = count + d.more_in_file.unwrap_or(0);
+            self.total += with_omitted;
+            self.failing |= d.severity.fails();
+            self.by_code_point
+                .add(format!("U+{:04X} {}", d.code, d.name), &d.file, count);
+            self.by_rule.add(d.rule.clone(), &d.file, count);
+            self.by_category.add(d.category.clone(), &d.file, count);
+            self.by_severity
+                .add(d.severity.as_str().to_string(), &d.file, count);
+            let extension = Path::new(&d.file).extension().map_or_else(
+                || "(none)".to_string(),
+                |e| e.to_string_lossy().to_lowercase(),
+            );
+            self.by_extension.add(extension, &d.file, with_omitted);
+            *self.by_file.entry(d.file.clone()).or_insert(0) += with_omitted;
+        }
+    }
+
+    /// Add the counts of another builder, such as the one of another `--root`.
+    pub fn merge(&mut self, other: SummaryBuilder) {
+        self.total += other.total;
+        self.by_code_point.merge(other.by_code_point);
+        self.by_rule.merge(other.by_rule);
+        self.by_category.merge(other.by_category);
+        self.by_severity.merge(other.by_severity);
+        self.by_extension.merge(other.by_extension);
+        for (file, count) in other.by_file {
+            *self.by_file.entry(file).or_insert(0) += count;
+        }
+        self.failing |= other.failing;
+    }
+
+    /// Detections counted, with those left out past the cap.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// True if a counted detection fails the scan (see [`rules::Severity::fails`]).
+    pub fn has_failures(&self) -> bool {
+        self.failing
+    }
+
+    pub fn finish(self, scanned_files: usize, duration: Duration) -> Summary {
+        let files = self.by_file.len();
+        let mut top_files: Vec<Count> = self
+            .by_file
+            .into_iter()
+            .map(|(key, count)| Count {
+                key,
+                count,
+                files: None,
+            })
+            .collect();
+        top_files.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+        top_files.truncate(TOP_FILES);
+        Summary {
+            total: self.total,
+            files,
+            scanned_files,
+            by_code_point: self.by_code_point.rows(),
+            by_rule: self.by_rule.rows(),
+            by_category: self.by_category.rows(),
+            by_severity: self.by_severity.rows(),
+            by_extension: self.by_extension.rows(),
+            top_files,
+            duration_ms: duration.as_millis(),
+        }
+    }
+}
+
+/// Suspicious characters by severity, for the verdict line on stderr and `counts` in the JSON
+/// report.
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct SeverityCounts {
+    pub error: usize,
+    pub warning: usize,
+    pub info: usize,
+}
+
+impl SeverityCounts {
+    /// The characters of `detections`, by severity.
+    pub fn new(detections: &[Detection]) -> Self {
+        let mut counts = SeverityCounts::default();
+        for d in detections {
+            let count = d.run.as_ref().map_or(1, |run| run.count);
+            match d.severity {
+                rules::Severity::Error => counts.error += count,
+                rules::Severity::Warning => counts.warning += count,
+                rules::Severity::Info => counts.info += count,
+            }
+        }
+        counts
+    }
+
+    /// Suspicious characters of every severity.
+    pub fn total(&self) -> usize {
+        self.error + self.warning + self.info
+    }
+
+    /// The severity counts of `summary`, whose detections may not have been kept (`stats`).
+    pub fn from_summary(summary: &Summary) -> Self {
+        let count = |severity: rules::Severity| {
+            summary
+                .by_severity
+                .iter()
+                .find(|row| row.key == severity.as_str())
+                .map_or(0, |row| row.count)
+        };
+        SeverityCounts {
+            error: count(rules::Severity::Error),
+            warning: count(rules::Severity::Warning),
+            info: count(rules::Severity::Info),
+        }
+    }
+}
+
+/// `count` and `noun`, with an `s` unless there is one.
+fn plural(count: usize, noun: &str) -> String {
+    match count {
+        1 => format!("1 {}", noun),
+        _ => format!("{} {}s", count, noun),
+    }
+}
+
+/// The one-line verdict printed on stderr at the end of every scan, whatever the format, for
+/// build logs: `icd: 3 errors, 17 warnings, 2 info in 142 files (1.8s)`.
+pub fn format_verdict(counts: SeverityCounts, scanned_files: usize, duration: Duration) -> String {
+    format!(
+        "icd: {}, {}, {} info in {} ({:.1}s)",
+        plural(counts.error, "error"),
+        plural(counts.warning, "warning"),
+        counts.info,
+        plural(scanned_files, "file"),
+        duration.as_secs_f64()
+    )
+}
+
+impl Summary {
+    pub fn new(detections: &[Detection], scanned_files: usize, duration: Duration) -> Self {
+        let mut builder = SummaryBuilder::default();
+        builder.add(detections);
+        builder.finish(scanned_files, duration)
+    }
+}
+
+/// Append one breakdown to `output`, with each key labeled by `label`.
+fn push_section(output: &mut String, title: &str, rows: &[Count], label: impl Fn(&str) -> String) {
+    if rows.is_empty() {
+        return;
+    }
+    output.push_str(&format!("  {}:\n", title));
+    let width = rows
+        .iter()
+        .map(|r| r.count.to_string().len())
+        .max()
+        .unwrap_or(1);
+    for row in rows {
+        let files = match row.files {
+            Some(1) => " (1 file)".to_string(),
+            Some(n) => format!(" ({} files)", n),
+            None => String::new(),
+        };
+        output.push_str(&format!(
+            "    {:>width$}  {}{}\n",
+            row.count,
+            label(&row.key),
+            files
+        ));
+    }
+}
+
+/// Format the summary for text output.
+pub fn format_summary(summary: &Summary) -> String {
+    let mut output = format!(
+        "Summary: {} suspicious character(s) in {} of {} file(s), scanned in {:.2}s\n",
+        summary.total,
+        summary.files,
+        summary.scanned_files,
+        summary.duration_ms as f64 / 1000.0
+    );
+    push_section(
+        &mut output,
+        "By code point",
+        &summary.by_code_point,
+        |key| key.to_string(),
+    );
+    push_section(
+        &mut output,
+        "By rule",
+        &summary.by_rule,
+        |key| match rules::find(key) {
+            Some(rule) => format!("{} ({})", key, rule.title),
+            None => key.to_string(),
+        },
+    );
+    push_section(
+        &mut output,
+        "By category",
+        &summary.by_category,
+        |key| key.to_string(),
+    );
+    push_section(
+        &mut output,
+        "By severity",
+        &summary.by_severity,
+        |key| key.to_string(),
+    );
+    push_section(
+        &mut output,
+        "By file extension",
+        &summary.by_extension,
+        |key| key.to_string(),
+    );
+    // File names may hold the very characters being counted
+    push_section(
+        &mut output,
+        "Top files",
+        &summary.top_files,
+        crate::visualize,
+    );
+    output
+}
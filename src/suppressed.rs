@@ -0,0 +1,27 @@
+//! `--show-suppressed`: the detections settings kept out of the report (`allow_emoji`,
+//! `prose`, `require_context`, `ignore_in_context`, and `--diff`), each with what dropped it,
+//! so an audit can check that every suppression is deliberate. They are listed apart from the
+//! findings, and neither counted nor failing the scan.
+
+use crate::{visualize, Detection};
+
+/// Format the suppressed detections for text output, one line each.
+pub fn format_suppressed(detections: &[Detection]) -> String {
+    let mut output = format!(
+        "Suppressed: {} detection(s), not counted\n",
+        detections.len()
+    );
+    for d in detections {
+        output.push_str(&format!(
+            "  {}:{}:{} U+{:04X} {} [{}] ({})\n",
+            visualize(&d.file),
+            d.line,
+            d.char_index,
+            d.code,
+            d.name,
+            d.rule,
+            d.suppressed.as_deref().unwrap_or_default()
+        ));
+    }
+    output
+}
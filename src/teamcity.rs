@@ -0,0 +1,93 @@
+//! `--format teamcity`: TeamCity service messages, so detections show up on the build's
+//! Inspections tab and count toward its inspection failure conditions.
+//!
+//! Each rule that fired is declared once with `inspectionType`, each detection is an
+//! `inspection`, and a `buildProblem` marks the build failed when any detection is an error
+//! or warning.
+
+use std::collections::BTreeSet;
+
+use crate::rules::{self, Severity};
+use crate::{visualize, Detection};
+
+/// Identity of the build problem, stable across builds so TeamCity can tell it is the same
+/// problem as last time.
+const PROBLEM_IDENTITY: &str = "invisible-char-detector";
+
+/// `value` escaped for a service message attribute.
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '|' => out.push_str("||"),
+            '\'' => out.push_str("|'"),
+            '\n' => out.push_str("|n"),
+            '\r' => out.push_str("|r"),
+            '[' => out.push_str("|["),
+            ']' => out.push_str("|]"),
+            _ if !ch.is_ascii() => out.push_str(&format!("|0x{:04X}", ch as u32)),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+/// TeamCity's name for a severity.
+fn severity_name(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "ERROR",
+        Severity::Warning => "WARNING",
+        Severity::Info => "INFO",
+    }
+}
+
+/// Format detections as TeamCity service messages.
+pub fn format_teamcity(detections: &[Detection]) -> String {
+    let mut output = String::new();
+
+    let fired: BTreeSet<&str> = detections.iter().map(|d| d.rule.as_str()).collect();
+    for id in fired {
+        let Some(rule) = rules::find(id) else {
+            continue;
+        };
+        output.push_str(&format!(
+            "##teamcity[inspectionType id='{}' name='{}' category='{}' description='{}']\n",
+            escape(rule.id),
+            escape(rule.title),
+            escape(rule.category),
+            escape(rule.risk)
+        ));
+    }
+
+    for d in detections {
+        let message = format!(
+            "U+{:04X} {} at column {}: {}",
+            d.code, d.name, d.utf16_column, d.description
+        );
+        // A character in a name has no line
+        let line = if d.line > 0 {
+            format!(" line='{}'", d.line)
+        } else {
+            String::new()
+        };
+        output.push_str(&format!(
+            "##teamcity[inspection typeId='{}' message='{}' file='{}'{} SEVERITY='{}' fingerprint='{}']\n",
+            escape(&d.rule),
+            escape(&message),
+            escape(&visualize(&d.file)),
+            line,
+            severity_name(d.severity),
+            escape(&d.fingerprint)
+        ));
+    }
+
+    let failing = detections.iter().filter(|d| d.severity.fails()).count();
+    if failing > 0 {
+        output.push_str(&format!(
+            "##teamcity[buildProblem description='{}' identity='{}']\n",
+            escape(&format!("{} suspicious character(s) found", failing)),
+            PROBLEM_IDENTITY
+        ));
+    }
+    output
+}
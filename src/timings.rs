@@ -0,0 +1,133 @@
+//! `--timings`: where a scan spends its time, for tuning exclusions and `--max-file-size`
+//! against a CI budget.
+//!
+//! Each file is timed from the moment a worker picks it up to its outcome, reading included.
+//! The report gives the wall-clock time of the whole scan, the bytes read and scanned
+//! (results reused from `--cache` count no bytes), the throughput, and the slowest files.
+
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// The time one file took.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileTime {
+    pub path: String,
+
+    /// Microseconds, which most files take well under a millisecond of.
+    pub duration_us: u128,
+
+    /// Bytes read and scanned.
+    pub bytes: u64,
+}
+
+/// Collects the time of each scanned file.
+#[derive(Debug, Default)]
+pub struct TimingsBuilder {
+    files: Vec<FileTime>,
+}
+
+impl TimingsBuilder {
+    pub fn add(&mut self, path: &str, duration: Duration, bytes: u64) {
+        self.files.push(FileTime {
+            path: path.to_string(),
+            duration_us: duration.as_micros(),
+            bytes,
+        });
+    }
+
+    /// Add the files timed by another builder.
+    pub fn merge(&mut self, other: TimingsBuilder) {
+        self.files.extend(other.files);
+    }
+
+    /// The timings of a scan that took `wall` in total, listing the `slowest` slowest files.
+    pub fn finish(mut self, wall: Duration, slowest: usize) -> Timings {
+        let bytes = self.files.iter().map(|f| f.bytes).sum();
+        let seconds = wall.as_secs_f64();
+        self.files.sort_by(|a, b| {
+            b.duration_us
+                .cmp(&a.duration_us)
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        let files = self.files.len();
+        self.files.truncate(slowest);
+        Timings {
+            wall_ms: wall.as_millis(),
+            files,
+            bytes,
+            bytes_per_second: if seconds > 0.0 {
+                (bytes as f64 / seconds) as u64
+            } else {
+                0
+            },
+            slowest: self.files,
+        }
+    }
+}
+
+/// Where the time of a scan went.
+#[derive(Debug, Serialize)]
+pub struct Timings {
+    /// Wall-clock time of the scan in milliseconds.
+    pub wall_ms: u128,
+
+    /// Files scanned.
+    pub files: usize,
+
+    /// Bytes read and scanned.
+    pub bytes: u64,
+
+    /// `bytes` over the wall-clock time.
+    pub bytes_per_second: u64,
+
+    /// The slowest files, slowest first.
+    pub slowest: Vec<FileTime>,
+}
+
+/// `bytes` in the largest unit that keeps it at least 1.
+fn human_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Format the timings for text output.
+pub fn format_timings(timings: &Timings) -> String {
+    let mut output = format!(
+        "Timings: {} file(s), {} in {:.2}s ({}/s)\n",
+        timings.files,
+        human_size(timings.bytes),
+        timings.wall_ms as f64 / 1000.0,
+        human_size(timings.bytes_per_second)
+    );
+    if timings.slowest.is_empty() {
+        return output;
+    }
+    output.push_str("  Slowest files:\n");
+    let durations: Vec<String> = timings
+        .slowest
+        .iter()
+        .map(|f| format!("{:.1}ms", f.duration_us as f64 / 1000.0))
+        .collect();
+    let width = durations.iter().map(String::len).max().unwrap_or(1);
+    for (file, duration) in timings.slowest.iter().zip(&durations) {
+        // File names may hold the very characters being looked for
+        output.push_str(&format!(
+            "    {:>width$}  {} ({})\n",
+            duration,
+            crate::visualize(&file.path),
+            human_size(file.bytes)
+        ));
+    }
+    output
+}
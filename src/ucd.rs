@@ -0,0 +1,147 @@
+//! Official Unicode character names and general categories.
+//!
+//! Names come from the Unicode Character Database via `unicode_names2`. Code points without a
+//! Name property get their formal alias (controls) or a code point label such as
+//! `<private-use-E000>`, following the conventions in chapter 4.8 of the Unicode Standard.
+
+use unicode_properties::{GeneralCategory, UnicodeGeneralCategory};
+
+/// Formal aliases (NameAliases.txt, type `control`) for the C0 and C1 controls, which have no
+/// Name property. U+0080, U+0081, U+0084, and U+0099 have no control alias.
+const CONTROL_ALIASES: &[(u32, &str)] = &[
+    (0x00, "NULL"),
+    (0x01, "START OF HEADING"),
+    (0x02, "START OF TEXT"),
+    (0x03, "END OF TEXT"),
+    (0x04, "END OF TRANSMISSION"),
+    (0x05, "ENQUIRY"),
+    (0x06, "ACKNOWLEDGE"),
+    (0x07, "ALERT"),
+    (0x08, "BACKSPACE"),
+    (0x09, "CHARACTER TABULATION"),
+    (0x0A, "LINE FEED"),
+    (0x0B, "LINE TABULATION"),
+    (0x0C, "FORM FEED"),
+    (0x0D, "CARRIAGE RETURN"),
+    (0x0E, "SHIFT OUT"),
+    (0x0F, "SHIFT IN"),
+    (0x10, "DATA LINK ESCAPE"),
+    (0x11, "DEVICE CONTROL ONE"),
+    (0x12, "DEVICE CONTROL TWO"),
+    (0x13, "DEVICE CONTROL THREE"),
+    (0x14, "DEVICE CONTROL FOUR"),
+    (0x15, "NEGATIVE ACKNOWLEDGE"),
+    (0x16, "SYNCHRONOUS IDLE"),
+    (0x17, "END OF TRANSMISSION BLOCK"),
+    (0x18, "CANCEL"),
+    (0x19, "END OF MEDIUM"),
+    (0x1A, "SUBSTITUTE"),
+    (0x1B, "ESCAPE"),
+    (0x1C, "INFORMATION SEPARATOR FOUR"),
+    (0x1D, "INFORMATION SEPARATOR THREE"),
+    (0x1E, "INFORMATION SEPARATOR TWO"),
+    (0x1F, "INFORMATION SEPARATOR ONE"),
+    (0x7F, "DELETE"),
+    (0x82, "BREAK PERMITTED HERE"),
+    (0x83, "NO BREAK HERE"),
+    (0x85, "NEXT LINE"),
+    (0x86, "START OF SELECTED AREA"),
+    (0x87, "END OF SELECTED AREA"),
+    (0x88, "CHARACTER TABULATION SET"),
+    (0x89, "CHARACTER TABULATION WITH JUSTIFICATION"),
+    (0x8A, "LINE TABULATION SET"),
+    (0x8B, "PARTIAL LINE FORWARD"),
+    (0x8C, "PARTIAL LINE BACKWARD"),
+    (0x8D, "REVERSE LINE FEED"),
+    (0x8E, "SINGLE SHIFT TWO"),
+    (0x8F, "SINGLE SHIFT THREE"),
+    (0x90, "DEVICE CONTROL STRING"),
+    (0x91, "PRIVATE USE ONE"),
+    (0x92, "PRIVATE USE TWO"),
+    (0x93, "SET TRANSMIT STATE"),
+    (0x94, "CANCEL CHARACTER"),
+    (0x95, "MESSAGE WAITING"),
+    (0x96, "START OF GUARDED AREA"),
+    (0x97, "END OF GUARDED AREA"),
+    (0x98, "START OF STRING"),
+    (0x9A, "SINGLE CHARACTER INTRODUCER"),
+    (0x9B, "CONTROL SEQUENCE INTRODUCER"),
+    (0x9C, "STRING TERMINATOR"),
+    (0x9D, "OPERATING SYSTEM COMMAND"),
+    (0x9E, "PRIVACY MESSAGE"),
+    (0x9F, "APPLICATION PROGRAM COMMAND"),
+];
+
+/// True for the 66 noncharacters: U+FDD0..U+FDEF and the last two code points of every plane.
+fn is_noncharacter(code: u32) -> bool {
+    (0xFDD0..=0xFDEF).contains(&code) || code & 0xFFFE == 0xFFFE
+}
+
+/// The official name of `code`, or its control alias or code point label if it has none.
+pub fn char_name(code: u32) -> String {
+    if let Some(name) = char::from_u32(code).and_then(unicode_names2::name) {
+        return name.to_string();
+    }
+    if let Ok(i) = CONTROL_ALIASES.binary_search_by_key(&code, |&(c, _)| c) {
+        return CONTROL_ALIASES[i].1.to_string();
+    }
+
+    let kind = match char::from_u32(code).map(|c| c.general_category()) {
+        Some(GeneralCategory::Control) => "control",
+        Some(GeneralCategory::PrivateUse) => "private-use",
+        None => "surrogate",
+        _ if is_noncharacter(code) => "noncharacter",
+        _ => "reserved",
+    };
+    format!("<{}-{:04X}>", kind, code)
+}
+
+/// Two-letter abbreviation and long name of a general category.
+fn category_names(category: GeneralCategory) -> (&'static str, &'static str) {
+    use GeneralCategory::*;
+    match category {
+        UppercaseLetter => ("Lu", "Uppercase Letter"),
+        LowercaseLetter => ("Ll", "Lowercase Letter"),
+        TitlecaseLetter => ("Lt", "Titlecase Letter"),
+        ModifierLetter => ("Lm", "Modifier Letter"),
+        OtherLetter => ("Lo", "Other Letter"),
+        NonspacingMark => ("Mn", "Nonspacing Mark"),
+        SpacingMark => ("Mc", "Spacing Mark"),
+        EnclosingMark => ("Me", "Enclosing Mark"),
+        DecimalNumber => ("Nd", "Decimal Number"),
+        LetterNumber => ("Nl", "Letter Number"),
+        OtherNumber => ("No", "Other Number"),
+        ConnectorPunctuation => ("Pc", "Connector Punctuation"),
+        DashPunctuation => ("Pd", "Dash Punctuation"),
+        OpenPunctuation => ("Ps", "Open Punctuation"),
+        ClosePunctuation => ("Pe", "Close Punctuation"),
+        InitialPunctuation => ("Pi", "Initial Punctuation"),
+        FinalPunctuation => ("Pf", "Final Punctuation"),
+        OtherPunctuation => ("Po", "Other Punctuation"),
+        MathSymbol => ("Sm", "Math Symbol"),
+        CurrencySymbol => ("Sc", "Currency Symbol"),
+        ModifierSymbol => ("Sk", "Modifier Symbol"),
+        OtherSymbol => ("So", "Other Symbol"),
+        SpaceSeparator => ("Zs", "Space Separator"),
+        LineSeparator => ("Zl", "Line Separator"),
+        ParagraphSeparator => ("Zp", "Paragraph Separator"),
+        Control => ("Cc", "Control"),
+        Format => ("Cf", "Format"),
+        Surrogate => ("Cs", "Surrogate"),
+        PrivateUse => ("Co", "Private Use"),
+        Unassigned => ("Cn", "Unassigned"),
+    }
+}
+
+/// General category of `code` as its two-letter abbreviation, e.g. `Cf`.
+pub fn general_category(code: u32) -> &'static str {
+    general_category_long(code).0
+}
+
+/// General category of `code` as (abbreviation, long name), e.g. `("Cf", "Format")`.
+pub fn general_category_long(code: u32) -> (&'static str, &'static str) {
+    match char::from_u32(code) {
+        Some(c) => category_names(c.general_category()),
+        None => ("Cs", "Surrogate"),
+    }
+}
@@ -0,0 +1,306 @@
+//! Filesystem traversal for glob patterns with explicit symlink handling.
+//!
+//! `glob::glob` silently follows symlinked directories, so a link pointing back up the
+//! tree makes it enumerate the same files over and over until the OS refuses to resolve
+//! the path. This walker matches paths with `glob::Pattern` but controls descent itself.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use glob::{MatchOptions, Pattern, PatternError};
+
+/// A path produced by the walker, tagged with how it should be treated.
+#[derive(Debug)]
+pub enum WalkEntry {
+    /// A file path matching the pattern that should be scanned.
+    Matched(PathBuf),
+
+    /// A matching symlink that was not followed because `--follow-symlinks` is off.
+    SymlinkNotFollowed(PathBuf),
+
+    /// A directory already reached through another path (symlink, bind mount, loop).
+    AlreadyVisited(PathBuf),
+
+    /// A file already matched under another path: a hard link, or the same file through a
+    /// bind mount or symlink. `first` is the path it was matched under first.
+    Duplicate { path: PathBuf, first: PathBuf },
+}
+
+impl WalkEntry {
+    /// The path this entry refers to, whatever its kind.
+    pub fn path(&self) -> &Path {
+        match self {
+            WalkEntry::Matched(path)
+            | WalkEntry::SymlinkNotFollowed(path)
+            | WalkEntry::AlreadyVisited(path)
+            | WalkEntry::Duplicate { path, .. } => path,
+        }
+    }
+}
+
+/// Identity of a file on disk, used to recognise the same file reached via different paths.
+///
+/// On Unix this is the (device, inode) pair; elsewhere the canonicalized path.
+#[cfg(unix)]
+type FileId = (u64, u64);
+#[cfg(not(unix))]
+type FileId = PathBuf;
+
+/// Resolve the identity of the file or directory at `path`, following symlinks.
+fn file_id(path: &Path) -> Option<FileId> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let meta = fs::metadata(path).ok()?;
+        Some((meta.dev(), meta.ino()))
+    }
+
+    #[cfg(not(unix))]
+    {
+        fs::canonicalize(path).ok()
+    }
+}
+
+/// Split a pattern into its leading wildcard-free directory and the remaining components.
+///
+/// Symlinks inside the literal prefix were named explicitly by the user and are always honored.
+fn split_literal_prefix(pattern: &str) -> (PathBuf, Vec<String>) {
+    let mut prefix = PathBuf::new();
+    let mut rest = Vec::new();
+
+    for component in Path::new(pattern).components() {
+        let text = component.as_os_str().to_string_lossy().into_owned();
+        if rest.is_empty() && !text.contains(['*', '?', '[']) {
+            prefix.push(component);
+        } else {
+            rest.push(text);
+        }
+    }
+
+    (prefix, rest)
+}
+
+/// Options used for every pattern match: `*` never crosses a path separator, and on Windows,
+/// whose file systems ignore case, `*.JS` matches `app.js`.
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: !cfg!(windows),
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// True if `path` matches `pattern` with the same rules the walker uses.
+pub fn matches(pattern: &Pattern, path: &Path) -> bool {
+    pattern.matches_path_with(path, MATCH_OPTIONS)
+}
+
+/// `path` without the `\\?\` prefix Windows uses for extended-length paths (beyond
+/// MAX_PATH), which `canonicalize` also adds. The standard library lengthens long paths by
+/// itself, and in a pattern the `?` would be a wildcard.
+pub fn strip_verbatim(path: &str) -> Cow<'_, str> {
+    if !cfg!(windows) {
+        return Cow::Borrowed(path);
+    }
+    if let Some(rest) = path.strip_prefix(r"\\?\UNC\") {
+        return Cow::Owned(format!(r"\\{}", rest));
+    }
+    Cow::Borrowed(path.strip_prefix(r"\\?\").unwrap_or(path))
+}
+
+/// `path` as reports show it. On Windows: without an extended-length prefix, and with one
+/// separator throughout, `\` or with `forward_slashes` `/`. Elsewhere `\` is an ordinary file
+/// name character and only `/` separates. The path inside an archive or document
+/// (`bundle.zip!dist/app.js`) always uses `/`.
+pub fn display_path(path: &str, forward_slashes: bool) -> String {
+    if !cfg!(windows) {
+        return path.to_string();
+    }
+    let path = strip_verbatim(path);
+    let (outer, inner) = match path.split_once('!') {
+        Some((outer, inner)) => (outer, Some(inner)),
+        None => (path.as_ref(), None),
+    };
+    let separator = if forward_slashes { '/' } else { '\\' };
+    let mut display: String = outer
+        .chars()
+        .map(|c| if c == '/' || c == '\\' { separator } else { c })
+        .collect();
+    if let Some(inner) = inner {
+        display.push('!');
+        display.push_str(inner);
+    }
+    display
+}
+
+/// The directory (or file) a pattern is rooted at: its wildcard-free prefix, or `.`.
+pub fn literal_root(pattern: &str) -> PathBuf {
+    let (base, _) = split_literal_prefix(pattern);
+    if base.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        base
+    }
+}
+
+struct Walker {
+    pattern: Pattern,
+    max_depth: Option<usize>,
+    follow_symlinks: bool,
+    visited_dirs: HashSet<FileId>,
+
+    /// The path each file was first matched under.
+    seen_files: HashMap<FileId, PathBuf>,
+    entries: Vec<WalkEntry>,
+}
+
+impl Walker {
+    fn matches(&self, path: &Path) -> bool {
+        matches(&self.pattern, path)
+    }
+
+    fn visit_dir(&mut self, dir: &Path, depth: usize) {
+        if self.max_depth.is_some_and(|max| depth >= max) {
+            return;
+        }
+
+        let read_from = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+        let Ok(read) = fs::read_dir(read_from) else {
+            return;
+        };
+
+        let mut children: Vec<_> = read.flatten().collect();
+        children.sort_by_key(|child| child.file_name());
+
+        for child in children {
+            let path = dir.join(child.file_name());
+            let Ok(file_type) = child.file_type() else {
+                continue;
+            };
+
+            let is_dir = if file_type.is_symlink() {
+                if !self.follow_symlinks {
+                    if self.matches(&path) {
+                        self.entries.push(WalkEntry::SymlinkNotFollowed(path));
+                    }
+                    continue;
+                }
+                // Broken links fall through as files and surface as read errors.
+                fs::metadata(&path).map(|m| m.is_dir()).unwrap_or(false)
+            } else {
+                file_type.is_dir()
+            };
+
+            if is_dir {
+                self.visit_subdir(path, depth + 1);
+            } else if self.matches(&path) {
+                self.push_file(path);
+            }
+        }
+    }
+
+    /// The identity of the directory at `path`, to enter it once: always on Unix, where a
+    /// bind mount can repeat a tree, and elsewhere when symlinks are followed.
+    fn dir_id(&self, path: &Path) -> Option<FileId> {
+        (cfg!(unix) || self.follow_symlinks)
+            .then(|| file_id(path))
+            .flatten()
+    }
+
+    fn visit_subdir(&mut self, path: PathBuf, depth: usize) {
+        if let Some(id) = self.dir_id(&path) {
+            if !self.visited_dirs.insert(id) {
+                self.entries.push(WalkEntry::AlreadyVisited(path));
+                return;
+            }
+        }
+
+        self.visit_dir(&path, depth);
+    }
+
+    /// The identity of the file at `path` if other paths below the root may lead to it too:
+    /// through a followed symlink, or (on Unix) as another hard link. Bind-mounted files are
+    /// caught earlier, with their directory.
+    fn shared_file_id(&self, path: &Path) -> Option<FileId> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let meta = fs::metadata(path).ok()?;
+            (self.follow_symlinks || meta.nlink() > 1).then(|| (meta.dev(), meta.ino()))
+        }
+
+        #[cfg(not(unix))]
+        {
+            self.follow_symlinks.then(|| file_id(path)).flatten()
+        }
+    }
+
+    fn push_file(&mut self, path: PathBuf) {
+        if let Some(id) = self.shared_file_id(&path) {
+            if let Some(first) = self.seen_files.get(&id) {
+                let first = first.clone();
+                self.entries.push(WalkEntry::Duplicate { path, first });
+                return;
+            }
+            self.seen_files.insert(id, path.clone());
+        }
+        self.entries.push(WalkEntry::Matched(path));
+    }
+}
+
+/// Enumerate file paths matching `pattern`, in sorted order.
+///
+/// Directories are traversed but never reported, so `**/*` doesn't produce a read error (and a
+/// `--fail-on-skip` failure) for every directory in the tree.
+///
+/// Without `follow_symlinks`, symlinks below the pattern's literal prefix are reported but never
+/// resolved. Either way each directory is entered at most once (which breaks symlink and bind
+/// mount loops) and each file is reported as `Matched` at most once, however many paths lead to
+/// it; the others are `Duplicate`s of the first.
+pub fn walk(pattern: &str, follow_symlinks: bool) -> Result<Vec<WalkEntry>, PatternError> {
+    let pattern = strip_verbatim(pattern);
+    let pattern = pattern.as_ref();
+    let compiled = Pattern::new(pattern)?;
+    let (base, rest) = split_literal_prefix(pattern);
+
+    let mut walker = Walker {
+        pattern: compiled,
+        max_depth: if rest.iter().any(|c| c.contains("**")) {
+            None
+        } else {
+            Some(rest.len())
+        },
+        follow_symlinks,
+        visited_dirs: HashSet::new(),
+        seen_files: HashMap::new(),
+        entries: Vec::new(),
+    };
+
+    if rest.is_empty() {
+        // No wildcards: the pattern names a single path.
+        if fs::symlink_metadata(&base).is_ok_and(|m| !m.is_dir()) {
+            walker.entries.push(WalkEntry::Matched(base));
+        }
+        return Ok(walker.entries);
+    }
+
+    let root = if base.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        base.as_path()
+    };
+    if !root.is_dir() {
+        return Ok(walker.entries);
+    }
+
+    if let Some(id) = walker.dir_id(root) {
+        walker.visited_dirs.insert(id);
+    }
+
+    walker.visit_dir(&base, 0);
+    Ok(walker.entries)
+}
@@ -0,0 +1,34 @@
+//! JavaScript interface (`wasm` feature): scan text in the browser or a VS Code web extension.
+//!
+//! Build with `cargo build --lib --release --target wasm32-unknown-unknown
+//! --no-default-features --features wasm` and run `wasm-bindgen` on the result. The file
+//! system, git, and server parts of the crate are never called from here, and `--context`
+//! parsing (the `syntax` feature) is left out because its grammars are C code.
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{detect_invisible_characters, DetectOptions};
+
+/// Scan `text` as the file `name` and return the detections as plain JavaScript objects, the
+/// way `--json` writes them.
+fn scan(name: &str, text: &str) -> Result<JsValue, JsError> {
+    let detections = detect_invisible_characters(text, name, &DetectOptions::default());
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    detections
+        .serialize(&serializer)
+        .map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Scan `text` and return an array of detections (empty when clean).
+#[wasm_bindgen]
+pub fn scan_text(text: &str) -> Result<JsValue, JsError> {
+    scan("<input>", text)
+}
+
+/// Scan `text` as the file `name`, which also enables name-dependent handling such as JSON and
+/// YAML structure paths.
+#[wasm_bindgen]
+pub fn scan_file(name: &str, text: &str) -> Result<JsValue, JsError> {
+    scan(name, text)
+}
@@ -0,0 +1,181 @@
+//! `--watch`: keep running after the initial scan and rescan files as they change.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use notify::{EventKind, RecursiveMode, Watcher};
+use serde::Serialize;
+
+use crate::logging;
+use crate::walk::{self, WalkEntry};
+use crate::render::OutputFormat;
+use crate::summary::SeverityCounts;
+use crate::{azure, jsonl, teamcity};
+use crate::{
+    format_short_output, format_text_output, process_entry, sort_detections, Detection,
+    FileOutcome, ScanConfig,
+};
+
+/// Quiet period used to batch the burst of events a single save usually produces.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// One incremental result, printed as a JSON line in `--json` mode.
+#[derive(Serialize)]
+struct WatchUpdate<'a> {
+    file: &'a str,
+    removed: bool,
+    detections: &'a [Detection],
+}
+
+fn print_update(config: &ScanConfig, file: &str, removed: bool, detections: &[Detection]) {
+    if matches!(config.format, OutputFormat::Json | OutputFormat::Jsonl) {
+        let update = WatchUpdate {
+            file,
+            removed,
+            detections,
+        };
+        if let Ok(json) = serde_json::to_string(&update) {
+            println!("{}", json);
+        }
+    } else if config.format == OutputFormat::Short {
+        // Only detections; a file that became clean simply has no lines
+        print!("{}", format_short_output(detections));
+    } else if config.format == OutputFormat::Teamcity {
+        print!("{}", teamcity::format_teamcity(detections));
+    } else if config.format == OutputFormat::Azure {
+        print!("{}", azure::format_azure(detections));
+    } else if removed {
+        println!("{}: removed", file);
+    } else if detections.is_empty() {
+        println!("{}: clean", file);
+    } else {
+        print!(
+            "{}",
+            format_text_output(detections, config.text_style())
+        );
+    }
+}
+
+fn print_full_report(config: &ScanConfig, state: &BTreeMap<String, Vec<Detection>>) {
+    let mut all: Vec<Detection> = state.values().flatten().cloned().collect();
+    sort_detections(&mut all);
+    if config.format == OutputFormat::Jsonl {
+        let findings = SeverityCounts::new(&all);
+        let _ = jsonl::write_jsonl(
+            &mut std::io::stdout().lock(),
+            &all,
+            &[],
+            state.len(),
+            findings,
+        );
+    } else if config.format == OutputFormat::Json {
+        if let Ok(json) = serde_json::to_string(&all) {
+            println!("{}", json);
+        }
+    } else if config.format == OutputFormat::Short {
+        print!("{}", format_short_output(&all));
+    } else if config.format == OutputFormat::Teamcity {
+        print!("{}", teamcity::format_teamcity(&all));
+    } else if config.format == OutputFormat::Azure {
+        print!("{}", azure::format_azure(&all));
+    } else {
+        println!(
+            "--- full report ---\n{}",
+            format_text_output(&all, config.text_style())
+        );
+    }
+}
+
+/// Watch the pattern's root directory and rescan matching files whenever they change.
+///
+/// `initial` is the result of the scan that was just reported; it seeds the state used for
+/// `--watch-full` reports. Only returns on error; stop with Ctrl-C.
+pub fn watch(config: &ScanConfig, initial: Vec<Detection>) -> Result<(), String> {
+    let pattern = glob::Pattern::new(&config.pattern)
+        .map_err(|e| format!("Invalid glob pattern: {}", e))?;
+    let base = walk::literal_root(&config.pattern);
+    let root = base
+        .canonicalize()
+        .map_err(|e| format!("could not watch {}: {}", base.display(), e))?;
+
+    let mut state: BTreeMap<String, Vec<Detection>> = BTreeMap::new();
+    for detection in initial {
+        state
+            .entry(detection.file.clone())
+            .or_default()
+            .push(detection);
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher =
+        notify::recommended_watcher(tx).map_err(|e| format!("could not start watcher: {}", e))?;
+    watcher
+        .watch(&root, RecursiveMode::Recursive)
+        .map_err(|e| format!("could not watch {}: {}", base.display(), e))?;
+
+    logging::info!("Watching {} for changes (Ctrl-C to stop)", base.display());
+
+    // Map an absolute event path back to the form the walker reports.
+    let reported = |absolute: &Path| -> Option<PathBuf> {
+        let relative = absolute.strip_prefix(&root).ok()?;
+        Some(if base == Path::new(".") {
+            relative.to_path_buf()
+        } else {
+            base.join(relative)
+        })
+    };
+
+    loop {
+        let mut changed = BTreeSet::new();
+        let first = rx.recv().map_err(|e| format!("watcher stopped: {}", e))?;
+        let mut pending = vec![first];
+        while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+            pending.push(event);
+        }
+
+        for event in pending {
+            match event {
+                Ok(event) if !matches!(event.kind, EventKind::Access(_)) => {
+                    changed.extend(event.paths.iter().filter_map(|p| reported(p)));
+                }
+                Ok(_) => {}
+                Err(e) => logging::warning!("watch error: {}", e),
+            }
+        }
+
+        let mut any_update = false;
+        for path in changed {
+            if !walk::matches(&pattern, &path) || path.is_dir() {
+                continue;
+            }
+            let key = path.to_string_lossy().into_owned();
+
+            if !path.exists() {
+                if state.remove(&key).is_some() {
+                    print_update(config, &key, true, &[]);
+                    any_update = true;
+                }
+                continue;
+            }
+
+            let (path_str, outcome) = process_entry(WalkEntry::Matched(path), config, None);
+            match outcome {
+                FileOutcome::Scanned { detections, .. } => {
+                    print_update(config, &path_str, false, &detections);
+                    state.insert(path_str, detections);
+                    any_update = true;
+                }
+                _ => {
+                    logging::debug!("(not scanned) {}", path_str);
+                    state.remove(&path_str);
+                }
+            }
+        }
+
+        if any_update && config.watch_full {
+            print_full_report(config, &state);
+        }
+    }
+}
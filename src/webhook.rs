@@ -0,0 +1,158 @@
+//! `--notify-webhook`: POST a summary of the findings to a webhook, for unattended sweeps
+//! that should push an alert instead of waiting for someone to read the build log.
+//!
+//! The default payload is JSON with the pattern, the [`Summary`], and with
+//! `--webhook-full-report` the detections. `--webhook-template` replaces it: `slack` is a
+//! built-in message for Slack incoming webhooks (and the many services that accept the same
+//! `{"text": ...}` body), and any other value is a file whose `{{placeholders}}` are filled
+//! in. The body is sent only when the scan fails, and must be valid JSON.
+
+use std::path::PathBuf;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::summary::Summary;
+use crate::{visualize, Detection};
+
+/// Seconds to wait for the webhook before giving up.
+#[cfg(feature = "http")]
+const TIMEOUT_SECS: u64 = 30;
+
+/// The built-in `slack` template.
+const SLACK_TEMPLATE: &str = r#"{
+  "text": ":rotating_light: invisible-char-detector found {{total}} suspicious character(s) in {{files}} file(s) of {{scanned_files}} scanned ({{pattern}})\n```\n{{top_files}}\n```"
+}
+"#;
+
+/// The body `--notify-webhook` sends (`--webhook-template`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Template {
+    /// The built-in Slack message.
+    Slack,
+
+    /// A template file.
+    File(PathBuf),
+}
+
+/// Parse a `--webhook-template` value.
+pub fn parse_template(value: &str) -> Result<Template, String> {
+    Ok(match value {
+        "slack" => Template::Slack,
+        path => Template::File(PathBuf::from(path)),
+    })
+}
+
+/// The default payload.
+#[derive(Serialize)]
+struct Payload<'a> {
+    tool: &'static str,
+    version: &'static str,
+    pattern: &'a str,
+    summary: &'a Summary,
+
+    /// With `--webhook-full-report`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detections: Option<&'a [Detection]>,
+}
+
+/// `value` escaped to go inside a JSON string, without the quotes.
+fn json_string_content(value: &str) -> String {
+    let quoted = serde_json::to_string(value).unwrap_or_default();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+/// Fill in the placeholders of `template`. Text placeholders are escaped for use inside a
+/// JSON string, with hidden characters in paths spelled out; `{{summary}}` and
+/// `{{detections}}` are JSON values.
+fn render(
+    template: &str,
+    pattern: &str,
+    summary: &Summary,
+    detections: &[Detection],
+) -> Result<String, String> {
+    let top_files: Vec<String> = summary
+        .top_files
+        .iter()
+        .map(|file| format!("{}: {}", visualize(&file.key), file.count))
+        .collect();
+    let text = [
+        ("total", summary.total.to_string()),
+        ("files", summary.files.to_string()),
+        ("scanned_files", summary.scanned_files.to_string()),
+        ("pattern", visualize(pattern)),
+        ("top_files", top_files.join("\n")),
+    ];
+    let json = [
+        (
+            "summary",
+            serde_json::to_string(summary).map_err(|e| e.to_string())?,
+        ),
+        (
+            "detections",
+            serde_json::to_string(detections).map_err(|e| e.to_string())?,
+        ),
+    ];
+
+    let mut body = template.to_string();
+    for (name, value) in text {
+        body = body.replace(&format!("{{{{{}}}}}", name), &json_string_content(&value));
+    }
+    for (name, value) in json {
+        body = body.replace(&format!("{{{{{}}}}}", name), &value);
+    }
+    serde_json::from_str::<Value>(&body).map_err(|e| {
+        format!(
+            "the webhook template is not valid JSON once filled in: {}",
+            e
+        )
+    })?;
+    Ok(body)
+}
+
+/// The body to send.
+pub fn payload(
+    template: Option<&Template>,
+    full_report: bool,
+    pattern: &str,
+    summary: &Summary,
+    detections: &[Detection],
+) -> Result<String, String> {
+    match template {
+        None => serde_json::to_string(&Payload {
+            tool: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            pattern,
+            summary,
+            detections: full_report.then_some(detections),
+        })
+        .map_err(|e| e.to_string()),
+        Some(Template::Slack) => render(SLACK_TEMPLATE, pattern, summary, detections),
+        Some(Template::File(path)) => {
+            let template =
+                std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            render(&template, pattern, summary, detections)
+        }
+    }
+}
+
+/// POST `body` to `url`.
+#[cfg(feature = "http")]
+pub fn notify(url: &str, body: &str) -> Result<(), String> {
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(std::time::Duration::from_secs(TIMEOUT_SECS)))
+        .build()
+        .into();
+    agent
+        .post(url)
+        .header("Content-Type", "application/json")
+        .send(body)
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// POST `body` to `url`.
+#[cfg(not(feature = "http"))]
+pub fn notify(_url: &str, _body: &str) -> Result<(), String> {
+    Err("this build has no HTTP support (the `http` feature)".to_string())
+}